@@ -9,6 +9,7 @@ pub struct AuthState {
     pub selected_character_id: Option<i64>,
     pub class: Option<CharacterClass>,
     pub specialization: Option<Specialization>,
+    pub entity_id: Option<u64>,  // Network ID of our character while in the world
 }
 
 /// Stores the spawn position received from server when character is selected
@@ -33,6 +34,7 @@ impl AuthState {
         self.selected_character_id = None;
         self.class = None;
         self.specialization = None;
+        self.entity_id = None;
     }
 
     pub fn select_character(&mut self, character_id: i64) {
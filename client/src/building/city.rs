@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use shared::city::{self, BuildingKind};
 use crate::player::GameWorld;
 use super::medieval_kit::*;
 
@@ -17,182 +18,24 @@ pub fn spawn_city_buildings(
 ) {
     info!("🏰 Building medieval city with kit assets...");
     
-    // ==================== NORTH SIDE ====================
-    
-    // 1. Large Tavern/Inn - "The Golden Dragon"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_tavern(),
-        Vec3::new(-8.0, 0.0, 28.0),
-        0.15,
-    );
-    
-    // 2. Smithy - "Ironforge"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_smithy(),
-        Vec3::new(-28.0, 0.0, 28.0),
-        -0.2,
-    );
-    
-    // 3. Medium House North 1
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_medium(),
-        Vec3::new(24.0, 0.0, 30.0),
-        0.3,
-    );
-    
-    // 4. Small House North 2
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_small(),
-        Vec3::new(32.0, 0.0, 26.0),
-        -0.4,
-    );
-    
-    // 5. Tower - "Watchtower"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_tower(),
-        Vec3::new(35.0, 0.0, 35.0),
-        0.0,
-    );
-    
-    // ==================== EAST SIDE ====================
-    
-    // 6. Medium House East 1
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_medium(),
-        Vec3::new(30.0, 0.0, 10.0),
-        0.1,
-    );
-    
-    // 7. Small House East 2
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_small(),
-        Vec3::new(31.0, 0.0, -8.0),
-        -0.25,
-    );
-    
-    // 8. Medium House East 3
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_medium(),
-        Vec3::new(27.0, 0.0, -20.0),
-        0.5,
-    );
-    
-    // ==================== SOUTH SIDE ====================
-    
-    // 9. Smithy South - "Blackforge"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_smithy(),
-        Vec3::new(2.0, 0.0, -30.0),
-        -0.1,
-    );
-    
-    // 10. Small House South 1
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_small(),
-        Vec3::new(18.0, 0.0, -31.0),
-        -0.3,
-    );
-    
-    // 11. Small Cottage South 2
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_small(),
-        Vec3::new(30.0, 0.0, -27.0),
-        0.2,
-    );
-    
-    // ==================== WEST SIDE ====================
-    
-    // 12. Church/Cathedral - "St. Michael's"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_church(),
-        Vec3::new(-32.0, 0.0, 8.0),
-        0.08,
-    );
-    
-    // 13. Medium House West 1
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_medium(),
-        Vec3::new(-30.0, 0.0, -16.0),
-        -0.15,
-    );
-    
-    // 14. Small House West 2
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_house_small(),
-        Vec3::new(-34.0, 0.0, -31.0),
-        0.45,
-    );
-    
-    // 15. Tavern West - "The Rusty Sword"
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_tavern(),
-        Vec3::new(-36.0, 0.0, 24.0),
-        -0.3,
-    );
-    
-    // ==================== PLAZA (Central Market) ====================
-    
-    // Market Stall 1 - East side
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_market_stall(),
-        Vec3::new(22.0, 0.0, 4.0),
-        0.6,
-    );
-    
-    // Market Stall 2 - East side
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_market_stall(),
-        Vec3::new(20.0, 0.0, -3.0),
-        -0.4,
-    );
-    
-    // Market Stall 3 - North side
-    spawn_building_from_template(
-        commands,
-        asset_server,
-        template_market_stall(),
-        Vec3::new(18.5, 0.0, 10.0),
-        0.2,
-    );
+    // Buildings come from the shared layout, which the server also uses for navigation
+    for building in city::buildings() {
+        let template = match building.kind {
+            BuildingKind::SmallHouse => template_house_small(),
+            BuildingKind::MediumHouse => template_house_medium(),
+            BuildingKind::Tavern => template_tavern(),
+            BuildingKind::Church => template_church(),
+            BuildingKind::Smithy => template_smithy(),
+            BuildingKind::MarketStall => template_market_stall(),
+            BuildingKind::Tower => template_tower(),
+        };
+        spawn_building_from_template(commands, asset_server, template, building.position, building.rotation);
+    }
     
     // ==================== DECORATIONS & PROPS ====================
     
     // Central Fountain (keep the existing fountain)
-    let fountain_mesh = meshes.add(Mesh::from(Cylinder::new(2.5, 3.0)));
+    let fountain_mesh = meshes.add(Mesh::from(Cylinder::new(city::FOUNTAIN_RADIUS, city::FOUNTAIN_HEIGHT)));
     let fountain_material = materials.add(StandardMaterial {
         base_color: Color::srgb(0.5, 0.5, 0.55),
         perceptual_roughness: 0.9,
@@ -202,11 +45,11 @@ pub fn spawn_city_buildings(
         PbrBundle {
             mesh: fountain_mesh,
             material: fountain_material,
-            transform: Transform::from_xyz(-6.0, 1.5, 6.0),
+            transform: Transform::from_translation(city::FOUNTAIN_POSITION + Vec3::Y * city::FOUNTAIN_HEIGHT / 2.0),
             ..default()
         },
         bevy_rapier3d::prelude::RigidBody::Fixed,
-        bevy_rapier3d::prelude::Collider::cylinder(1.5, 2.5),
+        bevy_rapier3d::prelude::Collider::cylinder(city::FOUNTAIN_HEIGHT / 2.0, city::FOUNTAIN_RADIUS),
         GameWorld,
     ));
     
@@ -286,7 +129,7 @@ pub fn spawn_city_buildings(
         Vec3::ONE,
     );
     
-    info!("✅ Medieval city complete - {} buildings spawned!", 15 + 3); // 15 main buildings + 3 market stalls
+    info!("✅ Medieval city complete - {} buildings spawned!", city::buildings().len());
 }
 
 // Note: Old procedural system functions removed
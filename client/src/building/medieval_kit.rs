@@ -8,6 +8,7 @@
 /// The kit uses 2m modular units!
 
 use bevy::prelude::*;
+use shared::city::BuildingKind;
 use crate::player::GameWorld;
 
 /// Base path for medieval village models
@@ -196,8 +197,8 @@ pub fn template_house_small() -> BuildingTemplate {
                 .with_offset(Vec3::new(0.0, 3.0, 0.0)),
         ],
         // Collider: 2m×3m×2m building
-        collider_size: Vec3::new(1.2, 1.5, 1.2), // Slightly larger than 2x3x2
-        collider_offset: Vec3::new(0.0, 1.5, 0.0), // Center at half height
+        collider_size: BuildingKind::SmallHouse.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::SmallHouse.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -254,8 +255,8 @@ pub fn template_house_medium() -> BuildingTemplate {
             BuildingComponent::new("Roof_RoundTiles_6x6.gltf")
                 .with_offset(Vec3::new(0.0, 3.0, 0.0)),
         ],
-        collider_size: Vec3::new(2.2, 1.5, 2.2),
-        collider_offset: Vec3::new(0.0, 1.5, 0.0),
+        collider_size: BuildingKind::MediumHouse.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::MediumHouse.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -315,8 +316,8 @@ pub fn template_tavern() -> BuildingTemplate {
             BuildingComponent::new("Roof_RoundTiles_6x8.gltf")
                 .with_offset(Vec3::new(0.0, 3.0, 0.0)),
         ],
-        collider_size: Vec3::new(3.2, 1.5, 2.2),
-        collider_offset: Vec3::new(0.0, 1.5, 0.0),
+        collider_size: BuildingKind::Tavern.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::Tavern.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -389,8 +390,8 @@ pub fn template_church() -> BuildingTemplate {
             BuildingComponent::new("Roof_RoundTiles_6x10.gltf")
                 .with_offset(Vec3::new(0.0, 3.0, 0.0)),
         ],
-        collider_size: Vec3::new(3.2, 1.5, 3.2),
-        collider_offset: Vec3::new(0.0, 1.5, 0.0),
+        collider_size: BuildingKind::Church.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::Church.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -437,8 +438,8 @@ pub fn template_smithy() -> BuildingTemplate {
             BuildingComponent::new("Prop_Chimney.gltf")
                 .with_offset(Vec3::new(-1.0, 3.5, -1.0)),
         ],
-        collider_size: Vec3::new(2.2, 1.5, 2.2),
-        collider_offset: Vec3::new(0.0, 1.5, 0.0),
+        collider_size: BuildingKind::Smithy.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::Smithy.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -466,8 +467,8 @@ pub fn template_market_stall() -> BuildingTemplate {
             BuildingComponent::new("Prop_Crate.gltf")
                 .with_offset(Vec3::new(-0.5, 0.0, 0.3)),
         ],
-        collider_size: Vec3::new(1.2, 1.5, 1.2),
-        collider_offset: Vec3::new(0.0, 1.5, 0.0),
+        collider_size: BuildingKind::MarketStall.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::MarketStall.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
 
@@ -533,7 +534,7 @@ pub fn template_tower() -> BuildingTemplate {
             BuildingComponent::new("Roof_RoundTiles_4x4.gltf")
                 .with_offset(Vec3::new(0.0, 6.0, 0.0)),
         ],
-        collider_size: Vec3::new(2.2, 3.0, 2.2), // Tall building
-        collider_offset: Vec3::new(0.0, 3.0, 0.0),
+        collider_size: BuildingKind::Tower.collider_half_extents(),
+        collider_offset: Vec3::new(0.0, BuildingKind::Tower.collider_half_extents().y, 0.0),  // Center at half height
     }
 }
@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use shared::{ClientMessage, SkillEffect, SkillId, StatusEffectState};
+use std::collections::HashMap;
+use crate::auth_state::AuthState;
+use crate::mobs::Mob;
+use crate::networking::{CombatEvent, NetworkClient, OtherPlayer};
+use crate::player::Player;
+use crate::ui::{PlayerStats, UILayerStack};
+use crate::GameFont;
+use crate::GameState;
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CombatTarget>()
+            .init_resource::<SkillCooldowns>()
+            .init_resource::<ActiveEffects>()
+            .add_systems(Update, (
+                cycle_target,
+                handle_skill_keys,
+                handle_combat_events,
+                update_damage_numbers,
+                update_invisibility,
+            ).run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), cleanup_combat);
+    }
+}
+
+/// Keys for the specialization's skills, in the order of `Specialization::skills()`
+const SKILL_KEYS: [KeyCode; 5] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+/// Targets further away than this are not picked by Tab (meters)
+const TARGET_RANGE: f32 = 30.0;
+
+/// How long a damage number stays on screen (seconds)
+const DAMAGE_NUMBER_LIFETIME: f32 = 1.2;
+
+/// How far a damage number rises during its lifetime (world units)
+const DAMAGE_NUMBER_RISE: f32 = 1.0;
+
+/// Entity our skills are aimed at (server entity ID)
+#[derive(Resource, Default)]
+pub struct CombatTarget(pub Option<u64>);
+
+/// Cooldowns of our skills as reported by the server: (ready again at, cooldown length),
+/// in seconds since startup
+#[derive(Resource, Default)]
+pub struct SkillCooldowns {
+    ready_at: HashMap<SkillId, (f64, f64)>,
+}
+
+impl SkillCooldowns {
+    /// Fraction of the cooldown still remaining (0 = ready)
+    pub fn remaining(&self, skill: SkillId, now: f64) -> f32 {
+        let Some(&(ready_at, cooldown)) = self.ready_at.get(&skill) else { return 0.0 };
+        if cooldown <= 0.0 {
+            return 0.0;
+        }
+        ((ready_at - now) / cooldown).clamp(0.0, 1.0) as f32
+    }
+}
+
+/// Our status effects as last reported by the server, with the time each one runs out
+/// (seconds since startup)
+#[derive(Resource, Default)]
+pub struct ActiveEffects {
+    pub effects: Vec<(StatusEffectState, f64)>,
+}
+
+/// Another player that used an invisibility skill. Hidden until the timer runs out.
+#[derive(Component)]
+struct Invisible(Timer);
+
+/// Floating number above an entity that was hit or healed
+#[derive(Component)]
+struct DamageNumber {
+    world_position: Vec3,
+    age: f32,
+}
+
+/// Select the nearest other player or mob with Tab, or the next one further away if one is selected
+fn cycle_target(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut target: ResMut<CombatTarget>,
+    player_query: Query<&Transform, With<Player>>,
+    others: Query<(&Transform, &OtherPlayer), Without<Invisible>>,
+    mobs: Query<(&Transform, &Mob)>,
+) {
+    if !keyboard.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else { return };
+
+    let players = others.iter().map(|(transform, other)| (transform, other.id));
+    let monsters = mobs.iter().map(|(transform, mob)| (transform, mob.id));
+    let mut candidates: Vec<(f32, u64)> = players.chain(monsters)
+        .map(|(transform, id)| (transform.translation.distance(player_transform.translation), id))
+        .filter(|(distance, _)| *distance <= TARGET_RANGE)
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let next = match target.0.and_then(|current| candidates.iter().position(|(_, id)| *id == current)) {
+        Some(index) => candidates.get(index + 1).or(candidates.first()),
+        None => candidates.first(),
+    };
+    target.0 = next.map(|(_, id)| *id);
+
+    match target.0 {
+        Some(id) => match mobs.iter().find(|(_, mob)| mob.id == id) {
+            Some((_, mob)) => info!("Target: {} ({:.0}/{:.0})", mob.kind.info().name, mob.health, mob.max_health),
+            None => info!("Target: player {}", id),
+        },
+        None => info!("No target in range"),
+    }
+}
+
+/// Ask the server to use the skill bound to a pressed key. The server validates and
+/// answers with SkillUsed or SkillFailed.
+fn handle_skill_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    auth_state: Res<AuthState>,
+    target: Res<CombatTarget>,
+    ui_stack: Res<UILayerStack>,
+    network: Option<Res<NetworkClient>>,
+) {
+    if ui_stack.top_layer().is_some_and(|layer| layer.blocks_input) {
+        return;
+    }
+    let Some(network) = network else { return };
+    let Some(specialization) = auth_state.specialization else { return };
+    let skills = specialization.skills();
+
+    for (key, skill) in SKILL_KEYS.iter().zip(skills) {
+        if !keyboard.just_pressed(*key) {
+            continue;
+        }
+        let target = if skill.info().needs_target() { target.0 } else { None };
+        if let Err(e) = network.send_message(&ClientMessage::UseSkill { skill, target }) {
+            error!("Failed to send UseSkill: {}", e);
+        }
+    }
+}
+
+fn handle_combat_events(
+    mut commands: Commands,
+    mut combat_events: EventReader<CombatEvent>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut cooldowns: ResMut<SkillCooldowns>,
+    mut active_effects: ResMut<ActiveEffects>,
+    mut target: ResMut<CombatTarget>,
+    auth_state: Res<AuthState>,
+    time: Res<Time>,
+    font: Res<GameFont>,
+    player_query: Query<&Transform, With<Player>>,
+    others: Query<(Entity, &Transform, &OtherPlayer)>,
+    mobs: Query<(&Transform, &Mob)>,
+) {
+    let own_id = auth_state.entity_id;
+    let position_of = |id: u64| {
+        if Some(id) == own_id {
+            player_query.get_single().ok().map(|t| t.translation)
+        } else {
+            others.iter().find(|(_, _, other)| other.id == id).map(|(_, t, _)| t.translation)
+                .or_else(|| mobs.iter().find(|(_, mob)| mob.id == id).map(|(t, _)| t.translation))
+        }
+    };
+
+    for event in combat_events.read() {
+        match event {
+            CombatEvent::SkillUsed { caster, skill, target, cooldown } => {
+                if Some(*caster) == own_id {
+                    let cooldown = *cooldown as f64;
+                    cooldowns.ready_at.insert(*skill, (time.elapsed_seconds_f64() + cooldown, cooldown));
+                }
+                debug!("{} used {} on {:?}", caster, skill.info().name, target);
+            }
+            CombatEvent::SkillFailed { skill, reason } => {
+                warn!("{} failed: {}", skill.info().name, reason);
+            }
+            CombatEvent::Damage { target: hit, amount, health, .. } => {
+                if let Some(position) = position_of(*hit) {
+                    // Red when we get hit, yellow when we hit something
+                    let color = if Some(*hit) == own_id { Color::srgb(1.0, 0.3, 0.3) } else { Color::srgb(1.0, 0.85, 0.2) };
+                    spawn_damage_number(&mut commands, &font, position, format!("{:.0}", amount), color);
+                }
+
+                // Nothing left to fight
+                if *health <= 0.0 && target.0 == Some(*hit) {
+                    target.0 = None;
+                }
+            }
+            CombatEvent::Healed { target, amount, .. } => {
+                if let Some(position) = position_of(*target) {
+                    spawn_damage_number(&mut commands, &font, position, format!("+{:.0}", amount), Color::srgb(0.3, 1.0, 0.4));
+                }
+            }
+            CombatEvent::EffectApplied { target, skill, duration, .. } => {
+                if Some(*target) == own_id {
+                    info!("{} affects you for {:.1}s", skill.info().name, duration);
+                } else if matches!(skill.info().effect, SkillEffect::Invisibility(_)) {
+                    // The server stops sending its position; hide the model where it vanished
+                    if let Some((entity, _, _)) = others.iter().find(|(_, _, other)| other.id == *target) {
+                        commands.entity(entity).insert(Invisible(Timer::from_seconds(*duration, TimerMode::Once)));
+                    }
+                }
+            }
+            CombatEvent::Vitals(vitals) => {
+                player_stats.health = vitals.health;
+                player_stats.max_health = vitals.max_health;
+                player_stats.mana = vitals.mana;
+                player_stats.max_mana = vitals.max_mana;
+                player_stats.stamina = vitals.stamina;
+                player_stats.max_stamina = vitals.max_stamina;
+            }
+            CombatEvent::StatusEffects { entity, effects } => {
+                if Some(*entity) == own_id {
+                    let now = time.elapsed_seconds_f64();
+                    active_effects.effects = effects.iter()
+                        .map(|effect| (effect.clone(), now + effect.remaining as f64))
+                        .collect();
+                }
+            }
+        }
+    }
+}
+
+fn spawn_damage_number(commands: &mut Commands, font: &GameFont, position: Vec3, label: String, color: Color) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                label,
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 24.0,
+                    color,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            z_index: ZIndex::Global(110),
+            visibility: Visibility::Hidden,  // Until it has been placed on screen
+            ..default()
+        },
+        DamageNumber {
+            world_position: position + Vec3::Y * 1.5,
+            age: 0.0,
+        },
+    ));
+}
+
+/// Let damage numbers rise and fade above where the hit landed
+fn update_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut numbers: Query<(Entity, &mut DamageNumber, &mut Style, &mut Text, &mut Visibility, &Node)>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return };
+
+    for (entity, mut number, mut style, mut text, mut visibility, node) in numbers.iter_mut() {
+        number.age += time.delta_seconds();
+        if number.age >= DAMAGE_NUMBER_LIFETIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let progress = number.age / DAMAGE_NUMBER_LIFETIME;
+        let world_position = number.world_position + Vec3::Y * DAMAGE_NUMBER_RISE * progress;
+        match camera.world_to_viewport(camera_transform, world_position) {
+            Some(screen_pos) => {
+                let size = node.size();
+                style.left = Val::Px(screen_pos.x - size.x / 2.0);
+                style.top = Val::Px(screen_pos.y - size.y / 2.0);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+
+        for section in text.sections.iter_mut() {
+            section.style.color.set_alpha(1.0 - progress);
+        }
+    }
+}
+
+fn update_invisibility(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut invisible: Query<(Entity, &mut Invisible, &mut Visibility)>,
+) {
+    for (entity, mut invisible, mut visibility) in invisible.iter_mut() {
+        if invisible.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invisible>();
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+fn cleanup_combat(
+    mut commands: Commands,
+    mut target: ResMut<CombatTarget>,
+    mut cooldowns: ResMut<SkillCooldowns>,
+    mut active_effects: ResMut<ActiveEffects>,
+    numbers: Query<Entity, With<DamageNumber>>,
+) {
+    target.0 = None;
+    cooldowns.ready_at.clear();
+    active_effects.effects.clear();
+    for entity in numbers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
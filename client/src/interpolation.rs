@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+
+/// Renders server-driven entities (remote players, later NPCs and monsters) slightly in
+/// the past, blending between timestamped snapshots instead of jumping at the snapshot rate.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InterpolationSettings>()
+            .init_resource::<ServerClock>()
+            .add_systems(Update, interpolate_snapshots);
+    }
+}
+
+/// Snapshots older than the render time that are kept around (for extrapolation)
+const MAX_SNAPSHOTS: usize = 32;
+
+/// Below this horizontal speed an interpolated entity counts as standing still
+const MOVING_SPEED_THRESHOLD: f32 = 0.2;
+
+#[derive(Resource)]
+pub struct InterpolationSettings {
+    /// How far in the past entities are rendered (seconds). Should cover about two
+    /// snapshot intervals so there is almost always a newer snapshot to blend towards.
+    pub delay: f64,
+    /// How long to keep moving along the last known velocity when snapshots are late (seconds)
+    pub max_extrapolation: f64,
+}
+
+impl Default for InterpolationSettings {
+    fn default() -> Self {
+        Self {
+            delay: 0.2,               // 2x the 100ms world state interval
+            max_extrapolation: 0.25,
+        }
+    }
+}
+
+/// Estimate of the server clock, learned from snapshot timestamps
+#[derive(Resource, Default)]
+pub struct ServerClock {
+    /// server_time - local_time; `None` until the first snapshot arrived
+    offset: Option<f64>,
+}
+
+impl ServerClock {
+    /// Feed a server timestamp that arrived at local time `now`
+    pub fn observe(&mut self, server_time: f64, now: f64) {
+        let sample = server_time - now;
+        self.offset = Some(match self.offset {
+            // Network delay only ever makes samples smaller, so the largest one is the
+            // most accurate - but drift slowly towards newer samples to follow clock drift
+            Some(offset) if sample < offset => offset + (sample - offset) * 0.01,
+            _ => sample,
+        });
+    }
+
+    /// Current server time, if known
+    pub fn now(&self, local_now: f64) -> Option<f64> {
+        self.offset.map(|offset| local_now + offset)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Snapshot {
+    time: f64,
+    position: Vec3,
+    yaw: f32,
+}
+
+/// Timestamped server states of one entity, consumed by [`interpolate_snapshots`]
+#[derive(Component, Default)]
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl SnapshotBuffer {
+    /// Add a server state. Out-of-order and duplicate snapshots are ignored.
+    pub fn push(&mut self, time: f64, position: Vec3, yaw: f32) {
+        if self.snapshots.back().is_some_and(|last| time <= last.time) {
+            return;
+        }
+        self.snapshots.push_back(Snapshot { time, position, yaw });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Facing of the newest snapshot (for updates that carry no rotation)
+    pub fn latest_yaw(&self) -> f32 {
+        self.snapshots.back().map_or(0.0, |s| s.yaw)
+    }
+
+    /// Position and yaw at `render_time`, blending or extrapolating as needed
+    fn sample(&mut self, render_time: f64, max_extrapolation: f64) -> Option<(Vec3, f32)> {
+        // Forget snapshots that are no longer needed to bracket the render time
+        while self.snapshots.len() > 2 && self.snapshots[1].time <= render_time {
+            self.snapshots.pop_front();
+        }
+
+        let first = *self.snapshots.front()?;
+        if self.snapshots.len() == 1 || render_time <= first.time {
+            return Some((first.position, first.yaw));
+        }
+
+        let from = self.snapshots[0];
+        let to = self.snapshots[1];
+        let span = (to.time - from.time).max(f64::EPSILON);
+
+        // t > 1 means we ran out of snapshots: extrapolate along the last velocity, briefly
+        let max_t = 1.0 + max_extrapolation / span;
+        let t = ((render_time - from.time) / span).min(max_t) as f32;
+
+        let position = from.position.lerp(to.position, t);
+        let yaw = from.yaw + shortest_angle(from.yaw, to.yaw) * t.min(1.0);
+        Some((position, yaw))
+    }
+}
+
+/// Movement of an interpolated entity, used to pick its animation
+#[derive(Component, Default)]
+pub struct InterpolatedMotion {
+    pub is_moving: bool,
+}
+
+fn interpolate_snapshots(
+    time: Res<Time>,
+    settings: Res<InterpolationSettings>,
+    clock: Res<ServerClock>,
+    mut query: Query<(&mut Transform, &mut SnapshotBuffer, Option<&mut InterpolatedMotion>)>,
+) {
+    let Some(server_now) = clock.now(time.elapsed_seconds_f64()) else { return };
+    let render_time = server_now - settings.delay;
+    let dt = time.delta_seconds();
+
+    for (mut transform, mut buffer, motion) in query.iter_mut() {
+        let Some((position, yaw)) = buffer.sample(render_time, settings.max_extrapolation) else { continue };
+
+        if let Some(mut motion) = motion {
+            let delta = position - transform.translation;
+            let speed = Vec3::new(delta.x, 0.0, delta.z).length() / dt.max(f32::EPSILON);
+            let is_moving = speed > MOVING_SPEED_THRESHOLD;
+            if motion.is_moving != is_moving {
+                motion.is_moving = is_moving;
+            }
+        }
+
+        transform.translation = position;
+        transform.rotation = Quat::from_rotation_y(yaw);
+    }
+}
+
+/// Signed difference `to - from`, wrapped to [-PI, PI]
+fn shortest_angle(from: f32, to: f32) -> f32 {
+    let diff = (to - from).rem_euclid(TAU);
+    if diff > TAU / 2.0 { diff - TAU } else { diff }
+}
@@ -0,0 +1,523 @@
+use bevy::prelude::*;
+use shared::items::{Equipment, EquipmentSlot, ItemId, ItemStack, ItemStats, Rarity, INVENTORY_SLOTS};
+use shared::ClientMessage;
+use crate::networking::{ItemEvent, NetworkClient};
+use crate::ui::{UILayerStack, UILayerType, NORMAL_BUTTON};
+use crate::GameFont;
+use crate::GameState;
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InventoryState>()
+            .add_systems(OnEnter(GameState::InGame), setup_inventory_window)
+            .add_systems(Update, (
+                handle_item_events,
+                handle_inventory_key,
+                handle_slot_clicks,
+                handle_equipment_clicks,
+                handle_use_clicks,
+                handle_drop_button,
+                update_inventory_window,
+            ).chain().run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), cleanup_inventory);
+    }
+}
+
+const INVENTORY_KEY: KeyCode = KeyCode::KeyI;
+
+const SLOTS_PER_ROW: usize = 8;
+const SLOT_SIZE: f32 = 48.0;
+
+const SELECTED_BORDER: Color = Color::srgb(1.0, 0.85, 0.2);
+const EMPTY_BORDER: Color = Color::srgb(0.25, 0.25, 0.25);
+
+/// Our inventory as last reported by the server, and the window showing it
+#[derive(Resource)]
+pub struct InventoryState {
+    pub slots: Vec<Option<ItemStack>>,
+    pub equipment: Equipment,
+    pub gold: u64,
+    pub open: bool,
+    selected: Option<usize>,  // Picked up with a click, moved with the next one
+    hovered: Option<usize>,
+    hovered_equipment: Option<EquipmentSlot>,
+    message: Option<String>,  // Last error from the server
+}
+
+impl Default for InventoryState {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; INVENTORY_SLOTS],
+            equipment: Equipment::default(),
+            gold: 0,
+            open: false,
+            selected: None,
+            hovered: None,
+            hovered_equipment: None,
+            message: None,
+        }
+    }
+}
+
+impl InventoryState {
+    fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots.get(slot).copied().flatten()
+    }
+}
+
+#[derive(Component)]
+struct InventoryWindow;
+
+#[derive(Component)]
+struct InventorySlot(usize);
+
+#[derive(Component)]
+struct EquipmentSlotButton(EquipmentSlot);
+
+/// Texts in the window that show part of the inventory
+#[derive(Component)]
+enum InventoryText {
+    Slot(usize),
+    Equipment(EquipmentSlot),
+    Info,   // Hovered item, or how to use the window
+    Stats,  // Bonuses of everything equipped
+    Gold,
+}
+
+#[derive(Component)]
+struct DropItemButton;
+
+fn handle_item_events(
+    mut item_events: EventReader<ItemEvent>,
+    mut inventory: ResMut<InventoryState>,
+) {
+    for event in item_events.read() {
+        match event {
+            ItemEvent::Updated { slots, equipment, gold } => {
+                inventory.slots = slots.clone();
+                inventory.slots.resize(INVENTORY_SLOTS, None);
+                inventory.equipment = *equipment;
+                inventory.gold = *gold;
+                inventory.message = None;
+                // A selection pointing at a slot that emptied is gone
+                if inventory.selected.is_some_and(|slot| inventory.get(slot).is_none()) {
+                    inventory.selected = None;
+                }
+            }
+            ItemEvent::Failed { reason } => {
+                warn!("Item: {}", reason);
+                inventory.message = Some(reason.clone());
+            }
+        }
+    }
+}
+
+fn handle_inventory_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut ui_stack: ResMut<UILayerStack>,
+    mut inventory: ResMut<InventoryState>,
+) {
+    if !keyboard.just_pressed(INVENTORY_KEY) || ui_stack.top_layer().is_some_and(|layer| layer.blocks_input) {
+        return;
+    }
+    inventory.open = !inventory.open;
+    inventory.selected = None;
+    if inventory.open {
+        ui_stack.push_layer(UILayerType::Inventory);
+    } else {
+        ui_stack.remove_layer(UILayerType::Inventory);
+    }
+}
+
+/// Left click picks an item up and puts it down on the next slot clicked (moving,
+/// stacking or swapping). Shift-click splits half a stack off into a free slot.
+fn handle_slot_clicks(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    interaction_query: Query<(&Interaction, &InventorySlot), Changed<Interaction>>,
+    mut inventory: ResMut<InventoryState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    for (interaction, slot) in interaction_query.iter() {
+        let slot = slot.0;
+        match interaction {
+            Interaction::Hovered => {
+                if inventory.hovered != Some(slot) {
+                    inventory.hovered = Some(slot);
+                    inventory.message = None;
+                }
+                continue;
+            }
+            Interaction::None => {
+                if inventory.hovered == Some(slot) {
+                    inventory.hovered = None;
+                }
+                continue;
+            }
+            Interaction::Pressed => {}
+        }
+
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let message = match (inventory.selected, inventory.get(slot)) {
+            (None, Some(stack)) if shift => {
+                let Some(to) = inventory.slots.iter().position(Option::is_none) else {
+                    inventory.message = Some("Kein freier Platz zum Teilen".to_string());
+                    continue;
+                };
+                if stack.count < 2 {
+                    continue;
+                }
+                Some(ClientMessage::SplitItem { slot: slot as u16, count: stack.count / 2, to: to as u16 })
+            }
+            (None, Some(_)) => {
+                inventory.selected = Some(slot);
+                None
+            }
+            (None, None) => None,
+            (Some(from), _) => {
+                inventory.selected = None;
+                (from != slot).then_some(ClientMessage::MoveItem { from: from as u16, to: slot as u16 })
+            }
+        };
+
+        let (Some(message), Some(network)) = (message, &network) else { continue };
+        if let Err(e) = network.send_message(&message) {
+            error!("Failed to send inventory change: {}", e);
+        }
+    }
+}
+
+/// Clicking an equipment slot takes off what is equipped there
+fn handle_equipment_clicks(
+    interaction_query: Query<(&Interaction, &EquipmentSlotButton), Changed<Interaction>>,
+    mut inventory: ResMut<InventoryState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        let slot = button.0;
+        match interaction {
+            Interaction::Hovered => {
+                if inventory.hovered_equipment != Some(slot) {
+                    inventory.hovered_equipment = Some(slot);
+                    inventory.message = None;
+                }
+            }
+            Interaction::None => {
+                if inventory.hovered_equipment == Some(slot) {
+                    inventory.hovered_equipment = None;
+                }
+            }
+            Interaction::Pressed => {
+                if inventory.equipment.get(slot).is_none() {
+                    continue;
+                }
+                let Some(network) = &network else { continue };
+                if let Err(e) = network.send_message(&ClientMessage::UnequipItem { slot }) {
+                    error!("Failed to send UnequipItem: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Right click on a slot uses the item in it, or puts it on if it can be worn
+fn handle_use_clicks(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    inventory: Res<InventoryState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    if !inventory.open || !mouse_buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some((slot, stack)) = inventory.hovered.and_then(|slot| inventory.get(slot).map(|stack| (slot, stack))) else { return };
+    let Some(network) = network else { return };
+    let equipment = stack.item.info().is_some_and(|info| info.item_type.is_equipment());
+    let message = if equipment {
+        ClientMessage::EquipItem { slot: slot as u16 }
+    } else {
+        ClientMessage::UseItem { slot: slot as u16 }
+    };
+    if let Err(e) = network.send_message(&message) {
+        error!("Failed to use item: {}", e);
+    }
+}
+
+/// Throw away the whole selected stack
+fn handle_drop_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<DropItemButton>)>,
+    mut inventory: ResMut<InventoryState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(slot) = inventory.selected.take() else { continue };
+        let Some(stack) = inventory.get(slot) else { continue };
+        let Some(network) = &network else { continue };
+        if let Err(e) = network.send_message(&ClientMessage::DropItem { slot: slot as u16, count: stack.count }) {
+            error!("Failed to send DropItem: {}", e);
+        }
+    }
+}
+
+fn setup_inventory_window(mut commands: Commands, font: Res<GameFont>) {
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(20.0),
+                bottom: Val::Px(120.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.08, 0.08, 0.1, 0.95).into(),
+            border_color: Color::srgb(0.4, 0.35, 0.25).into(),
+            border_radius: BorderRadius::all(Val::Px(6.0)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(150),  // Above the HUD, below dialogs (400)
+            ..default()
+        },
+        InventoryWindow,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Inventar", text_style(20.0, Color::WHITE)));
+
+        // Equipment
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for slot in EquipmentSlot::ALL {
+                spawn_slot_button(parent, text_style(10.0, Color::WHITE), EquipmentSlotButton(slot), InventoryText::Equipment(slot));
+            }
+        });
+        parent.spawn((
+            TextBundle::from_section("", text_style(13.0, Color::srgb(0.85, 0.85, 0.85))),
+            InventoryText::Stats,
+        ));
+
+        // Slot grid
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(SLOTS_PER_ROW as f32 * (SLOT_SIZE + 4.0)),
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                column_gap: Val::Px(4.0),
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for slot in 0..INVENTORY_SLOTS {
+                spawn_slot_button(parent, text_style(10.0, Color::WHITE), InventorySlot(slot), InventoryText::Slot(slot));
+            }
+        });
+
+        // Hovered item, or how to use the window
+        parent.spawn((
+            TextBundle::from_section("", text_style(13.0, Color::srgb(0.85, 0.85, 0.85))).with_style(Style {
+                max_width: Val::Px(SLOTS_PER_ROW as f32 * (SLOT_SIZE + 4.0)),
+                min_height: Val::Px(60.0),
+                ..default()
+            }),
+            InventoryText::Info,
+        ));
+
+        // Gold and drop button
+        parent.spawn(NodeBundle {
+            style: Style {
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", text_style(16.0, Color::srgb(1.0, 0.85, 0.2))),
+                InventoryText::Gold,
+            ));
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                },
+                DropItemButton,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Wegwerfen", text_style(14.0, Color::WHITE)));
+            });
+        });
+    });
+}
+
+/// A square slot with a label for what is in it
+fn spawn_slot_button(parent: &mut ChildBuilder, text_style: TextStyle, slot: impl Bundle, text: InventoryText) {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(SLOT_SIZE),
+                height: Val::Px(SLOT_SIZE),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            border_color: EMPTY_BORDER.into(),
+            ..default()
+        },
+        slot,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section("", text_style).with_text_justify(JustifyText::Center),
+            text,
+        ));
+    });
+}
+
+/// Show the window and what is in it whenever our inventory or the selection change
+fn update_inventory_window(
+    inventory: Res<InventoryState>,
+    mut window_query: Query<&mut Visibility, With<InventoryWindow>>,
+    mut slot_query: Query<(&InventorySlot, &mut BorderColor), Without<EquipmentSlotButton>>,
+    mut equipment_query: Query<(&EquipmentSlotButton, &mut BorderColor), Without<InventorySlot>>,
+    mut text_query: Query<(&mut Text, &InventoryText)>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+    if let Ok(mut visibility) = window_query.get_single_mut() {
+        *visibility = if inventory.open { Visibility::Visible } else { Visibility::Hidden };
+    }
+
+    for (slot, mut border) in slot_query.iter_mut() {
+        *border = match inventory.get(slot.0) {
+            _ if inventory.selected == Some(slot.0) => SELECTED_BORDER,
+            stack => item_border(stack.map(|stack| stack.item)),
+        }.into();
+    }
+    for (button, mut border) in equipment_query.iter_mut() {
+        *border = item_border(inventory.equipment.get(button.0)).into();
+    }
+
+    for (mut text, part) in text_query.iter_mut() {
+        text.sections[0].value = match part {
+            InventoryText::Slot(slot) => inventory.get(*slot).map(slot_label).unwrap_or_default(),
+            InventoryText::Equipment(slot) => inventory.equipment.get(*slot)
+                .map(|item| slot_label(ItemStack { item, count: 1 }))
+                .unwrap_or_else(|| slot.name().to_string()),
+            InventoryText::Info => info_text(&inventory),
+            InventoryText::Stats => stats_text(&inventory.equipment),
+            InventoryText::Gold => format!("{} Gold", inventory.gold),
+        };
+    }
+}
+
+fn item_border(item: Option<ItemId>) -> Color {
+    item.and_then(|item| item.info()).map_or(EMPTY_BORDER, |info| rarity_color(info.rarity))
+}
+
+/// Short name and count, there are no item icons yet
+fn slot_label(stack: ItemStack) -> String {
+    let name = stack.item.info().map_or_else(|| "?".to_string(), |info| info.name.chars().take(7).collect());
+    if stack.count > 1 {
+        format!("{}\n{}", name, stack.count)
+    } else {
+        name
+    }
+}
+
+fn info_text(inventory: &InventoryState) -> String {
+    if let Some(message) = &inventory.message {
+        return message.clone();
+    }
+    let hovered = inventory.hovered.and_then(|slot| inventory.get(slot)).map(|stack| stack.item)
+        .or_else(|| inventory.hovered_equipment.and_then(|slot| inventory.equipment.get(slot)));
+    let Some(item) = hovered else {
+        return "Klick: aufnehmen und ablegen - Shift+Klick: teilen - Rechtsklick: benutzen oder anlegen".to_string();
+    };
+    let Some(info) = item.info() else {
+        return "Unbekannter Gegenstand".to_string();
+    };
+
+    let mut lines = vec![
+        format!("{} ({}, {})", info.name, info.rarity.name(), info.item_type.name()),
+        info.description.clone(),
+    ];
+    let bonuses = bonus_text(&info.stats);
+    if !bonuses.is_empty() {
+        lines.push(bonuses);
+    }
+    lines.push(format!("Stufe {} - Wert {} Gold", info.required_level, info.value));
+    lines.join("\n")
+}
+
+fn stats_text(equipment: &Equipment) -> String {
+    let bonuses = bonus_text(&equipment.stats());
+    if bonuses.is_empty() {
+        "Keine Boni durch Ausrüstung".to_string()
+    } else {
+        bonuses
+    }
+}
+
+/// "+4 Angriff, +10 Leben" for the bonuses above zero
+fn bonus_text(stats: &ItemStats) -> String {
+    let bonuses: Vec<String> = [
+        (stats.attack, "Angriff"),
+        (stats.defense, "Verteidigung"),
+        (stats.health, "Leben"),
+        (stats.mana, "Mana"),
+        (stats.stamina, "Ausdauer"),
+    ].into_iter()
+        .filter(|(value, _)| *value > 0.0)
+        .map(|(value, name)| format!("+{} {}", value, name))
+        .collect();
+    bonuses.join(", ")
+}
+
+pub fn rarity_color(rarity: Rarity) -> Color {
+    match rarity {
+        Rarity::Common => Color::srgb(0.6, 0.6, 0.6),
+        Rarity::Uncommon => Color::srgb(0.3, 0.8, 0.3),
+        Rarity::Rare => Color::srgb(0.3, 0.5, 1.0),
+        Rarity::Epic => Color::srgb(0.7, 0.3, 0.9),
+        Rarity::Legendary => Color::srgb(1.0, 0.55, 0.1),
+    }
+}
+
+fn cleanup_inventory(
+    mut commands: Commands,
+    mut inventory: ResMut<InventoryState>,
+    mut ui_stack: ResMut<UILayerStack>,
+    windows: Query<Entity, With<InventoryWindow>>,
+) {
+    *inventory = InventoryState::default();
+    ui_stack.remove_layer(UILayerType::Inventory);
+    for entity in windows.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use shared::items::Loot;
+use shared::ClientMessage;
+use std::collections::HashMap;
+use crate::GameState;
+use crate::inventory::rarity_color;
+use crate::networking::{LootEvent, NetworkClient};
+use crate::player::{GameWorld, Player};
+use crate::ui::UILayerStack;
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroundItems>()
+            // Not gated on InGame, like mobs: loot near the spawn point can arrive before
+            // the state transition has been applied
+            .add_systems(Update, handle_loot_events)
+            .add_systems(Update, (
+                handle_pickup_key,
+                spin_ground_items,
+            ).run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), cleanup_ground_items);
+    }
+}
+
+const PICKUP_KEY: KeyCode = KeyCode::KeyF;
+
+/// Same as the server's pickup range (meters)
+const PICKUP_RANGE: f32 = 3.0;
+
+/// Maps server ground item IDs to the entities representing them
+#[derive(Resource, Default)]
+pub struct GroundItems {
+    entities: HashMap<u64, Entity>,
+}
+
+/// Gold or items lying on the ground, picked up with F
+#[derive(Component)]
+struct GroundItem {
+    id: u64,
+}
+
+fn handle_loot_events(
+    mut commands: Commands,
+    mut loot_events: EventReader<LootEvent>,
+    mut ground_items: ResMut<GroundItems>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in loot_events.read() {
+        match event {
+            LootEvent::Spawned { id, loot, position } => {
+                if ground_items.entities.contains_key(id) {
+                    continue;
+                }
+                let entity = spawn_ground_item(&mut commands, &mut meshes, &mut materials, *id, *loot, *position);
+                ground_items.entities.insert(*id, entity);
+            }
+            LootEvent::Removed { id } => {
+                if let Some(entity) = ground_items.entities.remove(id) {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+fn spawn_ground_item(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    id: u64,
+    loot: Loot,
+    position: Vec3,
+) -> Entity {
+    // Placeholders until there are item models: a coin stack for gold, a small box in
+    // the rarity colour for items
+    let (mesh, color, height) = match loot {
+        Loot::Gold(_) => (meshes.add(Cylinder::new(0.18, 0.1)), Color::srgb(1.0, 0.8, 0.2), 0.1),
+        Loot::Item(stack) => {
+            let color = stack.item.info().map_or(Color::srgb(0.6, 0.6, 0.6), |info| rarity_color(info.rarity));
+            (meshes.add(Cuboid::new(0.3, 0.3, 0.3)), color, 0.3)
+        }
+    };
+
+    debug!("Ground item {} ({:?}) at {:?}", id, loot, position);
+    commands.spawn((
+        SpatialBundle {
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        GroundItem { id },
+        GameWorld,
+    ))
+    .with_children(|parent| {
+        parent.spawn(PbrBundle {
+            mesh,
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                emissive: LinearRgba::from(color) * 0.4,  // Easy to spot in the grass
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, height / 2.0, 0.0),
+            ..default()
+        });
+    })
+    .id()
+}
+
+/// F picks up the closest pile in range; the server decides whether we may have it
+fn handle_pickup_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    ui_stack: Res<UILayerStack>,
+    player_query: Query<&Transform, With<Player>>,
+    item_query: Query<(&Transform, &GroundItem)>,
+    network: Option<Res<NetworkClient>>,
+) {
+    if !keyboard.just_pressed(PICKUP_KEY) || ui_stack.top_layer().is_some_and(|layer| layer.blocks_input) {
+        return;
+    }
+    let Ok(player) = player_query.get_single() else { return };
+    let Some(network) = network else { return };
+
+    // Piles lie on the ground, so only the distance across it counts
+    let distance = |transform: &Transform| (transform.translation - player.translation).xz().length();
+    let closest = item_query.iter()
+        .filter(|(transform, _)| distance(transform) <= PICKUP_RANGE)
+        .min_by(|(a, _), (b, _)| distance(a).total_cmp(&distance(b)));
+    let Some((_, item)) = closest else {
+        info!("Nothing to pick up nearby");
+        return;
+    };
+
+    if let Err(e) = network.send_message(&ClientMessage::PickUpItem { id: item.id }) {
+        error!("Failed to send PickUpItem: {}", e);
+    }
+}
+
+fn spin_ground_items(time: Res<Time>, mut query: Query<&mut Transform, With<GroundItem>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
+
+fn cleanup_ground_items(mut commands: Commands, mut ground_items: ResMut<GroundItems>) {
+    for (_, entity) in ground_items.entities.drain() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
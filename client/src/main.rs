@@ -2,24 +2,42 @@ mod auth_state;
 mod building;
 mod camera;
 mod collision;
+mod combat;
 mod physics;
 mod interaction;
+mod interpolation;
+mod inventory;
+mod loot;
+mod trade;
+mod mobs;
 mod networking;
 mod npc;
+mod party;
 mod player;
+mod prediction;
+mod remote_player;
 mod skybox;
 mod ui;
 
 use auth_state::{AuthState, SpawnPosition};
 
 use bevy::prelude::*;
-use ui::{UIStackPlugin, LoginPlugin, CharacterCreationPlugin, CharacterSelectionPlugin, GameUIPlugin, SettingsPlugin, PausePlugin, NpcDialogPlugin};
+use shared::balance::BalanceTables;
+use ui::{UIStackPlugin, LoginPlugin, CharacterCreationPlugin, CharacterSelectionPlugin, GameUIPlugin, SettingsPlugin, PausePlugin, NpcDialogPlugin, ShopDialogPlugin, DeathScreenPlugin};
 use networking::NetworkingPlugin;
 use player::PlayerPlugin;
+use remote_player::RemotePlayerPlugin;
+use interpolation::InterpolationPlugin;
 use camera::CameraPlugin;
 use npc::NpcPlugin;
 use interaction::InteractionPlugin;
 use collision::CollisionPlugin;
+use combat::CombatPlugin;
+use party::PartyPlugin;
+use inventory::InventoryPlugin;
+use loot::LootPlugin;
+use trade::TradePlugin;
+use mobs::MobPlugin;
 use building::BuildingPlugin;
 use skybox::SkyboxPlugin;
 
@@ -57,6 +75,14 @@ fn main() {
     let font_handle = asset_server.load("fonts/momo/momo.ttf");
     app.insert_resource(GameFont(font_handle));
     
+    // Balance tables from disk; without a valid file the built-in ones are used until
+    // the server sends its own on character selection
+    let balance_path = std::path::Path::new(shared::balance::DEFAULT_BALANCE_PATH);
+    match BalanceTables::load(balance_path).and_then(shared::balance::install) {
+        Ok(()) => info!("Balance tables loaded from {}", balance_path.display()),
+        Err(e) => error!("{} - using built-in balance tables", e),
+    }
+    
     app.init_state::<GameState>()
         .init_resource::<AuthState>()
         .init_resource::<SpawnPosition>()
@@ -82,6 +108,16 @@ fn main() {
         .add_plugins((
             SettingsPlugin,
             NpcDialogPlugin,
+            ShopDialogPlugin,
+            DeathScreenPlugin,
+            RemotePlayerPlugin,
+            InterpolationPlugin,
+            CombatPlugin,
+            PartyPlugin,
+            MobPlugin,
+            InventoryPlugin,
+            LootPlugin,
+            TradePlugin,
         ))
         .run();
 }
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use shared::MobType;
+use std::collections::HashMap;
+use crate::GameState;
+use crate::interpolation::{InterpolatedMotion, InterpolationSettings, ServerClock, SnapshotBuffer};
+use crate::networking::{CombatEvent, MobEvent, RemotePlayerEvent};
+use crate::player::GameWorld;
+
+pub struct MobPlugin;
+
+impl Plugin for MobPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mobs>()
+            // Not gated on InGame, like remote players: the first MobSpawned can arrive
+            // before the state transition has been applied
+            .add_systems(Update, (handle_mob_events, update_mob_health))
+            .add_systems(OnExit(GameState::InGame), cleanup_mobs);
+    }
+}
+
+/// Maps server mob IDs to the entities representing them
+#[derive(Resource, Default)]
+pub struct Mobs {
+    entities: HashMap<u64, Entity>,
+}
+
+/// A monster driven by the server
+#[derive(Component)]
+pub struct Mob {
+    pub id: u64,
+    pub kind: MobType,
+    pub health: f32,
+    pub max_health: f32,
+}
+
+fn handle_mob_events(
+    mut commands: Commands,
+    mut mob_events: EventReader<MobEvent>,
+    mut snapshot_events: EventReader<RemotePlayerEvent>,
+    mut mobs: ResMut<Mobs>,
+    mut buffers: Query<&mut SnapshotBuffer, With<Mob>>,
+    clock: Res<ServerClock>,
+    settings: Res<InterpolationSettings>,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let render_now = clock.now(time.elapsed_seconds_f64()).map(|t| t - settings.delay).unwrap_or(0.0);
+
+    for event in mob_events.read() {
+        match event {
+            MobEvent::Spawned { id, mob, position, health, max_health } => {
+                if mobs.entities.contains_key(id) {
+                    continue;
+                }
+                let mob = Mob { id: *id, kind: *mob, health: *health, max_health: *max_health };
+                let entity = spawn_mob(&mut commands, &mut meshes, &mut materials, mob, *position, render_now);
+                mobs.entities.insert(*id, entity);
+            }
+            MobEvent::Left { id } => {
+                if let Some(entity) = mobs.entities.remove(id) {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+
+    // Mobs move with the same snapshots as remote players
+    for event in snapshot_events.read() {
+        let RemotePlayerEvent::Snapshot { server_time, entities } = event else { continue };
+        for (id, state) in entities {
+            let Some(&entity) = mobs.entities.get(id) else { continue };
+            if let Ok(mut buffer) = buffers.get_mut(entity) {
+                buffer.push(*server_time, state.position(), state.yaw());
+            }
+        }
+    }
+}
+
+fn spawn_mob(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    mob: Mob,
+    position: Vec3,
+    snapshot_time: f64,
+) -> Entity {
+    let mut buffer = SnapshotBuffer::default();
+    buffer.push(snapshot_time, position, 0.0);
+
+    // Placeholder bodies until there are monster models
+    let (radius, height, color) = match mob.kind {
+        MobType::Wolf => (0.35, 0.6, Color::srgb(0.45, 0.45, 0.5)),
+        MobType::Wildschwein => (0.45, 0.5, Color::srgb(0.4, 0.28, 0.18)),
+        MobType::Bandit => (0.35, 1.1, Color::srgb(0.55, 0.2, 0.2)),
+        MobType::Ork => (0.5, 1.4, Color::srgb(0.25, 0.45, 0.2)),
+    };
+
+    debug!("Mob {:?} (ID: {}) spawned at {:?}", mob.kind, mob.id, position);
+    commands.spawn((
+        SpatialBundle {
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        mob,
+        buffer,
+        InterpolatedMotion::default(),
+        GameWorld,
+    ))
+    .with_children(|parent| {
+        parent.spawn(PbrBundle {
+            mesh: meshes.add(Capsule3d::new(radius, height)),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                perceptual_roughness: 0.9,
+                ..default()
+            }),
+            // Feet on the ground
+            transform: Transform::from_xyz(0.0, radius + height / 2.0, 0.0),
+            ..default()
+        });
+    })
+    .id()
+}
+
+/// Keep the health of mobs in sync with the damage and heals the server reports
+fn update_mob_health(
+    mut combat_events: EventReader<CombatEvent>,
+    mobs: Res<Mobs>,
+    mut mob_query: Query<&mut Mob>,
+) {
+    for event in combat_events.read() {
+        let (target, health) = match event {
+            CombatEvent::Damage { target, health, .. } | CombatEvent::Healed { target, health, .. } => (target, health),
+            _ => continue,
+        };
+        let Some(&entity) = mobs.entities.get(target) else { continue };
+        if let Ok(mut mob) = mob_query.get_mut(entity) {
+            mob.health = *health;
+        }
+    }
+}
+
+fn cleanup_mobs(mut commands: Commands, mut mobs: ResMut<Mobs>) {
+    for (_, entity) in mobs.entities.drain() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
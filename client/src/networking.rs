@@ -1,8 +1,12 @@
 use bevy::prelude::*;
-use shared::{ClientMessage, ServerMessage, AuthMessage, AuthResponse, SERVER_ADDR};
+use shared::{ClientMessage, ServerMessage, AuthMessage, AuthResponse, SERVER_ADDR, PROTOCOL_ID, HEARTBEAT_INTERVAL, CONNECTION_TIMEOUT};
+use shared::transport::ReliableEndpoint;
+use shared::balance::BalanceAssembler;
+use shared::snapshot::{self, SnapshotEntities, SnapshotHistory};
 use std::net::UdpSocket;
 use std::sync::{Arc, Mutex};
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use crate::auth_state::AuthState;
 use crate::GameState;
 
@@ -12,9 +16,22 @@ impl Plugin for NetworkingPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<LevelingEvent>()
             .add_event::<CharacterResponseEvent>()
+            .add_event::<RemotePlayerEvent>()
+            .add_event::<PositionCorrectionEvent>()
+            .add_event::<CombatEvent>()
+            .add_event::<PartyEvent>()
+            .add_event::<MobEvent>()
+            .add_event::<DeathEvent>()
+            .add_event::<ItemEvent>()
+            .add_event::<LootEvent>()
+            .add_event::<ShopEvent>()
+            .add_event::<TradeEvent>()
             .init_resource::<ServerConnectionState>()
+            .init_resource::<ReceivedSnapshots>()
             .add_systems(Startup, setup_network)
+            .add_systems(OnExit(GameState::InGame), clear_snapshots)
             .add_systems(Update, (
+                maintain_connection,
                 process_incoming_messages,
                 handle_auth_responses,
                 handle_leveling_events,
@@ -26,9 +43,12 @@ impl Plugin for NetworkingPlugin {
 /// Resource to track server connection status
 #[derive(Resource)]
 pub struct ServerConnectionState {
-    pub is_connected: bool,
-    pub last_check: f64,
-    pub check_interval: f64, // seconds between checks
+    pub is_connected: bool,   // Handshake completed and server still responding
+    pub last_check: f64,      // Last connect attempt
+    pub check_interval: f64,  // seconds between connect attempts
+    pub last_heartbeat: f64,  // Last keepalive sent to the server
+    pub rejected: Option<String>,  // Why the server refused us - no more connect attempts
+    pub session_ended: Option<String>,  // Why the server logged us out - shown on the login screen
 }
 
 impl Default for ServerConnectionState {
@@ -36,7 +56,10 @@ impl Default for ServerConnectionState {
         Self {
             is_connected: false,
             last_check: 0.0,
-            check_interval: 2.0, // Check every 2 seconds
+            check_interval: 2.0, // Retry every 2 seconds
+            last_heartbeat: 0.0,
+            rejected: None,
+            session_ended: None,
         }
     }
 }
@@ -44,7 +67,9 @@ impl Default for ServerConnectionState {
 #[derive(Resource)]
 pub struct NetworkClient {
     socket: Arc<Mutex<UdpSocket>>,
+    endpoint: Arc<Mutex<ReliableEndpoint>>,
     incoming_messages: Arc<Mutex<VecDeque<ServerMessage>>>,
+    last_received: Arc<Mutex<Instant>>,
     server_addr: String,
 }
 
@@ -54,25 +79,34 @@ impl NetworkClient {
         socket.set_nonblocking(true)?;
         
         let socket = Arc::new(Mutex::new(socket));
+        let endpoint = Arc::new(Mutex::new(ReliableEndpoint::new()));
         let incoming_messages = Arc::new(Mutex::new(VecDeque::new()));
+        let last_received = Arc::new(Mutex::new(Instant::now()));
         
         // Start listener thread
         let socket_clone = socket.clone();
+        let endpoint_clone = endpoint.clone();
         let messages_clone = incoming_messages.clone();
+        let last_received_clone = last_received.clone();
         
         std::thread::spawn(move || {
-            listen_for_messages(socket_clone, messages_clone);
+            listen_for_messages(socket_clone, endpoint_clone, messages_clone, last_received_clone);
         });
         
         Ok(Self {
             socket,
+            endpoint,
             incoming_messages,
+            last_received,
             server_addr: SERVER_ADDR.to_string(),
         })
     }
     
+    /// Send a message to the server. Important messages go over the reliable channel
+    /// and are resent until acknowledged; position updates are fire-and-forget.
     pub fn send_message(&self, message: &ClientMessage) -> Result<(), String> {
-        let data = bincode::serialize(message)
+        let data = self.endpoint.lock().unwrap()
+            .send(message, message.is_reliable(), std::time::Instant::now())
             .map_err(|e| format!("Serialization error: {}", e))?;
         
         let socket = self.socket.lock().unwrap();
@@ -86,11 +120,24 @@ impl NetworkClient {
         let mut messages = self.incoming_messages.lock().unwrap();
         messages.pop_front()
     }
+    
+    /// Time since the last datagram arrived from the server
+    pub fn time_since_last_received(&self) -> Duration {
+        self.last_received.lock().unwrap().elapsed()
+    }
+    
+    /// Start over with a fresh reliable channel (before a new handshake)
+    pub fn reset_channel(&self) {
+        *self.endpoint.lock().unwrap() = ReliableEndpoint::new();
+        *self.last_received.lock().unwrap() = Instant::now();
+    }
 }
 
 fn listen_for_messages(
     socket: Arc<Mutex<UdpSocket>>,
+    endpoint: Arc<Mutex<ReliableEndpoint>>,
     messages: Arc<Mutex<VecDeque<ServerMessage>>>,
+    last_received: Arc<Mutex<Instant>>,
 ) {
     let mut buf = [0u8; 65536];
     
@@ -98,12 +145,23 @@ fn listen_for_messages(
         let socket = socket.lock().unwrap();
         match socket.recv_from(&mut buf) {
             Ok((size, _src)) => {
-                if let Ok(msg) = bincode::deserialize::<ServerMessage>(&buf[..size]) {
+                *last_received.lock().unwrap() = Instant::now();
+                
+                // The endpoint releases reliable messages in order, so this may yield 0..n messages
+                let received: Vec<ServerMessage> = endpoint.lock().unwrap().receive_bytes(&buf[..size]);
+                if !received.is_empty() {
                     let mut messages = messages.lock().unwrap();
-                    messages.push_back(msg);
+                    messages.extend(received);
                 }
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                // Nothing to read - use the idle time to resend unacknowledged messages and send acks
+                let packets = endpoint.lock().unwrap().poll(std::time::Instant::now());
+                for packet in packets {
+                    if let Ok(data) = packet.to_bytes() {
+                        let _ = socket.send_to(&data, SERVER_ADDR);
+                    }
+                }
                 drop(socket);
                 std::thread::sleep(std::time::Duration::from_millis(10));
             }
@@ -122,31 +180,121 @@ fn setup_network(
 ) {
     match NetworkClient::new() {
         Ok(client) => {
-            info!("Network client initialized - ready to connect to {}", SERVER_ADDR);
-            connection_state.is_connected = true; // Assume connected when client initializes
+            info!("Network client initialized - connecting to {}", SERVER_ADDR);
+            if let Err(e) = client.send_message(&ClientMessage::Connect { protocol_id: PROTOCOL_ID }) {
+                error!("Failed to send connect request: {}", e);
+            }
             commands.insert_resource(client);
         }
         Err(e) => {
             error!("Failed to initialize network client: {}", e);
-            connection_state.is_connected = false;
         }
     }
+    connection_state.is_connected = false; // Set once the server accepts the handshake
 }
 
-// Connection state is now managed by successful message transmission
-// No need for periodic ping checks that spam fake login requests
+/// Retries the handshake while disconnected, sends keepalives while connected
+/// and detects a server that stopped responding
+fn maintain_connection(
+    time: Res<Time>,
+    network: Option<Res<NetworkClient>>,
+    mut connection_state: ResMut<ServerConnectionState>,
+    mut auth_state: ResMut<AuthState>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(network) = network else { return };
+    let now = time.elapsed_seconds_f64();
+    
+    // The server has already dropped our session
+    if connection_state.session_ended.is_some() && *current_state.get() != GameState::Login {
+        auth_state.logout();
+        next_state.set(GameState::Login);
+    }
+    
+    if !connection_state.is_connected {
+        // Retrying can't help, e.g. with an incompatible client version
+        if connection_state.rejected.is_some() {
+            return;
+        }
+        if now - connection_state.last_check >= connection_state.check_interval {
+            connection_state.last_check = now;
+            network.reset_channel();
+            if let Err(e) = network.send_message(&ClientMessage::Connect { protocol_id: PROTOCOL_ID }) {
+                error!("Failed to send connect request: {}", e);
+            }
+        }
+        return;
+    }
+    
+    if network.time_since_last_received() > CONNECTION_TIMEOUT {
+        error!("Lost connection to server (no response for {:?})", CONNECTION_TIMEOUT);
+        connection_state.is_connected = false;
+        connection_state.last_check = now;
+        
+        // The server has dropped our session - back to the login screen
+        if *current_state.get() != GameState::Login {
+            auth_state.logout();
+            next_state.set(GameState::Login);
+        }
+        return;
+    }
+    
+    if now - connection_state.last_heartbeat >= HEARTBEAT_INTERVAL.as_secs_f64() {
+        connection_state.last_heartbeat = now;
+        if let Err(e) = network.send_message(&ClientMessage::Heartbeat) {
+            warn!("Failed to send heartbeat: {}", e);
+        }
+    }
+}
 
 fn process_incoming_messages(
     network: Option<Res<NetworkClient>>,
     mut auth_events: EventWriter<AuthResponseEvent>,
     mut char_events: EventWriter<CharacterResponseEvent>,
     mut leveling_events: EventWriter<LevelingEvent>,
+    mut remote_player_events: EventWriter<RemotePlayerEvent>,
+    mut correction_events: EventWriter<PositionCorrectionEvent>,
+    mut combat_events: EventWriter<CombatEvent>,
+    mut party_events: EventWriter<PartyEvent>,
+    mut mob_events: EventWriter<MobEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut item_events: EventWriter<ItemEvent>,
+    mut loot_events: EventWriter<LootEvent>,
+    (mut shop_events, mut trade_events): (EventWriter<ShopEvent>, EventWriter<TradeEvent>),  // Grouped: systems take at most 16 parameters
     mut game_time: ResMut<crate::skybox::GameTime>,
+    mut connection_state: ResMut<ServerConnectionState>,
+    (mut received_snapshots, mut balance_assembler): (ResMut<ReceivedSnapshots>, Local<BalanceAssembler>),
 ) {
     let Some(network) = network else { return };
     
     while let Some(message) = network.get_message() {
         match message {
+            ServerMessage::ConnectionChallenge { challenge } => {
+                if let Err(e) = network.send_message(&ClientMessage::ChallengeResponse { challenge }) {
+                    error!("Failed to answer connection challenge: {}", e);
+                }
+            }
+            ServerMessage::ConnectionAccepted => {
+                if !connection_state.is_connected {
+                    info!("✅ Connected to server {}", SERVER_ADDR);
+                    // A new session numbers its ticks from zero again
+                    received_snapshots.0.clear();
+                }
+                connection_state.is_connected = true;
+            }
+            ServerMessage::ConnectionRejected { reason } => {
+                error!("Server rejected connection: {}", reason);
+                connection_state.is_connected = false;
+                connection_state.rejected = Some(reason);
+            }
+            ServerMessage::SessionEnded { reason } => {
+                warn!("Server ended our session: {}", reason);
+                connection_state.session_ended = Some(reason);
+            }
+            ServerMessage::Heartbeat => {
+                // Arrival already refreshed the timeout
+            }
             ServerMessage::AuthResponse(response) => {
                 auth_events.send(AuthResponseEvent(response));
             }
@@ -156,9 +304,10 @@ fn process_incoming_messages(
             ServerMessage::CharacterCreationFailed { reason } => {
                 char_events.send(CharacterResponseEvent::CreationFailed { reason });
             }
-            ServerMessage::CharacterSelected { character_id, character_name, position, character_class, level, experience, max_health, max_mana, max_stamina, specialization } => {
+            ServerMessage::CharacterSelected { character_id, entity_id, character_name, position, character_class, level, experience, max_health, max_mana, max_stamina, specialization } => {
                 char_events.send(CharacterResponseEvent::Selected { 
                     character_id,
+                    entity_id,
                     character_name,
                     position,
                     character_class,
@@ -200,6 +349,134 @@ fn process_incoming_messages(
                     debug!("Ignoring time update - already synced");
                 }
             }
+            ServerMessage::PlayerJoined { id, character, position, weapon } => {
+                remote_player_events.send(RemotePlayerEvent::Joined { id, character, position, weapon });
+            }
+            ServerMessage::EquipmentChanged { id, weapon } => {
+                remote_player_events.send(RemotePlayerEvent::EquipmentChanged { id, weapon });
+            }
+            ServerMessage::PlayerMoved { id, position } => {
+                remote_player_events.send(RemotePlayerEvent::Moved { id, position });
+            }
+            ServerMessage::PlayerLeft { id } => {
+                remote_player_events.send(RemotePlayerEvent::Left { id });
+            }
+            ServerMessage::WorldState { tick, baseline, server_time, entities, removed } => {
+                let base = match baseline {
+                    Some(baseline) => match received_snapshots.0.get(baseline) {
+                        Some(base) => Some(base),
+                        None => {
+                            // Baseline already forgotten - wait for one we can decode
+                            debug!("Dropping snapshot {}: unknown baseline {}", tick, baseline);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                let Some(entities) = snapshot::apply(base, &entities, &removed) else {
+                    warn!("Snapshot {} does not match baseline {:?}", tick, baseline);
+                    continue;
+                };
+
+                received_snapshots.0.insert(tick, entities.clone());
+                if let Err(e) = network.send_message(&ClientMessage::AckSnapshot { tick }) {
+                    warn!("Failed to ack snapshot {}: {}", tick, e);
+                }
+                remote_player_events.send(RemotePlayerEvent::Snapshot { server_time, entities });
+            }
+            ServerMessage::MobSpawned { id, mob, position, health, max_health } => {
+                mob_events.send(MobEvent::Spawned { id, mob, position, health, max_health });
+            }
+            ServerMessage::MobLeft { id } => {
+                mob_events.send(MobEvent::Left { id });
+            }
+            ServerMessage::GroundItemSpawned { id, loot, position } => {
+                loot_events.send(LootEvent::Spawned { id, loot, position });
+            }
+            ServerMessage::GroundItemRemoved { id } => {
+                loot_events.send(LootEvent::Removed { id });
+            }
+            ServerMessage::PositionCorrection { position, sequence } => {
+                warn!("Server rejected our movement - correcting to {:?} (input {})", position, sequence);
+                correction_events.send(PositionCorrectionEvent { position, sequence });
+            }
+            ServerMessage::SkillUsed { caster, skill, target, cooldown } => {
+                combat_events.send(CombatEvent::SkillUsed { caster, skill, target, cooldown });
+            }
+            ServerMessage::SkillFailed { skill, reason } => {
+                combat_events.send(CombatEvent::SkillFailed { skill, reason });
+            }
+            ServerMessage::Damage { source, target, skill, amount, health } => {
+                combat_events.send(CombatEvent::Damage { source, target, skill, amount, health });
+            }
+            ServerMessage::Healed { source, target, skill, amount, health } => {
+                combat_events.send(CombatEvent::Healed { source, target, skill, amount, health });
+            }
+            ServerMessage::EffectApplied { source, target, skill, duration } => {
+                combat_events.send(CombatEvent::EffectApplied { source, target, skill, duration });
+            }
+            ServerMessage::Vitals(vitals) => {
+                combat_events.send(CombatEvent::Vitals(vitals));
+            }
+            ServerMessage::StatusEffects { entity, effects } => {
+                combat_events.send(CombatEvent::StatusEffects { entity, effects });
+            }
+            ServerMessage::Died { experience_lost, respawn_point } => {
+                death_events.send(DeathEvent::Died { experience_lost, respawn_point });
+            }
+            ServerMessage::Respawned { position } => {
+                death_events.send(DeathEvent::Respawned { position });
+            }
+            ServerMessage::PartyInvitation { inviter, name } => {
+                party_events.send(PartyEvent::Invited { inviter, name });
+            }
+            ServerMessage::PartyMembers { members } => {
+                party_events.send(PartyEvent::Members(members));
+            }
+            ServerMessage::PartyMemberVitals { entity, vitals } => {
+                party_events.send(PartyEvent::MemberVitals { entity, vitals });
+            }
+            ServerMessage::PartyFailed { reason } => {
+                party_events.send(PartyEvent::Failed { reason });
+            }
+            ServerMessage::Inventory { slots, equipment, gold } => {
+                item_events.send(ItemEvent::Updated { slots, equipment, gold });
+            }
+            ServerMessage::ItemFailed { reason } => {
+                item_events.send(ItemEvent::Failed { reason });
+            }
+            ServerMessage::ShopStock { shop, stock } => {
+                shop_events.send(ShopEvent::Stock { shop, stock });
+            }
+            ServerMessage::ShopFailed { reason } => {
+                shop_events.send(ShopEvent::Failed { reason });
+            }
+            ServerMessage::TradeRequested { requester, name } => {
+                trade_events.send(TradeEvent::Requested { requester, name });
+            }
+            ServerMessage::TradeOpened { partner, name } => {
+                trade_events.send(TradeEvent::Opened { partner, name });
+            }
+            ServerMessage::TradeUpdated { ours, theirs } => {
+                trade_events.send(TradeEvent::Updated { ours, theirs });
+            }
+            ServerMessage::TradeCompleted => {
+                trade_events.send(TradeEvent::Completed);
+            }
+            ServerMessage::TradeCancelled { reason } => {
+                trade_events.send(TradeEvent::Cancelled { reason });
+            }
+            ServerMessage::TradeFailed { reason } => {
+                trade_events.send(TradeEvent::Failed { reason });
+            }
+            ServerMessage::BalanceChunk { index, total, data } => {
+                // Skill info and stat growth are read from the installed tables
+                match balance_assembler.add(index, total, &data).map(|tables| tables.and_then(shared::balance::install)) {
+                    Some(Ok(())) => info!("Balance tables updated by the server"),
+                    Some(Err(e)) => error!("Server sent unusable balance tables: {}", e),
+                    None => {}
+                }
+            }
             _ => {
                 // Handle other messages (gameplay, etc.)
             }
@@ -217,6 +494,7 @@ pub enum CharacterResponseEvent {
     CreationFailed { reason: String },
     Selected { 
         character_id: i64,
+        entity_id: u64,
         character_name: String,
         character_class: shared::CharacterClass,
         position: Vec3,
@@ -238,6 +516,106 @@ pub enum LevelingEvent {
     LevelUp { new_level: i32, new_max_health: f32, new_max_mana: f32, new_max_stamina: f32 },
 }
 
+/// Presence and movement of other players, consumed by the remote player plugin
+#[derive(Event)]
+pub enum RemotePlayerEvent {
+    Joined { id: u64, character: shared::CharacterData, position: Vec3, weapon: Option<shared::items::ItemId> },
+    Moved { id: u64, position: Vec3 },
+    EquipmentChanged { id: u64, weapon: Option<shared::items::ItemId> },
+    Left { id: u64 },
+    /// Full state of every nearby player, rebuilt from a delta snapshot
+    Snapshot { server_time: f64, entities: SnapshotEntities },
+}
+
+/// Monsters coming into and going out of view, consumed by the mob plugin.
+/// Their movement arrives with `RemotePlayerEvent::Snapshot`.
+#[derive(Event)]
+pub enum MobEvent {
+    Spawned { id: u64, mob: shared::MobType, position: Vec3, health: f32, max_health: f32 },
+    Left { id: u64 },
+}
+
+/// Gold and items lying on the ground near us
+#[derive(Event)]
+pub enum LootEvent {
+    Spawned { id: u64, loot: shared::items::Loot, position: Vec3 },
+    Removed { id: u64 },
+}
+
+/// Decoded world snapshots, kept as baselines for the server's deltas
+#[derive(Resource, Default)]
+pub struct ReceivedSnapshots(SnapshotHistory);
+
+fn clear_snapshots(mut received_snapshots: ResMut<ReceivedSnapshots>) {
+    received_snapshots.0.clear();
+}
+
+/// Outcome of skill use by us or by entities near us, consumed by the combat plugin
+#[derive(Event)]
+pub enum CombatEvent {
+    SkillUsed { caster: u64, skill: shared::SkillId, target: Option<u64>, cooldown: f32 },
+    SkillFailed { skill: shared::SkillId, reason: String },
+    Damage { source: u64, target: u64, skill: Option<shared::SkillId>, amount: f32, health: f32 },
+    Healed { source: u64, target: u64, skill: shared::SkillId, amount: f32, health: f32 },
+    EffectApplied { source: u64, target: u64, skill: shared::SkillId, duration: f32 },
+    /// Our own health, mana and stamina after a change
+    Vitals(shared::Vitals),
+    /// Every status effect on `entity` after a change
+    StatusEffects { entity: u64, effects: Vec<shared::StatusEffectState> },
+}
+
+/// Party invitations and membership, consumed by the party plugin
+#[derive(Event)]
+pub enum PartyEvent {
+    Invited { inviter: u64, name: String },
+    /// Everyone in our party including us; empty when we are not in one
+    Members(Vec<shared::PartyMember>),
+    MemberVitals { entity: u64, vitals: shared::Vitals },
+    Failed { reason: String },
+}
+
+/// Our character dying and getting up again, consumed by the death screen
+#[derive(Event)]
+pub enum DeathEvent {
+    Died { experience_lost: i64, respawn_point: String },
+    /// Respawned or resurrected; the position also arrives as a correction
+    Respawned { position: Vec3 },
+}
+
+/// Our inventory, consumed by the inventory window
+#[derive(Event)]
+pub enum ItemEvent {
+    /// The whole inventory after a change
+    Updated { slots: Vec<Option<shared::items::ItemStack>>, equipment: shared::items::Equipment, gold: u64 },
+    Failed { reason: String },
+}
+
+/// Answers of the merchant we are trading with
+#[derive(Event)]
+pub enum ShopEvent {
+    /// What is left of each listing (None = unlimited)
+    Stock { shop: shared::items::ShopId, stock: Vec<(shared::items::ItemId, Option<u32>)> },
+    Failed { reason: String },
+}
+
+/// Trading with another player
+#[derive(Event)]
+pub enum TradeEvent {
+    Requested { requester: u64, name: String },
+    Opened { partner: u64, name: String },
+    Updated { ours: shared::items::TradeOffer, theirs: shared::items::TradeOffer },
+    Completed,
+    Cancelled { reason: String },
+    Failed { reason: String },  // The trade goes on
+}
+
+/// Server-authoritative position of the local player as of input `sequence`
+#[derive(Event)]
+pub struct PositionCorrectionEvent {
+    pub position: Vec3,
+    pub sequence: u32,
+}
+
 fn handle_auth_responses(
     mut auth_events: EventReader<AuthResponseEvent>,
     mut auth_state: ResMut<AuthState>,
@@ -278,7 +656,7 @@ fn handle_leveling_events(
     for event in leveling_events.read() {
         match event {
             LevelingEvent::ExperienceGained { amount, new_total, xp_needed } => {
-                info!("+{} XP! ({}/{})", amount, new_total, xp_needed);
+                info!("{:+} XP! ({}/{})", amount, new_total, xp_needed);
                 player_stats.experience = *new_total;
                 player_stats.xp_needed = *xp_needed;
             }
@@ -341,6 +719,7 @@ fn handle_character_responses(
         match event {
             CharacterResponseEvent::Selected { 
                 character_id,
+                entity_id,
                 character_name,
                 character_class,
                 position,
@@ -380,6 +759,7 @@ fn handle_character_responses(
                 // Store class and specialization directly from server message
                 auth_state.class = Some(*character_class);
                 auth_state.specialization = *specialization;
+                auth_state.entity_id = Some(*entity_id);
                 
                 info!("  Class: {}", character_class.as_str());
                 if let Some(spec) = specialization {
@@ -408,7 +788,7 @@ fn handle_character_responses(
     }
 }
 
-#[allow(dead_code)]
+/// Marks an entity as another player's character, keyed by its server ID
 #[derive(Component)]
 pub struct OtherPlayer {
     pub id: u64,
@@ -3,6 +3,7 @@ use crate::GameState;
 use crate::player::GameWorld;
 use crate::collision::{Collider, ColliderShape, CollisionType, CollisionLayer, CollidingWith};
 use crate::GameFont;
+use shared::items::ShopId;
 
 pub struct NpcPlugin;
 
@@ -21,7 +22,7 @@ impl Plugin for NpcPlugin {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NpcType {
     SpecializationTrainer,
-    Merchant,
+    Merchant(ShopId),
     QuestGiver,
 }
 
@@ -103,7 +104,7 @@ fn update_npc_nameplate_ui_positions(
 }
 
 
-// NPC spawn data: (Position, Name, Type)
+// NPC spawn data: (Position, Name, Type). Merchants come from the balance tables.
 const NPC_SPAWN_POSITIONS: &[(Vec3, &str, NpcType)] = &[
     (Vec3::new(5.0, 1.0, 5.0), "Meister der Künste", NpcType::SpecializationTrainer),
 ];
@@ -141,7 +142,16 @@ fn spawn_npcs(
         return;
     }
 
-    for (position, name, npc_type) in NPC_SPAWN_POSITIONS {
+    let tables = shared::balance::current();
+    let mut shops: Vec<_> = tables.shops.iter().collect();
+    shops.sort_by_key(|(id, _)| **id);
+    let merchants = shops.into_iter()
+        .map(|(&id, shop)| (shop.position, shop.name.as_str(), NpcType::Merchant(id)));
+    let npcs = NPC_SPAWN_POSITIONS.iter()
+        .map(|&(position, name, npc_type)| (position, name, npc_type))
+        .chain(merchants);
+
+    for (position, name, npc_type) in npcs {
         info!("Spawning NPC '{}' at {:?}", name, position);
         
         // Spawn NPC model (golden capsule)
@@ -153,12 +163,12 @@ fn spawn_npcs(
                     emissive: Color::BLACK.into(),
                     ..default()
                 }),
-                transform: Transform::from_translation(*position),
+                transform: Transform::from_translation(position),
                 ..default()
             },
             Npc {
                 name: name.to_string(),
-                npc_type: *npc_type,
+                npc_type,
             },
             // RAPIER Collider for physics raycasting
             bevy_rapier3d::prelude::RigidBody::Fixed, // NPCs don't move
@@ -179,7 +189,7 @@ fn spawn_npcs(
         // Spawn invisible 3D marker for nameplate (1.2 units above NPC, same as player)
         commands.spawn((
             SpatialBundle {
-                transform: Transform::from_translation(*position + Vec3::Y * 1.2),
+                transform: Transform::from_translation(position + Vec3::Y * 1.2),
                 ..default()
             },
             NpcNameplate { npc_entity },
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use shared::{ClientMessage, PartyMember, Vitals};
+use std::collections::HashMap;
+use crate::auth_state::AuthState;
+use crate::combat::CombatTarget;
+use crate::networking::{NetworkClient, PartyEvent};
+use crate::ui::UILayerStack;
+use crate::GameFont;
+use crate::GameState;
+
+pub struct PartyPlugin;
+
+impl Plugin for PartyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PartyState>()
+            .add_systems(OnEnter(GameState::InGame), setup_party_frame)
+            .add_systems(Update, (
+                handle_party_keys,
+                handle_party_events,
+                update_party_frame,
+            ).run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), cleanup_party);
+    }
+}
+
+const INVITE_KEY: KeyCode = KeyCode::KeyP;   // Invite the current target
+const ACCEPT_KEY: KeyCode = KeyCode::KeyJ;   // Accept the latest invitation
+const LEAVE_KEY: KeyCode = KeyCode::KeyL;
+
+/// Our party as last reported by the server
+#[derive(Resource, Default)]
+pub struct PartyState {
+    pub members: Vec<PartyMember>,       // Including us, leader first; empty = no party
+    pub vitals: HashMap<u64, Vitals>,    // Members near enough for the server to report
+    pub invitation: Option<(u64, String)>,  // Inviter entity and name
+}
+
+/// Column of party member frames on the left edge of the screen
+#[derive(Component)]
+struct PartyFrame;
+
+fn handle_party_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    target: Res<CombatTarget>,
+    ui_stack: Res<UILayerStack>,
+    mut party: ResMut<PartyState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    if ui_stack.top_layer().is_some_and(|layer| layer.blocks_input) {
+        return;
+    }
+    let Some(network) = network else { return };
+
+    let message = if keyboard.just_pressed(INVITE_KEY) {
+        let Some(target) = target.0 else {
+            info!("Select a player with Tab to invite them");
+            return;
+        };
+        ClientMessage::PartyInvite { target }
+    } else if keyboard.just_pressed(ACCEPT_KEY) {
+        let Some((inviter, _)) = party.invitation.take() else { return };
+        ClientMessage::PartyAccept { inviter }
+    } else if keyboard.just_pressed(LEAVE_KEY) && !party.members.is_empty() {
+        ClientMessage::PartyLeave
+    } else {
+        return;
+    };
+
+    if let Err(e) = network.send_message(&message) {
+        error!("Failed to send party request: {}", e);
+    }
+}
+
+fn handle_party_events(
+    mut party_events: EventReader<PartyEvent>,
+    mut party: ResMut<PartyState>,
+) {
+    for event in party_events.read() {
+        match event {
+            PartyEvent::Invited { inviter, name } => {
+                info!("{} invites you to a party - press J to join", name);
+                party.invitation = Some((*inviter, name.clone()));
+            }
+            PartyEvent::Members(members) => {
+                party.vitals.retain(|entity, _| members.iter().any(|m| m.entity == *entity));
+                party.members = members.clone();
+            }
+            PartyEvent::MemberVitals { entity, vitals } => {
+                party.vitals.insert(*entity, *vitals);
+            }
+            PartyEvent::Failed { reason } => {
+                warn!("Party: {}", reason);
+            }
+        }
+    }
+}
+
+fn setup_party_frame(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(120.0),
+                left: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            focus_policy: bevy::ui::FocusPolicy::Pass,
+            ..default()
+        },
+        PartyFrame,
+    ));
+}
+
+/// Rebuild the frames of the other members whenever the party or their vitals change
+fn update_party_frame(
+    mut commands: Commands,
+    party: Res<PartyState>,
+    auth_state: Res<AuthState>,
+    font: Res<GameFont>,
+    frame_query: Query<Entity, With<PartyFrame>>,
+) {
+    if !party.is_changed() {
+        return;
+    }
+    let Ok(frame) = frame_query.get_single() else { return };
+
+    commands.entity(frame).despawn_descendants();
+    commands.entity(frame).with_children(|parent| {
+        for member in party.members.iter().filter(|m| Some(m.entity) != auth_state.entity_id) {
+            create_member_frame(parent, font.0.clone(), member, party.vitals.get(&member.entity));
+        }
+    });
+}
+
+fn create_member_frame(parent: &mut ChildBuilder, font: Handle<Font>, member: &PartyMember, vitals: Option<&Vitals>) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Px(150.0),
+            padding: UiRect::all(Val::Px(4.0)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        background_color: Color::srgba(0.1, 0.1, 0.12, 0.85).into(),
+        focus_policy: bevy::ui::FocusPolicy::Pass,
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            member.name.clone(),
+            TextStyle {
+                font,
+                font_size: 12.0,
+                color: Color::WHITE,
+            },
+        ));
+
+        // Grey bars while the member is too far away to be reported
+        let (health, mana) = match vitals {
+            Some(v) => (fraction(v.health, v.max_health), fraction(v.mana, v.max_mana)),
+            None => (0.0, 0.0),
+        };
+        create_member_bar(parent, health, Color::srgb(0.8, 0.15, 0.15));
+        create_member_bar(parent, mana, Color::srgb(0.2, 0.4, 0.9));
+    });
+}
+
+fn create_member_bar(parent: &mut ChildBuilder, fraction: f32, color: Color) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Px(6.0),
+            ..default()
+        },
+        background_color: Color::srgb(0.25, 0.25, 0.25).into(),
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(fraction * 100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: color.into(),
+            ..default()
+        });
+    });
+}
+
+fn fraction(value: f32, max: f32) -> f32 {
+    if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 }
+}
+
+fn cleanup_party(
+    mut commands: Commands,
+    mut party: ResMut<PartyState>,
+    frames: Query<Entity, With<PartyFrame>>,
+) {
+    *party = PartyState::default();
+    for entity in frames.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -3,20 +3,26 @@ use crate::GameState;
 use crate::camera::OrbitCamera;
 use crate::auth_state::SpawnPosition;
 use crate::networking::NetworkClient;
+use crate::prediction::{PredictionHistory, reset_prediction, record_player_input, reconcile_position_corrections};
 use crate::collision::{Collider, ColliderShape, CollisionType, CollisionLayer, CollidingWith, CollisionPushback};
 // Rapier is used via full path to avoid namespace pollution
 use crate::GameFont;
-use shared::ClientMessage;
+use shared::items::ItemId;
+use shared::{ClientMessage, PLAYER_MOVE_SPEED};
 use std::time::Duration;
 
 pub struct PlayerPlugin;
 
+/// Character model shared by the local player and remote players
+pub const PLAYER_MODEL_PATH: &str = "models/animation_library/Godot/AnimationLibrary_Godot_Standard.glb";
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app
             .init_resource::<PositionUpdateTimer>()
             .init_resource::<PlayerAnimations>()
-            .add_systems(OnEnter(GameState::InGame), (setup_player, setup_nameplate_ui))
+            .init_resource::<PredictionHistory>()
+            .add_systems(OnEnter(GameState::InGame), (setup_player, setup_nameplate_ui, reset_prediction))
             .add_systems(OnEnter(GameState::CharacterSelection), cleanup_player)
             .add_systems(OnEnter(GameState::Login), cleanup_player)
             .add_systems(OnExit(GameState::InGame), (send_disconnect, cleanup_nameplate_ui))
@@ -25,10 +31,15 @@ impl Plugin for PlayerPlugin {
                 debug_scene_hierarchy,
                 setup_animation_player,
                 update_player_animation,
-                send_position_updates,
+                reconcile_position_corrections.before(player_movement),
+                send_position_updates.after(reconcile_position_corrections),
+                // Recorded after sending: the update carries inputs that physics already applied
+                record_player_input.after(player_movement).after(send_position_updates),
                 update_nameplate_marker_position,
                 update_nameplate_ui_position,
                 update_nameplate_ui_text,
+                update_local_weapon,
+                attach_weapon_meshes.after(update_local_weapon),
             ).run_if(in_state(GameState::InGame)));
     }
 }
@@ -36,11 +47,28 @@ impl Plugin for PlayerPlugin {
 #[derive(Component)]
 pub struct Player {
     pub speed: f32,
+    pub sprinting: bool,  // Shift held while moving with stamina left
 }
 
 #[derive(Component)]
 pub struct GameWorld;
 
+/// Weapon a character (local or remote) holds, shown as a mesh in its right hand
+#[derive(Component, Default, PartialEq)]
+pub struct EquippedWeapon(pub Option<ItemId>);
+
+/// Weapon whose mesh is attached to a character right now
+#[derive(Component)]
+struct ShownWeapon(Option<ItemId>);
+
+/// Root of a weapon mesh, child of the hand bone
+#[derive(Component)]
+struct WeaponMesh;
+
+/// Right hand bone of the character model. Exporters name it differently, the first
+/// one found in the loaded scene is used.
+const HAND_BONE_NAMES: [&str; 4] = ["RightHand", "hand.r", "Hand.R", "mixamorig:RightHand"];
+
 /// 3D marker that follows player for nameplate positioning
 #[derive(Component)]
 struct PlayerNameplate;
@@ -61,12 +89,12 @@ impl Default for PositionUpdateTimer {
 #[derive(Component)]
 struct LastSentPosition(Vec3);
 
-/// Resource holding animation clip handles
+/// Resource holding animation clip handles (shared with remote players)
 #[derive(Resource)]
-struct PlayerAnimations {
-    graph: Handle<AnimationGraph>,
-    idle_index: AnimationNodeIndex,
-    walk_index: AnimationNodeIndex,
+pub(crate) struct PlayerAnimations {
+    pub(crate) graph: Handle<AnimationGraph>,
+    pub(crate) idle_index: AnimationNodeIndex,
+    pub(crate) walk_index: AnimationNodeIndex,
 }
 
 impl Default for PlayerAnimations {
@@ -89,6 +117,10 @@ struct PlayerAnimationState {
 #[derive(Component)]
 struct PlayerModel;
 
+/// Marker for the AnimationPlayer inside the local player's model
+#[derive(Component)]
+pub(crate) struct LocalAnimationPlayer;
+
 fn setup_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -112,7 +144,7 @@ fn setup_player(
         info!("Spawning player at position: {:?}", spawn_pos);
         
         // Load animation library
-        let model_path = "models/animation_library/Godot/AnimationLibrary_Godot_Standard.glb";
+        let model_path = PLAYER_MODEL_PATH;
         
         // Load animation clips
         let idle_clip: Handle<AnimationClip> = asset_server.load(GltfAssetLabel::Animation(9).from_asset(model_path));
@@ -140,7 +172,7 @@ fn setup_player(
                 transform: Transform::from_translation(spawn_pos),
                 ..default()
             },
-            Player { speed: 5.0 },
+            Player { speed: PLAYER_MOVE_SPEED, sprinting: false },
             // RAPIER PHYSICS - Gravity & Collision!
             bevy_rapier3d::prelude::RigidBody::Dynamic,  // Dynamic = affected by gravity
             bevy_rapier3d::prelude::Velocity::default(),  // Initial velocity (0,0,0)
@@ -167,6 +199,7 @@ fn setup_player(
             CollidingWith::default(),
             LastSentPosition(spawn_pos),
             PlayerAnimationState { is_moving: false },
+            EquippedWeapon::default(),
             GameWorld,
         )).id();
         
@@ -261,11 +294,13 @@ fn cleanup_player(
 fn player_movement(
     keyboard: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
-    mut player_query: Query<(&mut bevy_rapier3d::prelude::Velocity, &mut Transform, &Player)>,
+    mut player_query: Query<(&mut bevy_rapier3d::prelude::Velocity, &mut Transform, &mut Player)>,
+    mut player_stats: ResMut<crate::ui::PlayerStats>,
     camera_query: Query<&OrbitCamera>,
     free_cam_state: Res<crate::camera::FreeCamState>,
     pause_state: Res<crate::ui::PauseMenuState>,
     settings_state: Res<crate::ui::SettingsMenuState>,
+    death_state: Res<crate::ui::DeathState>,
 ) {
     // Don't move player if pause menu or settings menu is open
     if pause_state.visible || settings_state.visible {
@@ -283,7 +318,9 @@ fn player_movement(
         .map(|cam| cam.yaw)
         .unwrap_or(0.0);
 
-    for (mut velocity, mut transform, player) in player_query.iter_mut() {
+    let sprint = shared::balance::current().sprint;
+
+    for (mut velocity, mut transform, mut player) in player_query.iter_mut() {
         let mut input_direction = Vec3::ZERO;
 
         // Get input in local camera space
@@ -300,6 +337,11 @@ fn player_movement(
             input_direction.x += 1.0; // Right
         }
 
+        // The dead stay where they fell
+        if death_state.dead {
+            input_direction = Vec3::ZERO;
+        }
+
         if input_direction.length() > 0.0 {
             input_direction = input_direction.normalize();
             
@@ -307,8 +349,16 @@ fn player_movement(
             let rotation = Quat::from_rotation_y(camera_yaw);
             let world_direction = rotation * input_direction;
             
+            // Sprinting drains stamina; the server sends the authoritative value
+            player.sprinting = keyboard.pressed(KeyCode::ShiftLeft) && player_stats.stamina > 0.0;
+            let speed = if player.sprinting {
+                player_stats.stamina = (player_stats.stamina - sprint.stamina_per_second * time.delta_seconds()).max(0.0);
+                player.speed * sprint.speed_multiplier
+            } else {
+                player.speed
+            };
+
             // Set horizontal velocity (keep Y velocity for gravity!)
-            let speed = player.speed;
             velocity.linvel.x = world_direction.x * speed;
             velocity.linvel.z = world_direction.z * speed;
             
@@ -322,6 +372,7 @@ fn player_movement(
             transform.rotation = transform.rotation.slerp(target_rotation, rotation_speed * time.delta_seconds());
         } else {
             // No input - stop horizontal movement (but keep falling!)
+            player.sprinting = false;
             velocity.linvel.x = 0.0;
             velocity.linvel.z = 0.0;
         }
@@ -354,7 +405,7 @@ fn setup_animation_player(
             player.play(player_anims.idle_index).repeat();
             
             // Add AnimationTransitions for smooth transitions
-            commands.entity(anim_entity).insert(AnimationTransitions::new());
+            commands.entity(anim_entity).insert((AnimationTransitions::new(), LocalAnimationPlayer));
             
             info!("🎉 Animation system initialized successfully!");
             break;
@@ -443,7 +494,7 @@ fn log_hierarchy(
 /// Update player animation based on movement
 fn update_player_animation(
     mut player_query: Query<(&bevy_rapier3d::prelude::Velocity, &mut PlayerAnimationState), With<Player>>,
-    mut animation_players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+    mut animation_players: Query<(&mut AnimationPlayer, &mut AnimationTransitions), With<LocalAnimationPlayer>>,
     player_anims: Res<PlayerAnimations>,
 ) {
     let Ok((velocity, mut anim_state)) = player_query.get_single_mut() else { return };
@@ -477,7 +528,8 @@ fn update_player_animation(
 fn send_position_updates(
     time: Res<Time>,
     mut timer: ResMut<PositionUpdateTimer>,
-    mut player_query: Query<(&Transform, &mut LastSentPosition), With<Player>>,
+    mut player_query: Query<(&Transform, &Player, &mut LastSentPosition)>,
+    history: Res<PredictionHistory>,
     network: Option<Res<NetworkClient>>,
 ) {
     let Some(network) = network else { return };
@@ -486,14 +538,17 @@ fn send_position_updates(
     timer.0.tick(time.delta());
     
     if timer.0.just_finished() {
-        for (transform, mut last_sent) in player_query.iter_mut() {
+        for (transform, player, mut last_sent) in player_query.iter_mut() {
             let current_pos = transform.translation;
             
             // Only send if position changed significantly (> 0.01 units)
             if (current_pos - last_sent.0).length() > 0.01 {
                 // Send ABSOLUTE position to server (not delta!)
                 if let Err(e) = network.send_message(&ClientMessage::UpdatePosition { 
-                    position: current_pos 
+                    position: current_pos,
+                    yaw: transform.rotation.to_euler(EulerRot::YXZ).0,
+                    sequence: history.latest_sequence(),
+                    sprinting: player.sprinting,
                 }) {
                     error!("Failed to send position update: {}", e);
                 } else {
@@ -516,6 +571,98 @@ fn send_disconnect(network: Option<Res<NetworkClient>>) {
     }
 }
 
+// ============================================================================
+// WEAPONS
+// ============================================================================
+
+/// Our weapon follows the equipment the server reports
+fn update_local_weapon(
+    inventory: Res<crate::inventory::InventoryState>,
+    mut player_query: Query<&mut EquippedWeapon, With<Player>>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+    let weapon = inventory.equipment.get(shared::items::EquipmentSlot::Weapon);
+    for mut equipped in player_query.iter_mut() {
+        equipped.set_if_neq(EquippedWeapon(weapon));
+    }
+}
+
+/// Put the equipped weapon's mesh into the character's hand. Characters whose model has
+/// not loaded yet are tried again next frame.
+fn attach_weapon_meshes(
+    mut commands: Commands,
+    characters: Query<(Entity, &EquippedWeapon, Option<&ShownWeapon>)>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+    weapon_query: Query<Entity, With<WeaponMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (character, equipped, shown) in characters.iter() {
+        if shown.map_or(equipped.0.is_none(), |shown| shown.0 == equipped.0) {
+            continue;
+        }
+        let Some(hand) = children_query.iter_descendants(character).find(|&entity| {
+            name_query.get(entity).is_ok_and(|name| HAND_BONE_NAMES.contains(&name.as_str()))
+        }) else {
+            continue;
+        };
+
+        for old in children_query.iter_descendants(hand).filter(|&entity| weapon_query.contains(entity)) {
+            commands.entity(old).despawn_recursive();
+        }
+        if let Some(item) = equipped.0 {
+            let weapon = spawn_weapon_mesh(&mut commands, &mut meshes, &mut materials, item);
+            commands.entity(hand).add_child(weapon);
+        }
+        commands.entity(character).insert(ShownWeapon(equipped.0));
+    }
+}
+
+/// Simple sword built from boxes, pointing along the hand bone; the guard shows the
+/// item's rarity. Lengths in meters.
+fn spawn_weapon_mesh(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    item: ItemId,
+) -> Entity {
+    let rarity_color = item.info().map_or(Color::srgb(0.6, 0.6, 0.6), |info| crate::inventory::rarity_color(info.rarity));
+    let steel = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.75, 0.75, 0.8),
+        metallic: 0.9,
+        perceptual_roughness: 0.3,
+        ..default()
+    });
+    let grip = materials.add(Color::srgb(0.3, 0.18, 0.1));
+    let guard = materials.add(rarity_color);
+
+    commands.spawn((SpatialBundle::default(), WeaponMesh))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Cuboid::new(0.03, 0.15, 0.03)),
+                material: grip,
+                transform: Transform::from_xyz(0.0, 0.05, 0.0),
+                ..default()
+            });
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Cuboid::new(0.18, 0.03, 0.04)),
+                material: guard,
+                transform: Transform::from_xyz(0.0, 0.14, 0.0),
+                ..default()
+            });
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Cuboid::new(0.05, 0.7, 0.01)),
+                material: steel,
+                transform: Transform::from_xyz(0.0, 0.5, 0.0),
+                ..default()
+            });
+        })
+        .id()
+}
+
 // ============================================================================
 // NAMEPLATE SYSTEMS
 // ============================================================================
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use crate::networking::PositionCorrectionEvent;
+use crate::player::Player;
+
+/// Inputs are dropped after this many frames even if the server never answered
+const MAX_HISTORY: usize = 512;
+
+/// Corrections closer than this to our prediction are not worth a visible snap
+const RECONCILE_EPSILON: f32 = 0.01;
+
+/// One frame of local movement input
+struct PredictedInput {
+    sequence: u32,
+    /// Horizontal velocity the input requested (world space)
+    velocity: Vec3,
+    /// Frame time the input was applied for
+    dt: f32,
+    /// Where we expected the player to end up after this input
+    predicted: Vec3,
+}
+
+/// Sequence-numbered movement inputs that the server has not confirmed yet.
+///
+/// The local player moves immediately (prediction). Every position update carries the
+/// sequence of the newest input it includes; when the server corrects a position as of
+/// some sequence, the inputs after it are replayed on top of the corrected position.
+#[derive(Resource, Default)]
+pub struct PredictionHistory {
+    last_sequence: u32,
+    inputs: VecDeque<PredictedInput>,
+}
+
+impl PredictionHistory {
+    /// Store this frame's input and return its sequence number
+    fn record(&mut self, velocity: Vec3, dt: f32, predicted: Vec3) -> u32 {
+        self.last_sequence += 1;
+        self.inputs.push_back(PredictedInput {
+            sequence: self.last_sequence,
+            velocity,
+            dt,
+            predicted,
+        });
+        if self.inputs.len() > MAX_HISTORY {
+            self.inputs.pop_front();
+        }
+        self.last_sequence
+    }
+
+    /// Sequence of the newest input that has already been simulated (0 = none yet)
+    pub fn latest_sequence(&self) -> u32 {
+        self.last_sequence
+    }
+
+    /// Predicted position after input `sequence`, if still buffered
+    fn predicted_at(&self, sequence: u32) -> Option<Vec3> {
+        self.inputs.iter().find(|input| input.sequence == sequence).map(|input| input.predicted)
+    }
+
+    /// Drop inputs up to `sequence` and replay the rest on top of `authoritative`
+    fn reconcile(&mut self, sequence: u32, authoritative: Vec3) -> Vec3 {
+        while self.inputs.front().is_some_and(|input| input.sequence <= sequence) {
+            self.inputs.pop_front();
+        }
+
+        let mut position = authoritative;
+        for input in self.inputs.iter_mut() {
+            position += input.velocity * input.dt;
+            input.predicted = position;
+        }
+        position
+    }
+}
+
+pub fn reset_prediction(mut history: ResMut<PredictionHistory>) {
+    *history = PredictionHistory::default();
+}
+
+/// Record the movement input applied this frame (runs after `player_movement`)
+pub fn record_player_input(
+    time: Res<Time>,
+    mut history: ResMut<PredictionHistory>,
+    player_query: Query<(&Transform, &bevy_rapier3d::prelude::Velocity), With<Player>>,
+) {
+    let Ok((transform, velocity)) = player_query.get_single() else { return };
+
+    // Vertical motion is left to physics and not validated by the server
+    let horizontal = Vec3::new(velocity.linvel.x, 0.0, velocity.linvel.z);
+    let dt = time.delta_seconds();
+    history.record(horizontal, dt, transform.translation + horizontal * dt);
+}
+
+/// Apply server corrections and replay the inputs the server has not seen yet
+pub fn reconcile_position_corrections(
+    mut corrections: EventReader<PositionCorrectionEvent>,
+    mut history: ResMut<PredictionHistory>,
+    mut player_query: Query<(&mut Transform, &mut bevy_rapier3d::prelude::Velocity), With<Player>>,
+) {
+    let Ok((mut transform, mut velocity)) = player_query.get_single_mut() else {
+        corrections.clear();
+        return;
+    };
+
+    for PositionCorrectionEvent { position, sequence } in corrections.read() {
+        if let Some(predicted) = history.predicted_at(*sequence) {
+            let error = predicted.distance(*position);
+            if error < RECONCILE_EPSILON {
+                continue;
+            }
+            debug!("Prediction off by {:.2} at input {} - replaying", error, sequence);
+        }
+
+        transform.translation = history.reconcile(*sequence, *position);
+        velocity.linvel.y = 0.0;
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::GameState;
+use crate::GameFont;
+use crate::interpolation::{InterpolatedMotion, InterpolationSettings, ServerClock, SnapshotBuffer};
+use crate::networking::{OtherPlayer, RemotePlayerEvent};
+use crate::player::{EquippedWeapon, GameWorld, LocalAnimationPlayer, PlayerAnimations, PLAYER_MODEL_PATH};
+
+pub struct RemotePlayerPlugin;
+
+impl Plugin for RemotePlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RemotePlayers>()
+            // Not gated on InGame: PlayerJoined arrives in the same burst as CharacterSelected,
+            // before the state transition has been applied
+            .add_systems(Update, handle_remote_player_events)
+            .add_systems(OnExit(GameState::InGame), cleanup_remote_players)
+            .add_systems(Update, (
+                setup_remote_animation,
+                update_remote_animation,
+                setup_remote_nameplate_ui,
+                update_remote_nameplate_ui_positions.after(setup_remote_nameplate_ui),
+                cleanup_orphaned_nameplates,
+            ).run_if(in_state(GameState::InGame)));
+    }
+}
+
+/// Cross-fade time between idle and walk for remote players
+const ANIMATION_BLEND: Duration = Duration::from_millis(250);
+
+/// Maps server player IDs to the entities representing them
+#[derive(Resource, Default)]
+pub struct RemotePlayers {
+    entities: HashMap<u64, Entity>,
+}
+
+/// Display data for a remote player's nameplate
+#[derive(Component)]
+struct RemotePlayerInfo {
+    name: String,
+    level: i32,
+}
+
+/// AnimationPlayer inside a remote player's model, driven by its interpolated motion
+#[derive(Component)]
+struct RemoteAnimation {
+    owner: Entity,
+    is_moving: bool,
+}
+
+/// 2D UI overlay that displays a remote player's name and level
+#[derive(Component)]
+struct RemoteNameplateUI {
+    player_entity: Entity,
+}
+
+/// Marker to track if a remote player has a nameplate
+#[derive(Component)]
+struct HasRemoteNameplate;
+
+fn handle_remote_player_events(
+    mut commands: Commands,
+    mut events: EventReader<RemotePlayerEvent>,
+    mut remote_players: ResMut<RemotePlayers>,
+    mut player_query: Query<(&mut SnapshotBuffer, &mut RemotePlayerInfo, &mut EquippedWeapon)>,
+    mut clock: ResMut<ServerClock>,
+    settings: Res<InterpolationSettings>,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+) {
+    let local_now = time.elapsed_seconds_f64();
+
+    for event in events.read() {
+        // Events without a server timestamp are placed at the current render time
+        let render_now = clock.now(local_now).map(|t| t - settings.delay).unwrap_or(0.0);
+
+        match event {
+            RemotePlayerEvent::Joined { id, character, position, weapon } => {
+                if let Some(&entity) = remote_players.entities.get(id) {
+                    // Already known - just refresh it
+                    if let Ok((_, mut info, mut equipped)) = player_query.get_mut(entity) {
+                        info.level = character.level;
+                        equipped.set_if_neq(EquippedWeapon(*weapon));
+                    }
+                    continue;
+                }
+
+                info!("Player {} (ID: {}) entered the world at {:?}", character.name, id, position);
+                let entity = spawn_remote_player(&mut commands, &asset_server, *id, character, *position, *weapon, render_now);
+                remote_players.entities.insert(*id, entity);
+            }
+            RemotePlayerEvent::EquipmentChanged { id, weapon } => {
+                let Some(&entity) = remote_players.entities.get(id) else { continue };
+                if let Ok((_, _, mut equipped)) = player_query.get_mut(entity) {
+                    equipped.set_if_neq(EquippedWeapon(*weapon));
+                }
+            }
+            RemotePlayerEvent::Moved { id, position } => {
+                if let Some(&entity) = remote_players.entities.get(id) {
+                    if let Ok((mut buffer, _, _)) = player_query.get_mut(entity) {
+                        let yaw = buffer.latest_yaw();
+                        buffer.push(render_now, *position, yaw);
+                    }
+                }
+            }
+            RemotePlayerEvent::Left { id } => {
+                if let Some(entity) = remote_players.entities.remove(id) {
+                    info!("Player with ID {} left the world", id);
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+            RemotePlayerEvent::Snapshot { server_time, entities } => {
+                clock.observe(*server_time, local_now);
+
+                // Players are spawned by PlayerJoined (which carries name and appearance);
+                // snapshots only move them
+                for (id, state) in entities {
+                    let Some(&entity) = remote_players.entities.get(id) else { continue };
+                    if let Ok((mut buffer, mut info, _)) = player_query.get_mut(entity) {
+                        buffer.push(*server_time, state.position(), state.yaw());
+                        if info.level != state.level {
+                            info.level = state.level;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_remote_player(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    id: u64,
+    character: &shared::CharacterData,
+    position: Vec3,
+    weapon: Option<shared::items::ItemId>,
+    snapshot_time: f64,
+) -> Entity {
+    let mut buffer = SnapshotBuffer::default();
+    buffer.push(snapshot_time, position, 0.0);
+
+    commands.spawn((
+        SpatialBundle {
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        OtherPlayer { id },
+        RemotePlayerInfo {
+            name: character.name.clone(),
+            level: character.level,
+        },
+        buffer,
+        InterpolatedMotion::default(),
+        EquippedWeapon(weapon),
+        GameWorld,
+    ))
+    .with_children(|parent| {
+        parent.spawn(SceneBundle {
+            scene: asset_server.load(GltfAssetLabel::Scene(0).from_asset(PLAYER_MODEL_PATH)),
+            ..default()
+        });
+    })
+    .id()
+}
+
+/// Hook up the animation graph once a remote player's model has loaded
+fn setup_remote_animation(
+    mut commands: Commands,
+    mut animation_players: Query<
+        (Entity, &mut AnimationPlayer),
+        (Without<RemoteAnimation>, Without<LocalAnimationPlayer>),
+    >,
+    parent_query: Query<&Parent>,
+    remote_query: Query<(), With<OtherPlayer>>,
+    player_anims: Res<PlayerAnimations>,
+) {
+    // Graph is created when the local player spawns
+    if player_anims.graph == Handle::default() {
+        return;
+    }
+
+    for (anim_entity, mut player) in animation_players.iter_mut() {
+        let Some(owner) = parent_query.iter_ancestors(anim_entity).find(|&e| remote_query.contains(e)) else { continue };
+
+        player.play(player_anims.idle_index).repeat();
+        commands.entity(anim_entity).insert((
+            player_anims.graph.clone(),
+            AnimationTransitions::new(),
+            RemoteAnimation { owner, is_moving: false },
+        ));
+    }
+}
+
+/// Blend remote players between idle and walk based on their interpolated movement
+fn update_remote_animation(
+    mut animation_players: Query<(&mut AnimationPlayer, &mut AnimationTransitions, &mut RemoteAnimation)>,
+    motion_query: Query<&InterpolatedMotion>,
+    player_anims: Res<PlayerAnimations>,
+) {
+    for (mut player, mut transitions, mut animation) in animation_players.iter_mut() {
+        let Ok(motion) = motion_query.get(animation.owner) else { continue };
+        if motion.is_moving == animation.is_moving {
+            continue;
+        }
+        animation.is_moving = motion.is_moving;
+
+        let target_index = if motion.is_moving { player_anims.walk_index } else { player_anims.idle_index };
+        transitions.play(&mut player, target_index, ANIMATION_BLEND).repeat();
+    }
+}
+
+fn setup_remote_nameplate_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    player_query: Query<(Entity, &RemotePlayerInfo), Without<HasRemoteNameplate>>,
+) {
+    for (player_entity, info) in player_query.iter() {
+        commands.entity(player_entity).insert(HasRemoteNameplate);
+
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    padding: UiRect {
+                        left: Val::Px(8.0),
+                        right: Val::Px(8.0),
+                        top: Val::Px(4.0),
+                        bottom: Val::Px(4.0),
+                    },
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(100),
+                border_radius: BorderRadius::all(Val::Px(4.0)),
+                ..default()
+            },
+            RemoteNameplateUI { player_entity },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Lvl {} - {}", info.level, info.name),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 18.0,
+                    color: Color::WHITE, // White for other players, golden is the local player
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+fn update_remote_nameplate_ui_positions(
+    player_query: Query<(&Transform, &Visibility, Ref<RemotePlayerInfo>), With<OtherPlayer>>,
+    mut nameplate_ui_query: Query<(&mut Style, &mut Visibility, &Node, &RemoteNameplateUI, &Children), Without<OtherPlayer>>,
+    mut text_query: Query<&mut Text>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else { return };
+
+    for (mut style, mut nameplate_visibility, node, ui, children) in nameplate_ui_query.iter_mut() {
+        let Ok((transform, visibility, info)) = player_query.get(ui.player_entity) else { continue };
+
+        // Hidden players (invisibility) hide their nameplate too
+        let wanted = if *visibility == Visibility::Hidden { Visibility::Hidden } else { Visibility::Inherited };
+        if *nameplate_visibility != wanted {
+            *nameplate_visibility = wanted;
+        }
+
+        // Refresh the label when the level changes
+        if info.is_changed() {
+            for &child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(child) {
+                    text.sections[0].value = format!("Lvl {} - {}", info.level, info.name);
+                }
+            }
+        }
+
+        // Keep nameplate 1.2 units above the player, same as the local player
+        let world_pos = transform.translation + Vec3::Y * 1.2;
+
+        if let Some(screen_pos) = camera.world_to_viewport(camera_transform, world_pos) {
+            let size = node.size();
+            style.left = Val::Px(screen_pos.x - size.x / 2.0);
+            style.top = Val::Px(screen_pos.y - size.y - 5.0);
+        }
+    }
+}
+
+/// Remove nameplates whose remote player has been despawned
+fn cleanup_orphaned_nameplates(
+    mut commands: Commands,
+    nameplate_query: Query<(Entity, &RemoteNameplateUI)>,
+    player_query: Query<(), With<OtherPlayer>>,
+) {
+    for (entity, ui) in nameplate_query.iter() {
+        if !player_query.contains(ui.player_entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn cleanup_remote_players(
+    mut commands: Commands,
+    mut remote_players: ResMut<RemotePlayers>,
+    nameplate_query: Query<Entity, With<RemoteNameplateUI>>,
+) {
+    for (_, entity) in remote_players.entities.drain() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for entity in nameplate_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
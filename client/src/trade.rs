@@ -0,0 +1,375 @@
+use bevy::prelude::*;
+use shared::items::TradeOffer;
+use shared::ClientMessage;
+use crate::combat::CombatTarget;
+use crate::inventory::{rarity_color, InventoryState};
+use crate::networking::{NetworkClient, TradeEvent};
+use crate::ui::{UILayerStack, UILayerType, NORMAL_BUTTON};
+use crate::GameFont;
+use crate::GameState;
+
+pub struct TradePlugin;
+
+impl Plugin for TradePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TradeState>()
+            .add_systems(Update, (
+                handle_trade_keys,
+                handle_trade_events,
+                handle_trade_buttons,
+                send_trade_cancel,
+                update_trade_window,
+            ).chain().run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), cleanup_trade);
+    }
+}
+
+const REQUEST_KEY: KeyCode = KeyCode::KeyT;  // Ask the current target to trade
+const ACCEPT_KEY: KeyCode = KeyCode::KeyH;   // Accept the latest trade request
+
+/// Gold added to our offer per click
+const GOLD_STEPS: [u64; 2] = [10, 100];
+
+/// Our trade as last reported by the server
+#[derive(Resource, Default)]
+pub struct TradeState {
+    request: Option<(u64, String)>,  // Requester entity and name
+    partner: Option<(u64, String)>,  // Entity and name while trading
+    ours: TradeOffer,
+    theirs: TradeOffer,
+    message: Option<String>,         // Last error from the server
+    pub cancel: bool,                // Set by ESC: ask the server to cancel
+}
+
+#[derive(Component)]
+struct TradeWindow;
+
+#[derive(Component)]
+enum TradeButton {
+    Offer(usize),  // One more item from this inventory slot
+    Withdraw(u16), // Take everything from this slot back
+    AddGold(u64),
+    ClearGold,
+    Lock,
+    Confirm,
+    Cancel,
+}
+
+fn handle_trade_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    target: Res<CombatTarget>,
+    ui_stack: Res<UILayerStack>,
+    mut trade: ResMut<TradeState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    if ui_stack.top_layer().is_some_and(|layer| layer.blocks_input) || trade.partner.is_some() {
+        return;
+    }
+    let Some(network) = network else { return };
+
+    let message = if keyboard.just_pressed(REQUEST_KEY) {
+        let Some(target) = target.0 else {
+            info!("Select a player with Tab to trade with them");
+            return;
+        };
+        ClientMessage::TradeRequest { target }
+    } else if keyboard.just_pressed(ACCEPT_KEY) {
+        let Some((requester, _)) = trade.request.take() else { return };
+        ClientMessage::TradeAccept { requester }
+    } else {
+        return;
+    };
+
+    if let Err(e) = network.send_message(&message) {
+        error!("Failed to send trade request: {}", e);
+    }
+}
+
+fn handle_trade_events(
+    mut trade_events: EventReader<TradeEvent>,
+    mut trade: ResMut<TradeState>,
+    mut ui_stack: ResMut<UILayerStack>,
+) {
+    for event in trade_events.read() {
+        match event {
+            TradeEvent::Requested { requester, name } => {
+                info!("{} wants to trade - press H to accept", name);
+                trade.request = Some((*requester, name.clone()));
+            }
+            TradeEvent::Opened { partner, name } => {
+                info!("Trading with {}", name);
+                *trade = TradeState { partner: Some((*partner, name.clone())), ..default() };
+                ui_stack.push_layer(UILayerType::Trade);
+            }
+            TradeEvent::Updated { ours, theirs } => {
+                trade.ours = ours.clone();
+                trade.theirs = theirs.clone();
+                trade.message = None;
+            }
+            TradeEvent::Completed => {
+                info!("Trade completed");
+                end_trade(&mut trade, &mut ui_stack);
+            }
+            TradeEvent::Cancelled { reason } => {
+                info!("Trade cancelled: {}", reason);
+                end_trade(&mut trade, &mut ui_stack);
+            }
+            TradeEvent::Failed { reason } => {
+                warn!("Trade: {}", reason);
+                trade.message = Some(reason.clone());
+            }
+        }
+    }
+}
+
+fn end_trade(trade: &mut TradeState, ui_stack: &mut UILayerStack) {
+    // A request that came in during the trade can still be accepted
+    let request = trade.request.take();
+    *trade = TradeState { request, ..default() };
+    ui_stack.remove_layer(UILayerType::Trade);
+}
+
+/// Every change to our offer sends the whole new offer; the window follows the
+/// server's answer
+fn handle_trade_buttons(
+    interaction_query: Query<(&Interaction, &TradeButton), Changed<Interaction>>,
+    mut trade: ResMut<TradeState>,
+    inventory: Res<InventoryState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    let Some(network) = network else { return };
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut items: Vec<(u16, u32)> = trade.ours.items.iter().map(|(slot, stack)| (*slot, stack.count)).collect();
+        let mut gold = trade.ours.gold;
+        let message = match button {
+            TradeButton::Offer(slot) => {
+                let Some(stack) = inventory.slots.get(*slot).copied().flatten() else { continue };
+                match items.iter_mut().find(|(offered, _)| *offered as usize == *slot) {
+                    Some((_, count)) => *count = (*count + 1).min(stack.count),
+                    None => items.push((*slot as u16, 1)),
+                }
+                ClientMessage::TradeOffer { items, gold }
+            }
+            TradeButton::Withdraw(slot) => {
+                items.retain(|(offered, _)| offered != slot);
+                ClientMessage::TradeOffer { items, gold }
+            }
+            TradeButton::AddGold(amount) => {
+                gold = (gold + amount).min(inventory.gold);
+                ClientMessage::TradeOffer { items, gold }
+            }
+            TradeButton::ClearGold => ClientMessage::TradeOffer { items, gold: 0 },
+            TradeButton::Lock => ClientMessage::TradeLock,
+            TradeButton::Confirm => ClientMessage::TradeConfirm,
+            TradeButton::Cancel => {
+                trade.cancel = true;
+                continue;
+            }
+        };
+        if let Err(e) = network.send_message(&message) {
+            error!("Failed to send {:?}: {}", message, e);
+        }
+    }
+}
+
+fn send_trade_cancel(mut trade: ResMut<TradeState>, network: Option<Res<NetworkClient>>) {
+    if !trade.cancel {
+        return;
+    }
+    trade.cancel = false;
+    if let Some(network) = network {
+        if let Err(e) = network.send_message(&ClientMessage::TradeCancel) {
+            error!("Failed to send TradeCancel: {}", e);
+        }
+    }
+}
+
+/// (Re)build the window whenever either offer or our inventory changes
+fn update_trade_window(
+    mut commands: Commands,
+    trade: Res<TradeState>,
+    inventory: Res<InventoryState>,
+    font: Res<GameFont>,
+    window_query: Query<Entity, With<TradeWindow>>,
+) {
+    if !trade.is_changed() && !inventory.is_changed() {
+        return;
+    }
+    for entity in window_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some((_, partner_name)) = &trade.partner else { return };
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(200.0),
+                top: Val::Px(100.0),
+                width: Val::Px(620.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.08, 0.08, 0.1, 0.95).into(),
+            border_color: Color::srgb(0.4, 0.35, 0.25).into(),
+            border_radius: BorderRadius::all(Val::Px(6.0)),
+            z_index: ZIndex::Global(160),  // Above the inventory, below dialogs (400)
+            ..default()
+        },
+        TradeWindow,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(format!("Handel mit {}", partner_name), text_style(20.0, Color::WHITE)));
+
+        // Both offers side by side
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            spawn_offer(parent, "Dein Angebot", &trade.ours, true, &text_style);
+            spawn_offer(parent, &format!("Angebot von {}", partner_name), &trade.theirs, false, &text_style);
+        });
+
+        // What we could still add
+        if !trade.ours.locked {
+            parent.spawn(TextBundle::from_section("Anbieten:", text_style(16.0, Color::srgb(0.8, 0.8, 0.8))));
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(4.0),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for (slot, stack) in inventory.slots.iter().enumerate() {
+                    let Some(stack) = stack else { continue };
+                    let Some(info) = stack.item.info() else { continue };
+                    let label = format!("{} x{}", info.name, stack.count);
+                    spawn_button(parent, TradeButton::Offer(slot), label, text_style(13.0, rarity_color(info.rarity)));
+                }
+                for amount in GOLD_STEPS {
+                    spawn_button(parent, TradeButton::AddGold(amount), format!("+{} Gold", amount), text_style(13.0, Color::srgb(1.0, 0.84, 0.0)));
+                }
+                spawn_button(parent, TradeButton::ClearGold, "Kein Gold".to_string(), text_style(13.0, Color::WHITE));
+            });
+        }
+
+        // Status: the last error, or what happens next
+        let status = match &trade.message {
+            Some(message) => message.clone(),
+            None if !trade.ours.locked => "Lege dein Angebot fest, wenn es vollständig ist".to_string(),
+            None if !trade.theirs.locked => format!("Warte, bis {} das Angebot festlegt", partner_name),
+            None if !trade.ours.confirmed => "Beide Angebote stehen - bestätige den Handel".to_string(),
+            None => format!("Warte auf die Bestätigung von {}", partner_name),
+        };
+        let color = if trade.message.is_some() { Color::srgb(1.0, 0.3, 0.3) } else { Color::srgb(0.8, 0.8, 0.8) };
+        parent.spawn(TextBundle::from_section(status, text_style(14.0, color)));
+
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            if !trade.ours.locked {
+                spawn_button(parent, TradeButton::Lock, "Festlegen".to_string(), text_style(16.0, Color::WHITE));
+            } else if trade.theirs.locked && !trade.ours.confirmed {
+                spawn_button(parent, TradeButton::Confirm, "Bestätigen".to_string(), text_style(16.0, Color::WHITE));
+            }
+            spawn_button(parent, TradeButton::Cancel, "Abbrechen".to_string(), text_style(16.0, Color::WHITE));
+        });
+    });
+}
+
+/// One side of the trade; our own offered items can be taken back until we lock it
+fn spawn_offer(parent: &mut ChildBuilder, title: &str, offer: &TradeOffer, ours: bool, text_style: &impl Fn(f32, Color) -> TextStyle) {
+    // Green once the offer is final
+    let border = if offer.locked { Color::srgb(0.3, 0.8, 0.3) } else { Color::srgb(0.25, 0.25, 0.25) };
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(50.0),
+            min_height: Val::Px(120.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        background_color: Color::srgba(0.12, 0.12, 0.15, 0.9).into(),
+        border_color: border.into(),
+        ..default()
+    })
+    .with_children(|parent| {
+        let state = match (offer.locked, offer.confirmed) {
+            (_, true) => " (bestätigt)",
+            (true, false) => " (festgelegt)",
+            _ => "",
+        };
+        parent.spawn(TextBundle::from_section(format!("{}{}", title, state), text_style(16.0, Color::WHITE)));
+
+        for (slot, stack) in &offer.items {
+            let Some(info) = stack.item.info() else { continue };
+            let label = format!("{} x{}", info.name, stack.count);
+            if ours && !offer.locked {
+                spawn_button(parent, TradeButton::Withdraw(*slot), label, text_style(14.0, rarity_color(info.rarity)));
+            } else {
+                parent.spawn(TextBundle::from_section(label, text_style(14.0, rarity_color(info.rarity))));
+            }
+        }
+        parent.spawn(TextBundle::from_section(format!("{} Gold", offer.gold), text_style(14.0, Color::srgb(1.0, 0.84, 0.0))));
+    });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, button: TradeButton, label: String, style: TextStyle) {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        },
+        button,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(label, style));
+    });
+}
+
+fn cleanup_trade(
+    mut commands: Commands,
+    mut trade: ResMut<TradeState>,
+    mut ui_stack: ResMut<UILayerStack>,
+    windows: Query<Entity, With<TradeWindow>>,
+) {
+    *trade = TradeState::default();
+    ui_stack.remove_layer(UILayerType::Trade);
+    for entity in windows.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use crate::GameState;
+use crate::GameFont;
+use crate::interaction::NpcDialogState;
+use crate::networking::{DeathEvent, NetworkClient};
+use shared::ClientMessage;
+use super::{UILayerStack, UILayerType};
+
+pub struct DeathScreenPlugin;
+
+impl Plugin for DeathScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DeathState>()
+            .add_systems(Update, (
+                handle_death_events,
+                spawn_death_screen,
+                handle_respawn_button,
+                cleanup_death_screen,
+            ).chain().run_if(in_state(GameState::InGame)))
+            .add_systems(OnExit(GameState::InGame), reset_death_state);
+    }
+}
+
+/// Whether our character is dead, as last reported by the server
+#[derive(Resource, Default)]
+pub struct DeathState {
+    pub dead: bool,
+    pub experience_lost: i64,
+    pub respawn_point: String,
+    respawn_requested: bool,  // Respawn sent, waiting for the server
+}
+
+#[derive(Component)]
+struct DeathScreenUI;
+
+#[derive(Component)]
+struct RespawnButton;
+
+fn handle_death_events(
+    mut death_events: EventReader<DeathEvent>,
+    mut death_state: ResMut<DeathState>,
+    mut ui_stack: ResMut<UILayerStack>,
+    mut dialog_state: ResMut<NpcDialogState>,
+) {
+    for event in death_events.read() {
+        match event {
+            DeathEvent::Died { experience_lost, respawn_point } => {
+                info!("💀 Died - lost {} XP, respawn at {}", experience_lost, respawn_point);
+                *death_state = DeathState {
+                    dead: true,
+                    experience_lost: *experience_lost,
+                    respawn_point: respawn_point.clone(),
+                    respawn_requested: false,
+                };
+                if dialog_state.active {
+                    dialog_state.close_dialog();
+                }
+                ui_stack.push_layer(UILayerType::Death);
+            }
+            DeathEvent::Respawned { position } => {
+                info!("Back on our feet at {:?}", position);
+                death_state.dead = false;
+                ui_stack.remove_layer(UILayerType::Death);
+            }
+        }
+    }
+}
+
+/// Spawn the death screen once we are dead
+fn spawn_death_screen(
+    mut commands: Commands,
+    death_state: Res<DeathState>,
+    font: Res<GameFont>,
+    existing_screen: Query<Entity, With<DeathScreenUI>>,
+) {
+    if !death_state.dead || !existing_screen.is_empty() {
+        return;
+    }
+
+    let message = if death_state.experience_lost > 0 {
+        format!("Du hast {} Erfahrung verloren.", death_state.experience_lost)
+    } else {
+        "Du hast keine Erfahrung verloren.".to_string()
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.2, 0.0, 0.0, 0.5).into(),
+            z_index: ZIndex::Global(450), // Above NPC dialogs (400) but below pause/settings (500)
+            ..default()
+        },
+        DeathScreenUI,
+    ))
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(500.0),
+                padding: UiRect::all(Val::Px(30.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(15.0),
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            background_color: Color::srgb(0.1, 0.05, 0.05).into(),
+            border_color: Color::srgb(0.5, 0.1, 0.1).into(),
+            border_radius: BorderRadius::all(Val::Px(10.0)),
+            ..default()
+        })
+        .with_children(|parent| {
+            // Title
+            parent.spawn(TextBundle::from_section(
+                "Du bist gestorben",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 40.0,
+                    color: Color::srgb(0.9, 0.2, 0.2),
+                },
+            ));
+
+            // Penalty and what happens next
+            parent.spawn(TextBundle::from_section(
+                format!("{}\n\nWarte auf eine Wiederbelebung durch einen Gefährten\noder kehre zum nächsten Sammelpunkt zurück.", message),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+            ).with_text_justify(JustifyText::Center));
+
+            // Respawn button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(320.0),
+                        height: Val::Px(50.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.3, 0.2, 0.1).into(),
+                    ..default()
+                },
+                RespawnButton,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("Wiederbeleben ({})", death_state.respawn_point),
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+        });
+    });
+}
+
+fn handle_respawn_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RespawnButton>)>,
+    mut death_state: ResMut<DeathState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed || death_state.respawn_requested {
+            continue;
+        }
+        let Some(network) = &network else { continue };
+        if let Err(e) = network.send_message(&ClientMessage::Respawn) {
+            error!("Failed to request respawn: {}", e);
+        } else {
+            info!("Requested respawn at {}", death_state.respawn_point);
+            death_state.respawn_requested = true;
+        }
+    }
+}
+
+/// Remove the death screen once we are alive again
+fn cleanup_death_screen(
+    mut commands: Commands,
+    death_state: Res<DeathState>,
+    screen_query: Query<Entity, With<DeathScreenUI>>,
+) {
+    if death_state.dead {
+        return;
+    }
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Leaving the game (logout, disconnect) forgets the death; characters always enter
+/// the world alive
+fn reset_death_state(
+    mut commands: Commands,
+    mut death_state: ResMut<DeathState>,
+    mut ui_stack: ResMut<UILayerStack>,
+    screen_query: Query<Entity, With<DeathScreenUI>>,
+) {
+    *death_state = DeathState::default();
+    ui_stack.remove_layer(UILayerType::Death);
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -24,6 +24,8 @@ impl Plugin for GameUIPlugin {
                 update_instructions,
                 update_stat_bars,
                 update_xp_bar,
+                update_ability_cooldowns,
+                update_buff_bar,
                 handle_bottom_bar_buttons,
                 handle_dev_xp_key,
                 handle_dev_toggle_key,
@@ -109,6 +111,14 @@ enum BottomBarButton {
 #[derive(Component)]
 struct AbilitySlot(u8); // 1-9
 
+/// Row of status effect icons above the stats
+#[derive(Component)]
+struct BuffBar;
+
+/// Countdown below a buff icon: when the effect runs out (seconds since startup)
+#[derive(Component)]
+struct BuffTimer(f64);
+
 /// Resource to track dev mode state
 #[derive(Resource)]
 pub struct DevModeState {
@@ -270,6 +280,23 @@ fn setup_game_ui(mut commands: Commands, font: Res<GameFont>, mut ui_stack: ResM
             // RIGHT SIDE - Menu Buttons
             create_menu_buttons(parent, font_handle.clone());
         });
+
+        // Buff bar just above the stats, filled by update_buff_bar
+        parent.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(76.0),
+                    left: Val::Px(8.0),
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                },
+                focus_policy: bevy::ui::FocusPolicy::Pass,
+                ..default()
+            },
+            BuffBar,
+        ));
     });
 }
 
@@ -625,6 +652,117 @@ fn update_stat_bars(
     }
 }
 
+/// Darken ability slots while their skill is on cooldown
+fn update_ability_cooldowns(
+    time: Res<Time>,
+    cooldowns: Res<crate::combat::SkillCooldowns>,
+    auth_state: Res<crate::auth_state::AuthState>,
+    mut slot_query: Query<(&AbilitySlot, &mut BackgroundColor)>,
+) {
+    let skills = auth_state.specialization.map(|spec| spec.skills()).unwrap_or_default();
+    let now = time.elapsed_seconds_f64();
+
+    for (slot, mut background) in slot_query.iter_mut() {
+        let remaining = skills.get(slot.0 as usize - 1)
+            .map(|skill| cooldowns.remaining(*skill, now))
+            .unwrap_or(0.0);
+        let brightness = 0.2 - 0.12 * remaining;
+        *background = Color::srgba(brightness, brightness, brightness + 0.05, 0.9).into();
+    }
+}
+
+/// Rebuild the buff bar when our status effects change and count down their timers
+fn update_buff_bar(
+    mut commands: Commands,
+    time: Res<Time>,
+    font: Res<GameFont>,
+    active_effects: Res<crate::combat::ActiveEffects>,
+    bar_query: Query<Entity, With<BuffBar>>,
+    mut timer_query: Query<(&BuffTimer, &mut Text)>,
+) {
+    let Ok(bar) = bar_query.get_single() else { return };
+
+    if active_effects.is_changed() {
+        commands.entity(bar).despawn_descendants();
+        commands.entity(bar).with_children(|parent| {
+            for (effect, expires_at) in active_effects.effects.iter() {
+                create_buff_icon(parent, font.0.clone(), effect, *expires_at, time.elapsed_seconds_f64());
+            }
+        });
+        return;
+    }
+
+    let now = time.elapsed_seconds_f64();
+    for (timer, mut text) in timer_query.iter_mut() {
+        text.sections[0].value = format!("{:.0}s", (timer.0 - now).max(0.0).ceil());
+    }
+}
+
+fn create_buff_icon(parent: &mut ChildBuilder, font: Handle<Font>, effect: &shared::StatusEffectState, expires_at: f64, now: f64) {
+    // Red frame for debuffs, green for buffs
+    let border = if effect.kind.is_harmful() { Color::srgb(0.85, 0.2, 0.2) } else { Color::srgb(0.3, 0.8, 0.3) };
+    let label = if effect.stacks > 1 {
+        format!("{} x{}", buff_label(effect.kind), effect.stacks)
+    } else {
+        buff_label(effect.kind).to_string()
+    };
+
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Px(44.0),
+            height: Val::Px(38.0),
+            border: UiRect::all(Val::Px(1.5)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: Color::srgba(0.1, 0.1, 0.12, 0.85).into(),
+        border_color: border.into(),
+        focus_policy: bevy::ui::FocusPolicy::Pass,
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font: font.clone(),
+                font_size: 11.0,
+                color: Color::WHITE,
+            },
+        ));
+        parent.spawn((
+            TextBundle::from_section(
+                format!("{:.0}s", (expires_at - now).max(0.0).ceil()),
+                TextStyle {
+                    font,
+                    font_size: 10.0,
+                    color: Color::srgb(0.8, 0.8, 0.8),
+                },
+            ),
+            BuffTimer(expires_at),
+        ));
+    });
+}
+
+/// Short name of a status effect for its icon
+fn buff_label(kind: shared::EffectKind) -> &'static str {
+    use shared::EffectKind;
+    match kind {
+        EffectKind::DamageReduction => "Schutz",
+        EffectKind::DefenseReduction => "Rüst-",
+        EffectKind::DefenseBuff => "Rüst+",
+        EffectKind::AttackSpeed => "Tempo",
+        EffectKind::DamageBuff => "Kraft",
+        EffectKind::Invisible => "Unsicht",
+        EffectKind::Stun => "Betäubt",
+        EffectKind::CrowdControlImmunity => "Immun",
+        EffectKind::LastStand => "Bastion",
+        EffectKind::DamageOverTime => "DoT",
+        EffectKind::HealOverTime => "HoT",
+    }
+}
+
 fn handle_bottom_bar_buttons(
     interaction_query: Query<(&Interaction, &BottomBarButton), Changed<Interaction>>,
     mut next_state: ResMut<NextState<GameState>>,
@@ -679,6 +817,7 @@ fn update_xp_bar(
     }
 }
 
+/// GM command: only GM accounts get the XP, the server ignores it for everyone else
 fn handle_dev_xp_key(
     keyboard: Res<ButtonInput<KeyCode>>,
     network: Option<Res<crate::networking::NetworkClient>>,
@@ -686,10 +825,10 @@ fn handle_dev_xp_key(
     if keyboard.just_pressed(KeyCode::KeyK) {
         if let Some(network) = network {
             use shared::ClientMessage;
-            if let Err(e) = network.send_message(&ClientMessage::GainExperience { amount: 1000 }) {
-                error!("Failed to send GainExperience: {}", e);
+            if let Err(e) = network.send_message(&ClientMessage::GmGiveExperience { amount: 1000 }) {
+                error!("Failed to send GmGiveExperience: {}", e);
             } else {
-                info!("Sent +1000 XP request (Dev Key 'K')");
+                info!("Sent +1000 XP GM request (Dev Key 'K')");
             }
         }
     }
@@ -893,7 +1032,7 @@ fn handle_dev_panel_buttons(
                     let xp_for_next = shared::calculate_xp_for_level(next_level);
                     let xp_needed = xp_for_next - player_stats.experience;
                     
-                    if let Err(e) = network.send_message(&shared::ClientMessage::GainExperience { 
+                    if let Err(e) = network.send_message(&shared::ClientMessage::GmGiveExperience { 
                         amount: xp_needed 
                     }) {
                         error!("Failed to send AddLevel XP: {}", e);
@@ -912,7 +1051,7 @@ fn handle_dev_panel_buttons(
                             -1  // At 0 XP, send -1 to trigger level-down
                         };
                         
-                        if let Err(e) = network.send_message(&shared::ClientMessage::GainExperience { 
+                        if let Err(e) = network.send_message(&shared::ClientMessage::GmGiveExperience { 
                             amount: xp_to_remove 
                         }) {
                             error!("Failed to send RemoveLevel: {}", e);
@@ -924,7 +1063,7 @@ fn handle_dev_panel_buttons(
                     }
                 }
                 DevButton::Add1000XP => {
-                    if let Err(e) = network.send_message(&shared::ClientMessage::GainExperience { 
+                    if let Err(e) = network.send_message(&shared::ClientMessage::GmGiveExperience { 
                         amount: 1000 
                     }) {
                         error!("Failed to send +1000 XP: {}", e);
@@ -936,7 +1075,7 @@ fn handle_dev_panel_buttons(
                             // Reset to level 1 (XP = 0)
                             let xp_to_remove = -(player_stats.experience as i64);
                             
-                            if let Err(e) = network.send_message(&shared::ClientMessage::GainExperience { 
+                            if let Err(e) = network.send_message(&shared::ClientMessage::GmGiveExperience { 
                                 amount: xp_to_remove 
                             }) {
                                 error!("Failed to reset level: {}", e);
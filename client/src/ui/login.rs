@@ -4,7 +4,7 @@ use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ButtonState;
 use crate::GameState;
 use crate::GameFont;
-use crate::networking::{NetworkClient, send_auth_request, AuthResponseEvent};
+use crate::networking::{NetworkClient, ServerConnectionState, send_auth_request, AuthResponseEvent};
 use crate::ui::game_ui::DevModeState;
 use shared::{AuthMessage, AuthResponse};
 
@@ -23,6 +23,7 @@ impl Plugin for LoginPlugin {
                 update_submit_button_text,
                 update_status_display,
                 handle_auth_response_ui,
+                show_connection_notice,
                 animate_background_particles,
                 update_input_field_borders,
             ).run_if(in_state(GameState::Login)));
@@ -875,6 +876,24 @@ fn handle_auth_response_ui(
     }
 }
 
+/// Tell the user why we stopped trying to connect or were sent back here
+fn show_connection_notice(
+    mut connection_state: ResMut<ServerConnectionState>,
+    mut login_state: ResMut<LoginState>,
+) {
+    if !connection_state.is_changed() {
+        return;
+    }
+    if connection_state.session_ended.is_some() {
+        login_state.status_message = connection_state.session_ended.take().unwrap_or_default();
+    } else if let Some(reason) = &connection_state.rejected {
+        let message = format!("Server refused the connection: {}", reason);
+        if login_state.status_message != message {
+            login_state.status_message = message;
+        }
+    }
+}
+
 fn cleanup_login(
     mut commands: Commands,
     query: Query<Entity, With<LoginUI>>,
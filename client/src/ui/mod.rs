@@ -1,19 +1,23 @@
 mod login;
 mod character_creation;
 mod character_selection;
+mod death_screen;
 mod game_ui;
 mod npc_dialog;
 mod pause;
 mod settings;
+mod shop_dialog;
 mod ui_stack;
 
 pub use login::LoginPlugin;
 pub use character_creation::CharacterCreationPlugin;
 pub use character_selection::CharacterSelectionPlugin;
+pub use death_screen::{DeathScreenPlugin, DeathState};
 pub use game_ui::{GameUIPlugin, PlayerStats, PauseMenuState, SettingsMenuState, CustomColorButton};
 pub use npc_dialog::NpcDialogPlugin;
 pub use pause::PausePlugin;
 pub use settings::SettingsPlugin;
+pub use shop_dialog::ShopDialogPlugin;
 pub use ui_stack::{UIStackPlugin, UILayerStack, UILayerType};
 
 use bevy::prelude::*;
@@ -0,0 +1,310 @@
+use bevy::prelude::*;
+use shared::items::{ItemId, ShopId};
+use shared::ClientMessage;
+use crate::GameState;
+use crate::GameFont;
+use crate::interaction::NpcDialogState;
+use crate::inventory::{rarity_color, InventoryState};
+use crate::networking::{NetworkClient, ShopEvent};
+use crate::npc::NpcType;
+use super::{UILayerStack, UILayerType};
+
+pub struct ShopDialogPlugin;
+
+impl Plugin for ShopDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShopState>()
+            .add_systems(Update, (
+                open_shop,
+                handle_shop_events,
+                handle_shop_buttons,
+                spawn_shop_dialog,
+                cleanup_closed_shop,
+            ).chain().run_if(in_state(GameState::InGame)));
+    }
+}
+
+/// The merchant we are trading with, as last reported by the server
+#[derive(Resource, Default)]
+struct ShopState {
+    shop: Option<ShopId>,
+    stock: Vec<(ItemId, Option<u32>)>,  // What is left of each listing (None = unlimited)
+    message: Option<String>,            // Last error from the server
+}
+
+#[derive(Component)]
+struct ShopDialogUI;
+
+#[derive(Component)]
+enum ShopButton {
+    Buy(ItemId),
+    Sell(usize),  // One item from this inventory slot
+    Close,
+}
+
+/// Ask the server for the stock when a merchant's dialog opens. The layer is already
+/// registered by mouse_click_system, like for every NPC dialog.
+fn open_shop(
+    dialog_state: Res<NpcDialogState>,
+    mut shop_state: ResMut<ShopState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    let Some(NpcType::Merchant(shop)) = dialog_state.npc_type else { return };
+    if !dialog_state.active || shop_state.shop == Some(shop) {
+        return;
+    }
+    *shop_state = ShopState { shop: Some(shop), ..default() };
+    if let Some(network) = network {
+        if let Err(e) = network.send_message(&ClientMessage::OpenShop { shop }) {
+            error!("Failed to send OpenShop: {}", e);
+        }
+    }
+}
+
+fn handle_shop_events(mut shop_events: EventReader<ShopEvent>, mut shop_state: ResMut<ShopState>) {
+    for event in shop_events.read() {
+        match event {
+            ShopEvent::Stock { shop, stock } => {
+                if shop_state.shop == Some(*shop) {
+                    shop_state.stock = stock.clone();
+                    shop_state.message = None;
+                }
+            }
+            ShopEvent::Failed { reason } => {
+                warn!("Shop: {}", reason);
+                shop_state.message = Some(reason.clone());
+            }
+        }
+    }
+}
+
+/// Buying and selling only ask the server; the dialog follows its answers
+fn handle_shop_buttons(
+    interaction_query: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+    shop_state: Res<ShopState>,
+    mut dialog_state: ResMut<NpcDialogState>,
+    network: Option<Res<NetworkClient>>,
+) {
+    let Some(shop) = shop_state.shop else { return };
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let message = match button {
+            ShopButton::Buy(item) => ClientMessage::BuyItem { shop, item: *item, count: 1 },
+            ShopButton::Sell(slot) => ClientMessage::SellItem { shop, slot: *slot as u16, count: 1 },
+            ShopButton::Close => {
+                dialog_state.close_dialog();
+                continue;
+            }
+        };
+        if let Some(network) = &network {
+            if let Err(e) = network.send_message(&message) {
+                error!("Failed to send {:?}: {}", message, e);
+            }
+        }
+    }
+}
+
+/// (Re)build the dialog whenever the stock or our inventory changes
+fn spawn_shop_dialog(
+    mut commands: Commands,
+    shop_state: Res<ShopState>,
+    inventory: Res<InventoryState>,
+    font: Res<GameFont>,
+    existing_dialog: Query<Entity, With<ShopDialogUI>>,
+) {
+    let Some(shop) = shop_state.shop else { return };
+    if !existing_dialog.is_empty() && !shop_state.is_changed() && !inventory.is_changed() {
+        return;
+    }
+    for entity in existing_dialog.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let tables = shared::balance::current();
+    let Some(info) = tables.shop(shop) else { return };
+    let text = |text: String, size: f32, color: Color| TextBundle::from_section(
+        text,
+        TextStyle { font: font.0.clone(), font_size: size, color },
+    );
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            z_index: ZIndex::Global(400), // Same as the NPC dialog it replaces
+            ..default()
+        },
+        ShopDialogUI,
+    ))
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(900.0),
+                padding: UiRect::all(Val::Px(30.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(15.0),
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            background_color: Color::srgb(0.15, 0.1, 0.05).into(),
+            border_color: Color::srgb(0.6, 0.4, 0.1).into(),
+            border_radius: BorderRadius::all(Val::Px(10.0)),
+            ..default()
+        })
+        .with_children(|parent| {
+            // Title
+            parent.spawn(text(info.name.clone(), 36.0, Color::srgb(1.0, 0.9, 0.3)).with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }));
+
+            // Wares on the left, what we could sell on the right
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(30.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                spawn_column(parent, "Angebot", &font, |parent| {
+                    for listing in &info.listings {
+                        let Some(item) = listing.item.info() else { continue };
+                        let left = shop_state.stock.iter()
+                            .find(|(id, _)| *id == listing.item)
+                            .and_then(|(_, left)| *left);
+                        let label = match left {
+                            Some(left) => format!("{} - {} Gold ({} übrig)", item.name, listing.price, left),
+                            None => format!("{} - {} Gold", item.name, listing.price),
+                        };
+                        spawn_row(parent, text(label, 16.0, rarity_color(item.rarity)), ShopButton::Buy(listing.item), "Kaufen", &font);
+                    }
+                });
+
+                spawn_column(parent, "Verkaufen", &font, |parent| {
+                    for (slot, stack) in inventory.slots.iter().enumerate() {
+                        let Some(stack) = stack else { continue };
+                        let Some(item) = stack.item.info().filter(|item| info.buys_item(item)) else { continue };
+                        let label = format!("{} x{} - {} Gold", item.name, stack.count, item.value);
+                        spawn_row(parent, text(label, 16.0, rarity_color(item.rarity)), ShopButton::Sell(slot), "Verkaufen", &font);
+                    }
+                });
+            });
+
+            // Gold, or the last error
+            let (status, color) = match &shop_state.message {
+                Some(message) => (message.clone(), Color::srgb(1.0, 0.3, 0.3)),
+                None => (format!("Dein Gold: {}", inventory.gold), Color::srgb(1.0, 0.84, 0.0)),
+            };
+            parent.spawn(text(status, 20.0, color));
+
+            // Close button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        height: Val::Px(50.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.3, 0.2, 0.1).into(),
+                    ..default()
+                },
+                ShopButton::Close,
+            ))
+            .with_children(|parent| {
+                parent.spawn(text("Schließen".to_string(), 24.0, Color::WHITE));
+            });
+        });
+    });
+}
+
+/// A titled column of rows; rows that don't fit are cut off
+fn spawn_column(parent: &mut ChildBuilder, title: &str, font: &GameFont, rows: impl FnOnce(&mut ChildBuilder)) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(50.0),
+            max_height: Val::Px(420.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            overflow: Overflow::clip_y(),
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(title, TextStyle {
+            font: font.0.clone(),
+            font_size: 24.0,
+            color: Color::srgb(1.0, 0.8, 0.2),
+        }));
+        rows(parent);
+    });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, label: TextBundle, button: ShopButton, action: &str, font: &GameFont) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.0),
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(label);
+        parent.spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(100.0),
+                    height: Val::Px(30.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgb(0.2, 0.6, 0.2).into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(action, TextStyle {
+                font: font.0.clone(),
+                font_size: 16.0,
+                color: Color::WHITE,
+            }));
+        });
+    });
+}
+
+/// Remove the dialog when it's closed (button or ESC)
+fn cleanup_closed_shop(
+    mut commands: Commands,
+    dialog_state: Res<NpcDialogState>,
+    mut shop_state: ResMut<ShopState>,
+    dialog_query: Query<Entity, With<ShopDialogUI>>,
+    mut ui_stack: ResMut<UILayerStack>,
+) {
+    if dialog_state.active || shop_state.shop.is_none() {
+        return;
+    }
+    ui_stack.remove_layer(UILayerType::NpcDialog);
+    *shop_state = ShopState::default();
+    for entity in dialog_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
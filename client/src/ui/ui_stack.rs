@@ -81,9 +81,12 @@ impl UILayerStack {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UILayerType {
     GameUI,        // Base in-game UI (health bars, etc.)
+    Inventory,     // Inventory window, the game goes on around it
+    Trade,         // Trade window, walking away cancels the trade
     NpcDialog,     // NPC conversation dialogs
     PauseMenu,     // Pause menu
     Settings,      // Settings menu
+    Death,         // Death screen until we respawn
 }
 
 /// A single UI layer with metadata
@@ -98,9 +101,12 @@ impl UILayer {
     pub fn new(layer_type: UILayerType) -> Self {
         let (priority, blocks_input) = match layer_type {
             UILayerType::GameUI => (100, false),      // Base layer, doesn't block
+            UILayerType::Inventory => (150, false),   // Window over the HUD, still walking around
+            UILayerType::Trade => (160, false),       // Like the inventory, above it
             UILayerType::PauseMenu => (200, true),    // Blocks game input
             UILayerType::Settings => (250, true),     // Blocks everything below
-            UILayerType::NpcDialog => (300, true),    // Highest priority overlay
+            UILayerType::NpcDialog => (300, true),    // Conversation overlay
+            UILayerType::Death => (350, true),        // Highest priority overlay: nothing to do but respawn
         };
         
         Self {
@@ -129,6 +135,8 @@ fn handle_escape_key(
     mut ui_stack: ResMut<UILayerStack>,
     mut next_state: ResMut<NextState<crate::GameState>>,
     mut npc_dialog_state: ResMut<crate::interaction::NpcDialogState>,
+    mut inventory_state: ResMut<crate::inventory::InventoryState>,
+    mut trade_state: ResMut<crate::trade::TradeState>,
     current_state: Res<State<crate::GameState>>,
 ) {
     use crate::GameState;
@@ -147,6 +155,14 @@ fn handle_escape_key(
                 npc_dialog_state.close_dialog();
                 ui_stack.remove_layer(UILayerType::NpcDialog);
             }
+            UILayerType::Inventory => {
+                inventory_state.open = false;
+                ui_stack.remove_layer(UILayerType::Inventory);
+            }
+            UILayerType::Trade => {
+                // The window stays until the server confirms the cancellation
+                trade_state.cancel = true;
+            }
             UILayerType::Settings => {
                 // Back to InGame (settings opened from pause menu overlay)
                 next_state.set(GameState::InGame);
@@ -157,6 +173,9 @@ fn handle_escape_key(
                 // Just remove it if it exists
                 ui_stack.remove_layer(UILayerType::PauseMenu);
             }
+            UILayerType::Death => {
+                // Only respawning (or a resurrection) closes the death screen
+            }
             UILayerType::GameUI => {
                 // ESC in GameUI is now handled by game_ui.rs (pause menu toggle)
                 // Do nothing here to avoid conflicts
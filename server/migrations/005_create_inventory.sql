@@ -0,0 +1,10 @@
+-- Inventory slots of each character (empty slots have no row)
+CREATE TABLE IF NOT EXISTS inventory_items (
+    character_id INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+
+    PRIMARY KEY (character_id, slot),
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+);
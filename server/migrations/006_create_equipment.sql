@@ -0,0 +1,9 @@
+-- Equipped items of each character (empty equipment slots have no row)
+CREATE TABLE IF NOT EXISTS equipped_items (
+    character_id INTEGER NOT NULL,
+    slot TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+
+    PRIMARY KEY (character_id, slot),
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- Every purchase from and sale to a merchant, written with the inventory it changed
+CREATE TABLE IF NOT EXISTS shop_transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    character_id INTEGER NOT NULL,
+    shop_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    count INTEGER NOT NULL,   -- Bought (positive) or sold (negative)
+    gold INTEGER NOT NULL,    -- Paid (positive) or received (negative)
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+);
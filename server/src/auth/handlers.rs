@@ -75,19 +75,53 @@ pub async fn handle_login(
     username: String,
     password: String,
 ) -> AuthResponse {
+    let login = match verify_login(pool, username, password).await {
+        Ok(login) => login,
+        Err(response) => return response,
+    };
+    let user_id = login.user_id;
+
+    let response = start_session(session_manager, login);
+
+    // Update last login
+    if matches!(response, AuthResponse::LoginSuccess { .. }) {
+        if let Err(e) = db::users::update_last_login(pool, user_id).await {
+            log::error!("Error updating last login: {}", e);
+        }
+    }
+
+    response
+}
+
+/// Credentials checked and characters loaded - ready to become a session
+#[derive(Debug)]
+pub struct VerifiedLogin {
+    pub user_id: i64,
+    pub username: String,
+    pub is_gm: bool,
+    pub characters: Vec<CharacterSummary>,
+}
+
+/// Database part of a login: check credentials and load the character list.
+/// Runs on the persistence worker; the session is created by [`start_session`].
+pub async fn verify_login(
+    pool: &SqlitePool,
+    username: String,
+    password: String,
+) -> Result<VerifiedLogin, AuthResponse> {
     // Find user by username
     let user = match db::users::find_by_username(pool, &username).await {
         Ok(Some(user)) => user,
         Ok(None) => {
-            return AuthResponse::LoginFailed {
+            return Err(AuthResponse::LoginFailed {
                 reason: "Invalid username or password".to_string(),
-            };
+            });
         }
         Err(e) => {
             log::error!("Database error finding user: {}", e);
-            return AuthResponse::LoginFailed {
+            return Err(AuthResponse::LoginFailed {
                 reason: "Internal server error".to_string(),
-            };
+            });
         }
     };
 
@@ -95,31 +129,18 @@ pub async fn handle_login(
     match verify_password(&password, &user.password_hash) {
         Ok(true) => {}
         Ok(false) => {
-            return AuthResponse::LoginFailed {
+            return Err(AuthResponse::LoginFailed {
                 reason: "Invalid username or password".to_string(),
-            };
+            });
         }
         Err(e) => {
             log::error!("Error verifying password: {}", e);
-            return AuthResponse::LoginFailed {
+            return Err(AuthResponse::LoginFailed {
                 reason: "Internal server error".to_string(),
-            };
+            });
         }
     }
 
-    // Check if user is already logged in
-    if session_manager.is_user_logged_in(user.id) {
-        log::warn!("User '{}' attempted to login while already logged in", username);
-        return AuthResponse::LoginFailed {
-            reason: "This account is already logged in. Please logout first or wait a few minutes.".to_string(),
-        };
-    }
-
-    // Update last login
-    if let Err(e) = db::users::update_last_login(pool, user.id).await {
-        log::error!("Error updating last login: {}", e);
-    }
-
     // Get user's characters
     let characters = match db::characters::get_user_characters(pool, user.id).await {
         Ok(chars) => chars.into_iter().map(|c| CharacterSummary {
@@ -142,8 +163,26 @@ pub async fn handle_login(
         }
     };
 
+    Ok(VerifiedLogin {
+        user_id: user.id,
+        username: user.username,
+        is_gm: user.is_gm,
+        characters,
+    })
+}
+
+/// In-memory part of a login: reject duplicate logins and create the session
+pub fn start_session(session_manager: &mut SessionManager, login: VerifiedLogin) -> AuthResponse {
+    // Check if user is already logged in
+    if session_manager.is_user_logged_in(login.user_id) {
+        log::warn!("User '{}' attempted to login while already logged in", login.username);
+        return AuthResponse::LoginFailed {
+            reason: "This account is already logged in. Please logout first or wait a few minutes.".to_string(),
+        };
+    }
+
     // Create JWT token
-    let token = match create_token(user.id, &user.username, TOKEN_DURATION_HOURS) {
+    let token = match create_token(login.user_id, &login.username, TOKEN_DURATION_HOURS) {
         Ok(t) => t,
         Err(e) => {
             log::error!("Error creating token: {}", e);
@@ -154,13 +193,14 @@ pub async fn handle_login(
     };
 
     // Add session
-    let session = SessionData::new(user.id, user.username.clone(), token.clone(), TOKEN_DURATION_HOURS);
+    let mut session = SessionData::new(login.user_id, login.username.clone(), token.clone(), TOKEN_DURATION_HOURS);
+    session.is_gm = login.is_gm;
     session_manager.add_session(token.clone(), session);
 
-    log::info!("User '{}' logged in successfully", username);
+    log::info!("User '{}' logged in successfully", login.username);
 
     AuthResponse::LoginSuccess {
         token,
-        characters,
+        characters: login.characters,
     }
 }
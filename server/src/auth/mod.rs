@@ -6,4 +6,4 @@ pub mod handlers;
 pub use password::{hash_password, verify_password};
 pub use jwt::{create_token, verify_token, Claims};
 pub use session::{SessionManager, SessionData};
-pub use handlers::{handle_register, handle_login};
+pub use handlers::{handle_register, handle_login, verify_login, start_session, VerifiedLogin};
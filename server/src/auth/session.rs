@@ -6,6 +6,7 @@ pub struct SessionData {
     pub user_id: i64,
     pub username: String,
     pub character_id: Option<i64>,
+    pub is_gm: bool,  // Account may use GM commands
     pub token: String,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
@@ -18,6 +19,7 @@ impl SessionData {
             user_id,
             username,
             character_id: None,
+            is_gm: false,
             token: token.clone(),
             created_at: now,
             expires_at: now + Duration::hours(duration_hours),
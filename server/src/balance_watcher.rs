@@ -0,0 +1,49 @@
+use shared::balance::BalanceTables;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// How often the balance file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watch the balance file and hand every valid new version to the simulation.
+/// A broken edit is logged and ignored; the tables in use stay until the file is fixed.
+pub fn spawn_balance_watcher(path: PathBuf, updates: UnboundedSender<BalanceTables>) {
+    tokio::spawn(watch_balance_file(path, updates));
+}
+
+async fn watch_balance_file(path: PathBuf, updates: UnboundedSender<BalanceTables>) {
+    let mut last_modified = modified(&path).await;
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        let current = modified(&path).await;
+        if current == last_modified {
+            continue;
+        }
+        last_modified = current;
+
+        let text = match tokio::fs::read_to_string(&path).await {
+            Ok(text) => text,
+            Err(e) => {
+                log::error!("Cannot read balance file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        match BalanceTables::parse(&text) {
+            Ok(tables) => {
+                log::info!("Balance file {} changed, reloading", path.display());
+                if updates.send(tables).is_err() {
+                    break; // Simulation has shut down
+                }
+            }
+            Err(e) => log::error!("Balance file not reloaded: {}", e),
+        }
+    }
+    log::info!("Balance watcher stopped");
+}
+
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.and_then(|meta| meta.modified()).ok()
+}
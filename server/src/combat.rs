@@ -0,0 +1,462 @@
+use shared::bevy::prelude::Vec3;
+use shared::balance::RegenRates;
+use shared::items::Equipment;
+use shared::{CharacterData, SkillEffect, SkillId, SkillInfo, Vitals};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use crate::status::{self, EffectOrigin, StatusEffects};
+
+pub use shared::EffectKind;
+
+/// Skill power (damage and healing) before multipliers at level 1
+const BASE_POWER: f32 = 10.0;
+
+/// Skill power gained per character level
+const POWER_PER_LEVEL: f32 = 3.0;
+
+/// Defense that halves incoming damage; more defense keeps helping, but less and less
+const DEFENSE_HALVING: f32 = 50.0;
+
+/// Extra range allowed on targeted skills (the target kept moving while the request travelled)
+const RANGE_TOLERANCE: f32 = 1.0;
+
+/// How long Letzte Bastion stays ready to catch a lethal hit
+pub const LAST_STAND_WINDOW: Duration = Duration::from_secs(10);
+
+/// +100% defense once Letzte Bastion has triggered
+const LAST_STAND_DEFENSE_DURATION: Duration = Duration::from_secs(5);
+
+/// Maximum stamina of combatants created without a character (level 1 value)
+const DEFAULT_MAX_STAMINA: f32 = 100.0;
+
+/// A combatant counts as in combat this long after it last dealt or took damage
+pub const COMBAT_DURATION: Duration = Duration::from_secs(5);
+
+/// Why a skill could not be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastError {
+    Dead,
+    Stunned,
+    NotUnlocked { required_level: i32 },
+    WrongSpecialization,
+    OnCooldown { remaining: f32 },
+    NotEnoughMana { required: f32, available: f32 },
+    NoTarget,
+    InvalidTarget,
+    TargetDead,
+    TargetAlive,
+    OutOfRange { distance: f32, range: f32 },
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastError::Dead => write!(f, "You are dead"),
+            CastError::Stunned => write!(f, "You are stunned"),
+            CastError::NotUnlocked { required_level } => write!(f, "Requires level {}", required_level),
+            CastError::WrongSpecialization => write!(f, "Skill belongs to another specialization"),
+            CastError::OnCooldown { remaining } => write!(f, "Skill is on cooldown ({:.1}s)", remaining),
+            CastError::NotEnoughMana { required, available } => {
+                write!(f, "Not enough mana ({:.0}/{:.0})", available, required)
+            }
+            CastError::NoTarget => write!(f, "No target selected"),
+            CastError::InvalidTarget => write!(f, "Invalid target"),
+            CastError::TargetDead => write!(f, "Target is dead"),
+            CastError::TargetAlive => write!(f, "Target is not dead"),
+            CastError::OutOfRange { distance, range } => {
+                write!(f, "Target is out of range ({:.1}m, max {:.1}m)", distance, range)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodicKind {
+    Damage,
+    Heal,
+}
+
+impl PeriodicKind {
+    fn effect_kind(self) -> EffectKind {
+        match self {
+            PeriodicKind::Damage => EffectKind::DamageOverTime,
+            PeriodicKind::Heal => EffectKind::HealOverTime,
+        }
+    }
+}
+
+/// One tick of a damage or heal over time, as applied by [`Combatant::apply_periodic`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodicTick {
+    pub source: u64,
+    pub skill: SkillId,
+    pub kind: PeriodicKind,
+    pub amount: f32,
+}
+
+/// Health, mana, stamina, cooldowns and status effects of anything that takes part in combat
+#[derive(Debug, Clone)]
+pub struct Combatant {
+    pub health: f32,
+    pub max_health: f32,
+    pub mana: f32,
+    pub max_mana: f32,
+    pub stamina: f32,
+    pub max_stamina: f32,
+    pub attack: f32,     // From equipment: added to skill power for damage
+    pub defense: f32,    // From equipment: reduces all incoming damage
+    pub status: StatusEffects,
+    cooldowns: HashMap<SkillId, Instant>,    // Skill -> ready again at
+    in_combat_until: Option<Instant>,        // Set by dealing or taking damage
+}
+
+impl Combatant {
+    /// Full health and mana
+    pub fn new(max_health: f32, max_mana: f32) -> Self {
+        Self {
+            health: max_health,
+            max_health,
+            mana: max_mana,
+            max_mana,
+            stamina: DEFAULT_MAX_STAMINA,
+            max_stamina: DEFAULT_MAX_STAMINA,
+            attack: 0.0,
+            defense: 0.0,
+            status: StatusEffects::default(),
+            cooldowns: HashMap::new(),
+            in_combat_until: None,
+        }
+    }
+
+    /// Full health, mana and stamina for the character's level, class and equipment
+    pub fn for_character(character: &CharacterData, equipment: &Equipment) -> Self {
+        let (max_health, max_mana, max_stamina) = shared::calculate_stats(character.level, &character.class, equipment);
+        let stats = equipment.stats();
+        Self {
+            stamina: max_stamina,
+            max_stamina,
+            attack: stats.attack,
+            defense: stats.defense,
+            ..Self::new(max_health, max_mana)
+        }
+    }
+
+    /// Take on the maximums, attack and defense of changed equipment (or balance tables)
+    /// without refilling
+    pub fn set_equipment(&mut self, character: &CharacterData, equipment: &Equipment) {
+        let (max_health, max_mana, max_stamina) = shared::calculate_stats(character.level, &character.class, equipment);
+        self.set_max_vitals(max_health, max_mana, max_stamina);
+        let stats = equipment.stats();
+        self.attack = stats.attack;
+        self.defense = stats.defense;
+    }
+
+    /// Change the maximums (level change) and refill
+    pub fn reset_vitals(&mut self, max_health: f32, max_mana: f32, max_stamina: f32) {
+        self.max_health = max_health;
+        self.health = max_health;
+        self.max_mana = max_mana;
+        self.mana = max_mana;
+        self.max_stamina = max_stamina;
+        self.stamina = max_stamina;
+    }
+
+    /// New maximums without refilling; current values are capped to them
+    pub fn set_max_vitals(&mut self, max_health: f32, max_mana: f32, max_stamina: f32) {
+        self.max_health = max_health;
+        self.health = self.health.min(max_health);
+        self.max_mana = max_mana;
+        self.mana = self.mana.min(max_mana);
+        self.max_stamina = max_stamina;
+        self.stamina = self.stamina.min(max_stamina);
+    }
+
+    pub fn vitals(&self) -> Vitals {
+        Vitals {
+            health: self.health,
+            max_health: self.max_health,
+            mana: self.mana,
+            max_mana: self.max_mana,
+            stamina: self.stamina,
+            max_stamina: self.max_stamina,
+        }
+    }
+
+    /// Dealing or taking damage keeps a combatant in combat for COMBAT_DURATION
+    pub fn enter_combat(&mut self, now: Instant) {
+        self.in_combat_until = Some(now + COMBAT_DURATION);
+    }
+
+    pub fn in_combat(&self, now: Instant) -> bool {
+        self.in_combat_until.is_some_and(|until| until > now)
+    }
+
+    /// Restore `seconds` worth of health, mana and stamina at the given rates
+    /// (fractions of the maximum per second). The dead don't regenerate.
+    pub fn regenerate(&mut self, rates: &RegenRates, seconds: f32) {
+        if !self.is_alive() {
+            return;
+        }
+        self.health = (self.health + self.max_health * rates.health * seconds).min(self.max_health);
+        self.mana = (self.mana + self.max_mana * rates.mana * seconds).min(self.max_mana);
+        self.stamina = (self.stamina + self.max_stamina * rates.stamina * seconds).min(self.max_stamina);
+    }
+
+    /// Use up stamina, e.g. for sprinting. Never goes below zero.
+    pub fn spend_stamina(&mut self, amount: f32) {
+        self.stamina = (self.stamina - amount.max(0.0)).max(0.0);
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0.0
+    }
+
+    pub fn health_fraction(&self) -> f32 {
+        if self.max_health > 0.0 { self.health / self.max_health } else { 0.0 }
+    }
+
+    /// Seconds until `skill` can be used again (0 = ready)
+    pub fn cooldown_remaining(&self, skill: SkillId, now: Instant) -> f32 {
+        self.cooldowns.get(&skill)
+            .map(|ready_at| ready_at.saturating_duration_since(now).as_secs_f32())
+            .unwrap_or(0.0)
+    }
+
+    /// Pay for a validated cast and start its cooldown. Returns the cooldown in seconds.
+    pub fn begin_cast(&mut self, skill: SkillId, info: &SkillInfo, now: Instant) -> f32 {
+        self.mana = (self.mana - info.mana_cost).max(0.0);
+        let cooldown = info.cooldown / (1.0 + self.effect(EffectKind::AttackSpeed, now).unwrap_or(0.0));
+        self.cooldowns.insert(skill, now + Duration::from_secs_f32(cooldown.max(0.0)));
+        cooldown
+    }
+
+    /// Start or refresh an effect cast by `origin` (None = no caster). Returns false if
+    /// the combatant is immune to it or dead.
+    pub fn apply_effect_from(
+        &mut self,
+        origin: Option<EffectOrigin>,
+        kind: EffectKind,
+        magnitude: f32,
+        duration: Duration,
+        now: Instant,
+    ) -> bool {
+        self.is_alive() && self.status.apply(kind, origin, magnitude, duration, now)
+    }
+
+    /// Combined magnitude of an effect that is still running
+    pub fn effect(&self, kind: EffectKind, now: Instant) -> Option<f32> {
+        self.status.magnitude(kind, now)
+    }
+
+    pub fn has_effect(&self, kind: EffectKind, now: Instant) -> bool {
+        self.effect(kind, now).is_some()
+    }
+
+    pub fn is_stunned(&self, now: Instant) -> bool {
+        self.has_effect(EffectKind::Stun, now)
+    }
+
+    /// Factor applied to incoming damage by defense and the active buffs and debuffs
+    pub fn damage_taken_multiplier(&self, now: Instant) -> f32 {
+        let mut multiplier = defense_multiplier(self.defense);
+        if let Some(reduction) = self.effect(EffectKind::DamageReduction, now) {
+            multiplier *= 1.0 - reduction.clamp(0.0, 1.0);
+        }
+        if let Some(reduction) = self.effect(EffectKind::DefenseReduction, now) {
+            multiplier *= 1.0 + reduction;
+        }
+        if let Some(buff) = self.effect(EffectKind::DefenseBuff, now) {
+            multiplier /= 1.0 + buff;
+        }
+        multiplier
+    }
+
+    /// Factor applied to outgoing damage by the active buffs
+    pub fn damage_dealt_multiplier(&self, now: Instant) -> f32 {
+        1.0 + self.effect(EffectKind::DamageBuff, now).unwrap_or(0.0)
+    }
+
+    /// Apply incoming damage after buffs and debuffs. Returns the damage dealt.
+    pub fn take_damage(&mut self, amount: f32, now: Instant) -> f32 {
+        let amount = (amount * self.damage_taken_multiplier(now)).max(0.0);
+        self.enter_combat(now);
+        let before = self.health;
+        self.health = (self.health - amount).max(0.0);
+
+        // Letzte Bastion: survive the killing blow once
+        if self.health <= 0.0 {
+            if let Some(survive_with) = self.effect(EffectKind::LastStand, now) {
+                let origin = self.status.origin(EffectKind::LastStand, now);
+                self.status.remove(EffectKind::LastStand);
+                self.health = survive_with.max(1.0).min(self.max_health);
+                self.status.apply(EffectKind::DefenseBuff, origin, 1.0, LAST_STAND_DEFENSE_DURATION, now);
+            }
+        }
+
+        // Nothing lasts beyond death
+        if self.health <= 0.0 {
+            self.status.clear();
+        }
+        before - self.health
+    }
+
+    /// Restore health up to the maximum. The dead can't be healed. Returns the health gained.
+    pub fn heal(&mut self, amount: f32) -> f32 {
+        if !self.is_alive() {
+            return 0.0;
+        }
+        let before = self.health;
+        self.health = (self.health + amount.max(0.0)).min(self.max_health);
+        self.health - before
+    }
+
+    /// Restore mana up to the maximum. The dead gain nothing. Returns the mana gained.
+    pub fn restore_mana(&mut self, amount: f32) -> f32 {
+        if !self.is_alive() {
+            return 0.0;
+        }
+        let before = self.mana;
+        self.mana = (self.mana + amount.max(0.0)).min(self.max_mana);
+        self.mana - before
+    }
+
+    /// Bring a dead combatant back with a fraction of its maximum health. Returns the health gained.
+    pub fn resurrect(&mut self, health_fraction: f32) -> f32 {
+        if self.is_alive() {
+            return 0.0;
+        }
+        self.status.clear();
+        self.health = (self.max_health * health_fraction.clamp(0.0, 1.0)).max(1.0);
+        self.health
+    }
+
+    /// Start a damage or heal over time. Casting the same skill again refreshes it.
+    pub fn add_periodic(
+        &mut self,
+        source: u64,
+        skill: SkillId,
+        kind: PeriodicKind,
+        per_second: f32,
+        duration: f32,
+        now: Instant,
+    ) -> bool {
+        let per_tick = per_second * status::TICK_INTERVAL.as_secs_f32();
+        let origin = EffectOrigin { source, skill };
+        self.apply_effect_from(Some(origin), kind.effect_kind(), per_tick, Duration::from_secs_f32(duration), now)
+    }
+
+    /// Apply every damage and heal over time that is due
+    pub fn apply_periodic(&mut self, now: Instant) -> Vec<PeriodicTick> {
+        let mut ticks = Vec::new();
+        for tick in self.status.due_ticks(now) {
+            let (kind, amount) = match tick.kind {
+                EffectKind::HealOverTime => (PeriodicKind::Heal, self.heal(tick.amount)),
+                _ => (PeriodicKind::Damage, self.take_damage(tick.amount, now)),
+            };
+            ticks.push(PeriodicTick { source: tick.origin.source, skill: tick.origin.skill, kind, amount });
+            if !self.is_alive() {
+                break;
+            }
+        }
+        ticks
+    }
+}
+
+/// Whether the character has reached the skill's level and owns it through its specialization
+pub fn check_unlocked(character: &CharacterData, skill: SkillId, info: &SkillInfo) -> Result<(), CastError> {
+    let owns_skill = character.specialization.is_some_and(|spec| spec.skills().contains(&skill));
+    if !owns_skill {
+        return Err(CastError::WrongSpecialization);
+    }
+    if character.level < info.required_level {
+        return Err(CastError::NotUnlocked { required_level: info.required_level });
+    }
+    Ok(())
+}
+
+/// Target of a cast as seen by the validation
+pub struct CastTarget<'a> {
+    pub position: Vec3,
+    pub combatant: &'a Combatant,
+}
+
+/// Run every check for casting `skill` and return its data if the cast may go ahead.
+/// Checks: caster can act, skill unlocked, cooldown, mana, then target and range.
+pub fn validate_cast(
+    character: &CharacterData,
+    caster: &Combatant,
+    caster_position: Vec3,
+    skill: SkillId,
+    target: Option<CastTarget>,
+    now: Instant,
+) -> Result<SkillInfo, CastError> {
+    let info = skill.info();
+
+    if !caster.is_alive() {
+        return Err(CastError::Dead);
+    }
+    if caster.is_stunned(now) {
+        return Err(CastError::Stunned);
+    }
+
+    check_unlocked(character, skill, &info)?;
+
+    let remaining = caster.cooldown_remaining(skill, now);
+    if remaining > 0.0 {
+        return Err(CastError::OnCooldown { remaining });
+    }
+
+    if caster.mana < info.mana_cost {
+        return Err(CastError::NotEnoughMana { required: info.mana_cost, available: caster.mana });
+    }
+
+    if info.needs_target() {
+        let target = target.ok_or(CastError::NoTarget)?;
+        if target.combatant.has_effect(EffectKind::Invisible, now) {
+            return Err(CastError::InvalidTarget);
+        }
+        let resurrect = matches!(info.effect, SkillEffect::Resurrect(_));
+        if resurrect && target.combatant.is_alive() {
+            return Err(CastError::TargetAlive);
+        }
+        if !resurrect && !target.combatant.is_alive() {
+            return Err(CastError::TargetDead);
+        }
+        let distance = caster_position.distance(target.position);
+        if distance > info.range + RANGE_TOLERANCE {
+            return Err(CastError::OutOfRange { distance, range: info.range });
+        }
+    }
+
+    Ok(info)
+}
+
+/// Damage or healing of a 1.0 multiplier for a character of `level`
+pub fn skill_power(level: i32) -> f32 {
+    BASE_POWER + POWER_PER_LEVEL * (level - 1).max(0) as f32
+}
+
+/// Raw damage of a skill cast by a character of `level` with `attack` from equipment,
+/// before buffs and the target's modifiers
+pub fn skill_damage(level: i32, attack: f32, info: &SkillInfo) -> f32 {
+    (skill_power(level) + attack.max(0.0)) * info.damage_multiplier
+}
+
+/// Share of incoming damage that gets through `defense`
+pub fn defense_multiplier(defense: f32) -> f32 {
+    DEFENSE_HALVING / (DEFENSE_HALVING + defense.max(0.0))
+}
+
+/// Horizontal distance from `point` to the line segment `start`-`end`
+pub fn distance_to_segment(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+    let flat = |v: Vec3| Vec3::new(v.x, 0.0, v.z);
+    let (point, start, end) = (flat(point), flat(start), flat(end));
+    let segment = end - start;
+    let length_sq = segment.length_squared();
+    if length_sq <= f32::EPSILON {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(segment) / length_sq).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
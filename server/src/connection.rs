@@ -0,0 +1,76 @@
+use shared::transport::ReliableEndpoint;
+use std::time::{Duration, Instant};
+
+/// Handshake progress of a client address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Opened for a `Connect`, no challenge handed out yet
+    AwaitingConnect,
+    /// `Connect` accepted, waiting for the client to echo the challenge
+    Challenged { challenge: u64 },
+    /// Handshake complete - gameplay messages are accepted
+    Connected,
+}
+
+/// Everything the server tracks per client address
+pub struct ClientConnection {
+    pub endpoint: ReliableEndpoint,
+    pub state: ConnectionState,
+    /// When the last datagram from this client arrived
+    pub last_received: Instant,
+    /// When we last sent a heartbeat to this client
+    pub last_heartbeat_sent: Instant,
+}
+
+impl ClientConnection {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            endpoint: ReliableEndpoint::new(),
+            state: ConnectionState::AwaitingConnect,
+            last_received: now,
+            last_heartbeat_sent: now,
+        }
+    }
+
+    /// Restart the handshake with a fresh reliable channel (client reconnected)
+    pub fn challenge(&mut self, challenge: u64) {
+        self.endpoint = ReliableEndpoint::new();
+        self.state = ConnectionState::Challenged { challenge };
+    }
+
+    /// Answer a `Connect`: a client still answering keeps its challenge (the first
+    /// one may have been lost), anyone else starts over with `new_challenge`.
+    /// Returns the challenge to send.
+    pub fn connect(&mut self, new_challenge: u64) -> u64 {
+        match self.state {
+            ConnectionState::Challenged { challenge } => challenge,
+            _ => {
+                self.challenge(new_challenge);
+                new_challenge
+            }
+        }
+    }
+
+    /// Complete the handshake if the client echoed the right challenge
+    pub fn accept(&mut self, challenge: u64) -> bool {
+        match self.state {
+            ConnectionState::Challenged { challenge: expected } if expected == challenge => {
+                self.state = ConnectionState::Connected;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == ConnectionState::Connected
+    }
+
+    pub fn is_timed_out(&self, now: Instant, timeout: Duration) -> bool {
+        now.duration_since(self.last_received) > timeout
+    }
+
+    pub fn heartbeat_due(&self, now: Instant, interval: Duration) -> bool {
+        now.duration_since(self.last_heartbeat_sent) >= interval
+    }
+}
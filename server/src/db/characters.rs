@@ -22,6 +22,7 @@ pub struct Character {
     pub created_at: DateTime<Utc>,
     pub last_played: Option<DateTime<Utc>>,
     pub specialization: Option<String>,
+    pub gold: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -105,7 +106,7 @@ pub async fn load_character(
                pos_x, pos_y, pos_z,
                skin_color_r, skin_color_g, skin_color_b,
                hair_color_r, hair_color_g, hair_color_b,
-               created_at, last_played, specialization
+               created_at, last_played, specialization, gold
         FROM characters
         WHERE id = ?1
         "#
@@ -133,6 +134,7 @@ pub async fn load_character(
         created_at: r.get(15),
         last_played: r.get(16),
         specialization: r.get(17),
+        gold: r.get(18),
     }))
 }
 
@@ -0,0 +1,115 @@
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use shared::items::{EquipmentSlot, ItemId, ItemStack};
+
+/// Load a character's occupied inventory slots as (slot, stack)
+pub async fn load_inventory(
+    pool: &SqlitePool,
+    character_id: i64,
+) -> Result<Vec<(usize, ItemStack)>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT slot, item_id, count FROM inventory_items WHERE character_id = ?1 ORDER BY slot"
+    )
+    .bind(character_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| {
+        let slot: i64 = r.get(0);
+        let item_id: i64 = r.get(1);
+        let count: i64 = r.get(2);
+        (slot as usize, ItemStack { item: ItemId(item_id as u32), count: count as u32 })
+    }).collect())
+}
+
+/// Load a character's equipped items. Slots this version doesn't know are skipped.
+pub async fn load_equipment(
+    pool: &SqlitePool,
+    character_id: i64,
+) -> Result<Vec<(EquipmentSlot, ItemId)>, sqlx::Error> {
+    let rows = sqlx::query("SELECT slot, item_id FROM equipped_items WHERE character_id = ?1")
+        .bind(character_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.into_iter().filter_map(|r| {
+        let slot: String = r.get(0);
+        let item_id: i64 = r.get(1);
+        EquipmentSlot::from_string(&slot).map(|slot| (slot, ItemId(item_id as u32)))
+    }).collect())
+}
+
+/// Replace a character's inventory, equipment and gold in one transaction
+pub async fn save_inventory(
+    pool: &SqlitePool,
+    character_id: i64,
+    items: &[(usize, ItemStack)], // (slot, stack)
+    equipment: &[(EquipmentSlot, ItemId)],
+    gold: u64,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    write_inventory(&mut tx, character_id, items, equipment, gold).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A character's whole inventory as saved: (character_id, slots, equipment, gold)
+pub type SavedInventory = (i64, Vec<(usize, ItemStack)>, Vec<(EquipmentSlot, ItemId)>, u64);
+
+/// Replace the inventories of several characters in one transaction, so items and gold
+/// traded between players are never saved on both sides or on neither
+pub async fn save_inventories(pool: &SqlitePool, inventories: &[SavedInventory]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for (character_id, items, equipment, gold) in inventories {
+        write_inventory(&mut tx, *character_id, items, equipment, *gold).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace a character's inventory, equipment and gold inside the caller's transaction
+pub(super) async fn write_inventory(
+    conn: &mut SqliteConnection,
+    character_id: i64,
+    items: &[(usize, ItemStack)], // (slot, stack)
+    equipment: &[(EquipmentSlot, ItemId)],
+    gold: u64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM inventory_items WHERE character_id = ?1")
+        .bind(character_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (slot, stack) in items {
+        sqlx::query(
+            "INSERT INTO inventory_items (character_id, slot, item_id, count) VALUES (?1, ?2, ?3, ?4)"
+        )
+        .bind(character_id)
+        .bind(*slot as i64)
+        .bind(stack.item.0 as i64)
+        .bind(stack.count as i64)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM equipped_items WHERE character_id = ?1")
+        .bind(character_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (slot, item) in equipment {
+        sqlx::query("INSERT INTO equipped_items (character_id, slot, item_id) VALUES (?1, ?2, ?3)")
+            .bind(character_id)
+            .bind(slot.as_str())
+            .bind(item.0 as i64)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query("UPDATE characters SET gold = ?1 WHERE id = ?2")
+        .bind(gold as i64)
+        .bind(character_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
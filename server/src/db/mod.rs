@@ -1,5 +1,7 @@
 pub mod users;
 pub mod characters;
+pub mod items;
+pub mod shops;
 
 use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
 use sqlx::Row;
@@ -86,6 +88,59 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         .await?;
     log::info!("Migration 003_add_specialization completed");
 
+    // Migration 004: GM flag for accounts (only settable in the database)
+    let column_exists = sqlx::query("PRAGMA table_info(users)")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .any(|row| {
+            let name: String = row.get(1);
+            name == "is_gm"
+        });
+
+    if !column_exists {
+        sqlx::query("ALTER TABLE users ADD COLUMN is_gm INTEGER NOT NULL DEFAULT 0")
+            .execute(pool)
+            .await?;
+        log::info!("Migration 004_add_gm_flag: Column added");
+    } else {
+        log::info!("Migration 004_add_gm_flag: Column already exists");
+    }
+
+    // Migration 005: Inventory slots and gold
+    sqlx::query(include_str!("../../migrations/005_create_inventory.sql"))
+        .execute(pool)
+        .await?;
+
+    let column_exists = sqlx::query("PRAGMA table_info(characters)")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .any(|row| {
+            let name: String = row.get(1);
+            name == "gold"
+        });
+
+    if !column_exists {
+        sqlx::query("ALTER TABLE characters ADD COLUMN gold INTEGER NOT NULL DEFAULT 0")
+            .execute(pool)
+            .await?;
+        log::info!("Migration 005_create_inventory: Gold column added");
+    }
+    log::info!("Migration 005_create_inventory completed");
+
+    // Migration 006: Equipment
+    sqlx::query(include_str!("../../migrations/006_create_equipment.sql"))
+        .execute(pool)
+        .await?;
+    log::info!("Migration 006_create_equipment completed");
+
+    // Migration 007: Shop transactions
+    sqlx::query(include_str!("../../migrations/007_create_shop_transactions.sql"))
+        .execute(pool)
+        .await?;
+    log::info!("Migration 007_create_shop_transactions completed");
+
     log::info!("All migrations completed successfully");
     Ok(())
 }
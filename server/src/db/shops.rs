@@ -0,0 +1,31 @@
+use sqlx::SqlitePool;
+use shared::items::{EquipmentSlot, ItemId, ItemStack, ShopId};
+
+/// Save a character's inventory after trading with a merchant, together with a record
+/// of the trade, in one transaction: either both are written or neither
+pub async fn save_trade(
+    pool: &SqlitePool,
+    character_id: i64,
+    items: &[(usize, ItemStack)], // (slot, stack)
+    equipment: &[(EquipmentSlot, ItemId)],
+    gold: u64,
+    trade: (ShopId, ItemId, i64, i64), // (shop, item, count, gold)
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    super::items::write_inventory(&mut tx, character_id, items, equipment, gold).await?;
+
+    let (shop, item, count, price) = trade;
+    sqlx::query(
+        "INSERT INTO shop_transactions (character_id, shop_id, item_id, count, gold) VALUES (?1, ?2, ?3, ?4, ?5)"
+    )
+    .bind(character_id)
+    .bind(shop.0 as i64)
+    .bind(item.0 as i64)
+    .bind(count)
+    .bind(price)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
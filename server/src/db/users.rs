@@ -9,6 +9,7 @@ pub struct User {
     pub email: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
+    pub is_gm: bool,  // Game master: may use GM commands
 }
 
 /// Create a new user
@@ -36,7 +37,7 @@ pub async fn find_by_username(
     username: &str,
 ) -> Result<Option<User>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT id, username, password_hash, email, created_at, last_login FROM users WHERE username = ?1"
+        "SELECT id, username, password_hash, email, created_at, last_login, is_gm FROM users WHERE username = ?1"
     )
     .bind(username)
     .fetch_optional(pool)
@@ -49,6 +50,7 @@ pub async fn find_by_username(
         email: r.get(3),
         created_at: r.get(4),
         last_login: r.get(5),
+        is_gm: r.get(6),
     }))
 }
 
@@ -58,7 +60,7 @@ pub async fn find_by_id(
     user_id: i64,
 ) -> Result<Option<User>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT id, username, password_hash, email, created_at, last_login FROM users WHERE id = ?1"
+        "SELECT id, username, password_hash, email, created_at, last_login, is_gm FROM users WHERE id = ?1"
     )
     .bind(user_id)
     .fetch_optional(pool)
@@ -71,6 +73,7 @@ pub async fn find_by_id(
         email: r.get(3),
         created_at: r.get(4),
         last_login: r.get(5),
+        is_gm: r.get(6),
     }))
 }
 
@@ -102,3 +105,18 @@ pub async fn username_exists(
     let count: i64 = row.get(0);
     Ok(count > 0)
 }
+
+/// Grant or revoke GM rights (no in-game way to do this)
+pub async fn set_gm(
+    pool: &SqlitePool,
+    user_id: i64,
+    is_gm: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET is_gm = ?1 WHERE id = ?2")
+        .bind(is_gm)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+/// Allocates network entity IDs for everything replicated to clients.
+///
+/// IDs are handed out sequentially and never reused while the server is running,
+/// so a late packet about a despawned entity can never be mistaken for a new one.
+/// `0` is reserved as "no entity".
+#[derive(Debug)]
+pub struct EntityIdAllocator {
+    next_id: u64,
+}
+
+impl EntityIdAllocator {
+    pub fn new() -> Self {
+        Self { next_id: 1 }
+    }
+
+    /// Allocate a fresh ID
+    pub fn allocate(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+impl Default for EntityIdAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
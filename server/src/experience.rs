@@ -0,0 +1,63 @@
+// Experience and levels.
+//
+// Players only gain experience from server events: killing mobs (scaled by how the
+// mob's level compares to theirs) and, once quests exist, quest rewards. GM accounts
+// can additionally grant or take away levels for testing. Dying costs a share of the
+// experience towards the next level (see `balance::DeathBalance`).
+
+/// Highest character level
+pub const MAX_LEVEL: i32 = 100;
+
+/// Bonus per level a mob is above the player, up to `MAX_BONUS_LEVELS`
+const BONUS_PER_LEVEL: f32 = 0.1;
+const MAX_BONUS_LEVELS: i32 = 5;
+
+/// Reduction per level a mob is below the player; 10 levels below gives nothing
+const PENALTY_PER_LEVEL: f32 = 0.1;
+
+/// Share of a mob's experience for a player `difference` levels below it (negative:
+/// the mob is weaker than the player)
+pub fn level_difference_factor(difference: i32) -> f32 {
+    if difference >= 0 {
+        1.0 + BONUS_PER_LEVEL * difference.min(MAX_BONUS_LEVELS) as f32
+    } else {
+        (1.0 + PENALTY_PER_LEVEL * difference as f32).max(0.0)
+    }
+}
+
+/// Experience for one player's share of a kill
+pub fn kill_experience(base: i64, mob_level: i32, player_level: i32) -> i64 {
+    (base as f32 * level_difference_factor(mob_level - player_level)).round() as i64
+}
+
+/// Add experience, leveling up as often as it covers. Returns the new level and the
+/// experience into that level. Experience keeps accumulating at the maximum level.
+pub fn add_experience(level: i32, experience: i64, amount: i64) -> (i32, i64) {
+    let mut level = level;
+    let mut experience = experience + amount.max(0);
+    while level < MAX_LEVEL {
+        let needed = shared::calculate_xp_for_level(level + 1);
+        if experience < needed {
+            break;
+        }
+        experience -= needed;
+        level += 1;
+    }
+    (level, experience)
+}
+
+/// Experience needed for the next level (0 at the maximum level)
+pub fn experience_needed(level: i32) -> i64 {
+    if level < MAX_LEVEL {
+        shared::calculate_xp_for_level(level + 1)
+    } else {
+        0
+    }
+}
+
+/// Experience lost on death: `fraction` of what the current level needs, but never
+/// more than the player has, so dying never costs a level
+pub fn death_penalty(level: i32, experience: i64, fraction: f32) -> i64 {
+    let penalty = (experience_needed(level) as f32 * fraction.max(0.0)).round() as i64;
+    penalty.clamp(0, experience.max(0))
+}
@@ -0,0 +1,199 @@
+use shared::bevy::prelude::Vec3;
+use std::collections::{HashMap, HashSet};
+
+/// Extra distance an entity must move past the view radius before it is dropped again.
+/// Prevents enter/leave spam for entities standing right on the edge.
+const LEAVE_MARGIN: f32 = 5.0;
+
+/// Visibility change for one observer, produced by [`InterestManager::update`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterestEvent {
+    /// `entity` came into range of `observer` and must be spawned on its client
+    Enter { observer: u64, entity: u64 },
+    /// `entity` went out of range of `observer` (or was removed) and must be despawned
+    Leave { observer: u64, entity: u64 },
+}
+
+/// Grid-based area-of-interest management for world replication.
+///
+/// Every replicated entity is bucketed into a 2D grid (X/Z plane). Observers (players)
+/// only get told about entities within `view_radius`, and the manager remembers what each
+/// observer currently sees so it can emit enter/leave events when that changes.
+#[derive(Debug)]
+pub struct InterestManager {
+    /// Grid cell size (in world units)
+    cell_size: f32,
+    /// Distance within which an entity becomes visible
+    view_radius: f32,
+    /// Map of cell coordinates to entities in that cell
+    cells: HashMap<(i32, i32), HashSet<u64>>,
+    /// Current position and cell of every tracked entity
+    entities: HashMap<u64, (Vec3, (i32, i32))>,
+    /// Entities each observer currently knows about
+    observers: HashMap<u64, HashSet<u64>>,
+}
+
+impl InterestManager {
+    /// Create a manager where observers see everything within `view_radius`
+    pub fn new(view_radius: f32) -> Self {
+        Self {
+            // One cell per view radius keeps queries to a 3x3 block of cells
+            cell_size: view_radius.max(1.0),
+            view_radius,
+            cells: HashMap::new(),
+            entities: HashMap::new(),
+            observers: HashMap::new(),
+        }
+    }
+
+    pub fn view_radius(&self) -> f32 {
+        self.view_radius
+    }
+
+    /// Change how far observers see. The grid is rebuilt for the new radius; what
+    /// observers see changes with the next `update`.
+    pub fn set_view_radius(&mut self, view_radius: f32) {
+        self.view_radius = view_radius;
+        self.cell_size = view_radius.max(1.0);
+        self.cells.clear();
+        let positions: Vec<(u64, Vec3)> = self.entities.iter().map(|(&entity, &(pos, _))| (entity, pos)).collect();
+        for (entity, pos) in positions {
+            let cell = self.world_to_cell(pos);
+            self.entities.insert(entity, (pos, cell));
+            self.cells.entry(cell).or_default().insert(entity);
+        }
+    }
+
+    /// Convert world position to grid cell coordinates
+    fn world_to_cell(&self, pos: Vec3) -> (i32, i32) {
+        let x = (pos.x / self.cell_size).floor() as i32;
+        let z = (pos.z / self.cell_size).floor() as i32;
+        (x, z)
+    }
+
+    /// Insert or move an entity
+    pub fn update_entity(&mut self, entity: u64, pos: Vec3) {
+        let new_cell = self.world_to_cell(pos);
+
+        if let Some((old_pos, old_cell)) = self.entities.get_mut(&entity) {
+            *old_pos = pos;
+            if *old_cell == new_cell {
+                return;
+            }
+            let old_cell = std::mem::replace(old_cell, new_cell);
+            if let Some(cell) = self.cells.get_mut(&old_cell) {
+                cell.remove(&entity);
+                if cell.is_empty() {
+                    self.cells.remove(&old_cell);
+                }
+            }
+        } else {
+            self.entities.insert(entity, (pos, new_cell));
+        }
+
+        self.cells.entry(new_cell).or_default().insert(entity);
+    }
+
+    /// Stop tracking an entity. Returns a leave event for every observer that could see it.
+    pub fn remove_entity(&mut self, entity: u64) -> Vec<InterestEvent> {
+        if let Some((_, cell)) = self.entities.remove(&entity) {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.remove(&entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+
+        // A removed entity no longer observes anything either
+        self.observers.remove(&entity);
+
+        let mut events = Vec::new();
+        for (&observer, visible) in self.observers.iter_mut() {
+            if visible.remove(&entity) {
+                events.push(InterestEvent::Leave { observer, entity });
+            }
+        }
+        events
+    }
+
+    /// Start computing visibility for `observer`. It must also be tracked as an entity.
+    pub fn add_observer(&mut self, observer: u64) {
+        self.observers.entry(observer).or_default();
+    }
+
+    /// Current position of a tracked entity
+    pub fn position(&self, entity: u64) -> Option<Vec3> {
+        self.entities.get(&entity).map(|(pos, _)| *pos)
+    }
+
+    /// All entities within `radius` of `pos` (horizontal distance)
+    pub fn query_radius(&self, pos: Vec3, radius: f32) -> Vec<u64> {
+        let center = self.world_to_cell(pos);
+        let cell_radius = (radius / self.cell_size).ceil() as i32;
+        let radius_sq = radius * radius;
+
+        let mut result = Vec::new();
+        for dx in -cell_radius..=cell_radius {
+            for dz in -cell_radius..=cell_radius {
+                let Some(cell) = self.cells.get(&(center.0 + dx, center.1 + dz)) else { continue };
+                for &entity in cell {
+                    let (entity_pos, _) = self.entities[&entity];
+                    if horizontal_distance_sq(pos, entity_pos) <= radius_sq {
+                        result.push(entity);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Entities `observer` currently knows about
+    pub fn visible_to(&self, observer: u64) -> impl Iterator<Item = u64> + '_ {
+        self.observers.get(&observer).into_iter().flat_map(|set| set.iter().copied())
+    }
+
+    /// Recompute visibility for every observer and return what changed
+    pub fn update(&mut self) -> Vec<InterestEvent> {
+        let leave_radius_sq = (self.view_radius + LEAVE_MARGIN).powi(2);
+        let mut events = Vec::new();
+
+        let observer_ids: Vec<u64> = self.observers.keys().copied().collect();
+        for observer in observer_ids {
+            let Some(observer_pos) = self.position(observer) else { continue };
+
+            let in_range: HashSet<u64> = self.query_radius(observer_pos, self.view_radius)
+                .into_iter()
+                .filter(|&entity| entity != observer)
+                .collect();
+
+            let entities = &self.entities;
+            let visible = self.observers.get_mut(&observer).unwrap();
+
+            // Drop entities that moved clearly out of range (or vanished)
+            visible.retain(|entity| {
+                let keep = entities.get(entity).is_some_and(|(pos, _)| {
+                    horizontal_distance_sq(observer_pos, *pos) <= leave_radius_sq
+                });
+                if !keep {
+                    events.push(InterestEvent::Leave { observer, entity: *entity });
+                }
+                keep
+            });
+
+            for entity in in_range {
+                if visible.insert(entity) {
+                    events.push(InterestEvent::Enter { observer, entity });
+                }
+            }
+        }
+
+        events
+    }
+}
+
+fn horizontal_distance_sq(a: Vec3, b: Vec3) -> f32 {
+    let dx = a.x - b.x;
+    let dz = a.z - b.z;
+    dx * dx + dz * dz
+}
@@ -0,0 +1,282 @@
+// Character inventories: a fixed number of slots holding item stacks, the equipped
+// items and gold.
+//
+// Every change goes through here and either happens completely or not at all. A slot
+// holds one kind of item, never more than its stack size and never zero of it. The
+// server sends the owner the whole inventory after each change and saves it.
+
+use shared::items::{Equipment, EquipmentSlot, ItemEffect, ItemId, ItemStack, Loot, INVENTORY_SLOTS};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Consumables share one cooldown, so potions can't be chained
+pub const ITEM_USE_COOLDOWN: Duration = Duration::from_secs(2);
+
+/// What new characters start with
+pub const STARTER_ITEMS: [(ItemId, u32); 3] = [
+    (ItemId(1), 5),    // Kleiner Heiltrank
+    (ItemId(3), 3),    // Kleiner Manatrank
+    (ItemId(200), 1),  // Rostiges Schwert
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemError {
+    InvalidSlot,
+    EmptySlot,
+    SlotOccupied,
+    InvalidCount,
+    UnknownItem,
+    Full,
+    NotUsable,
+    NotEquipment,
+    NothingEquipped,
+    RequiresLevel { required_level: i32 },
+    OnCooldown { remaining: f32 },
+    Dead,
+}
+
+impl fmt::Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemError::InvalidSlot => write!(f, "Invalid inventory slot"),
+            ItemError::EmptySlot => write!(f, "Slot is empty"),
+            ItemError::SlotOccupied => write!(f, "Target slot is not empty"),
+            ItemError::InvalidCount => write!(f, "Invalid item count"),
+            ItemError::UnknownItem => write!(f, "Unknown item"),
+            ItemError::Full => write!(f, "Inventory is full"),
+            ItemError::NotUsable => write!(f, "Item can't be used"),
+            ItemError::NotEquipment => write!(f, "Item can't be equipped"),
+            ItemError::NothingEquipped => write!(f, "Nothing equipped there"),
+            ItemError::RequiresLevel { required_level } => write!(f, "Requires level {}", required_level),
+            ItemError::OnCooldown { remaining } => write!(f, "Items are on cooldown ({:.1}s)", remaining),
+            ItemError::Dead => write!(f, "You are dead"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    equipment: Equipment,
+    pub gold: u64,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![None; INVENTORY_SLOTS],
+            equipment: Equipment::default(),
+            gold: 0,
+        }
+    }
+}
+
+impl Inventory {
+    /// Rebuild from saved slots and equipment. Stacks of items that no longer exist, in
+    /// slots out of range or over their stack size are dropped with a warning, and so is
+    /// equipment that doesn't fit its slot (any more).
+    pub fn from_saved(
+        items: impl IntoIterator<Item = (usize, ItemStack)>,
+        equipment: impl IntoIterator<Item = (EquipmentSlot, ItemId)>,
+        gold: u64,
+    ) -> Self {
+        let mut inventory = Self { gold, ..Self::default() };
+        for (slot, item) in equipment {
+            let fits = item.info().is_some_and(|info| info.item_type.equipment_slot() == Some(slot));
+            if !fits || inventory.equipment.get(slot).is_some() {
+                log::warn!("Dropping invalid equipment {:?} in slot {:?}", item, slot);
+                continue;
+            }
+            inventory.equipment.set(slot, Some(item));
+        }
+        for (slot, stack) in items {
+            let stack_size = stack_size(stack.item);
+            let valid = slot < INVENTORY_SLOTS && stack.count > 0 && stack_size.is_some_and(|max| stack.count <= max);
+            if !valid || inventory.slots[slot].is_some() {
+                log::warn!("Dropping invalid inventory entry {:?} in slot {}", stack, slot);
+                continue;
+            }
+            inventory.slots[slot] = Some(stack);
+        }
+        inventory
+    }
+
+    /// Inventory of a new character
+    pub fn starter() -> Self {
+        let mut inventory = Self::default();
+        for (item, count) in STARTER_ITEMS {
+            if let Err(e) = inventory.add(item, count) {
+                log::error!("Starter item {:?}: {}", item, e);
+            }
+        }
+        inventory
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<ItemStack> {
+        self.slots.get(slot).copied().flatten()
+    }
+
+    pub fn equipment(&self) -> &Equipment {
+        &self.equipment
+    }
+
+    /// Whether `count` more of `item` fit
+    pub fn has_room(&self, item: ItemId, count: u32) -> bool {
+        let Some(stack_size) = stack_size(item) else { return false };
+        let room: u64 = self.slots.iter().map(|slot| match slot {
+            None => stack_size as u64,
+            Some(stack) if stack.item == item => stack_size.saturating_sub(stack.count) as u64,
+            Some(_) => 0,
+        }).sum();
+        room >= count as u64
+    }
+
+    /// Put items in: topping up existing stacks first, then into the first empty slots
+    pub fn add(&mut self, item: ItemId, count: u32) -> Result<(), ItemError> {
+        let stack_size = stack_size(item).ok_or(ItemError::UnknownItem)?;
+        if count == 0 {
+            return Err(ItemError::InvalidCount);
+        }
+        if !self.has_room(item, count) {
+            return Err(ItemError::Full);
+        }
+
+        let mut left = count;
+        for stack in self.slots.iter_mut().flatten().filter(|stack| stack.item == item) {
+            let moved = left.min(stack_size.saturating_sub(stack.count));
+            stack.count += moved;
+            left -= moved;
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if left == 0 {
+                break;
+            }
+            let moved = left.min(stack_size);
+            *slot = Some(ItemStack { item, count: moved });
+            left -= moved;
+        }
+        Ok(())
+    }
+
+    /// Put a picked up pile in: gold always fits, items like `add`
+    pub fn add_loot(&mut self, loot: Loot) -> Result<(), ItemError> {
+        match loot {
+            Loot::Gold(amount) => {
+                self.gold = self.gold.saturating_add(amount);
+                Ok(())
+            }
+            Loot::Item(stack) => self.add(stack.item, stack.count),
+        }
+    }
+
+    /// Move a stack onto another slot: into an empty slot, onto the same item (as much
+    /// as fits there) or swapping places with a different item
+    pub fn move_item(&mut self, from: usize, to: usize) -> Result<(), ItemError> {
+        self.check_slot(from)?;
+        self.check_slot(to)?;
+        let stack = self.slots[from].ok_or(ItemError::EmptySlot)?;
+        if from == to {
+            return Ok(());
+        }
+
+        match self.slots[to] {
+            Some(target) if target.item == stack.item => {
+                let stack_size = stack_size(stack.item).ok_or(ItemError::UnknownItem)?;
+                let moved = stack.count.min(stack_size.saturating_sub(target.count));
+                self.slots[to] = Some(ItemStack { count: target.count + moved, ..target });
+                self.slots[from] = (stack.count > moved).then_some(ItemStack { count: stack.count - moved, ..stack });
+            }
+            _ => self.slots.swap(from, to),
+        }
+        Ok(())
+    }
+
+    /// Split `count` items off a stack into an empty slot
+    pub fn split(&mut self, slot: usize, count: u32, to: usize) -> Result<(), ItemError> {
+        self.check_slot(slot)?;
+        self.check_slot(to)?;
+        let stack = self.slots[slot].ok_or(ItemError::EmptySlot)?;
+        if self.slots[to].is_some() {
+            return Err(ItemError::SlotOccupied);
+        }
+        if count == 0 || count >= stack.count {
+            return Err(ItemError::InvalidCount);
+        }
+        self.slots[slot] = Some(ItemStack { count: stack.count - count, ..stack });
+        self.slots[to] = Some(ItemStack { count, ..stack });
+        Ok(())
+    }
+
+    /// Take `count` items out of a slot (dropping, using, selling)
+    pub fn take(&mut self, slot: usize, count: u32) -> Result<ItemStack, ItemError> {
+        self.check_slot(slot)?;
+        let stack = self.slots[slot].ok_or(ItemError::EmptySlot)?;
+        if count == 0 || count > stack.count {
+            return Err(ItemError::InvalidCount);
+        }
+        self.slots[slot] = (stack.count > count).then_some(ItemStack { count: stack.count - count, ..stack });
+        Ok(ItemStack { count, ..stack })
+    }
+
+    /// Equip the item in `slot` if a character of `level` may wear it. Whatever was
+    /// equipped in its place goes into that slot.
+    pub fn equip(&mut self, slot: usize, level: i32) -> Result<(), ItemError> {
+        self.check_slot(slot)?;
+        let stack = self.get(slot).ok_or(ItemError::EmptySlot)?;
+        let info = stack.item.info().ok_or(ItemError::UnknownItem)?;
+        let equipment_slot = info.item_type.equipment_slot().ok_or(ItemError::NotEquipment)?;
+        if level < info.required_level {
+            return Err(ItemError::RequiresLevel { required_level: info.required_level });
+        }
+        let previous = self.equipment.set(equipment_slot, Some(stack.item));
+        self.slots[slot] = previous.map(|item| ItemStack { item, count: 1 });
+        Ok(())
+    }
+
+    /// Take off what is equipped in `equipment_slot` and put it into the first empty slot
+    pub fn unequip(&mut self, equipment_slot: EquipmentSlot) -> Result<(), ItemError> {
+        let item = self.equipment.get(equipment_slot).ok_or(ItemError::NothingEquipped)?;
+        let slot = self.slots.iter().position(Option::is_none).ok_or(ItemError::Full)?;
+        self.slots[slot] = Some(ItemStack { item, count: 1 });
+        self.equipment.set(equipment_slot, None);
+        Ok(())
+    }
+
+    /// What using the item in `slot` does, if a character of `level` who last used an
+    /// item at `last_use` can use it now. Doesn't take the item yet.
+    pub fn check_use(&self, slot: usize, level: i32, last_use: Option<Instant>, now: Instant) -> Result<ItemEffect, ItemError> {
+        self.check_slot(slot)?;
+        let stack = self.get(slot).ok_or(ItemError::EmptySlot)?;
+        let info = stack.item.info().ok_or(ItemError::UnknownItem)?;
+        let effect = info.use_effect.ok_or(ItemError::NotUsable)?;
+        if level < info.required_level {
+            return Err(ItemError::RequiresLevel { required_level: info.required_level });
+        }
+        if let Some(ready) = last_use.map(|last| last + ITEM_USE_COOLDOWN) {
+            if ready > now {
+                return Err(ItemError::OnCooldown { remaining: (ready - now).as_secs_f32() });
+            }
+        }
+        Ok(effect)
+    }
+
+    /// Slots with something in them, for saving
+    pub fn stacks(&self) -> Vec<(usize, ItemStack)> {
+        self.slots.iter().enumerate()
+            .filter_map(|(slot, stack)| stack.map(|stack| (slot, stack)))
+            .collect()
+    }
+
+    fn check_slot(&self, slot: usize) -> Result<(), ItemError> {
+        if slot < self.slots.len() { Ok(()) } else { Err(ItemError::InvalidSlot) }
+    }
+}
+
+/// Stack size of an item in the active balance tables; None for unknown items
+fn stack_size(item: ItemId) -> Option<u32> {
+    item.info().map(|info| info.stack_size)
+}
@@ -1,2 +1,20 @@
 pub mod db;
 pub mod auth;
+pub mod entity_id;
+pub mod interest;
+pub mod connection;
+pub mod movement;
+pub mod combat;
+pub mod status;
+pub mod party;
+pub mod experience;
+pub mod inventory;
+pub mod loot;
+pub mod shops;
+pub mod trade;
+pub mod mobs;
+pub mod mob_ai;
+pub mod navigation;
+pub mod persistence;
+pub mod network;
+pub mod balance_watcher;
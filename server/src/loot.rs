@@ -0,0 +1,153 @@
+// Loot: rolling mob loot tables and the piles lying on the ground.
+//
+// A dead mob's loot lands around its body, one ground item per gold drop or item
+// stack. For the first `OWNER_TIME` only the players who earned the kill (the tagging
+// player's party) may pick it up, afterwards anyone. Piles nobody wants despawn after
+// `DESPAWN_TIME`. Ground items are network entities like mobs: the interest manager
+// spawns and despawns them on clients, but they never move.
+
+use crate::mobs::Xorshift;
+use shared::balance::BalanceTables;
+use shared::bevy::prelude::Vec3;
+use shared::items::{ItemId, ItemStack, Loot, LootDrop, LootTable, Rarity};
+use std::f32::consts::TAU;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long only the owners may pick up a mob's loot
+pub const OWNER_TIME: Duration = Duration::from_secs(10);
+
+/// How long a pile stays on the ground
+pub const DESPAWN_TIME: Duration = Duration::from_secs(120);
+
+/// Furthest a player may stand from a pile to pick it up (meters)
+pub const PICKUP_RANGE: f32 = 3.0;
+
+/// Distance of the piles from the mob when it drops several (meters)
+const SCATTER_RADIUS: f32 = 0.8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LootError {
+    Gone,
+    TooFar,
+    NotYours { remaining: f32 },
+}
+
+impl fmt::Display for LootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LootError::Gone => write!(f, "Item is gone"),
+            LootError::TooFar => write!(f, "Too far away"),
+            LootError::NotYours { remaining } => write!(f, "Belongs to someone else for {:.0}s", remaining.ceil()),
+        }
+    }
+}
+
+/// A pile on the ground
+#[derive(Debug, Clone)]
+pub struct GroundItem {
+    pub id: u64,          // Network entity ID
+    pub loot: Loot,
+    pub position: Vec3,
+    owners: Vec<u64>,     // May pick it up before `free_at`; empty = anyone
+    free_at: Instant,
+    despawn_at: Instant,
+}
+
+impl GroundItem {
+    /// A pile only `owners` may pick up for `OWNER_TIME` (without owners, anyone may)
+    pub fn new(id: u64, loot: Loot, position: Vec3, owners: Vec<u64>, now: Instant) -> Self {
+        Self {
+            id,
+            loot,
+            position,
+            owners,
+            free_at: now + OWNER_TIME,
+            despawn_at: now + DESPAWN_TIME,
+        }
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now >= self.despawn_at
+    }
+
+    /// Whether `player` standing at `position` may pick the pile up now
+    pub fn check_pickup(&self, player: u64, position: Vec3, now: Instant) -> Result<(), LootError> {
+        // Piles lie on the ground, players report the middle of their body
+        let offset = position - self.position;
+        if Vec3::new(offset.x, 0.0, offset.z).length() > PICKUP_RANGE {
+            return Err(LootError::TooFar);
+        }
+        let reserved = !self.owners.is_empty() && !self.owners.contains(&player);
+        if reserved && now < self.free_at {
+            return Err(LootError::NotYours { remaining: (self.free_at - now).as_secs_f32() });
+        }
+        Ok(())
+    }
+}
+
+/// Roll a mob's loot: its gold, then one pick per roll. Items and counts come from
+/// `tables`; picks of unknown items or of a rarity without fitting equipment drop nothing.
+pub fn roll(tables: &BalanceTables, table: &LootTable, mob_level: i32, rng: &mut Xorshift) -> Vec<Loot> {
+    let mut drops = Vec::new();
+    let gold = random_between(rng, table.gold.0, table.gold.1);
+    if gold > 0 {
+        drops.push(Loot::Gold(gold as u64));
+    }
+
+    let total_weight: u32 = table.entries.iter().map(|entry| entry.weight).sum();
+    if total_weight == 0 {
+        return drops;
+    }
+    for _ in 0..table.rolls {
+        let mut pick = random_between(rng, 0, total_weight - 1);
+        let Some(entry) = table.entries.iter().find(|entry| {
+            let hit = pick < entry.weight;
+            pick = pick.saturating_sub(entry.weight);
+            hit
+        }) else { continue };
+
+        let stack = match entry.drop {
+            LootDrop::Nothing => None,
+            LootDrop::Item(item, least, most) => tables.item(item)
+                .map(|info| ItemStack { item, count: random_between(rng, least, most).min(info.stack_size) }),
+            LootDrop::Equipment(rarity) => random_equipment(tables, rarity, mob_level, rng)
+                .map(|item| ItemStack { item, count: 1 }),
+        };
+        drops.extend(stack.filter(|stack| stack.count > 0).map(Loot::Item));
+    }
+    drops
+}
+
+/// Where the `index`th of `count` piles lands around `center`, so they don't overlap
+pub fn scatter(center: Vec3, index: usize, count: usize) -> Vec3 {
+    if count <= 1 {
+        return center;
+    }
+    let angle = TAU * index as f32 / count as f32;
+    center + Vec3::new(angle.cos(), 0.0, angle.sin()) * SCATTER_RADIUS
+}
+
+/// Any equipment of `rarity` a character of `level` could wear
+fn random_equipment(tables: &BalanceTables, rarity: Rarity, level: i32, rng: &mut Xorshift) -> Option<ItemId> {
+    let mut candidates: Vec<ItemId> = tables.items.iter()
+        .filter(|(_, info)| info.item_type.is_equipment() && info.rarity == rarity && info.required_level <= level)
+        .map(|(&id, _)| id)
+        .collect();
+    // Map order differs between runs; the pick should only depend on the generator
+    candidates.sort();
+    if candidates.is_empty() {
+        return None;
+    }
+    let index = random_between(rng, 0, candidates.len() as u32 - 1);
+    Some(candidates[index as usize])
+}
+
+/// Random number in least..=most
+fn random_between(rng: &mut Xorshift, least: u32, most: u32) -> u32 {
+    if most <= least {
+        return least;
+    }
+    let span = (most - least) as f32 + 1.0;
+    least + ((rng.next_unit() * span) as u32).min(most - least)
+}
@@ -1,13 +1,55 @@
 mod db;
 mod auth;
+mod entity_id;
+mod interest;
+mod connection;
+mod movement;
+mod combat;
+mod status;
+mod party;
+mod experience;
+mod inventory;
+mod loot;
+mod shops;
+mod trade;
+mod mobs;
+mod mob_ai;
+mod navigation;
+mod persistence;
+mod network;
+mod balance_watcher;
 
-use shared::{ClientMessage, ServerMessage, AuthMessage, SERVER_ADDR};
-use sqlx::SqlitePool;
+use shared::{ClientMessage, ServerMessage, AuthMessage, SkillId, SkillInfo, SkillEffect, Vitals, PartyMember, SERVER_ADDR, PROTOCOL_ID, HEARTBEAT_INTERVAL, CONNECTION_TIMEOUT, PLAYER_MOVE_SPEED};
 use std::collections::HashMap;
-use std::net::{UdpSocket, SocketAddr};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Instant, Duration};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use auth::SessionManager;
-use shared::bevy::prelude::Vec3;
+use entity_id::EntityIdAllocator;
+use interest::{InterestManager, InterestEvent};
+use connection::ClientConnection;
+use movement::{MovementValidator, MoveCheck};
+use combat::{Combatant, CastError, CastTarget, EffectKind, PeriodicKind};
+use status::EffectOrigin;
+use party::PartyManager;
+use inventory::{Inventory, ItemError};
+use loot::{GroundItem, LootError};
+use shops::{ShopError, ShopStock, ShopTrade};
+use trade::{TradeError, TradeManager, TRADE_RANGE};
+use mobs::{Mob, MobSpawner, Xorshift};
+use mob_ai::MobAction;
+use navigation::NavGrid;
+use persistence::{DbJob, DbResult};
+use db::items::SavedInventory;
+use network::Datagram;
+use shared::balance::{self, BalanceTables};
+use shared::items::{EquipmentSlot, ItemEffect, ItemId, Loot, ShopId, ShopInfo};
+use shared::bevy::prelude::{Quat, Vec3};
+use shared::snapshot::{self, EntitySnapshot, SnapshotEntities, SnapshotHistory};
+use shared::transport::ReliableEndpoint;
 
 // Game Time System
 // 15 minute real-time = 24 hour game-time → 96x speed
@@ -15,6 +57,34 @@ use shared::bevy::prelude::Vec3;
 // Time is sent ONCE on login, then calculated locally on client
 const TIME_SPEED_MULTIPLIER: f32 = 96.0;
 
+// Fixed simulation rate (60 Hz). Everything that touches game state runs on this tick;
+// networking and the database live in their own tasks and talk to it through channels.
+const SIM_TICK_INTERVAL: Duration = Duration::from_micros(16_667);
+
+// How often every client receives a snapshot of the other players (10 Hz)
+const WORLD_STATE_INTERVAL: Duration = Duration::from_millis(100);
+
+// Most addresses that may be in the middle of the handshake at once
+const MAX_PENDING_HANDSHAKES: usize = 256;
+
+// How often health, mana and stamina regenerate and changed vitals are sent
+const VITALS_INTERVAL: Duration = Duration::from_millis(500);
+
+// Stamina starts regenerating this long after the last sprinting move
+const SPRINT_REGEN_DELAY: Duration = Duration::from_secs(1);
+
+// How often mobs think and move (matches the snapshot rate)
+const MOB_AI_INTERVAL: Duration = Duration::from_millis(100);
+
+// Party members this close to a mob when it dies share its experience (meters)
+const KILL_SHARE_RADIUS: f32 = 40.0;
+
+// Where a dash ends, measured from the target (meters)
+const DASH_STOP_DISTANCE: f32 = 1.5;
+
+// How far from its line a piercing shot still hits (meters)
+const PIERCE_WIDTH: f32 = 1.0;
+
 #[derive(Debug, Clone)]
 struct GameTime {
     hour: f32,              // 0.0 - 24.0 (12.0 = noon, 0.0 = midnight)
@@ -24,22 +94,94 @@ struct GameTime {
 // Server-side player state with position tracking
 #[derive(Debug, Clone)]
 struct PlayerState {
-    id: u64,
+    id: u64,                // Network entity ID (unique per server run)
     character: shared::CharacterData,
     character_id: i64,      // DB ID for saving
     user_id: i64,           // User ID for session cleanup
     position: Vec3,
+    yaw: f32,               // Facing reported by the client
     dirty: bool,            // Position changed since last save?
     last_save: Instant,     // When was last DB save?
+    movement: MovementValidator,  // Speed check for client-reported positions
+    last_input_sequence: u32,     // Newest client input reflected in `position`
+    sent_snapshots: SnapshotHistory,  // What we told this client, by tick
+    acked_snapshot: Option<u32>,      // Newest tick the client decoded (delta baseline)
+    combat: Combatant,                // Health, mana, cooldowns and active effects
+    last_sprint: Option<Instant>,     // Last accepted move while sprinting
+    sent_vitals: Vitals,              // What the owner and party last received
+    gm: bool,                         // Account may use GM commands
+    dead: bool,                       // Died and has not respawned yet
+    inventory: Inventory,
+    last_item_use: Option<Instant>,   // Consumables share a cooldown
+}
+
+/// A validated skill cast while its effect is applied
+struct Cast {
+    caster: u64,
+    origin: Vec3,        // Caster position
+    yaw: f32,            // Caster facing
+    skill: SkillId,
+    target: Option<u64>,
+    damage: f32,         // Direct damage, including the caster's buffs
+    power: f32,          // Base amount for heals and effects over time
+    now: Instant,
+}
+
+/// Direction a player with this yaw faces (models look down -Z)
+fn facing(yaw: f32) -> Vec3 {
+    Quat::from_rotation_y(yaw) * Vec3::NEG_Z
+}
+
+/// The whole inventory, as its owner gets it after every change
+fn inventory_message(inventory: &Inventory) -> ServerMessage {
+    ServerMessage::Inventory {
+        slots: inventory.slots().to_vec(),
+        equipment: *inventory.equipment(),
+        gold: inventory.gold,
+    }
+}
+
+fn saved_inventory(player: &PlayerState) -> SavedInventory {
+    let inventory = &player.inventory;
+    (player.character_id, inventory.stacks(), inventory.equipment().iter().collect(), inventory.gold)
+}
+
+/// The tables in use, encoded for `ServerMessage::BalanceChunk`
+fn balance_chunks() -> Vec<Vec<u8>> {
+    balance::current().to_chunks().unwrap_or_else(|e| {
+        log::error!("Cannot encode balance tables: {}", e);
+        Vec::new()
+    })
 }
 
 struct GameServer {
-    socket: UdpSocket,
-    db_pool: SqlitePool,
+    incoming: UnboundedReceiver<Datagram>,  // From the receive task
+    outgoing: UnboundedSender<Datagram>,    // To the send task
+    db: UnboundedSender<DbJob>,              // To the database worker
+    db_results: UnboundedReceiver<DbResult>, // From the database worker
+    balance_updates: UnboundedReceiver<BalanceTables>,  // Changed balance file
+    connections: HashMap<SocketAddr, ClientConnection>,  // Handshake + reliable channel state per client
     session_manager: SessionManager,
+    entity_ids: EntityIdAllocator,
+    interest: InterestManager,
+    entity_addrs: HashMap<u64, SocketAddr>,  // Network entity ID -> owning client
+    movement_violations: HashMap<i64, u32>,  // Character ID -> rejected moves (kept across sessions)
+    party: PartyManager,
+    mobs: HashMap<u64, Mob>,  // Network entity ID -> mob
+    spawner: MobSpawner,
+    nav: NavGrid,  // Walkable ground around the city buildings
+    last_mob_update: Instant,
+    ground_items: HashMap<u64, GroundItem>,  // Network entity ID -> pile on the ground
+    loot_rng: Xorshift,
+    shop_stock: ShopStock,
+    trades: TradeManager,
     players: HashMap<String, PlayerState>,
     last_update: Instant,
     last_batch_save: Instant,
+    last_world_broadcast: Instant,
+    last_vitals_update: Instant,
+    start_time: Instant,  // Reference point for snapshot timestamps
+    snapshot_tick: u32,   // Number of the last WorldState broadcast
     save_interval: Duration,  // How often to auto-save (5 minutes)
     game_time: GameTime,
 }
@@ -51,19 +193,55 @@ impl GameServer {
         let db_pool = db::init_database(database_url).await?;
         log::info!("Database initialized successfully");
 
-        // Setup UDP socket
-        let socket = UdpSocket::bind(SERVER_ADDR)?;
-        socket.set_nonblocking(true)?;
+        // Balance tables; a broken file stops the server before anyone connects
+        let balance_path = PathBuf::from(balance::DEFAULT_BALANCE_PATH);
+        balance::install(BalanceTables::load(&balance_path)?)?;
+        log::info!("Balance tables loaded from {}", balance_path.display());
+        let (balance_tx, balance_updates) = mpsc::unbounded_channel();
+        balance_watcher::spawn_balance_watcher(balance_path, balance_tx);
+
+        // Database worker
+        let (results_tx, db_results) = mpsc::unbounded_channel();
+        let db = persistence::spawn_db_worker(db_pool, results_tx);
+
+        // Setup UDP socket with separate receive and send tasks
+        let socket = Arc::new(UdpSocket::bind(SERVER_ADDR).await?);
         log::info!("Server started on {}", SERVER_ADDR);
 
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        tokio::spawn(network::receive_loop(socket.clone(), incoming_tx));
+        tokio::spawn(network::send_loop(socket, outgoing_rx));
+
         let now = Instant::now();
         Ok(Self {
-            socket,
-            db_pool,
+            incoming,
+            outgoing,
+            db,
+            db_results,
+            balance_updates,
+            connections: HashMap::new(),
             session_manager: SessionManager::new(),
+            entity_ids: EntityIdAllocator::new(),
+            interest: InterestManager::new(balance::current().interest_radius),
+            entity_addrs: HashMap::new(),
+            movement_violations: HashMap::new(),
+            party: PartyManager::new(),
+            mobs: HashMap::new(),
+            spawner: MobSpawner::new(mobs::default_regions(), uuid::Uuid::new_v4().as_u64_pair().0),
+            nav: NavGrid::city(),
+            last_mob_update: now,
+            ground_items: HashMap::new(),
+            loot_rng: Xorshift::new(uuid::Uuid::new_v4().as_u64_pair().0),
+            shop_stock: ShopStock::new(),
+            trades: TradeManager::new(),
             players: HashMap::new(),
             last_update: now,
             last_batch_save: now,
+            last_world_broadcast: now,
+            last_vitals_update: now,
+            start_time: now,
+            snapshot_tick: 0,
             save_interval: Duration::from_secs(5 * 60), // 5 minutes
             game_time: GameTime {
                 hour: 12.0,      // Start at noon (12:00)
@@ -72,16 +250,84 @@ impl GameServer {
         })
     }
 
-    async fn update(&mut self) {
-        let mut buf = [0u8; 65536];
-        
+    /// Run the simulation at a fixed rate until the process exits
+    async fn run(&mut self) {
+        let mut ticker = tokio::time::interval(SIM_TICK_INTERVAL);
+        // After a stall, continue at the normal rate instead of bursting to catch up
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            self.tick();
+        }
+    }
+
+    /// One simulation step. Never waits on the network or the database.
+    fn tick(&mut self) {
         // Receive messages
-        while let Ok((size, src)) = self.socket.recv_from(&mut buf) {
-            if let Ok(client_msg) = bincode::deserialize::<ClientMessage>(&buf[..size]) {
-                self.handle_client_message(src, client_msg).await;
+        while let Ok((src, data)) = self.incoming.try_recv() {
+            let now = Instant::now();
+            if !self.connections.contains_key(&src) && !self.open_connection(src, &data, now) {
+                continue;
+            }
+            let Some(connection) = self.connections.get_mut(&src) else { continue };
+            connection.last_received = now;
+
+            let connected = connection.is_connected();
+            let messages: Vec<ClientMessage> = connection.endpoint.receive_bytes(&data);
+
+            for client_msg in messages {
+                // Only the handshake is accepted before the client has connected
+                if !connected && !client_msg.is_handshake() {
+                    log::debug!("Dropping message from unconnected client {}", src);
+                    continue;
+                }
+                self.handle_client_message(src, client_msg);
             }
         }
 
+        // Answers from the database worker
+        while let Ok(result) = self.db_results.try_recv() {
+            self.handle_db_result(result);
+        }
+
+        // Hot-reloaded balance file
+        while let Ok(tables) = self.balance_updates.try_recv() {
+            self.apply_balance(tables);
+        }
+
+        // Drop clients we have not heard from in a while (crashed, lost network)
+        self.disconnect_timed_out_clients();
+
+        // Keepalive + resend unacknowledged reliable messages and flush pending acks
+        self.send_heartbeats();
+        self.flush_connections();
+
+        // Damage and heals over time, expiry, buff bar updates
+        self.update_status_effects();
+
+        // Fill spawn regions, clear out dead mobs, let the living ones act
+        self.update_mobs();
+        if self.last_mob_update.elapsed() >= MOB_AI_INTERVAL {
+            self.update_mob_ai(self.last_mob_update.elapsed());
+            self.last_mob_update = Instant::now();
+        }
+
+        // Loot nobody picked up
+        self.despawn_ground_items();
+
+        // Trades between players who walked apart
+        self.cancel_distant_trades();
+
+        // Players killed by any of the above, or brought back by a resurrection
+        self.update_deaths();
+
+        // Regeneration and vitals for owners and party members
+        if self.last_vitals_update.elapsed() >= VITALS_INTERVAL {
+            self.update_vitals(self.last_vitals_update.elapsed());
+            self.last_vitals_update = Instant::now();
+        }
+
         // Update game time (but don't broadcast - clients calculate locally)
         self.update_game_time();
 
@@ -96,92 +342,422 @@ impl GameServer {
 
         // Auto-save positions periodically (every 10 seconds check)
         if self.last_batch_save.elapsed().as_secs() >= 10 {
-            self.auto_save_positions().await;
+            self.auto_save_positions();
             self.last_batch_save = Instant::now();
         }
+
+        // Replicate player positions to the clients that can see them
+        if self.last_world_broadcast.elapsed() >= WORLD_STATE_INTERVAL {
+            self.update_interest();
+            self.broadcast_world_state();
+            self.last_world_broadcast = Instant::now();
+        }
     }
 
-    async fn handle_client_message(&mut self, client_addr: SocketAddr, message: ClientMessage) {
+    fn handle_client_message(&mut self, client_addr: SocketAddr, message: ClientMessage) {
         match message {
+            ClientMessage::Connect { protocol_id } => {
+                self.handle_connect(client_addr, protocol_id);
+            }
+            ClientMessage::ChallengeResponse { challenge } => {
+                self.handle_challenge_response(client_addr, challenge);
+            }
+            ClientMessage::Heartbeat => {
+                // Nothing to do - receiving it already refreshed last_received
+            }
             ClientMessage::Auth(auth_msg) => {
-                self.handle_auth_message(client_addr, auth_msg).await;
+                self.handle_auth_message(client_addr, auth_msg);
             }
             ClientMessage::CreateCharacter { token, character } => {
-                self.handle_create_character(client_addr, token, character).await;
+                self.handle_create_character(client_addr, token, character);
             }
             ClientMessage::SelectCharacter { token, character_id } => {
-                self.handle_select_character(client_addr, token, character_id).await;
+                self.handle_select_character(client_addr, token, character_id);
             }
             ClientMessage::DeleteCharacter { token, character_id } => {
-                self.handle_delete_character(client_addr, token, character_id).await;
-            }
-            ClientMessage::Join { character } => {
-                log::info!("Player {} joined with character: {}", client_addr, character.name);
-                
-                let player_state = PlayerState {
-                    id: client_addr.to_string().len() as u64,
-                    character: character.clone(),
-                    character_id: 0, // Will be set when we integrate with SelectCharacter
-                    user_id: 0, // Legacy join - no user_id available
-                    position: Vec3::new(0.0, 1.0, 0.0),
-                    dirty: false,
-                    last_save: Instant::now(),
-                };
-
-                self.players.insert(client_addr.to_string(), player_state);
+                self.handle_delete_character(client_addr, token, character_id);
             }
             ClientMessage::Move { direction } => {
                 let addr_str = client_addr.to_string();
-                if let Some(player) = self.players.get_mut(&addr_str) {
-                    player.position += direction * 5.0 * 0.016;
-                    player.dirty = true; // Mark as dirty for auto-save
-                    log::debug!("Player {} moved to {:?}", addr_str, player.position);
+                if let Some(player) = self.players.get(&addr_str) {
+                    let position = player.position + direction.clamp_length_max(1.0) * PLAYER_MOVE_SPEED * 0.016;
+                    let (yaw, sequence) = (player.yaw, player.last_input_sequence);
+                    self.handle_position_update(client_addr, position, yaw, sequence, false);
                 }
             }
-            ClientMessage::UpdatePosition { position } => {
-                let addr_str = client_addr.to_string();
-                if let Some(player) = self.players.get_mut(&addr_str) {
-                    player.position = position;
-                    player.dirty = true;
-                    log::debug!("Player {} position updated to {:?}", addr_str, player.position);
+            ClientMessage::UpdatePosition { position, yaw, sequence, sprinting } => {
+                self.handle_position_update(client_addr, position, yaw, sequence, sprinting);
+            }
+            ClientMessage::AckSnapshot { tick } => {
+                if let Some(player) = self.players.get_mut(&client_addr.to_string()) {
+                    // Acks are unreliable and may arrive out of order; only move forward
+                    let newer = !matches!(player.acked_snapshot, Some(acked) if tick <= acked);
+                    if newer && player.sent_snapshots.get(tick).is_some() {
+                        player.acked_snapshot = Some(tick);
+                    }
                 }
             }
-            ClientMessage::GainExperience { amount } => {
-                self.handle_gain_experience(client_addr, amount).await;
+            ClientMessage::GmGiveExperience { amount } => {
+                self.handle_gm_experience(client_addr, amount);
+            }
+            ClientMessage::UseSkill { skill, target } => {
+                self.handle_use_skill(client_addr, skill, target);
+            }
+            ClientMessage::Respawn => {
+                self.handle_respawn(client_addr);
+            }
+            ClientMessage::MoveItem { from, to } => {
+                self.change_inventory(client_addr, |inventory| inventory.move_item(from as usize, to as usize));
+            }
+            ClientMessage::SplitItem { slot, count, to } => {
+                self.change_inventory(client_addr, |inventory| inventory.split(slot as usize, count, to as usize));
+            }
+            ClientMessage::DropItem { slot, count } => {
+                self.handle_drop_item(client_addr, slot as usize, count);
+            }
+            ClientMessage::UseItem { slot } => {
+                self.handle_use_item(client_addr, slot as usize);
+            }
+            ClientMessage::EquipItem { slot } => {
+                let level = self.players.get(&client_addr.to_string()).map_or(0, |p| p.character.level);
+                self.change_equipment(client_addr, |inventory| inventory.equip(slot as usize, level));
+            }
+            ClientMessage::UnequipItem { slot } => {
+                self.change_equipment(client_addr, |inventory| inventory.unequip(slot));
+            }
+            ClientMessage::PickUpItem { id } => {
+                self.handle_pick_up(client_addr, id);
+            }
+            ClientMessage::OpenShop { shop } => {
+                self.handle_open_shop(client_addr, shop);
+            }
+            ClientMessage::BuyItem { shop, item, count } => {
+                self.handle_buy_item(client_addr, shop, item, count);
+            }
+            ClientMessage::SellItem { shop, slot, count } => {
+                self.handle_sell_item(client_addr, shop, slot as usize, count);
             }
             ClientMessage::ChooseSpecialization { token, specialization } => {
-                self.handle_choose_specialization(client_addr, token, specialization).await;
+                self.handle_choose_specialization(client_addr, token, specialization);
+            }
+            ClientMessage::TradeRequest { target } => {
+                self.handle_trade_request(client_addr, target);
+            }
+            ClientMessage::TradeAccept { requester } => {
+                self.handle_trade_accept(client_addr, requester);
+            }
+            ClientMessage::TradeOffer { items, gold } => {
+                let items: Vec<(usize, u32)> = items.into_iter().map(|(slot, count)| (slot as usize, count)).collect();
+                self.handle_trade_offer(client_addr, &items, gold);
+            }
+            ClientMessage::TradeLock => {
+                self.handle_trade_lock(client_addr);
+            }
+            ClientMessage::TradeConfirm => {
+                self.handle_trade_confirm(client_addr);
+            }
+            ClientMessage::TradeCancel => {
+                if let Some(player) = self.players.get(&client_addr.to_string()) {
+                    let player = player.id;
+                    self.cancel_trade(player, "Trade cancelled");
+                }
+            }
+            ClientMessage::PartyInvite { target } => {
+                self.handle_party_invite(client_addr, target);
+            }
+            ClientMessage::PartyAccept { inviter } => {
+                self.handle_party_accept(client_addr, inviter);
+            }
+            ClientMessage::PartyLeave => {
+                if let Some(player) = self.players.get(&client_addr.to_string()) {
+                    let member = player.id;
+                    self.leave_party(member);
+                }
             }
             ClientMessage::Disconnect => {
-                let addr_str = client_addr.to_string();
-                log::info!("Player {} disconnecting", addr_str);
-                
-                // Save position and cleanup session before removing player
-                if let Some(player) = self.players.get(&addr_str) {
-                    self.save_player_position(player).await;
-                    
-                    // Remove user's session to allow re-login
-                    if player.user_id != 0 {
-                        let removed_count = self.session_manager.remove_user_sessions(player.user_id);
-                        if removed_count > 0 {
-                            log::info!("Removed {} session(s) for user_id {}", removed_count, player.user_id);
-                        }
+                log::info!("Player {} disconnecting", client_addr);
+                self.remove_player(client_addr);
+            }
+        }
+    }
+
+    /// Continue a request once the database worker has answered it
+    fn handle_db_result(&mut self, result: DbResult) {
+        match result {
+            DbResult::Registered { addr, response } => {
+                self.send_response(addr, ServerMessage::AuthResponse(response));
+            }
+            DbResult::LoginVerified { addr, result } => {
+                self.handle_login_verified(addr, result);
+            }
+            DbResult::CharacterCreated { addr, result } => match result {
+                Ok(character_id) => {
+                    log::info!("Character {} created for {}", character_id, addr);
+                    self.send_response(addr, ServerMessage::CharacterCreated { character_id });
+                }
+                Err(reason) => {
+                    self.send_response(addr, ServerMessage::CharacterCreationFailed { reason });
+                }
+            },
+            DbResult::CharacterLoaded { addr, token, character_id, result } => {
+                self.handle_character_loaded(addr, token, character_id, result);
+            }
+            DbResult::CharacterDeleted { addr, character_id, result } => match result {
+                Ok(true) => {
+                    self.send_response(addr, ServerMessage::CharacterDeleted { character_id });
+                }
+                Ok(false) => {
+                    self.send_response(addr, ServerMessage::CharacterDeletionFailed {
+                        reason: "Character not found or not owned by you".to_string(),
+                    });
+                }
+                Err(reason) => {
+                    self.send_response(addr, ServerMessage::CharacterDeletionFailed { reason });
+                }
+            },
+            DbResult::SpecializationSaved { addr, character_id, specialization, success } => {
+                self.handle_specialization_saved(addr, character_id, specialization, success);
+            }
+            DbResult::PositionsNotSaved { character_ids } => {
+                // Re-mark as dirty so the next auto-save retries
+                for player in self.players.values_mut() {
+                    if character_ids.contains(&player.character_id) {
+                        player.dirty = true;
                     }
                 }
-                
-                self.players.remove(&addr_str);
             }
+            DbResult::PlayerTradeNotSaved { character_ids } => {
+                self.end_unsaved_trade(character_ids);
+            }
+        }
+    }
+
+    /// The database gave up on a trade between players: log both out so they come
+    /// back with the inventories the database has, then let the worker load them again
+    fn end_unsaved_trade(&mut self, character_ids: [i64; 2]) {
+        log::error!("Trade between characters {} and {} was not saved - logging both out", character_ids[0], character_ids[1]);
+        let addrs: Vec<SocketAddr> = self.players.iter()
+            .filter(|(_, player)| character_ids.contains(&player.character_id))
+            .filter_map(|(addr, _)| addr.parse().ok())
+            .collect();
+
+        for addr in addrs {
+            self.send_response(addr, ServerMessage::SessionEnded {
+                reason: "Your last trade could not be saved and was undone. Please log in again.".to_string(),
+            });
+            self.remove_player(addr);
+        }
+        self.queue_db(DbJob::ReleaseCharacters { character_ids });
+    }
+
+    /// Queue work for the database worker
+    fn queue_db(&self, job: DbJob) {
+        if let Err(e) = self.db.send(job) {
+            log::error!("Database worker is gone, dropping job: {:?}", e.0);
+        }
+    }
+
+    /// Accept a client-reported position if it is reachable at the player's speed,
+    /// otherwise snap the client back to the last valid position
+    fn handle_position_update(&mut self, client_addr: SocketAddr, position: Vec3, yaw: f32, sequence: u32, sprinting: bool) {
+        let addr_str = client_addr.to_string();
+        let Some(player) = self.players.get_mut(&addr_str) else { return };
+
+        // Position updates are unreliable - ignore ones overtaken by a newer update
+        if sequence < player.last_input_sequence {
+            return;
+        }
+
+        // Stunned and dead players stay where they are
+        let now = Instant::now();
+        let frozen = player.combat.is_stunned(now) || !player.combat.is_alive();
+        if frozen && position != player.position {
+            player.last_input_sequence = sequence;
+            let correction = ServerMessage::PositionCorrection { position: player.position, sequence };
+            self.send_response(client_addr, correction);
+            return;
+        }
+
+        // Sprinting is only allowed with stamina left; it drains per meter covered
+        let sprint = balance::current().sprint;
+        let sprinting = sprinting && player.combat.stamina > 0.0;
+        let max_speed = if sprinting { PLAYER_MOVE_SPEED * sprint.speed_multiplier } else { PLAYER_MOVE_SPEED };
+        let check = if sprinting {
+            player.movement.check_at_speed(player.position, position, now, max_speed)
+        } else {
+            player.movement.check(player.position, position, now)
+        };
+
+        match check {
+            MoveCheck::Accepted => {
+                if sprinting {
+                    let distance = Vec3::new(position.x - player.position.x, 0.0, position.z - player.position.z).length();
+                    player.combat.spend_stamina(distance / max_speed * sprint.stamina_per_second);
+                    player.last_sprint = Some(now);
+                }
+                player.position = position;
+                if yaw.is_finite() {
+                    player.yaw = yaw;
+                }
+                player.last_input_sequence = sequence;
+                player.dirty = true; // Mark as dirty for auto-save
+                self.interest.update_entity(player.id, player.position);
+                log::debug!("Player {} position updated to {:?}", addr_str, player.position);
+            }
+            MoveCheck::TooFast { distance, allowed } => {
+                let violations = self.movement_violations.entry(player.character_id).or_insert(0);
+                *violations += 1;
+                log::warn!(
+                    "Movement violation #{} by '{}' (character {}): moved {:.2} units, allowed {:.2} - correcting to {:?}",
+                    violations, player.character.name, player.character_id, distance, allowed, player.position
+                );
+
+                // The client replays its inputs after `sequence` on top of the corrected position
+                player.last_input_sequence = sequence;
+                let correction = ServerMessage::PositionCorrection { position: player.position, sequence };
+                self.send_response(client_addr, correction);
+            }
+        }
+    }
+
+    /// Track a new client address. Only a `Connect` opens a connection, so stray or
+    /// spoofed datagrams leave nothing behind; unfinished handshakes are capped.
+    fn open_connection(&mut self, src: SocketAddr, data: &[u8], now: Instant) -> bool {
+        let messages: Vec<ClientMessage> = ReliableEndpoint::new().receive_bytes(data);
+        if !messages.iter().any(|message| matches!(message, ClientMessage::Connect { .. })) {
+            log::debug!("Ignoring datagram from unknown address {}", src);
+            return false;
+        }
+
+        let handshakes = self.connections.values().filter(|c| !c.is_connected()).count();
+        if handshakes >= MAX_PENDING_HANDSHAKES {
+            log::warn!("Ignoring Connect from {}: {} handshakes already pending", src, handshakes);
+            return false;
+        }
+        self.connections.insert(src, ClientConnection::new(now));
+        true
+    }
+
+    /// Step 1 of the handshake: check the protocol and hand out a challenge
+    fn handle_connect(&mut self, client_addr: SocketAddr, protocol_id: u64) {
+        if protocol_id != PROTOCOL_ID {
+            log::warn!("Rejected {}: protocol {} (expected {})", client_addr, protocol_id, PROTOCOL_ID);
+            self.send_response(client_addr, ServerMessage::ConnectionRejected {
+                reason: "Incompatible client version".to_string(),
+            });
+            // Nothing to keep for a client that can't connect
+            if self.connections.get(&client_addr).is_some_and(|c| !c.is_connected()) {
+                self.connections.remove(&client_addr);
+            }
+            return;
+        }
+
+        // A connected client only starts over after giving up on us, so its player
+        // would be stranded in the world with a dead channel
+        if self.connections.get(&client_addr).is_some_and(|c| c.is_connected()) {
+            log::info!("Client {} reconnected - removing its player", client_addr);
+            self.remove_player(client_addr);
+        }
+
+        let Some(connection) = self.connections.get_mut(&client_addr) else { return };
+        let challenge = connection.connect(uuid::Uuid::new_v4().as_u64_pair().0);
+        self.send_response(client_addr, ServerMessage::ConnectionChallenge { challenge });
+    }
+
+    /// Step 2 of the handshake: the client proves it received our challenge
+    fn handle_challenge_response(&mut self, client_addr: SocketAddr, challenge: u64) {
+        let accepted = self.connections
+            .get_mut(&client_addr)
+            .is_some_and(|c| c.accept(challenge));
+
+        // A stale answer (e.g. to a challenge from before a restart) is only ignored:
+        // the client asks again, and a rejection would stop it for good
+        if accepted {
+            log::info!("Client {} connected", client_addr);
+            self.send_response(client_addr, ServerMessage::ConnectionAccepted);
+        } else {
+            log::debug!("Ignoring wrong challenge response from {}", client_addr);
+        }
+    }
+
+    /// Save and remove the player entity of a client and free its login session
+    fn remove_player(&mut self, client_addr: SocketAddr) {
+        let addr_str = client_addr.to_string();
+
+        // Save position and cleanup session before removing player
+        if let Some(player) = self.players.get(&addr_str) {
+            self.save_player_position(player);
+            
+            // Remove user's session to allow re-login
+            if player.user_id != 0 {
+                let removed_count = self.session_manager.remove_user_sessions(player.user_id);
+                if removed_count > 0 {
+                    log::info!("Removed {} session(s) for user_id {}", removed_count, player.user_id);
+                }
+            }
+        }
+        
+        self.leave_world(client_addr);
+    }
+
+    /// Remove clients that went silent for longer than CONNECTION_TIMEOUT
+    fn disconnect_timed_out_clients(&mut self) {
+        let now = Instant::now();
+        let timed_out: Vec<SocketAddr> = self.connections.iter()
+            .filter(|(_, c)| c.is_timed_out(now, CONNECTION_TIMEOUT))
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in timed_out {
+            if self.players.contains_key(&addr.to_string()) {
+                log::warn!("Client {} timed out - removing player", addr);
+            } else {
+                log::debug!("Client {} timed out", addr);
+            }
+            self.remove_player(addr);
+            self.connections.remove(&addr);
+        }
+    }
+
+    /// Keep idle connections alive so the client can detect a dead server
+    fn send_heartbeats(&mut self) {
+        let now = Instant::now();
+        let due: Vec<SocketAddr> = self.connections.iter_mut()
+            .filter(|(_, c)| c.is_connected() && c.heartbeat_due(now, HEARTBEAT_INTERVAL))
+            .map(|(addr, c)| {
+                c.last_heartbeat_sent = now;
+                *addr
+            })
+            .collect();
+
+        for addr in due {
+            self.send_response(addr, ServerMessage::Heartbeat);
         }
     }
 
-    async fn handle_auth_message(&mut self, client_addr: SocketAddr, auth_msg: AuthMessage) {
-        let response = match auth_msg {
+    fn handle_auth_message(&mut self, client_addr: SocketAddr, auth_msg: AuthMessage) {
+        // Password hashing and lookups happen on the database worker
+        match auth_msg {
             AuthMessage::Register { username, password, email } => {
-                auth::handle_register(&self.db_pool, username, password, email).await
+                self.queue_db(DbJob::Register { addr: client_addr, username, password, email });
             }
             AuthMessage::Login { username, password } => {
-                auth::handle_login(&self.db_pool, &mut self.session_manager, username, password).await
+                self.queue_db(DbJob::Login { addr: client_addr, username, password });
+            }
+        }
+    }
+
+    fn handle_login_verified(&mut self, client_addr: SocketAddr, result: Result<auth::VerifiedLogin, shared::AuthResponse>) {
+        let response = match result {
+            Ok(login) => {
+                let user_id = login.user_id;
+                let response = auth::start_session(&mut self.session_manager, login);
+                if matches!(response, shared::AuthResponse::LoginSuccess { .. }) {
+                    self.queue_db(DbJob::UpdateLastLogin { user_id });
+                }
+                response
             }
+            Err(response) => response,
         };
 
         // Check if login was successful BEFORE sending response
@@ -199,7 +775,7 @@ impl GameServer {
         }
     }
 
-    async fn handle_create_character(&mut self, client_addr: SocketAddr, token: String, character: shared::CharacterData) {
+    fn handle_create_character(&mut self, client_addr: SocketAddr, token: String, character: shared::CharacterData) {
         // Validate token
         let session = match self.session_manager.validate_token(&token) {
             Some(s) => s,
@@ -211,41 +787,32 @@ impl GameServer {
             }
         };
 
-        // Check if character name exists
-        match db::characters::character_name_exists(&self.db_pool, &character.name).await {
-            Ok(true) => {
-                self.send_response(client_addr, ServerMessage::CharacterCreationFailed {
-                    reason: "Character name already exists".to_string(),
-                });
-                return;
-            }
-            Ok(false) => {}
-            Err(e) => {
-                log::error!("Error checking character name: {}", e);
-                self.send_response(client_addr, ServerMessage::CharacterCreationFailed {
-                    reason: "Internal server error".to_string(),
-                });
-                return;
-            }
-        }
+        log::info!("Creating character '{}' for user {}", character.name, session.username);
+        let user_id = session.user_id;
+        self.queue_db(DbJob::CreateCharacter { addr: client_addr, user_id, character });
+    }
 
-        // Create character
-        match db::characters::create_character(&self.db_pool, session.user_id, &character).await {
-            Ok(char_id) => {
-                log::info!("Character '{}' created for user {}", character.name, session.username);
-                self.send_response(client_addr, ServerMessage::CharacterCreated { character_id: char_id });
-            }
-            Err(e) => {
-                log::error!("Error creating character: {}", e);
-                self.send_response(client_addr, ServerMessage::CharacterCreationFailed {
-                    reason: "Failed to create character".to_string(),
-                });
-            }
+    fn handle_select_character(&mut self, client_addr: SocketAddr, token: String, character_id: i64) {
+        // Validate token
+        if self.session_manager.validate_token(&token).is_none() {
+            self.send_response(client_addr, ServerMessage::CharacterSelectionFailed {
+                reason: "Invalid or expired token".to_string(),
+            });
+            return;
         }
+
+        // Load position and stats; continues in handle_character_loaded
+        self.queue_db(DbJob::LoadCharacter { addr: client_addr, token, character_id });
     }
 
-    async fn handle_select_character(&mut self, client_addr: SocketAddr, token: String, character_id: i64) {
-        // Validate token
+    fn handle_character_loaded(
+        &mut self,
+        client_addr: SocketAddr,
+        token: String,
+        character_id: i64,
+        result: Result<Option<persistence::LoadedCharacter>, String>,
+    ) {
+        // The session may have expired while the character was loading
         let session = match self.session_manager.validate_token(&token) {
             Some(s) => s,
             None => {
@@ -258,10 +825,11 @@ impl GameServer {
 
         let user_id = session.user_id;
         let username = session.username.clone();
+        let gm = session.is_gm;
 
         // Verify character belongs to user and load position
-        match db::characters::load_character(&self.db_pool, character_id).await {
-            Ok(Some(character)) => {
+        match result {
+            Ok(Some(persistence::LoadedCharacter { character, items, equipment })) => {
                 if character.user_id != user_id {
                     self.send_response(client_addr, ServerMessage::CharacterSelectionFailed {
                         reason: "Character does not belong to you".to_string(),
@@ -282,17 +850,36 @@ impl GameServer {
                     
                     // Create PlayerState for this character (entering world)
                     let character_data = character.to_character_data();
+                    let inventory = Inventory::from_saved(items, equipment, character.gold.max(0) as u64);
+                    let combat = Combatant::for_character(&character_data, inventory.equipment());
+                    let (max_health, max_mana, max_stamina) = (combat.max_health, combat.max_mana, combat.max_stamina);
+                    let inventory_message = ServerMessage::Inventory {
+                        slots: inventory.slots().to_vec(),
+                        equipment: *inventory.equipment(),
+                        gold: inventory.gold,
+                    };
                     let player_state = PlayerState {
-                        id: client_addr.to_string().len() as u64, // Simple ID generation
+                        id: self.entity_ids.allocate(),
                         character: character_data,
                         character_id,
                         user_id,
                         position,
+                        yaw: 0.0,
                         dirty: false,
                         last_save: Instant::now(),
+                        movement: MovementValidator::new(PLAYER_MOVE_SPEED, Instant::now()),
+                        last_input_sequence: 0,
+                        sent_snapshots: SnapshotHistory::default(),
+                        acked_snapshot: None,
+                        combat,
+                        last_sprint: None,
+                        sent_vitals: Vitals::default(),
+                        gm,
+                        dead: false,
+                        inventory,
+                        last_item_use: None,
                     };
                     
-                    self.players.insert(client_addr.to_string(), player_state);
                     log::info!("Player state created for character {} at {:?}", character_id, position);
                     
                     // Convert string class to CharacterClass
@@ -304,18 +891,18 @@ impl GameServer {
                         _ => shared::CharacterClass::Krieger,
                     };
                     
-                    // Calculate stats for level
-                    let (max_health, max_mana, max_stamina) = 
-                        shared::calculate_stats_for_level(character.level, &char_class);
-                    
                     // Parse specialization from DB
                     let specialization = character.specialization.as_ref().and_then(|s| {
                         shared::Specialization::from_string(s)
                     });
                     
+                    // Our balance tables first, the client may have an older file
+                    self.send_balance(client_addr, &balance_chunks());
+
                     // Send character_id, name, class, position, level, XP, and stats to client
                     self.send_response(client_addr, ServerMessage::CharacterSelected { 
                         character_id,
+                        entity_id: player_state.id,
                         character_name: character.name.clone(),
                         character_class: char_class,
                         position,
@@ -326,6 +913,10 @@ impl GameServer {
                         max_stamina,
                         specialization,
                     });
+                    self.send_response(client_addr, inventory_message);
+
+                    // Announce the new player and tell them who is already here
+                    self.enter_world(client_addr, player_state);
                 }
             }
             Ok(None) => {
@@ -333,16 +924,13 @@ impl GameServer {
                     reason: "Character not found".to_string(),
                 });
             }
-            Err(e) => {
-                log::error!("Error getting character: {}", e);
-                self.send_response(client_addr, ServerMessage::CharacterSelectionFailed {
-                    reason: "Internal server error".to_string(),
-                });
+            Err(reason) => {
+                self.send_response(client_addr, ServerMessage::CharacterSelectionFailed { reason });
             }
         }
     }
 
-    async fn handle_delete_character(&mut self, client_addr: SocketAddr, token: String, character_id: i64) {
+    fn handle_delete_character(&mut self, client_addr: SocketAddr, token: String, character_id: i64) {
         let session = match self.session_manager.validate_token(&token) {
             Some(s) => s,
             None => {
@@ -353,27 +941,11 @@ impl GameServer {
             }
         };
 
-        match db::characters::delete_character(&self.db_pool, character_id, session.user_id).await {
-            Ok(true) => {
-                self.send_response(client_addr, ServerMessage::CharacterDeleted {
-                    character_id,
-                });
-            }
-            Ok(false) => {
-                self.send_response(client_addr, ServerMessage::CharacterDeletionFailed {
-                    reason: "Character not found or not owned by you".to_string(),
-                });
-            }
-            Err(e) => {
-                log::error!("Error deleting character: {}", e);
-                self.send_response(client_addr, ServerMessage::CharacterDeletionFailed {
-                    reason: "Internal server error".to_string(),
-                });
-            }
-        }
+        let user_id = session.user_id;
+        self.queue_db(DbJob::DeleteCharacter { addr: client_addr, user_id, character_id });
     }
 
-    async fn handle_choose_specialization(
+    fn handle_choose_specialization(
         &mut self,
         client_addr: SocketAddr,
         token: String,
@@ -393,8 +965,6 @@ impl GameServer {
             }
         };
 
-        let user_id = session.user_id;
-
         // 2. Get current character ID from session
         let character_id = match session.character_id {
             Some(id) => id,
@@ -409,10 +979,11 @@ impl GameServer {
             }
         };
 
-        // 3. Load character from database
-        let character = match db::characters::load_character(&self.db_pool, character_id).await {
-            Ok(Some(c)) => c,
-            Ok(None) => {
+        // 3. The character must be in the world - its in-memory state is authoritative
+        let addr_str = client_addr.to_string();
+        let character = match self.players.get(&addr_str) {
+            Some(player) if player.character_id == character_id => player.character.clone(),
+            _ => {
                 self.send_response(
                     client_addr,
                     ServerMessage::SpecializationFailed {
@@ -421,30 +992,9 @@ impl GameServer {
                 );
                 return;
             }
-            Err(e) => {
-                log::error!("Error loading character: {}", e);
-                self.send_response(
-                    client_addr,
-                    ServerMessage::SpecializationFailed {
-                        reason: "Internal server error".to_string(),
-                    },
-                );
-                return;
-            }
         };
 
-        // 4. Verify ownership
-        if character.user_id != user_id {
-            self.send_response(
-                client_addr,
-                ServerMessage::SpecializationFailed {
-                    reason: "Character does not belong to you".to_string(),
-                },
-            );
-            return;
-        }
-
-        // 5. Check level requirement (must be at least level 5)
+        // 4. Check level requirement (must be at least level 5)
         if character.level < 5 {
             self.send_response(
                 client_addr,
@@ -455,7 +1005,7 @@ impl GameServer {
             return;
         }
 
-        // 6. Check if specialization already chosen
+        // 5. Check if specialization already chosen
         if character.specialization.is_some() {
             self.send_response(
                 client_addr,
@@ -466,74 +1016,1375 @@ impl GameServer {
             return;
         }
 
-        // 7. Verify specialization matches character class
-        let char_class = match character.class.as_str() {
-            "Krieger" => shared::CharacterClass::Krieger,
-            "Ninja" => shared::CharacterClass::Ninja,
-            "Sura" => shared::CharacterClass::Sura,
-            "Schamane" => shared::CharacterClass::Schamane,
-            _ => shared::CharacterClass::Krieger,
-        };
-
-        if !specialization.is_valid_for_class(char_class) {
+        // 6. Verify specialization matches character class
+        if !specialization.is_valid_for_class(character.class) {
             self.send_response(
                 client_addr,
                 ServerMessage::SpecializationFailed {
                     reason: format!(
                         "Specialization {} is not valid for class {}",
                         specialization.name(),
-                        char_class.as_str()
+                        character.class.as_str()
                     ),
                 },
             );
             return;
         }
 
-        // 8. Save specialization to database
-        let spec_str = specialization.as_str();
-        match db::characters::update_specialization(&self.db_pool, character_id, spec_str).await {
-            Ok(_) => {
-                log::info!(
-                    "Character {} (user {}) chose specialization: {}",
-                    character.name,
-                    user_id,
-                    specialization.name()
-                );
+        // 7. Claim it now so a second request can't race the save, then persist
+        if let Some(player) = self.players.get_mut(&addr_str) {
+            player.character.specialization = Some(specialization);
+        }
+        self.queue_db(DbJob::SaveSpecialization { addr: client_addr, character_id, specialization });
+    }
 
-                // Update player state if they're in the world
-                let addr_str = client_addr.to_string();
-                if let Some(player) = self.players.get_mut(&addr_str) {
-                    player.character.specialization = Some(specialization);
-                }
+    fn handle_party_invite(&mut self, client_addr: SocketAddr, target: u64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let (inviter, name) = (player.id, player.character.name.clone());
+        let Some(&target_addr) = self.entity_addrs.get(&target) else {
+            self.send_response(client_addr, ServerMessage::PartyFailed { reason: "Player not found".to_string() });
+            return;
+        };
 
-                // Send success response
-                self.send_response(
-                    client_addr,
-                    ServerMessage::SpecializationChosen { specialization },
-                );
+        match self.party.invite(inviter, target, Instant::now()) {
+            Ok(()) => {
+                log::info!("Player {} invited {} to a party", inviter, target);
+                self.send_response(target_addr, ServerMessage::PartyInvitation { inviter, name });
             }
-            Err(e) => {
-                log::error!("Error saving specialization: {}", e);
-                self.send_response(
-                    client_addr,
-                    ServerMessage::SpecializationFailed {
-                        reason: "Failed to save specialization".to_string(),
-                    },
+            Err(e) => self.send_response(client_addr, ServerMessage::PartyFailed { reason: e.to_string() }),
+        }
+    }
+
+    fn handle_party_accept(&mut self, client_addr: SocketAddr, inviter: u64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let invitee = player.id;
+        if !self.entity_addrs.contains_key(&inviter) {
+            self.send_response(client_addr, ServerMessage::PartyFailed { reason: "Player not found".to_string() });
+            return;
+        }
+
+        match self.party.accept(invitee, inviter, Instant::now()) {
+            Ok(members) => {
+                log::info!("Player {} joined the party of {}", invitee, inviter);
+                self.send_party_members(&members);
+            }
+            Err(e) => self.send_response(client_addr, ServerMessage::PartyFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// Remove a player from its party and tell everyone involved
+    fn leave_party(&mut self, member: u64) {
+        if self.party.members(member).is_empty() {
+            return;
+        }
+        let remaining = self.party.leave(member);
+        let mut alone = vec![member];
+        if remaining.len() == 1 {
+            // The party was dissolved
+            alone.extend(remaining);
+        } else {
+            self.send_party_members(&remaining);
+        }
+        for entity in alone {
+            if let Some(&addr) = self.entity_addrs.get(&entity) {
+                self.send_response(addr, ServerMessage::PartyMembers { members: Vec::new() });
+            }
+        }
+    }
+
+    /// Send the member list to every member, then each member's vitals to the others
+    fn send_party_members(&mut self, members: &[u64]) {
+        let list: Vec<PartyMember> = members.iter()
+            .filter_map(|&entity| self.player_by_entity(entity)
+                .map(|player| PartyMember { entity, name: player.character.name.clone() }))
+            .collect();
+        for &entity in members {
+            if let Some(&addr) = self.entity_addrs.get(&entity) {
+                self.send_response(addr, ServerMessage::PartyMembers { members: list.clone() });
+            }
+        }
+        for &entity in members {
+            self.send_vitals(entity);
+        }
+    }
+
+    fn handle_specialization_saved(
+        &mut self,
+        client_addr: SocketAddr,
+        character_id: i64,
+        specialization: shared::Specialization,
+        success: bool,
+    ) {
+        let player = self.players.values_mut().find(|p| p.character_id == character_id);
+
+        if success {
+            if let Some(player) = player {
+                log::info!(
+                    "Character {} (user {}) chose specialization: {}",
+                    player.character.name,
+                    player.user_id,
+                    specialization.name()
+                );
+            }
+
+            // Send success response
+            self.send_response(
+                client_addr,
+                ServerMessage::SpecializationChosen { specialization },
+            );
+        } else {
+            // Undo the claim so the player can try again
+            if let Some(player) = player {
+                player.character.specialization = None;
+            }
+            self.send_response(
+                client_addr,
+                ServerMessage::SpecializationFailed {
+                    reason: "Failed to save specialization".to_string(),
+                },
+            );
+        }
+    }
+
+    /// Validate a skill cast, apply its effect and tell the clients nearby what happened
+    fn handle_use_skill(&mut self, client_addr: SocketAddr, skill: SkillId, target: Option<u64>) {
+        let now = Instant::now();
+        let addr_str = client_addr.to_string();
+        let Some(caster) = self.players.get(&addr_str) else { return };
+        let caster_id = caster.id;
+        let info = skill.info();
+
+        // Only targeted skills look at the target; they can't be cast on yourself
+        let target = if info.needs_target() { target } else { None };
+        let validation = match target {
+            Some(id) if id == caster_id => Err(CastError::InvalidTarget),
+            Some(id) => match self.placement(id).zip(self.combatant(id)) {
+                Some(((position, _), combatant)) => {
+                    let target = CastTarget { position, combatant };
+                    combat::validate_cast(&caster.character, &caster.combat, caster.position, skill, Some(target), now)
+                }
+                None => Err(CastError::InvalidTarget),
+            },
+            None => combat::validate_cast(&caster.character, &caster.combat, caster.position, skill, None, now),
+        };
+
+        if let Err(e) = validation {
+            log::debug!("{} can't use {:?}: {}", caster.character.name, skill, e);
+            self.send_response(client_addr, ServerMessage::SkillFailed { skill, reason: e.to_string() });
+            return;
+        }
+
+        let Some(caster) = self.players.get_mut(&addr_str) else { return };
+        let cooldown = caster.combat.begin_cast(skill, &info, now);
+        let power = combat::skill_power(caster.character.level);
+        let cast = Cast {
+            caster: caster_id,
+            origin: caster.position,
+            yaw: caster.yaw,
+            skill,
+            target,
+            damage: combat::skill_damage(caster.character.level, caster.combat.attack, &info) * caster.combat.damage_dealt_multiplier(now),
+            power,
+            now,
+        };
+        log::debug!("{} used {:?} on {:?}", caster.character.name, skill, target);
+
+        let mut events = vec![ServerMessage::SkillUsed { caster: caster_id, skill, target, cooldown }];
+        self.apply_skill_effect(&cast, &info, &mut events);
+        self.publish_combat_events(cast.origin, caster_id, events);
+    }
+
+    /// Broadcast combat events around `origin` and send updated vitals to everyone whose
+    /// health or mana changed (plus `caster`, who paid mana)
+    fn publish_combat_events(&mut self, origin: Vec3, caster: u64, events: Vec<ServerMessage>) {
+        let mut changed = vec![caster];
+        for event in &events {
+            match event {
+                ServerMessage::Damage { target, .. } | ServerMessage::Healed { target, .. } => changed.push(*target),
+                _ => {}
+            }
+        }
+
+        for event in &events {
+            self.broadcast_nearby(origin, event);
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        for entity in changed {
+            self.send_vitals(entity);
+        }
+    }
+
+    /// Carry out what a validated skill does. Players are the only combatants so far -
+    /// damage hits every other player in range, heals reach every player in range.
+    fn apply_skill_effect(&mut self, cast: &Cast, info: &SkillInfo, events: &mut Vec<ServerMessage>) {
+        let caster = cast.caster;
+
+        match info.effect {
+            SkillEffect::None => {
+                if let Some(target) = cast.target {
+                    self.deal_damage(cast, target, cast.damage, events);
+                }
+            }
+            SkillEffect::AreaDamage(radius) => {
+                for target in self.area_targets(cast, radius) {
+                    self.deal_damage(cast, target, cast.damage, events);
+                }
+            }
+            SkillEffect::DamageReduction(amount, duration) => {
+                self.apply_status(cast, caster, EffectKind::DamageReduction, amount, duration, events);
+            }
+            SkillEffect::Taunt(radius) => {
+                // Only monsters react to taunts
+                let duration = mob_ai::TAUNT_DURATION.as_secs_f32();
+                for target in self.interest.query_radius(cast.origin, radius) {
+                    let Some(mob) = self.mobs.get_mut(&target) else { continue };
+                    if mob.combat.is_alive() && !mob.brain.is_evading() {
+                        mob.brain.threat.taunt(caster, cast.now);
+                        events.push(ServerMessage::EffectApplied { source: caster, target, skill: cast.skill, duration });
+                    }
+                }
+            }
+            SkillEffect::Stun(duration, radius) => {
+                for target in self.area_targets(cast, radius) {
+                    self.deal_damage(cast, target, cast.damage, events);
+                    self.apply_status(cast, target, EffectKind::Stun, 1.0, duration, events);
+                }
+            }
+            SkillEffect::CrowdControlImmunity(duration) => {
+                self.apply_status(cast, caster, EffectKind::CrowdControlImmunity, 1.0, duration, events);
+            }
+            SkillEffect::Revive(health, _) => {
+                let window = combat::LAST_STAND_WINDOW.as_secs_f32();
+                self.apply_status(cast, caster, EffectKind::LastStand, health, window, events);
+            }
+            SkillEffect::DefenseReduction(amount, duration, radius) => {
+                for target in self.area_targets(cast, radius) {
+                    self.apply_status(cast, target, EffectKind::DefenseReduction, amount, duration, events);
+                }
+            }
+            SkillEffect::ExecuteDamage(threshold, bonus) => {
+                if let Some(target) = cast.target {
+                    let wounded = self.combatant(target).is_some_and(|c| c.health_fraction() < threshold);
+                    let damage = if wounded { cast.damage * (1.0 + bonus) } else { cast.damage };
+                    self.deal_damage(cast, target, damage, events);
+                }
+            }
+            SkillEffect::AttackSpeedBuff(amount, duration) => {
+                self.apply_status(cast, caster, EffectKind::AttackSpeed, amount, duration, events);
+            }
+            SkillEffect::DashStun(_, duration) => {
+                if let Some(target) = cast.target {
+                    if let Some(target_position) = self.interest.position(target) {
+                        let away = Vec3::new(cast.origin.x - target_position.x, 0.0, cast.origin.z - target_position.z);
+                        let destination = target_position + away.normalize_or_zero() * DASH_STOP_DISTANCE;
+                        self.move_player(caster, destination, cast.now);
+                    }
+                    self.deal_damage(cast, target, cast.damage, events);
+                    self.apply_status(cast, target, EffectKind::Stun, 1.0, duration, events);
+                }
+            }
+            SkillEffect::Heal(multiplier, radius) => {
+                for target in self.allies_near(caster, cast.origin, radius) {
+                    self.heal(cast, target, cast.power * multiplier, events);
+                }
+            }
+            SkillEffect::HealOverTime(multiplier, duration) => {
+                let applied = self.combatant_mut(caster).is_some_and(|combatant| {
+                    combatant.add_periodic(caster, cast.skill, PeriodicKind::Heal, cast.power * multiplier, duration, cast.now)
+                });
+                if applied {
+                    events.push(ServerMessage::EffectApplied { source: caster, target: caster, skill: cast.skill, duration });
+                }
+            }
+            SkillEffect::DamageOverTime(multiplier, duration) => {
+                if let Some(target) = cast.target {
+                    self.deal_damage(cast, target, cast.damage, events);
+                    let applied = self.combatant_mut(target).is_some_and(|combatant| {
+                        combatant.add_periodic(caster, cast.skill, PeriodicKind::Damage, cast.power * multiplier, duration, cast.now)
+                    });
+                    if applied {
+                        events.push(ServerMessage::EffectApplied { source: caster, target, skill: cast.skill, duration });
+                    }
+                }
+            }
+            SkillEffect::Lifesteal(fraction, radius) => {
+                let mut drained = 0.0;
+                for target in self.area_targets(cast, radius) {
+                    drained += self.deal_damage(cast, target, cast.damage, events);
+                }
+                self.heal(cast, caster, drained * fraction, events);
+            }
+            SkillEffect::Invisibility(duration) => {
+                self.apply_status(cast, caster, EffectKind::Invisible, 1.0, duration, events);
+            }
+            SkillEffect::TeleportBehind(distance) => {
+                if let Some(target) = cast.target {
+                    if let Some((position, yaw)) = self.placement(target) {
+                        let destination = position - facing(yaw) * distance;
+                        self.move_player(caster, destination, cast.now);
+                    }
+                    self.deal_damage(cast, target, cast.damage, events);
+                }
+            }
+            SkillEffect::ChainLightning(jumps, jump_radius, falloff) => {
+                let Some(first) = cast.target else { return };
+                let mut hit = vec![first];
+                let mut damage = cast.damage;
+                let mut current = first;
+                loop {
+                    self.deal_damage(cast, current, damage, events);
+                    if hit.len() > jumps as usize {
+                        break;
+                    }
+                    // Jump to the closest enemy that has not been hit yet
+                    let Some(from) = self.interest.position(current) else { break };
+                    let next = self.enemies_near(caster, from, jump_radius)
+                        .into_iter()
+                        .filter(|entity| !hit.contains(entity))
+                        .filter_map(|entity| self.interest.position(entity).map(|p| (entity, p.distance(from))))
+                        .min_by(|a, b| a.1.total_cmp(&b.1));
+                    let Some((next, _)) = next else { break };
+                    hit.push(next);
+                    current = next;
+                    damage *= falloff;
+                }
+            }
+            SkillEffect::Resurrect(health_fraction) => {
+                if let Some(target) = cast.target {
+                    if let Some(combatant) = self.combatant_mut(target) {
+                        let amount = combatant.resurrect(health_fraction);
+                        let health = combatant.health;
+                        if amount > 0.0 {
+                            events.push(ServerMessage::Healed { source: caster, target, skill: cast.skill, amount, health });
+                        }
+                    }
+                }
+            }
+            SkillEffect::DamageBuff(amount, duration) => {
+                self.apply_status(cast, caster, EffectKind::DamageBuff, amount, duration, events);
+            }
+            SkillEffect::LeapBack(distance) => {
+                let destination = cast.origin - facing(cast.yaw) * distance;
+                self.move_player(caster, destination, cast.now);
+            }
+            SkillEffect::Pierce(length) => {
+                let Some(target_position) = cast.target.and_then(|t| self.interest.position(t)) else { return };
+                let direction = Vec3::new(target_position.x - cast.origin.x, 0.0, target_position.z - cast.origin.z)
+                    .normalize_or_zero();
+                let end = cast.origin + direction * length;
+                let in_line: Vec<u64> = self.enemies_near(caster, cast.origin, length)
+                    .into_iter()
+                    .filter(|&entity| self.interest.position(entity)
+                        .is_some_and(|p| combat::distance_to_segment(p, cast.origin, end) <= PIERCE_WIDTH))
+                    .collect();
+                for target in in_line {
+                    self.deal_damage(cast, target, cast.damage, events);
+                }
+            }
+        }
+    }
+
+    /// Combat state of a player or mob
+    fn combatant(&self, entity: u64) -> Option<&Combatant> {
+        if let Some(mob) = self.mobs.get(&entity) {
+            return Some(&mob.combat);
+        }
+        self.player_by_entity(entity).map(|p| &p.combat)
+    }
+
+    fn combatant_mut(&mut self, entity: u64) -> Option<&mut Combatant> {
+        if let Some(mob) = self.mobs.get_mut(&entity) {
+            return Some(&mut mob.combat);
+        }
+        let addr = self.entity_addrs.get(&entity)?;
+        self.players.get_mut(&addr.to_string()).map(|p| &mut p.combat)
+    }
+
+    /// Position and facing of a player or mob
+    fn placement(&self, entity: u64) -> Option<(Vec3, f32)> {
+        if let Some(mob) = self.mobs.get(&entity) {
+            return Some((mob.position, mob.yaw));
+        }
+        self.player_by_entity(entity).map(|p| (p.position, p.yaw))
+    }
+
+    /// Living combatants within `radius` of `origin` that are not in the caster's party
+    fn enemies_near(&self, caster: u64, origin: Vec3, radius: f32) -> Vec<u64> {
+        self.living_near(caster, origin, radius)
+            .into_iter()
+            .filter(|&entity| !self.party.same_party(caster, entity))
+            .collect()
+    }
+
+    /// The caster and its living party members within `radius` of `origin`
+    fn allies_near(&self, caster: u64, origin: Vec3, radius: f32) -> Vec<u64> {
+        let mut allies = vec![caster];
+        if radius > 0.0 {
+            allies.extend(self.living_near(caster, origin, radius)
+                .into_iter()
+                .filter(|&entity| self.party.same_party(caster, entity)));
+        }
+        allies
+    }
+
+    /// Living combatants other than `caster` within `radius` of `origin`, friend or foe
+    fn living_near(&self, caster: u64, origin: Vec3, radius: f32) -> Vec<u64> {
+        self.interest.query_radius(origin, radius)
+            .into_iter()
+            .filter(|&entity| entity != caster)
+            .filter(|&entity| self.combatant(entity).is_some_and(|c| c.is_alive()))
+            .collect()
+    }
+
+    /// Who an area effect hits: around the target for targeted skills, around the caster
+    /// otherwise. Radius 0 hits just the target.
+    fn area_targets(&self, cast: &Cast, radius: f32) -> Vec<u64> {
+        if radius <= 0.0 {
+            return cast.target.into_iter().collect();
+        }
+        let center = cast.target
+            .and_then(|target| self.interest.position(target))
+            .unwrap_or(cast.origin);
+        self.enemies_near(cast.caster, center, radius)
+    }
+
+    /// Returns the damage actually dealt
+    fn deal_damage(&mut self, cast: &Cast, target: u64, amount: f32, events: &mut Vec<ServerMessage>) -> f32 {
+        if amount <= 0.0 {
+            return 0.0;
+        }
+        // Leashed mobs walking home can't be hurt
+        if self.mobs.get(&target).is_some_and(|mob| mob.brain.is_evading()) {
+            return 0.0;
+        }
+        if let Some(caster) = self.combatant_mut(cast.caster) {
+            caster.enter_combat(cast.now);
+        }
+        let Some(combatant) = self.combatant_mut(target) else { return 0.0 };
+        let dealt = combatant.take_damage(amount, cast.now);
+        let health = combatant.health;
+        events.push(ServerMessage::Damage { source: cast.caster, target, skill: Some(cast.skill), amount: dealt, health });
+
+        let specialization = self.player_by_entity(cast.caster).and_then(|p| p.character.specialization);
+        if let Some(mob) = self.mobs.get_mut(&target) {
+            mob.brain.threat.add(cast.caster, dealt * mob_ai::threat_multiplier(specialization));
+            mob.tag(cast.caster);
+        }
+        dealt
+    }
+
+    fn heal(&mut self, cast: &Cast, target: u64, amount: f32, events: &mut Vec<ServerMessage>) {
+        if amount <= 0.0 {
+            return;
+        }
+        let Some(combatant) = self.combatant_mut(target) else { return };
+        let healed = combatant.heal(amount);
+        let health = combatant.health;
+        if healed > 0.0 {
+            events.push(ServerMessage::Healed { source: cast.caster, target, skill: cast.skill, amount: healed, health });
+        }
+
+        // Mobs fighting the healed player go after the healer too
+        for mob in self.mobs.values_mut() {
+            if mob.brain.threat.contains(target) {
+                mob.brain.threat.add(cast.caster, healed * mob_ai::HEAL_THREAT_FACTOR);
+            }
+        }
+    }
+
+    fn apply_status(
+        &mut self,
+        cast: &Cast,
+        target: u64,
+        kind: EffectKind,
+        magnitude: f32,
+        duration: f32,
+        events: &mut Vec<ServerMessage>,
+    ) {
+        let Some(combatant) = self.combatant_mut(target) else { return };
+        let origin = EffectOrigin { source: cast.caster, skill: cast.skill };
+        if combatant.apply_effect_from(Some(origin), kind, magnitude, Duration::from_secs_f32(duration), cast.now) {
+            events.push(ServerMessage::EffectApplied { source: cast.caster, target, skill: cast.skill, duration });
+        }
+    }
+
+    /// Put a player somewhere else (dash, teleport, leap) and snap its client there
+    fn move_player(&mut self, entity: u64, position: Vec3, now: Instant) {
+        let Some(&addr) = self.entity_addrs.get(&entity) else { return };
+        let Some(player) = self.players.get_mut(&addr.to_string()) else { return };
+
+        player.position = position;
+        player.movement.reset(now);
+        player.dirty = true;
+
+        let correction = ServerMessage::PositionCorrection {
+            position: player.position,
+            sequence: player.last_input_sequence,
+        };
+        self.interest.update_entity(entity, position);
+        self.send_response(addr, correction);
+    }
+
+    /// Apply due damage and heals over time, drop expired effects and send every player
+    /// whose effects changed the new list
+    fn update_status_effects(&mut self) {
+        let now = Instant::now();
+        let mut ticked = Vec::new();
+        let mut changed = Vec::new();
+        for player in self.players.values_mut() {
+            for tick in player.combat.apply_periodic(now) {
+                let (source, target, skill, amount, health) = (tick.source, player.id, tick.skill, tick.amount, player.combat.health);
+                let event = match tick.kind {
+                    PeriodicKind::Damage => ServerMessage::Damage { source, target, skill: Some(skill), amount, health },
+                    PeriodicKind::Heal => ServerMessage::Healed { source, target, skill, amount, health },
+                };
+                ticked.push((player.position, target, event));
+            }
+
+            player.combat.status.expire(now);
+            if player.combat.status.take_changed() {
+                let effects = player.combat.status.states(now);
+                changed.push((player.id, ServerMessage::StatusEffects { entity: player.id, effects }));
+            }
+        }
+
+        // Mobs have no buff bar, their effects just tick and run out
+        for mob in self.mobs.values_mut() {
+            for tick in mob.combat.apply_periodic(now) {
+                let (source, target, skill, amount, health) = (tick.source, mob.id, tick.skill, tick.amount, mob.combat.health);
+                if tick.kind == PeriodicKind::Damage {
+                    mob.brain.threat.add(source, amount);
+                    mob.tag(source);
+                }
+                let event = match tick.kind {
+                    PeriodicKind::Damage => ServerMessage::Damage { source, target, skill: Some(skill), amount, health },
+                    PeriodicKind::Heal => ServerMessage::Healed { source, target, skill, amount, health },
+                };
+                ticked.push((mob.position, target, event));
+            }
+            mob.combat.status.expire(now);
+        }
+
+        for (position, target, event) in ticked {
+            self.publish_combat_events(position, target, vec![event]);
+        }
+        for (entity, message) in changed {
+            if let Some(&addr) = self.entity_addrs.get(&entity) {
+                self.send_response(addr, message);
+            }
+        }
+    }
+
+    /// Use new balance tables: adjust the interest radius and everyone's maximum
+    /// health/mana and push the tables to the players in the world
+    fn apply_balance(&mut self, tables: BalanceTables) {
+        if let Err(e) = balance::install(tables) {
+            log::error!("Balance tables rejected: {}", e);
+            return;
+        }
+
+        self.interest.set_view_radius(balance::current().interest_radius);
+        let chunks = balance_chunks();
+        let entities: Vec<u64> = self.players.values_mut().map(|player| {
+            player.combat.set_equipment(&player.character, player.inventory.equipment());
+            player.id
+        }).collect();
+
+        for entity in entities {
+            if let Some(&addr) = self.entity_addrs.get(&entity) {
+                self.send_balance(addr, &chunks);
+                self.send_vitals(entity);
+            }
+        }
+        log::info!("Balance tables updated for {} players", self.players.len());
+    }
+
+    /// Send a player's vitals to its owner and to party members near it
+    fn send_vitals(&mut self, entity: u64) {
+        let Some(&addr) = self.entity_addrs.get(&entity) else { return };
+        let Some(player) = self.players.get_mut(&addr.to_string()) else { return };
+        let vitals = player.combat.vitals();
+        let position = player.position;
+        player.sent_vitals = vitals;
+        self.send_response(addr, ServerMessage::Vitals(vitals));
+
+        let message = ServerMessage::PartyMemberVitals { entity, vitals };
+        for member in self.party.members(entity) {
+            if member == entity {
+                continue;
+            }
+            let Some(other) = self.player_by_entity(member) else { continue };
+            if other.position.distance(position) <= self.interest.view_radius() {
+                let other_addr = self.entity_addrs[&member];
+                self.send_response(other_addr, message.clone());
+            }
+        }
+    }
+
+    /// Regenerate every player by `elapsed` and send vitals that changed since last time.
+    /// Rates are per class, lower in combat; stamina waits while sprinting.
+    fn update_vitals(&mut self, elapsed: Duration) {
+        let now = Instant::now();
+        let tables = balance::current();
+        let seconds = elapsed.as_secs_f32();
+        let mut changed = Vec::new();
+        for player in self.players.values_mut() {
+            let class = tables.class(player.character.class);
+            let mut rates = if player.combat.in_combat(now) { class.combat_regen } else { class.regen };
+            if player.last_sprint.is_some_and(|last| now.duration_since(last) < SPRINT_REGEN_DELAY) {
+                rates.stamina = 0.0;
+            }
+            player.combat.regenerate(&rates, seconds);
+
+            if player.combat.vitals() != player.sent_vitals {
+                changed.push(player.id);
+            }
+        }
+
+        for entity in changed {
+            self.send_vitals(entity);
+        }
+    }
+
+    /// Notice players that died since the last tick: they lose experience and are told
+    /// where they can respawn. Players a resurrection brought back get up where they lie.
+    fn update_deaths(&mut self) {
+        let mut died = Vec::new();
+        let mut revived = Vec::new();
+        for player in self.players.values_mut() {
+            let alive = player.combat.is_alive();
+            if !alive && !player.dead {
+                player.dead = true;
+                died.push(player.id);
+            } else if alive && player.dead {
+                player.dead = false;
+                revived.push((player.id, player.position));
+            }
+        }
+
+        let tables = balance::current();
+        for entity in died {
+            let Some(&addr) = self.entity_addrs.get(&entity) else { continue };
+            let Some(player) = self.player_by_entity(entity) else { continue };
+            let (level, experience) = (player.character.level, player.character.experience);
+            let lost = experience::death_penalty(level, experience, tables.death.experience_loss);
+            let respawn_point = tables.death.nearest_respawn_point(player.position)
+                .map(|point| point.name.clone())
+                .unwrap_or_default();
+            log::info!("Character {} died at {:?} and lost {} XP", player.character_id, player.position, lost);
+
+            if lost > 0 {
+                self.set_progress(addr, level, experience - lost, -lost);
+            }
+            self.send_response(addr, ServerMessage::Died { experience_lost: lost, respawn_point });
+        }
+        for (entity, position) in revived {
+            if let Some(&addr) = self.entity_addrs.get(&entity) {
+                self.send_response(addr, ServerMessage::Respawned { position });
+            }
+        }
+    }
+
+    /// A dead player gets up at the respawn point nearest to where it died, with part of
+    /// its health and mana
+    fn handle_respawn(&mut self, client_addr: SocketAddr) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        if player.combat.is_alive() {
+            return;  // Not dead, or a resurrection came first
+        }
+        let tables = balance::current();
+        let Some(point) = tables.death.nearest_respawn_point(player.position) else { return };
+        player.combat.resurrect(tables.death.respawn_health);
+        player.combat.mana = player.combat.max_mana * tables.death.respawn_mana;
+        player.dead = false;
+
+        let (entity, position) = (player.id, point.position);
+        log::info!("Character {} respawned at {}", player.character_id, point.name);
+        self.move_player(entity, position, Instant::now());
+        self.send_response(client_addr, ServerMessage::Respawned { position });
+        self.send_vitals(entity);
+    }
+
+    /// Change a player's inventory. Afterwards the owner gets the new inventory and it
+    /// is saved; if the change is not possible, the owner is told why.
+    fn change_inventory(&mut self, client_addr: SocketAddr, change: impl FnOnce(&mut Inventory) -> Result<(), ItemError>) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        match change(&mut player.inventory) {
+            Ok(()) => self.inventory_changed(client_addr),
+            Err(e) => self.send_response(client_addr, ServerMessage::ItemFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// Change what a player wears: like `change_inventory`, and afterwards its stats
+    /// follow the new equipment and nearby players see a new weapon
+    fn change_equipment(&mut self, client_addr: SocketAddr, change: impl FnOnce(&mut Inventory) -> Result<(), ItemError>) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let weapon = player.inventory.equipment().get(EquipmentSlot::Weapon);
+        if let Err(e) = change(&mut player.inventory) {
+            self.send_response(client_addr, ServerMessage::ItemFailed { reason: e.to_string() });
+            return;
+        }
+
+        player.combat.set_equipment(&player.character, player.inventory.equipment());
+        let (entity, position) = (player.id, player.position);
+        let new_weapon = player.inventory.equipment().get(EquipmentSlot::Weapon);
+        self.inventory_changed(client_addr);
+        self.send_vitals(entity);
+        if new_weapon != weapon {
+            self.broadcast_nearby(position, &ServerMessage::EquipmentChanged { id: entity, weapon: new_weapon });
+        }
+    }
+
+    /// Send a player its whole inventory and save it
+    fn inventory_changed(&mut self, client_addr: SocketAddr) {
+        self.send_inventory(client_addr, None);
+    }
+
+    /// Send a player its whole inventory and save it, together with the merchant trade
+    /// that changed it
+    fn send_inventory(&mut self, client_addr: SocketAddr, trade: Option<ShopTrade>) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let message = inventory_message(&player.inventory);
+        let (character_id, items, equipment, gold) = saved_inventory(player);
+        let job = match trade {
+            None => DbJob::SaveInventory { character_id, items, equipment, gold },
+            Some(trade) => DbJob::SaveShopTrade { character_id, items, equipment, gold, trade },
+        };
+        self.send_response(client_addr, message);
+        self.queue_db(job);
+    }
+
+    /// Use up one consumable from a slot: restores health or mana
+    fn handle_use_item(&mut self, client_addr: SocketAddr, slot: usize) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let now = Instant::now();
+        let result = if player.combat.is_alive() {
+            player.inventory.check_use(slot, player.character.level, player.last_item_use, now)
+        } else {
+            Err(ItemError::Dead)
+        };
+        let effect = match result {
+            Ok(effect) => effect,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ItemFailed { reason: e.to_string() });
+                return;
+            }
+        };
+
+        if player.inventory.take(slot, 1).is_err() {
+            return;  // check_use found the item
+        }
+        match effect {
+            ItemEffect::RestoreHealth(amount) => { player.combat.heal(amount); }
+            ItemEffect::RestoreMana(amount) => { player.combat.restore_mana(amount); }
+        }
+        player.last_item_use = Some(now);
+        let entity = player.id;
+        self.inventory_changed(client_addr);
+        self.send_vitals(entity);
+    }
+
+    /// Spawn mobs into free region slots and remove the dead ones, whose slots
+    /// respawn after the region's timer
+    fn update_mobs(&mut self) {
+        let now = Instant::now();
+        for request in self.spawner.due_spawns(now) {
+            let id = self.entity_ids.allocate();
+            let mob = Mob::new(id, request.mob, request.region, request.position, request.yaw, now);
+            self.interest.update_entity(id, mob.position);
+            self.mobs.insert(id, mob);
+        }
+
+        let dead: Vec<u64> = self.mobs.values()
+            .filter(|mob| !mob.combat.is_alive())
+            .map(|mob| mob.id)
+            .collect();
+        for id in dead {
+            let events = self.interest.remove_entity(id);
+            self.send_interest_events(&events);
+            if let Some(mob) = self.mobs.remove(&id) {
+                self.reward_kill(&mob);
+                self.drop_loot(&mob);
+                self.spawner.died(mob.region, now);
+                let region = &self.spawner.regions()[mob.region];
+                log::debug!(
+                    "{:?} {} died in {} ({}/{} left, respawn in {:?})",
+                    mob.kind, id, region.name, self.spawner.population(mob.region), region.max_population, region.respawn
                 );
             }
         }
     }
 
-    fn send_response(&self, addr: SocketAddr, message: ServerMessage) {
-        if let Ok(data) = bincode::serialize(&message) {
-            if let Err(e) = self.socket.send_to(&data, addr) {
-                log::error!("Error sending response to {}: {}", addr, e);
+    /// Experience for a dead mob: split between the tagging player's party members
+    /// near it (or the tagger alone), each share scaled by their own level
+    fn reward_kill(&mut self, mob: &Mob) {
+        let Some(tagger) = mob.tagged_by else { return };
+        let mut members = self.party.members(tagger);
+        if members.is_empty() {
+            members.push(tagger);
+        }
+        let near: Vec<(u64, i32)> = members.into_iter()
+            .filter_map(|member| self.player_by_entity(member))
+            .filter(|p| p.position.distance(mob.position) <= KILL_SHARE_RADIUS)
+            .map(|p| (p.id, p.character.level))
+            .collect();
+        if near.is_empty() {
+            return;
+        }
+
+        let share = mob.kind.info().experience / near.len() as i64;
+        for (member, level) in near {
+            self.award_experience(member, experience::kill_experience(share, mob.level(), level));
+        }
+    }
+
+    /// Roll a dead mob's loot and put it on the ground around it, reserved for the
+    /// tagging player's party for a while. Mobs nobody tagged drop nothing.
+    fn drop_loot(&mut self, mob: &Mob) {
+        let Some(tagger) = mob.tagged_by else { return };
+        let tables = balance::current();
+        let Some(table) = tables.loot(mob.kind) else { return };
+        let drops = loot::roll(&tables, table, mob.level(), &mut self.loot_rng);
+
+        let mut owners = self.party.members(tagger);
+        if owners.is_empty() {
+            owners.push(tagger);
+        }
+        let now = Instant::now();
+        let count = drops.len();
+        for (index, drop) in drops.into_iter().enumerate() {
+            self.spawn_ground_item(drop, loot::scatter(mob.position, index, count), owners.clone(), now);
+        }
+    }
+
+    /// Put a pile on the ground; clients near it see it with the next interest update
+    fn spawn_ground_item(&mut self, loot: Loot, position: Vec3, owners: Vec<u64>, now: Instant) {
+        let id = self.entity_ids.allocate();
+        let position = Vec3::new(position.x, 0.0, position.z);
+        log::debug!("Ground item {} ({:?}) at {:?}", id, loot, position);
+        self.interest.update_entity(id, position);
+        self.ground_items.insert(id, GroundItem::new(id, loot, position, owners, now));
+    }
+
+    /// Take a pile off the ground and despawn it on the clients that could see it
+    fn remove_ground_item(&mut self, id: u64) -> Option<GroundItem> {
+        // Ground items are still in `ground_items` while their removal is sent
+        let events = self.interest.remove_entity(id);
+        self.send_interest_events(&events);
+        self.ground_items.remove(&id)
+    }
+
+    fn despawn_ground_items(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u64> = self.ground_items.values()
+            .filter(|item| item.is_expired(now))
+            .map(|item| item.id)
+            .collect();
+        for id in expired {
+            self.remove_ground_item(id);
+        }
+    }
+
+    /// Put a pile from the ground into a player's inventory, if it may have it and it fits
+    fn handle_pick_up(&mut self, client_addr: SocketAddr, id: u64) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let result = match self.ground_items.get(&id) {
+            _ if !player.combat.is_alive() => Err(ItemError::Dead.to_string()),
+            None => Err(LootError::Gone.to_string()),
+            Some(item) => item.check_pickup(player.id, player.position, Instant::now())
+                .map_err(|e| e.to_string())
+                .and_then(|()| player.inventory.add_loot(item.loot).map_err(|e| e.to_string())),
+        };
+        if let Err(reason) = result {
+            self.send_response(client_addr, ServerMessage::ItemFailed { reason });
+            return;
+        }
+
+        if let Some(item) = self.remove_ground_item(id) {
+            log::info!("{} picked up {:?}", client_addr, item.loot);
+        }
+        self.inventory_changed(client_addr);
+    }
+
+    /// Take items out of a slot and leave them on the ground at the player's feet,
+    /// free for anyone to pick up
+    fn handle_drop_item(&mut self, client_addr: SocketAddr, slot: usize, count: u32) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let stack = match player.inventory.take(slot, count) {
+            Ok(stack) => stack,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ItemFailed { reason: e.to_string() });
+                return;
+            }
+        };
+        let position = player.position;
+        log::info!("{} dropped {}x item {}", client_addr, stack.count, stack.item.0);
+        self.inventory_changed(client_addr);
+        self.spawn_ground_item(Loot::Item(stack), position, Vec::new(), Instant::now());
+    }
+
+    /// Show a player what a merchant next to it has left
+    fn handle_open_shop(&mut self, client_addr: SocketAddr, shop: ShopId) {
+        let tables = balance::current();
+        let info = match self.check_shop(client_addr, shop, &tables) {
+            Ok(info) => info,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ShopFailed { reason: e.to_string() });
+                return;
+            }
+        };
+        let stock = self.shop_stock.listings(shop, info, Instant::now());
+        self.send_response(client_addr, ServerMessage::ShopStock { shop, stock });
+    }
+
+    /// Buy from a merchant. The new inventory is saved together with the purchase;
+    /// the buyer also gets the merchant's new stock.
+    fn handle_buy_item(&mut self, client_addr: SocketAddr, shop: ShopId, item: ItemId, count: u32) {
+        let tables = balance::current();
+        let now = Instant::now();
+        let info = match self.check_shop(client_addr, shop, &tables) {
+            Ok(info) => info,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ShopFailed { reason: e.to_string() });
+                return;
+            }
+        };
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let trade = match self.shop_stock.buy(shop, info, item, count, &mut player.inventory, now) {
+            Ok(trade) => trade,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ShopFailed { reason: e.to_string() });
+                return;
+            }
+        };
+
+        log::info!("{} bought {}x item {} from shop {} for {} gold", client_addr, trade.count, item.0, shop.0, trade.gold);
+        self.send_inventory(client_addr, Some(trade));
+        let stock = self.shop_stock.listings(shop, info, now);
+        self.send_response(client_addr, ServerMessage::ShopStock { shop, stock });
+    }
+
+    /// Sell items from a slot to a merchant. The new inventory is saved together with
+    /// the sale.
+    fn handle_sell_item(&mut self, client_addr: SocketAddr, shop: ShopId, slot: usize, count: u32) {
+        let tables = balance::current();
+        let info = match self.check_shop(client_addr, shop, &tables) {
+            Ok(info) => info,
+            Err(e) => {
+                self.send_response(client_addr, ServerMessage::ShopFailed { reason: e.to_string() });
+                return;
+            }
+        };
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        match shops::sell(shop, info, slot, count, &mut player.inventory) {
+            Ok(trade) => {
+                log::info!("{} sold {}x item {} to shop {} for {} gold", client_addr, -trade.count, trade.item.0, shop.0, -trade.gold);
+                self.send_inventory(client_addr, Some(trade));
+            }
+            Err(e) => self.send_response(client_addr, ServerMessage::ShopFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// The merchant a living player may trade with right now
+    fn check_shop<'a>(&self, client_addr: SocketAddr, shop: ShopId, tables: &'a BalanceTables) -> Result<&'a ShopInfo, ShopError> {
+        let player = self.players.get(&client_addr.to_string()).ok_or(ShopError::UnknownShop)?;
+        if !player.combat.is_alive() {
+            return Err(ItemError::Dead.into());
+        }
+        let info = tables.shop(shop).ok_or(ShopError::UnknownShop)?;
+        shops::check_range(info, player.position)?;
+        Ok(info)
+    }
+
+    /// Ask another player nearby to trade
+    fn handle_trade_request(&mut self, client_addr: SocketAddr, target: u64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let (requester, name, position) = (player.id, player.character.name.clone(), player.position);
+        let found = self.entity_addrs.get(&target).copied().zip(self.player_by_entity(target).map(|p| p.position));
+        let Some((target_addr, target_position)) = found else {
+            self.send_response(client_addr, ServerMessage::TradeFailed { reason: "Player not found".to_string() });
+            return;
+        };
+
+        let result = if position.distance(target_position) > TRADE_RANGE {
+            Err(TradeError::TooFar)
+        } else {
+            self.trades.request(requester, target, Instant::now())
+        };
+        match result {
+            Ok(()) => {
+                log::info!("Player {} asked {} to trade", requester, target);
+                self.send_response(target_addr, ServerMessage::TradeRequested { requester, name });
+            }
+            Err(e) => self.send_response(client_addr, ServerMessage::TradeFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// Open a trade with the player who asked us, if it is still nearby
+    fn handle_trade_accept(&mut self, client_addr: SocketAddr, requester: u64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let (target, target_name, position) = (player.id, player.character.name.clone(), player.position);
+        let Some((requester_name, requester_position)) = self.player_by_entity(requester)
+            .map(|p| (p.character.name.clone(), p.position)) else {
+            self.send_response(client_addr, ServerMessage::TradeFailed { reason: "Player not found".to_string() });
+            return;
+        };
+
+        let result = if position.distance(requester_position) > TRADE_RANGE {
+            Err(TradeError::TooFar)
+        } else {
+            self.trades.accept(target, requester, Instant::now())
+        };
+        if let Err(e) = result {
+            self.send_response(client_addr, ServerMessage::TradeFailed { reason: e.to_string() });
+            return;
+        }
+
+        log::info!("Players {} and {} started trading", requester, target);
+        self.send_response(client_addr, ServerMessage::TradeOpened { partner: requester, name: requester_name });
+        if let Some(&requester_addr) = self.entity_addrs.get(&requester) {
+            self.send_response(requester_addr, ServerMessage::TradeOpened { partner: target, name: target_name });
+        }
+        self.send_trade_offers(target);
+    }
+
+    /// Replace our offer with items and gold from our inventory
+    fn handle_trade_offer(&mut self, client_addr: SocketAddr, items: &[(usize, u32)], gold: u64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let entity = player.id;
+        match self.trades.set_offer(entity, items, gold, &player.inventory) {
+            Ok(()) => self.send_trade_offers(entity),
+            Err(e) => self.send_response(client_addr, ServerMessage::TradeFailed { reason: e.to_string() }),
+        }
+    }
+
+    fn handle_trade_lock(&mut self, client_addr: SocketAddr) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let entity = player.id;
+        match self.trades.lock(entity) {
+            Ok(()) => self.send_trade_offers(entity),
+            Err(e) => self.send_response(client_addr, ServerMessage::TradeFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// Agree to the swap; when both players have agreed, it happens
+    fn handle_trade_confirm(&mut self, client_addr: SocketAddr) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else { return };
+        let entity = player.id;
+        match self.trades.confirm(entity) {
+            Ok(true) => self.complete_trade(entity),
+            Ok(false) => self.send_trade_offers(entity),
+            Err(e) => self.send_response(client_addr, ServerMessage::TradeFailed { reason: e.to_string() }),
+        }
+    }
+
+    /// Swap the offers of a trade both players agreed to. Both new inventories are
+    /// saved in one transaction; if the swap isn't possible any more, the trade is
+    /// cancelled and nothing changes.
+    fn complete_trade(&mut self, entity: u64) {
+        let Some((partner, ours, theirs)) = self.trades.close(entity) else { return };
+        let (Some(&addr), Some(&partner_addr)) = (self.entity_addrs.get(&entity), self.entity_addrs.get(&partner)) else { return };
+        let (Some(player), Some(other)) = (self.players.get(&addr.to_string()), self.players.get(&partner_addr.to_string())) else { return };
+        let (mut inventory, mut partner_inventory) = (player.inventory.clone(), other.inventory.clone());
+        if let Err(e) = trade::swap(&mut inventory, &ours, &mut partner_inventory, &theirs) {
+            log::info!("Trade between players {} and {} failed: {}", entity, partner, e);
+            for addr in [addr, partner_addr] {
+                self.send_response(addr, ServerMessage::TradeCancelled { reason: e.to_string() });
+            }
+            return;
+        }
+
+        for (addr, inventory) in [(addr, inventory), (partner_addr, partner_inventory)] {
+            let Some(player) = self.players.get_mut(&addr.to_string()) else { continue };
+            player.inventory = inventory;
+            let message = inventory_message(&player.inventory);
+            self.send_response(addr, ServerMessage::TradeCompleted);
+            self.send_response(addr, message);
+        }
+        log::info!("Players {} and {} traded {:?} for {:?}", entity, partner, ours, theirs);
+        let saved = [addr, partner_addr].map(|addr| self.players.get(&addr.to_string()).map(saved_inventory));
+        if let [Some(first), Some(second)] = saved {
+            self.queue_db(DbJob::SavePlayerTrade { inventories: [first, second] });
+        }
+    }
+
+    /// Cancel a player's trade, if it has one, and tell both players
+    fn cancel_trade(&mut self, entity: u64, reason: &str) {
+        let Some((partner, _, _)) = self.trades.close(entity) else { return };
+        self.send_trade_cancelled(entity, reason);
+        self.send_trade_cancelled(partner, reason);
+    }
+
+    fn send_trade_cancelled(&mut self, entity: u64, reason: &str) {
+        if let Some(&addr) = self.entity_addrs.get(&entity) {
+            self.send_response(addr, ServerMessage::TradeCancelled { reason: reason.to_string() });
+        }
+    }
+
+    /// Send both players of a trade the offers as they are now
+    fn send_trade_offers(&mut self, entity: u64) {
+        let Some(partner) = self.trades.partner(entity) else { return };
+        for player in [entity, partner] {
+            let Some((ours, theirs)) = self.trades.offers(player) else { continue };
+            let message = ServerMessage::TradeUpdated { ours: ours.clone(), theirs: theirs.clone() };
+            if let Some(&addr) = self.entity_addrs.get(&player) {
+                self.send_response(addr, message);
+            }
+        }
+    }
+
+    /// Cancel the trades of players who are too far apart
+    fn cancel_distant_trades(&mut self) {
+        let too_far: Vec<u64> = self.trades.trades().into_iter()
+            .filter(|&(player, partner)| {
+                let position = |entity| self.player_by_entity(entity).map(|p| p.position);
+                match (position(player), position(partner)) {
+                    (Some(a), Some(b)) => a.distance(b) > TRADE_RANGE,
+                    _ => true,
+                }
+            })
+            .map(|(player, _)| player)
+            .collect();
+        for player in too_far {
+            self.cancel_trade(player, &TradeError::TooFar.to_string());
+        }
+    }
+
+    /// Let every mob think and move; attacks are applied afterwards
+    fn update_mob_ai(&mut self, elapsed: Duration) {
+        let now = Instant::now();
+        let tables = balance::current();
+        let ids: Vec<u64> = self.mobs.keys().copied().collect();
+        let mut attacks = Vec::new();
+        for id in ids {
+            let Some(position) = self.mobs.get(&id).map(|mob| mob.position) else { continue };
+            // Players that can see the mob and could be attacked by it
+            let players: Vec<(u64, Vec3)> = self.interest.query_radius(position, self.interest.view_radius())
+                .into_iter()
+                .filter_map(|entity| self.player_by_entity(entity))
+                .filter(|p| p.combat.is_alive() && !p.combat.has_effect(EffectKind::Invisible, now))
+                .map(|p| (p.id, p.position))
+                .collect();
+
+            let Some(mob) = self.mobs.get_mut(&id) else { continue };
+            if let Some(action) = mob_ai::think(mob, tables.mob(mob.kind), &self.nav, &players, now, elapsed.as_secs_f32()) {
+                attacks.push((id, action));
+            }
+            let position = mob.position;
+            self.interest.update_entity(id, position);
+        }
+
+        for (mob_id, action) in attacks {
+            match action {
+                MobAction::Attack { target } => self.mob_attack(mob_id, target, now),
+            }
+        }
+    }
+
+    fn mob_attack(&mut self, mob_id: u64, target: u64, now: Instant) {
+        let Some(mob) = self.mobs.get(&mob_id) else { return };
+        let origin = mob.position;
+        let damage = mob.kind.info().damage * mob.combat.damage_dealt_multiplier(now);
+        let Some(combatant) = self.combatant_mut(target) else { return };
+        let dealt = combatant.take_damage(damage, now);
+        let health = combatant.health;
+        let event = ServerMessage::Damage { source: mob_id, target, skill: None, amount: dealt, health };
+        self.publish_combat_events(origin, mob_id, vec![event]);
+    }
+
+    /// Send a message to every client that can see `position`
+    fn broadcast_nearby(&mut self, position: Vec3, message: &ServerMessage) {
+        let recipients: Vec<SocketAddr> = self.interest.query_radius(position, self.interest.view_radius())
+            .into_iter()
+            .filter_map(|entity| self.entity_addrs.get(&entity).copied())
+            .collect();
+        for addr in recipients {
+            self.send_response(addr, message.clone());
+        }
+    }
+
+    /// Add a player to the world. Nearby players are exchanged on the next interest update.
+    fn enter_world(&mut self, client_addr: SocketAddr, player_state: PlayerState) {
+        let addr_str = client_addr.to_string();
+
+        // Re-selecting a character from the same address replaces the old entity
+        if self.players.contains_key(&addr_str) {
+            self.leave_world(client_addr);
+        }
+
+        log::info!("{} entered the world as entity {}", player_state.character.name, player_state.id);
+
+        self.interest.update_entity(player_state.id, player_state.position);
+        self.interest.add_observer(player_state.id);
+        self.entity_addrs.insert(player_state.id, client_addr);
+        self.players.insert(addr_str, player_state);
+    }
+
+    /// Remove a player from the world and tell the clients that could see it to despawn it
+    fn leave_world(&mut self, client_addr: SocketAddr) {
+        let Some(player) = self.players.remove(&client_addr.to_string()) else { return };
+
+        self.leave_party(player.id);
+        if let Some(partner) = self.trades.leave(player.id) {
+            self.send_trade_cancelled(partner, "Your trade partner left");
+        }
+        self.entity_addrs.remove(&player.id);
+        let events = self.interest.remove_entity(player.id);
+        self.send_interest_events(&events);
+    }
+
+    /// Recompute visibility and spawn/despawn entities on the affected clients
+    fn update_interest(&mut self) {
+        let events = self.interest.update();
+        self.send_interest_events(&events);
+    }
+
+    fn send_interest_events(&mut self, events: &[InterestEvent]) {
+        let mut outgoing = Vec::new();
+        for event in events {
+            match *event {
+                InterestEvent::Enter { observer, entity } => {
+                    let Some(&addr) = self.entity_addrs.get(&observer) else { continue };
+                    if let Some(mob) = self.mobs.get(&entity) {
+                        outgoing.push((addr, ServerMessage::MobSpawned {
+                            id: mob.id,
+                            mob: mob.kind,
+                            position: mob.position,
+                            health: mob.combat.health,
+                            max_health: mob.combat.max_health,
+                        }));
+                        continue;
+                    }
+                    if let Some(item) = self.ground_items.get(&entity) {
+                        outgoing.push((addr, ServerMessage::GroundItemSpawned {
+                            id: item.id,
+                            loot: item.loot,
+                            position: item.position,
+                        }));
+                        continue;
+                    }
+                    let Some(player) = self.player_by_entity(entity) else { continue };
+                    outgoing.push((addr, ServerMessage::PlayerJoined {
+                        id: player.id,
+                        character: player.character.clone(),
+                        position: player.position,
+                        weapon: player.inventory.equipment().get(EquipmentSlot::Weapon),
+                    }));
+                }
+                InterestEvent::Leave { observer, entity } => {
+                    let Some(&addr) = self.entity_addrs.get(&observer) else { continue };
+                    // Mobs and ground items are still in their maps while their removal is sent
+                    let message = if self.mobs.contains_key(&entity) {
+                        ServerMessage::MobLeft { id: entity }
+                    } else if self.ground_items.contains_key(&entity) {
+                        ServerMessage::GroundItemRemoved { id: entity }
+                    } else {
+                        ServerMessage::PlayerLeft { id: entity }
+                    };
+                    outgoing.push((addr, message));
+                }
+            }
+        }
+
+        for (addr, message) in outgoing {
+            self.send_response(addr, message);
+        }
+    }
+
+    fn player_by_entity(&self, entity: u64) -> Option<&PlayerState> {
+        let addr = self.entity_addrs.get(&entity)?;
+        self.players.get(&addr.to_string())
+    }
+
+    /// Send every client a snapshot of the players and mobs within its area of interest,
+    /// delta-encoded against the last snapshot that client acknowledged
+    fn broadcast_world_state(&mut self) {
+        self.snapshot_tick += 1;
+        let tick = self.snapshot_tick;
+        let server_time = self.start_time.elapsed().as_secs_f64();
+        let now = Instant::now();
+
+        let mut snapshots: Vec<(String, SnapshotEntities)> = Vec::new();
+        for (recipient_addr, recipient) in &self.players {
+            let players = self.interest.visible_to(recipient.id)
+                .filter_map(|entity| self.player_by_entity(entity))
+                // Invisible players don't give their position away
+                .filter(|p| p.id == recipient.id || !p.combat.has_effect(EffectKind::Invisible, now))
+                .map(|p| (p.id, EntitySnapshot::new(p.position, p.yaw, p.character.level)));
+            let mobs = self.interest.visible_to(recipient.id)
+                .filter_map(|entity| self.mobs.get(&entity))
+                .map(|mob| (mob.id, EntitySnapshot::new(mob.position, mob.yaw, mob.level())));
+            let entities: SnapshotEntities = players.chain(mobs).collect();
+            snapshots.push((recipient_addr.clone(), entities));
+        }
+
+        let mut outgoing = Vec::new();
+        for (recipient_addr, entities) in snapshots {
+            let Ok(addr) = recipient_addr.parse::<SocketAddr>() else { continue };
+            let Some(recipient) = self.players.get_mut(&recipient_addr) else { continue };
+
+            let baseline = recipient.acked_snapshot
+                .and_then(|acked| recipient.sent_snapshots.get(acked).map(|entities| (acked, entities)));
+            let delta = snapshot::diff(baseline.map(|(_, entities)| entities), &entities);
+            let baseline_tick = baseline.map(|(acked, _)| acked);
+
+            if entities.is_empty() && delta.is_empty() {
+                continue;
+            }
+            recipient.sent_snapshots.insert(tick, entities);
+
+            outgoing.push((addr, ServerMessage::WorldState {
+                tick,
+                baseline: baseline_tick,
+                server_time,
+                entities: delta.entities,
+                removed: delta.removed,
+            }));
+        }
+
+        for (addr, message) in outgoing {
+            self.send_response(addr, message);
+        }
+    }
+
+    fn send_response(&mut self, addr: SocketAddr, message: ServerMessage) {
+        let Some(connection) = self.connections.get_mut(&addr) else {
+            log::debug!("Not sending to {} - no connection", addr);
+            return;
+        };
+        match connection.endpoint.send(&message, message.is_reliable(), Instant::now()) {
+            Ok(data) => {
+                if self.outgoing.send((addr, data)).is_err() {
+                    log::error!("Send task is gone, dropping response to {}", addr);
+                }
+            }
+            Err(e) => log::error!("Error serializing response for {}: {}", addr, e),
+        }
+    }
+
+    /// Send the balance tables, one chunk per message so that each fits a datagram
+    fn send_balance(&mut self, addr: SocketAddr, chunks: &[Vec<u8>]) {
+        let total = chunks.len() as u16;
+        for (index, data) in chunks.iter().enumerate() {
+            self.send_response(addr, ServerMessage::BalanceChunk { index: index as u16, total, data: data.clone() });
+        }
+    }
+
+    /// Send due resends and standalone acks on every connection
+    fn flush_connections(&mut self) {
+        let now = Instant::now();
+        for (addr, connection) in self.connections.iter_mut() {
+            for packet in connection.endpoint.poll(now) {
+                if let Ok(data) = packet.to_bytes() {
+                    let _ = self.outgoing.send((*addr, data));
+                }
             }
         }
     }
 
     /// Auto-save positions for players that need it (dirty flag + time-based)
-    async fn auto_save_positions(&mut self) {
+    fn auto_save_positions(&mut self) {
         let mut positions_to_save = Vec::new();
         let now = Instant::now();
 
@@ -551,42 +2402,19 @@ impl GameServer {
             }
         }
 
-        // Batch save all positions in one transaction
+        // Batch save all positions in one transaction (failures come back as PositionsNotSaved)
         if !positions_to_save.is_empty() {
-            match db::characters::batch_save_positions(&self.db_pool, &positions_to_save).await {
-                Ok(_) => {
-                    log::info!("Auto-saved positions for {} players", positions_to_save.len());
-                }
-                Err(e) => {
-                    log::error!("Error batch saving positions: {}", e);
-                    // Re-mark as dirty on error so we retry
-                    for (char_id, _, _, _) in positions_to_save {
-                        if let Some(player) = self.players.values_mut().find(|p| p.character_id == char_id) {
-                            player.dirty = true;
-                        }
-                    }
-                }
-            }
+            self.queue_db(DbJob::SavePositions(positions_to_save));
         }
     }
 
-    /// Save a single player's position immediately (for disconnects)
-    async fn save_player_position(&self, player: &PlayerState) {
+    /// Save a single player's position (for disconnects)
+    fn save_player_position(&self, player: &PlayerState) {
         if player.dirty {
-            match db::characters::update_position(
-                &self.db_pool,
-                player.character_id,
-                player.position.x,
-                player.position.y,
-                player.position.z,
-            ).await {
-                Ok(_) => {
-                    log::info!("Saved position for character {} on disconnect", player.character_id);
-                }
-                Err(e) => {
-                    log::error!("Error saving position on disconnect: {}", e);
-                }
-            }
+            self.queue_db(DbJob::SavePosition {
+                character_id: player.character_id,
+                position: player.position,
+            });
         }
     }
 
@@ -605,125 +2433,83 @@ impl GameServer {
 
     /// Send initial time to a specific client (called once after login)
     /// No longer broadcasts to all players - clients calculate time locally
-    fn send_initial_time(&self, client_addr: SocketAddr) {
+    fn send_initial_time(&mut self, client_addr: SocketAddr) {
         let message = ServerMessage::TimeUpdate {
             hour: self.game_time.hour,
         };
         
-        if let Ok(data) = bincode::serialize(&message) {
-            let _ = self.socket.send_to(&data, client_addr);
+        self.send_response(client_addr, message);
+    }
+
+    /// Give a player experience earned in the game (mob kills, later quest rewards).
+    /// This is the only way players gain experience outside of GM commands.
+    fn award_experience(&mut self, entity: u64, amount: i64) {
+        let Some(&addr) = self.entity_addrs.get(&entity) else { return };
+        let Some(player) = self.players.get(&addr.to_string()) else { return };
+        if amount <= 0 {
+            return;
         }
+        let (level, experience) = experience::add_experience(player.character.level, player.character.experience, amount);
+        log::info!("Character {} gained {} XP", player.character_id, amount);
+        self.set_progress(addr, level, experience, amount);
     }
 
-    /// Handle experience gain and level-ups
-    async fn handle_gain_experience(&mut self, client_addr: SocketAddr, amount: i64) {
-        let addr_str = client_addr.to_string();
-        
-        // Extract data we need before borrowing
-        let (character_id, current_level, current_xp, character_class) = {
-            let player = match self.players.get(&addr_str) {
-                Some(p) => p,
-                None => {
-                    log::warn!("No player state for {}", addr_str);
-                    return;
-                }
-            };
-            (player.character_id, player.character.level, player.character.experience, player.character.class.clone())
+    /// GM command: grant experience, or take away a level with a negative amount
+    fn handle_gm_experience(&mut self, client_addr: SocketAddr, amount: i64) {
+        let Some(player) = self.players.get(&client_addr.to_string()) else {
+            log::warn!("No player state for {}", client_addr);
+            return;
         };
-        
-        // Add experience (can be negative for level down)
-        let mut new_xp = current_xp + amount;
-        
-        log::info!("Character {} gained {} XP (total: {})", character_id, amount, new_xp);
-        
-        let mut new_level = current_level;
-        let mut level_changed = false;
-        
-        // Handle level-downs FIRST (negative XP) - for DEV commands
-        if new_xp < 0 {
-            if new_level > 1 {
-                // Go down one level and set XP to 0
-                new_level -= 1;
-                new_xp = 0;
-                level_changed = true;
-                log::info!("DEV: Character {} leveled DOWN to {} (XP reset to 0)", character_id, new_level);
-            } else {
-                // At level 1, just set XP to 0
-                new_xp = 0;
-                log::info!("DEV: Character {} already at level 1, XP reset to 0", character_id);
-            }
-        } else {
-            // Handle level-ups (positive XP) - only if XP is positive!
-            while new_level < 100 {
-                let xp_needed = shared::calculate_xp_for_level(new_level + 1);
-                
-                if new_xp >= xp_needed {
-                    // Level up!
-                    new_xp -= xp_needed;
-                    new_level += 1;
-                    level_changed = true;
-                    log::info!("Character {} leveled UP to {}!", character_id, new_level);
-                } else {
-                    break;
-                }
-            }
-        }
-        
-        // Update character data in player state
-        if let Some(player) = self.players.get_mut(&addr_str) {
-            player.character.level = new_level;
-            player.character.experience = new_xp;
+        if !player.gm {
+            log::warn!("Character {} ({}) tried a GM command without permission", player.character_id, client_addr);
+            return;
         }
-        
-        // Calculate XP needed for next level
-        let xp_needed = if new_level < 100 {
-            shared::calculate_xp_for_level(new_level + 1)
+
+        let (character_id, level) = (player.character_id, player.character.level);
+        let (new_level, new_experience) = if amount >= 0 {
+            experience::add_experience(level, player.character.experience, amount)
         } else {
-            0 // Max level
+            // Down one level (not below 1) with the experience reset
+            ((level - 1).max(1), 0)
         };
-        
-        // Send experience gained message
+        log::info!("GM: Character {} now level {} with {} XP ({:+} XP requested)", character_id, new_level, new_experience, amount);
+        self.set_progress(client_addr, new_level, new_experience, amount);
+    }
+
+    /// Store a player's new level and experience, tell the client and save it
+    fn set_progress(&mut self, client_addr: SocketAddr, level: i32, experience: i64, amount: i64) {
+        let Some(player) = self.players.get_mut(&client_addr.to_string()) else { return };
+        let level_changed = player.character.level != level;
+        player.character.level = level;
+        player.character.experience = experience;
+        let character_id = player.character_id;
+        let max_stats = level_changed.then(|| {
+            let stats = shared::calculate_stats(level, &player.character.class, player.inventory.equipment());
+            player.combat.reset_vitals(stats.0, stats.1, stats.2);
+            stats
+        });
+
         self.send_response(client_addr, ServerMessage::ExperienceGained {
             amount,
-            new_total: new_xp,
-            xp_needed,
+            new_total: experience,
+            xp_needed: experience::experience_needed(level),
         });
-        
-        // If level changed (up or down), send level-up message with new stats
-        if level_changed {
-            let char_class = match character_class.as_str() {
-                "Krieger" => shared::CharacterClass::Krieger,
-                "Ninja" => shared::CharacterClass::Ninja,
-                "Sura" => shared::CharacterClass::Sura,
-                "Schamane" => shared::CharacterClass::Schamane,
-                _ => shared::CharacterClass::Krieger,
-            };
-            
-            let (max_health, max_mana, max_stamina) = 
-                shared::calculate_stats_for_level(new_level, &char_class);
-            
+
+        // Level changed (up, or down by GM): new maximum stats
+        if let Some((max_health, max_mana, max_stamina)) = max_stats {
             self.send_response(client_addr, ServerMessage::LevelUp {
-                new_level,
+                new_level: level,
                 new_max_health: max_health,
                 new_max_mana: max_mana,
                 new_max_stamina: max_stamina,
             });
-            
             log::info!(
                 "Character {} is now level {} (HP: {}, Mana: {}, Stamina: {})",
-                character_id, new_level, max_health, max_mana, max_stamina
+                character_id, level, max_health, max_mana, max_stamina
             );
         }
-        
-        // Save to database
-        match db::characters::update_level_and_xp(&self.db_pool, character_id, new_level, new_xp).await {
-            Ok(_) => {
-                log::debug!("Updated level/XP in database for character {}", character_id);
-            }
-            Err(e) => {
-                log::error!("Error updating level/XP in database: {}", e);
-            }
-        }
+
+        self.queue_db(DbJob::SaveLevel { character_id, level, experience });
     }
 }
 
@@ -735,8 +2521,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     log::info!("Game server running. Press Ctrl+C to stop.");
     
-    loop {
-        server.update().await;
-        tokio::time::sleep(tokio::time::Duration::from_millis(16)).await; // ~60 FPS
-    }
+    server.run().await;
+    Ok(())
 }
@@ -0,0 +1,306 @@
+// Mob behaviour, run by the server tick.
+//
+// Without a target a mob idles and wanders around its spawn point. Players that come
+// within its aggro radius, or hurt it, go on its threat table; it chases and attacks
+// whoever has the most threat. Taunts force the taunter on top for a while. A mob that
+// is dragged too far from home (or loses every target) drops its threat and walks back,
+// evading all damage until it is home again with full health and no longer tagged.
+
+use crate::mobs::{Mob, Xorshift};
+use crate::navigation::NavGrid;
+use shared::bevy::prelude::Vec3;
+use shared::{MobInfo, Specialization};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How far a mob follows its target away from its spawn point (meters)
+pub const LEASH_RADIUS: f32 = 30.0;
+
+/// Distance at which a mob hits its target (meters)
+pub const ATTACK_RANGE: f32 = 2.0;
+
+/// Time between two hits of a mob
+pub const ATTACK_INTERVAL: Duration = Duration::from_millis(2000);
+
+/// How long a taunt forces a mob onto the taunter
+pub const TAUNT_DURATION: Duration = Duration::from_secs(4);
+
+/// Threat a taunter ends up with, relative to the highest threat before the taunt
+const TAUNT_THREAT_FACTOR: f32 = 1.1;
+
+/// Threat for noticing a player nearby, before anyone hit the mob
+const AGGRO_THREAT: f32 = 1.0;
+
+/// Healing someone a mob is fighting earns this much threat per point healed
+pub const HEAL_THREAT_FACTOR: f32 = 0.5;
+
+/// Threat per point of damage by Leibwächter (tank); everyone else has 1
+const TANK_THREAT_MULTIPLIER: f32 = 2.0;
+
+/// Wandering stays this close to the spawn point (meters)
+const WANDER_RADIUS: f32 = 6.0;
+
+/// Pause between two wander moves (seconds)
+const IDLE_MIN: f32 = 3.0;
+const IDLE_MAX: f32 = 8.0;
+
+/// Speeds relative to the mob's move speed
+const WANDER_SPEED_FACTOR: f32 = 0.4;
+const RETURN_SPEED_FACTOR: f32 = 1.5;
+
+/// Close enough to a destination to count as arrived (meters)
+const ARRIVE_DISTANCE: f32 = 0.3;
+
+/// A chased target that moved this far from where the path led gets a new path (meters)
+const REPATH_DISTANCE: f32 = 2.0;
+
+/// Threat generated per point of damage by a player of this specialization
+pub fn threat_multiplier(specialization: Option<Specialization>) -> f32 {
+    match specialization {
+        Some(Specialization::Leibwaechter) => TANK_THREAT_MULTIPLIER,
+        _ => 1.0,
+    }
+}
+
+/// Accumulated threat per attacker. The attacker with the most threat is the target,
+/// unless a taunt is running.
+#[derive(Debug, Clone, Default)]
+pub struct ThreatTable {
+    threat: HashMap<u64, f32>,
+    taunt: Option<(u64, Instant)>,  // Taunter and when the taunt ends
+}
+
+impl ThreatTable {
+    pub fn add(&mut self, attacker: u64, amount: f32) {
+        *self.threat.entry(attacker).or_insert(0.0) += amount.max(0.0);
+    }
+
+    /// Force `taunter` to be the target until the taunt ends. It also ends up with more
+    /// threat than anyone else, so it keeps the mob afterwards unless others catch up.
+    pub fn taunt(&mut self, taunter: u64, now: Instant) {
+        let highest = self.threat.values().copied().fold(0.0, f32::max);
+        let threat = self.threat.entry(taunter).or_insert(0.0);
+        *threat = threat.max(highest * TAUNT_THREAT_FACTOR).max(AGGRO_THREAT);
+        self.taunt = Some((taunter, now + TAUNT_DURATION));
+    }
+
+    pub fn contains(&self, attacker: u64) -> bool {
+        self.threat.contains_key(&attacker)
+    }
+
+    /// Who the mob attacks
+    pub fn target(&self, now: Instant) -> Option<u64> {
+        if let Some((taunter, until)) = self.taunt {
+            if until > now && self.threat.contains_key(&taunter) {
+                return Some(taunter);
+            }
+        }
+        self.threat.iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(a.0)))  // Ties: lowest ID
+            .map(|(&attacker, _)| attacker)
+    }
+
+    /// Forget attackers for which `keep` is false
+    pub fn retain(&mut self, mut keep: impl FnMut(u64) -> bool) {
+        self.threat.retain(|&attacker, _| keep(attacker));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.threat.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.threat.clear();
+        self.taunt = None;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MobState {
+    Idle { until: Instant },
+    Wander { destination: Vec3 },
+    Chase { target: u64 },
+    /// Leashed: walking home, evading damage
+    Return,
+}
+
+/// What a mob decided to do this update besides moving
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MobAction {
+    Attack { target: u64 },
+}
+
+/// AI state of one mob
+#[derive(Debug, Clone)]
+pub struct MobBrain {
+    pub state: MobState,
+    pub home: Vec3,
+    pub threat: ThreatTable,
+    next_attack: Instant,
+    route: Route,
+    rng: Xorshift,
+}
+
+/// Path around buildings towards a destination out of sight
+#[derive(Debug, Clone, Default)]
+struct Route {
+    goal: Vec3,
+    waypoints: Vec<Vec3>,  // Next waypoint last
+}
+
+impl MobBrain {
+    pub fn new(home: Vec3, seed: u64, now: Instant) -> Self {
+        let mut rng = Xorshift::new(seed);
+        let until = now + idle_time(&mut rng);
+        Self {
+            state: MobState::Idle { until },
+            home,
+            threat: ThreatTable::default(),
+            next_attack: now,
+            route: Route::default(),
+            rng,
+        }
+    }
+
+    /// Walking home after a leash; takes no damage and ignores players
+    pub fn is_evading(&self) -> bool {
+        self.state == MobState::Return
+    }
+}
+
+/// Run one AI update. `players` are the attackable players (alive, visible) near the
+/// mob with their positions; `elapsed` is the time since the last update in seconds.
+pub fn think(mob: &mut Mob, info: &MobInfo, nav: &NavGrid, players: &[(u64, Vec3)], now: Instant, elapsed: f32) -> Option<MobAction> {
+    if !mob.combat.is_alive() || mob.combat.is_stunned(now) {
+        return None;
+    }
+    let brain = &mut mob.brain;
+
+    if brain.state == MobState::Return {
+        let home = brain.home;
+        if walk(mob, nav, home, info.move_speed * RETURN_SPEED_FACTOR * elapsed) {
+            let max_health = mob.combat.max_health;
+            mob.combat.heal(max_health);
+            mob.tagged_by = None;  // A fresh fight, anyone can claim it
+            mob.brain.state = MobState::Idle { until: now + idle_time(&mut mob.brain.rng) };
+        }
+        return None;
+    }
+
+    // Targets that died, vanished or went out of reach are forgotten
+    brain.threat.retain(|attacker| players.iter().any(|(id, _)| *id == attacker));
+
+    // Notice players that come too close
+    if brain.threat.is_empty() {
+        let nearest = players.iter()
+            .map(|(id, position)| (*id, horizontal_distance(mob.position, *position)))
+            .filter(|(_, distance)| *distance <= info.aggro_radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((id, _)) = nearest {
+            brain.threat.add(id, AGGRO_THREAT);
+        }
+    }
+
+    let Some(target) = brain.threat.target(now) else {
+        return idle(mob, info, nav, now, elapsed);
+    };
+
+    // Dragged too far from home
+    if horizontal_distance(mob.position, brain.home) > LEASH_RADIUS {
+        brain.threat.clear();
+        brain.state = MobState::Return;
+        return None;
+    }
+
+    brain.state = MobState::Chase { target };
+    let &(_, target_position) = players.iter().find(|(id, _)| *id == target)?;
+    if horizontal_distance(mob.position, target_position) > ATTACK_RANGE {
+        walk(mob, nav, target_position, info.move_speed * elapsed);
+        return None;
+    }
+
+    face(&mut mob.yaw, target_position - mob.position);
+    if now < brain.next_attack {
+        return None;
+    }
+    brain.next_attack = now + ATTACK_INTERVAL;
+    Some(MobAction::Attack { target })
+}
+
+/// No target: stand around, now and then walk somewhere near home. A mob that just
+/// lost its last target walks home first.
+fn idle(mob: &mut Mob, info: &MobInfo, nav: &NavGrid, now: Instant, elapsed: f32) -> Option<MobAction> {
+    let brain = &mut mob.brain;
+    match brain.state {
+        MobState::Chase { .. } => {
+            brain.state = MobState::Return;
+        }
+        MobState::Idle { until } if now >= until => {
+            let angle = brain.rng.next_unit() * std::f32::consts::TAU;
+            let distance = WANDER_RADIUS * brain.rng.next_unit().sqrt();
+            let destination = brain.home + Vec3::new(angle.cos() * distance, 0.0, angle.sin() * distance);
+            brain.state = MobState::Wander { destination };
+        }
+        MobState::Wander { destination } => {
+            let step = info.move_speed * WANDER_SPEED_FACTOR * elapsed;
+            if walk(mob, nav, destination, step) {
+                mob.brain.state = MobState::Idle { until: now + idle_time(&mut mob.brain.rng) };
+            }
+        }
+        MobState::Idle { .. } | MobState::Return => {}
+    }
+    None
+}
+
+fn idle_time(rng: &mut Xorshift) -> Duration {
+    Duration::from_secs_f32(IDLE_MIN + (IDLE_MAX - IDLE_MIN) * rng.next_unit())
+}
+
+/// Move up to `max_step` towards `goal`, around buildings when they are in the way.
+/// Returns whether the goal (or the closest reachable point to it) was reached.
+fn walk(mob: &mut Mob, nav: &NavGrid, goal: Vec3, max_step: f32) -> bool {
+    let route = &mut mob.brain.route;
+    if nav.line_of_sight(mob.position, goal) {
+        route.waypoints.clear();
+        return step_towards(&mut mob.position, &mut mob.yaw, goal, max_step);
+    }
+
+    if route.waypoints.is_empty() || horizontal_distance(route.goal, goal) > REPATH_DISTANCE {
+        let Some(mut waypoints) = nav.find_path(mob.position, goal) else {
+            return true;  // No way there: as close as it gets
+        };
+        waypoints.reverse();
+        *route = Route { goal, waypoints };
+    }
+    let Some(&waypoint) = route.waypoints.last() else { return true };
+    if step_towards(&mut mob.position, &mut mob.yaw, waypoint, max_step) {
+        route.waypoints.pop();
+        return route.waypoints.is_empty();
+    }
+    false
+}
+
+/// Move up to `max_step` towards `goal` on the ground plane, facing the direction of
+/// travel. Returns whether the goal was reached.
+fn step_towards(position: &mut Vec3, yaw: &mut f32, goal: Vec3, max_step: f32) -> bool {
+    let offset = Vec3::new(goal.x - position.x, 0.0, goal.z - position.z);
+    let distance = offset.length();
+    if distance <= ARRIVE_DISTANCE {
+        return true;
+    }
+    face(yaw, offset);
+    let step = max_step.min(distance);
+    *position += offset / distance * step;
+    distance - step <= ARRIVE_DISTANCE
+}
+
+/// Turn to look along `direction` (models look down -Z, like players)
+fn face(yaw: &mut f32, direction: Vec3) {
+    if direction.x != 0.0 || direction.z != 0.0 {
+        *yaw = (-direction.x).atan2(-direction.z);
+    }
+}
+
+pub fn horizontal_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec3::new(a.x - b.x, 0.0, a.z - b.z).length()
+}
@@ -0,0 +1,198 @@
+// Monsters: spawn regions around the city, population caps and respawn timers.
+//
+// Every region keeps up to `max_population` mobs of one type alive at random points
+// inside its circle. A mob that dies frees its slot after the region's respawn time.
+// Mobs take their network entity IDs from the same allocator as players and are
+// replicated the same way: interest manager for spawn/despawn, WorldState snapshots
+// for movement.
+
+use crate::combat::Combatant;
+use crate::mob_ai::MobBrain;
+use shared::bevy::prelude::Vec3;
+use shared::MobType;
+use std::f32::consts::TAU;
+use std::time::{Duration, Instant};
+
+/// Where one type of mob lives
+#[derive(Debug, Clone)]
+pub struct SpawnRegion {
+    pub name: &'static str,
+    pub mob: MobType,
+    pub center: Vec3,
+    pub radius: f32,
+    pub max_population: usize,
+    pub respawn: Duration,  // From death until the slot is filled again
+}
+
+/// The fields around the city (it covers roughly -40..40 on both axes)
+pub fn default_regions() -> Vec<SpawnRegion> {
+    vec![
+        SpawnRegion {
+            name: "Nordwiese",
+            mob: MobType::Wolf,
+            center: Vec3::new(0.0, 0.0, 58.0),
+            radius: 12.0,
+            max_population: 8,
+            respawn: Duration::from_secs(20),
+        },
+        SpawnRegion {
+            name: "Eichenhain",
+            mob: MobType::Wildschwein,
+            center: Vec3::new(58.0, 0.0, 0.0),
+            radius: 12.0,
+            max_population: 6,
+            respawn: Duration::from_secs(30),
+        },
+        SpawnRegion {
+            name: "Räuberlager",
+            mob: MobType::Bandit,
+            center: Vec3::new(-58.0, 0.0, -40.0),
+            radius: 10.0,
+            max_population: 5,
+            respawn: Duration::from_secs(45),
+        },
+        SpawnRegion {
+            name: "Orkschanze",
+            mob: MobType::Ork,
+            center: Vec3::new(45.0, 0.0, -60.0),
+            radius: 10.0,
+            max_population: 3,
+            respawn: Duration::from_secs(90),
+        },
+    ]
+}
+
+/// A monster in the world
+#[derive(Debug, Clone)]
+pub struct Mob {
+    pub id: u64,             // Network entity ID
+    pub kind: MobType,
+    pub region: usize,       // Index into the spawner's regions
+    pub position: Vec3,
+    pub yaw: f32,
+    pub combat: Combatant,
+    pub brain: MobBrain,     // AI state, see `mob_ai`
+    pub tagged_by: Option<u64>,  // First player to hurt it: their party gets the kill
+}
+
+impl Mob {
+    pub fn new(id: u64, kind: MobType, region: usize, position: Vec3, yaw: f32, now: Instant) -> Self {
+        let info = kind.info();
+        Self {
+            id,
+            kind,
+            region,
+            position,
+            yaw,
+            combat: Combatant::new(info.max_health, 0.0),
+            brain: MobBrain::new(position, id, now),
+            tagged_by: None,
+        }
+    }
+
+    /// A player hurt the mob; the first one claims the kill
+    pub fn tag(&mut self, attacker: u64) {
+        self.tagged_by.get_or_insert(attacker);
+    }
+
+    pub fn level(&self) -> i32 {
+        self.kind.info().level
+    }
+}
+
+/// A mob the spawner wants placed in the world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnRequest {
+    pub region: usize,
+    pub mob: MobType,
+    pub position: Vec3,
+    pub yaw: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RegionState {
+    alive: usize,
+    respawns: Vec<Instant>,  // Slots of dead mobs, free again at these times
+}
+
+/// Keeps every region at its population cap, with a delay after each death
+#[derive(Debug, Clone)]
+pub struct MobSpawner {
+    regions: Vec<SpawnRegion>,
+    state: Vec<RegionState>,
+    rng: Xorshift,  // Spawn points
+}
+
+impl MobSpawner {
+    pub fn new(regions: Vec<SpawnRegion>, seed: u64) -> Self {
+        let state = vec![RegionState::default(); regions.len()];
+        Self {
+            regions,
+            state,
+            rng: Xorshift::new(seed),
+        }
+    }
+
+    pub fn regions(&self) -> &[SpawnRegion] {
+        &self.regions
+    }
+
+    /// Living mobs of a region, as counted by the spawner
+    pub fn population(&self, region: usize) -> usize {
+        self.state.get(region).map_or(0, |state| state.alive)
+    }
+
+    /// Mobs to spawn now: every free slot whose respawn time has passed.
+    /// The caller must spawn all of them; they count as alive from here on.
+    pub fn due_spawns(&mut self, now: Instant) -> Vec<SpawnRequest> {
+        let mut requests = Vec::new();
+        for index in 0..self.regions.len() {
+            let state = &mut self.state[index];
+            state.respawns.retain(|at| *at > now);
+
+            let free = self.regions[index].max_population.saturating_sub(state.alive + state.respawns.len());
+            state.alive += free;
+            for _ in 0..free {
+                let (position, yaw) = self.random_point(index);
+                requests.push(SpawnRequest { region: index, mob: self.regions[index].mob, position, yaw });
+            }
+        }
+        requests
+    }
+
+    /// A mob of `region` died; its slot is filled again after the region's respawn time
+    pub fn died(&mut self, region: usize, now: Instant) {
+        let Some(state) = self.state.get_mut(region) else { return };
+        state.alive = state.alive.saturating_sub(1);
+        state.respawns.push(now + self.regions[region].respawn);
+    }
+
+    /// Uniformly distributed point inside a region's circle, facing a random direction
+    fn random_point(&mut self, region: usize) -> (Vec3, f32) {
+        let (center, radius) = (self.regions[region].center, self.regions[region].radius);
+        let distance = radius * self.rng.next_unit().sqrt();
+        let angle = self.rng.next_unit() * TAU;
+        let position = center + Vec3::new(angle.cos() * distance, 0.0, angle.sin() * distance);
+        (position, self.rng.next_unit() * TAU)
+    }
+}
+
+/// Small pseudo random generator for spawn points, wandering and loot. Not secure:
+/// where players gain from predicting it (loot), seed it randomly and keep it private.
+#[derive(Debug, Clone)]
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        // Spread nearby seeds (like consecutive entity IDs) apart; zero would stay zero forever
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15).max(1))
+    }
+
+    /// Next random number in 0..1
+    pub fn next_unit(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,92 @@
+use shared::bevy::prelude::Vec3;
+use std::time::{Duration, Instant};
+
+/// Headroom over the nominal speed (frame timing, packet jitter)
+const SPEED_TOLERANCE: f32 = 1.25;
+
+/// Distance allowed on top of the speed budget. Covers updates that were delayed and
+/// then arrive back-to-back. It is part of the budget, so it can't be claimed again
+/// with every packet.
+const POSITION_SLACK: f32 = 1.0;
+
+/// The budget never holds more than this much movement time - standing still must
+/// not bank a teleport
+const MAX_ELAPSED: Duration = Duration::from_secs(1);
+
+/// Fastest fall the client's physics can reach (meters per second)
+const MAX_FALL_SPEED: f32 = 55.0;
+
+/// Result of checking one position update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveCheck {
+    Accepted,
+    /// The client moved further than its speed allows since the last accepted update
+    TooFast { distance: f32, allowed: f32 },
+}
+
+/// Checks client-reported positions against a maximum speed.
+///
+/// Every accepted move is paid from a budget of meters that fills up with elapsed
+/// time and is capped, so many small updates can't add up to more than the speed
+/// allows. Horizontal movement and climbing share one budget limited by `max_speed`;
+/// falling has its own, limited by `MAX_FALL_SPEED`.
+#[derive(Debug, Clone)]
+pub struct MovementValidator {
+    max_speed: f32,
+    budget: f32,
+    fall_budget: f32,
+    last_accepted: Instant,
+}
+
+impl MovementValidator {
+    pub fn new(max_speed: f32, now: Instant) -> Self {
+        Self {
+            max_speed,
+            budget: POSITION_SLACK,
+            fall_budget: POSITION_SLACK,
+            last_accepted: now,
+        }
+    }
+
+    /// Validate a move from the last accepted position `from` to `to`
+    pub fn check(&mut self, from: Vec3, to: Vec3, now: Instant) -> MoveCheck {
+        self.check_at_speed(from, to, now, self.max_speed)
+    }
+
+    /// Like `check`, with another speed limit for this update (e.g. sprinting)
+    pub fn check_at_speed(&mut self, from: Vec3, to: Vec3, now: Instant, max_speed: f32) -> MoveCheck {
+        let elapsed = now.duration_since(self.last_accepted);
+        let allowed = refill(self.budget, max_speed * SPEED_TOLERANCE, elapsed);
+        let fall_allowed = refill(self.fall_budget, MAX_FALL_SPEED, elapsed);
+
+        let horizontal = Vec3::new(to.x - from.x, 0.0, to.z - from.z).length();
+        let climb = (to.y - from.y).max(0.0);
+        let distance = horizontal.max(climb);
+        let fall = (from.y - to.y).max(0.0);
+
+        if !to.is_finite() || distance > allowed {
+            return MoveCheck::TooFast { distance, allowed };
+        }
+        if fall > fall_allowed {
+            return MoveCheck::TooFast { distance: fall, allowed: fall_allowed };
+        }
+
+        self.budget = allowed - distance;
+        self.fall_budget = fall_allowed - fall;
+        self.last_accepted = now;
+        MoveCheck::Accepted
+    }
+
+    /// Restart timing, e.g. after the server moved the player itself
+    pub fn reset(&mut self, now: Instant) {
+        self.budget = POSITION_SLACK;
+        self.fall_budget = POSITION_SLACK;
+        self.last_accepted = now;
+    }
+}
+
+/// What a budget holds after `elapsed` at `speed`, up to `MAX_ELAPSED` of movement
+fn refill(budget: f32, speed: f32, elapsed: Duration) -> f32 {
+    let cap = speed * MAX_ELAPSED.as_secs_f32() + POSITION_SLACK;
+    (budget + speed * elapsed.min(MAX_ELAPSED).as_secs_f32()).min(cap)
+}
@@ -0,0 +1,239 @@
+// Server-side navigation around the city buildings.
+//
+// The ground is a grid of square cells; a cell is blocked when its center lies within
+// `agent_radius` of an obstacle footprint (`shared::city`, the same boxes the client
+// uses as colliders). Paths are found with A* over the 8 neighbours of a cell and then
+// smoothed by dropping every waypoint the walker can already see past. Pure data, no
+// Bevy app: it runs in the headless server tick.
+
+use shared::bevy::prelude::Vec3;
+use shared::city::{self, Footprint};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Cell edge length of the city grid (meters)
+pub const CELL_SIZE: f32 = 1.0;
+
+/// The city grid covers -HALF_SIZE..HALF_SIZE on X and Z: the city and the spawn
+/// regions around it, plus leash distance
+pub const HALF_SIZE: f32 = 128.0;
+
+/// Clearance kept from walls (about a body radius)
+pub const AGENT_RADIUS: f32 = 0.6;
+
+/// Give up on a path after visiting this many cells (unreachable goals)
+const MAX_EXPANSIONS: usize = 40_000;
+
+/// Step costs in tenths of a cell
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Blocked cells on the ground plane. Positions outside the grid count as open field.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    origin: Vec3,      // Corner of cell (0, 0)
+    cell_size: f32,
+    width: usize,      // Cells along X
+    depth: usize,      // Cells along Z
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Grid centered on the world origin, `half_size` meters in every direction
+    pub fn new(footprints: &[Footprint], half_size: f32, cell_size: f32, agent_radius: f32) -> Self {
+        let cells = ((half_size * 2.0) / cell_size).ceil().max(1.0) as usize;
+        let origin = Vec3::new(-half_size, 0.0, -half_size);
+        let mut grid = Self {
+            origin,
+            cell_size,
+            width: cells,
+            depth: cells,
+            blocked: vec![false; cells * cells],
+        };
+        for z in 0..grid.depth {
+            for x in 0..grid.width {
+                let center = grid.cell_center((x, z));
+                grid.blocked[z * grid.width + x] = footprints.iter().any(|f| f.contains(center, agent_radius));
+            }
+        }
+        grid
+    }
+
+    /// The grid of the city layout the client spawns
+    pub fn city() -> Self {
+        Self::new(&city::footprints(), HALF_SIZE, CELL_SIZE, AGENT_RADIUS)
+    }
+
+    pub fn is_walkable(&self, position: Vec3) -> bool {
+        match self.cell_of(position) {
+            Some(cell) => !self.is_blocked(cell),
+            None => true,
+        }
+    }
+
+    /// Whether a straight walk from `from` to `to` stays on walkable ground
+    pub fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        let offset = Vec3::new(to.x - from.x, 0.0, to.z - from.z);
+        // Quarter-cell samples: inflation by the agent radius covers what lies between
+        let steps = (offset.length() / (self.cell_size * 0.25)).ceil() as usize;
+        (0..=steps).all(|i| {
+            let t = if steps == 0 { 0.0 } else { i as f32 / steps as f32 };
+            self.is_walkable(from + offset * t)
+        })
+    }
+
+    /// Waypoints from `from` to `to` around obstacles, excluding the start (heights are
+    /// not meaningful). The last waypoint is `to`, or the nearest reachable point if `to`
+    /// is inside an obstacle. None if there is no way there.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        if self.line_of_sight(from, to) {
+            return Some(vec![to]);
+        }
+        let start = self.nearest_open(self.clamped_cell(from))?;
+        let goal = self.nearest_open(self.clamped_cell(to))?;
+
+        let mut waypoints: Vec<Vec3> = self.search(start, goal)?.into_iter()
+            .map(|cell| self.cell_center(cell))
+            .collect();
+        if self.is_walkable(to) {
+            waypoints.push(to);
+        }
+        Some(self.smooth(from, &waypoints))
+    }
+
+    /// A* from `start` to `goal`; the cells after `start` up to and including `goal`
+    fn search(&self, start: (usize, usize), goal: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        let index = |(x, z): (usize, usize)| z * self.width + x;
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut came_from = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+
+        cost[index(start)] = 0;
+        open.push(Reverse((octile(start, goal), index(start))));
+        let mut expansions = 0;
+
+        while let Some(Reverse((_, current))) = open.pop() {
+            let cell = (current % self.width, current / self.width);
+            if cell == goal {
+                let mut cells = Vec::new();
+                let mut at = current;
+                while at != index(start) {
+                    cells.push((at % self.width, at / self.width));
+                    at = came_from[at];
+                }
+                cells.reverse();
+                return Some(cells);
+            }
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                return None;
+            }
+
+            for (neighbour, step) in self.neighbours(cell) {
+                let next = index(neighbour);
+                let next_cost = cost[current] + step;
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    came_from[next] = current;
+                    open.push(Reverse((next_cost + octile(neighbour, goal), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Open neighbours with their step cost. Diagonals must not cut a blocked corner.
+    fn neighbours(&self, (x, z): (usize, usize)) -> impl Iterator<Item = ((usize, usize), u32)> + '_ {
+        const OFFSETS: [(i32, i32); 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+        OFFSETS.iter().filter_map(move |&(dx, dz)| {
+            let neighbour = self.offset_cell((x, z), dx, dz)?;
+            if self.is_blocked(neighbour) {
+                return None;
+            }
+            if dx != 0 && dz != 0 {
+                let side_x = self.offset_cell((x, z), dx, 0)?;
+                let side_z = self.offset_cell((x, z), 0, dz)?;
+                if self.is_blocked(side_x) || self.is_blocked(side_z) {
+                    return None;
+                }
+                return Some((neighbour, DIAGONAL_COST));
+            }
+            Some((neighbour, STRAIGHT_COST))
+        })
+    }
+
+    /// Keep only the waypoints where the walker has to turn: from each kept point, walk
+    /// ahead while the next waypoint is still in line of sight
+    fn smooth(&self, from: Vec3, waypoints: &[Vec3]) -> Vec<Vec3> {
+        let mut path = Vec::new();
+        let mut anchor = from;
+        let mut next = 0;
+        while next < waypoints.len() {
+            let mut farthest = next;
+            while farthest + 1 < waypoints.len() && self.line_of_sight(anchor, waypoints[farthest + 1]) {
+                farthest += 1;
+            }
+            anchor = waypoints[farthest];
+            path.push(anchor);
+            next = farthest + 1;
+        }
+        path
+    }
+
+    /// Closest open cell to `cell`, searching outwards ring by ring
+    fn nearest_open(&self, cell: (usize, usize)) -> Option<(usize, usize)> {
+        let max_ring = self.width.max(self.depth) as i32;
+        for ring in 0..max_ring {
+            for dz in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs() != ring && dz.abs() != ring {
+                        continue;
+                    }
+                    if let Some(candidate) = self.offset_cell(cell, dx, dz) {
+                        if !self.is_blocked(candidate) {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn is_blocked(&self, (x, z): (usize, usize)) -> bool {
+        self.blocked[z * self.width + x]
+    }
+
+    fn cell_of(&self, position: Vec3) -> Option<(usize, usize)> {
+        let x = ((position.x - self.origin.x) / self.cell_size).floor();
+        let z = ((position.z - self.origin.z) / self.cell_size).floor();
+        if x < 0.0 || z < 0.0 || x >= self.width as f32 || z >= self.depth as f32 {
+            return None;
+        }
+        Some((x as usize, z as usize))
+    }
+
+    /// Cell of a position, or the nearest edge cell when it is outside the grid
+    fn clamped_cell(&self, position: Vec3) -> (usize, usize) {
+        let x = ((position.x - self.origin.x) / self.cell_size).floor().clamp(0.0, (self.width - 1) as f32);
+        let z = ((position.z - self.origin.z) / self.cell_size).floor().clamp(0.0, (self.depth - 1) as f32);
+        (x as usize, z as usize)
+    }
+
+    fn offset_cell(&self, (x, z): (usize, usize), dx: i32, dz: i32) -> Option<(usize, usize)> {
+        let x = x.checked_add_signed(dx as isize)?;
+        let z = z.checked_add_signed(dz as isize)?;
+        (x < self.width && z < self.depth).then_some((x, z))
+    }
+
+    fn cell_center(&self, (x, z): (usize, usize)) -> Vec3 {
+        self.origin + Vec3::new((x as f32 + 0.5) * self.cell_size, 0.0, (z as f32 + 0.5) * self.cell_size)
+    }
+}
+
+/// Distance estimate with diagonal moves, in step cost units
+fn octile(a: (usize, usize), b: (usize, usize)) -> u32 {
+    let dx = a.0.abs_diff(b.0) as u32;
+    let dz = a.1.abs_diff(b.1) as u32;
+    STRAIGHT_COST * dx.max(dz) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dz)
+}
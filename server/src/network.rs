@@ -0,0 +1,40 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// One UDP datagram and the client it came from / goes to
+pub type Datagram = (SocketAddr, Vec<u8>);
+
+/// Largest datagram we accept
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+/// Read datagrams as they arrive and hand them to the simulation
+pub async fn receive_loop(socket: Arc<UdpSocket>, incoming: UnboundedSender<Datagram>) {
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+
+    loop {
+        match socket.recv_from(&mut buf).await {
+            Ok((size, src)) => {
+                if incoming.send((src, buf[..size].to_vec())).is_err() {
+                    break; // Simulation has shut down
+                }
+            }
+            Err(e) => {
+                // E.g. ICMP "port unreachable" from a client that just closed - not fatal
+                log::debug!("Error receiving datagram: {}", e);
+            }
+        }
+    }
+    log::info!("Receive task stopped");
+}
+
+/// Write datagrams produced by the simulation to the socket
+pub async fn send_loop(socket: Arc<UdpSocket>, mut outgoing: UnboundedReceiver<Datagram>) {
+    while let Some((addr, data)) = outgoing.recv().await {
+        if let Err(e) = socket.send_to(&data, addr).await {
+            log::error!("Error sending to {}: {}", addr, e);
+        }
+    }
+    log::info!("Send task stopped");
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Most players in one party
+pub const MAX_PARTY_SIZE: usize = 5;
+
+/// How long an invitation can be accepted
+pub const INVITE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartyError {
+    InviteSelf,
+    AlreadyInParty,
+    NotLeader,
+    PartyFull,
+    NoInvitation,
+}
+
+impl fmt::Display for PartyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartyError::InviteSelf => write!(f, "You can't invite yourself"),
+            PartyError::AlreadyInParty => write!(f, "Player is already in a party"),
+            PartyError::NotLeader => write!(f, "Only the party leader can invite"),
+            PartyError::PartyFull => write!(f, "Party is full"),
+            PartyError::NoInvitation => write!(f, "Invitation is no longer valid"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Invitation {
+    inviter: u64,
+    expires: Instant,
+}
+
+/// Parties of players, by network entity ID. The first member is the leader.
+#[derive(Debug, Default)]
+pub struct PartyManager {
+    parties: HashMap<u64, Vec<u64>>,        // Party ID -> members
+    party_of: HashMap<u64, u64>,            // Member -> party ID
+    invitations: HashMap<u64, Invitation>,  // Invited player -> newest invitation
+    next_party_id: u64,
+}
+
+impl PartyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Invite `invitee` into the inviter's party (a new one if the inviter has none)
+    pub fn invite(&mut self, inviter: u64, invitee: u64, now: Instant) -> Result<(), PartyError> {
+        if inviter == invitee {
+            return Err(PartyError::InviteSelf);
+        }
+        if self.party_of.contains_key(&invitee) {
+            return Err(PartyError::AlreadyInParty);
+        }
+        let members = self.members(inviter);
+        if members.first().is_some_and(|leader| *leader != inviter) {
+            return Err(PartyError::NotLeader);
+        }
+        if members.len() >= MAX_PARTY_SIZE {
+            return Err(PartyError::PartyFull);
+        }
+        self.invitations.insert(invitee, Invitation { inviter, expires: now + INVITE_TIMEOUT });
+        Ok(())
+    }
+
+    /// Join the party of the player who invited us. Returns the members afterwards.
+    pub fn accept(&mut self, invitee: u64, inviter: u64, now: Instant) -> Result<Vec<u64>, PartyError> {
+        let valid = self.invitations.get(&invitee)
+            .is_some_and(|invitation| invitation.inviter == inviter && invitation.expires > now);
+        if !valid {
+            return Err(PartyError::NoInvitation);
+        }
+        self.invitations.remove(&invitee);
+        if self.party_of.contains_key(&invitee) {
+            return Err(PartyError::AlreadyInParty);
+        }
+
+        let party_id = match self.party_of.get(&inviter) {
+            Some(&party_id) => party_id,
+            None => {
+                let party_id = self.next_party_id;
+                self.next_party_id += 1;
+                self.parties.insert(party_id, vec![inviter]);
+                self.party_of.insert(inviter, party_id);
+                party_id
+            }
+        };
+        let members = self.parties.get_mut(&party_id).expect("party of a member exists");
+        if members.len() >= MAX_PARTY_SIZE {
+            return Err(PartyError::PartyFull);
+        }
+        members.push(invitee);
+        self.party_of.insert(invitee, party_id);
+        Ok(members.clone())
+    }
+
+    /// Leave the current party (also used on disconnect). Returns the members left
+    /// behind; a party of one is dissolved, so its last member is returned as well.
+    pub fn leave(&mut self, member: u64) -> Vec<u64> {
+        self.invitations.remove(&member);
+        let Some(party_id) = self.party_of.remove(&member) else { return Vec::new() };
+        let Some(members) = self.parties.get_mut(&party_id) else { return Vec::new() };
+        members.retain(|m| *m != member);
+        let remaining = members.clone();
+
+        if remaining.len() <= 1 {
+            self.parties.remove(&party_id);
+            for m in &remaining {
+                self.party_of.remove(m);
+            }
+        }
+        remaining
+    }
+
+    /// Everyone in the member's party including the member, leader first.
+    /// Empty when not in a party.
+    pub fn members(&self, member: u64) -> Vec<u64> {
+        self.party_of.get(&member)
+            .and_then(|party_id| self.parties.get(party_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn same_party(&self, a: u64, b: u64) -> bool {
+        match (self.party_of.get(&a), self.party_of.get(&b)) {
+            (Some(party_a), Some(party_b)) => party_a == party_b,
+            _ => false,
+        }
+    }
+}
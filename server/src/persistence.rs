@@ -0,0 +1,275 @@
+use shared::{AuthResponse, CharacterData, Specialization};
+use shared::bevy::prelude::Vec3;
+use shared::items::{EquipmentSlot, ItemId, ItemStack};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use crate::auth::{self, VerifiedLogin};
+use crate::db;
+use crate::db::characters::Character;
+use crate::db::items::SavedInventory;
+use crate::inventory::Inventory;
+use crate::shops::ShopTrade;
+
+/// How often a failed trade between players is saved again before giving up
+const TRADE_SAVE_RETRIES: u32 = 3;
+
+/// Wait before the first retry; doubled for every further one
+const TRADE_SAVE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// A character as loaded for playing, with everything it carries
+#[derive(Debug)]
+pub struct LoadedCharacter {
+    pub character: Character,
+    pub items: Vec<(usize, ItemStack)>,  // (slot, stack)
+    pub equipment: Vec<(EquipmentSlot, ItemId)>,
+}
+
+/// Work for the database worker. Jobs run one after another in the order they were
+/// queued, so a save queued before a load is always visible to that load.
+#[derive(Debug)]
+pub enum DbJob {
+    // Requests answered with a `DbResult`
+    Register { addr: SocketAddr, username: String, password: String, email: Option<String> },
+    Login { addr: SocketAddr, username: String, password: String },
+    CreateCharacter { addr: SocketAddr, user_id: i64, character: CharacterData },
+    LoadCharacter { addr: SocketAddr, token: String, character_id: i64 },
+    DeleteCharacter { addr: SocketAddr, user_id: i64, character_id: i64 },
+    SaveSpecialization { addr: SocketAddr, character_id: i64, specialization: Specialization },
+
+    // Fire-and-forget writes
+    UpdateLastLogin { user_id: i64 },
+    SavePosition { character_id: i64, position: Vec3 },
+    SavePositions(Vec<(i64, f32, f32, f32)>),  // (character_id, x, y, z)
+    SaveLevel { character_id: i64, level: i32, experience: i64 },
+    SaveInventory {
+        character_id: i64,
+        items: Vec<(usize, ItemStack)>,  // (slot, stack)
+        equipment: Vec<(EquipmentSlot, ItemId)>,
+        gold: u64,
+    },
+    /// An inventory changed by a merchant trade, saved together with the trade
+    SaveShopTrade {
+        character_id: i64,
+        items: Vec<(usize, ItemStack)>,  // (slot, stack)
+        equipment: Vec<(EquipmentSlot, ItemId)>,
+        gold: u64,
+        trade: ShopTrade,
+    },
+    /// Both inventories after a trade between two players, saved together. Failed saves
+    /// are retried before any later job runs. Answered only if the save failed for
+    /// good: both characters are then held - no inventory saves, no loads - until
+    /// `ReleaseCharacters`.
+    SavePlayerTrade { inventories: [SavedInventory; 2] },
+    /// The players of an unsaved trade have left the world; their characters are as
+    /// the database has them again
+    ReleaseCharacters { character_ids: [i64; 2] },
+}
+
+/// Outcome of a `DbJob`, handed back to the simulation on its next tick
+#[derive(Debug)]
+pub enum DbResult {
+    Registered { addr: SocketAddr, response: AuthResponse },
+    LoginVerified { addr: SocketAddr, result: Result<VerifiedLogin, AuthResponse> },
+    CharacterCreated { addr: SocketAddr, result: Result<i64, String> },
+    CharacterLoaded { addr: SocketAddr, token: String, character_id: i64, result: Result<Option<LoadedCharacter>, String> },
+    CharacterDeleted { addr: SocketAddr, character_id: i64, result: Result<bool, String> },
+    SpecializationSaved { addr: SocketAddr, character_id: i64, specialization: Specialization, success: bool },
+    /// A batch position save failed - these characters must be saved again
+    PositionsNotSaved { character_ids: Vec<i64> },
+    /// A trade between players could not be saved - the players must leave the world
+    /// without it, then `ReleaseCharacters`
+    PlayerTradeNotSaved { character_ids: [i64; 2] },
+}
+
+/// Start the database worker. Jobs go in through the returned sender, results come
+/// out through `results`.
+pub fn spawn_db_worker(pool: SqlitePool, results: UnboundedSender<DbResult>) -> UnboundedSender<DbJob> {
+    let (jobs_tx, jobs_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_db_worker(pool, jobs_rx, results));
+    jobs_tx
+}
+
+async fn run_db_worker(pool: SqlitePool, mut jobs: UnboundedReceiver<DbJob>, results: UnboundedSender<DbResult>) {
+    // Characters whose trade could not be saved
+    let mut held = HashSet::new();
+    while let Some(job) = jobs.recv().await {
+        if let Some(result) = execute(&pool, job, &mut held).await {
+            if results.send(result).is_err() {
+                break; // Simulation has shut down
+            }
+        }
+    }
+    log::info!("Database worker stopped");
+}
+
+async fn execute(pool: &SqlitePool, job: DbJob, held: &mut HashSet<i64>) -> Option<DbResult> {
+    // Until they are released, saving held characters' inventories would write half a
+    // trade, and loading them would read the state from before it
+    match &job {
+        DbJob::LoadCharacter { addr, token, character_id } if held.contains(character_id) => {
+            let result = Err("Character is being saved, try again in a moment".to_string());
+            return Some(DbResult::CharacterLoaded { addr: *addr, token: token.clone(), character_id: *character_id, result });
+        }
+        DbJob::SaveInventory { character_id, .. } | DbJob::SaveShopTrade { character_id, .. } if held.contains(character_id) => {
+            log::warn!("Not saving the inventory of character {}: its trade was not saved", character_id);
+            return None;
+        }
+        DbJob::SavePlayerTrade { inventories } if inventories.iter().any(|saved| held.contains(&saved.0)) => {
+            log::warn!("Not saving a trade of characters {} and {}: an earlier trade was not saved", inventories[0].0, inventories[1].0);
+            return None;
+        }
+        _ => {}
+    }
+
+    match job {
+        DbJob::Register { addr, username, password, email } => {
+            let response = auth::handle_register(pool, username, password, email).await;
+            Some(DbResult::Registered { addr, response })
+        }
+        DbJob::Login { addr, username, password } => {
+            let result = auth::verify_login(pool, username, password).await;
+            Some(DbResult::LoginVerified { addr, result })
+        }
+        DbJob::CreateCharacter { addr, user_id, character } => {
+            let result = create_character(pool, user_id, &character).await;
+            Some(DbResult::CharacterCreated { addr, result })
+        }
+        DbJob::LoadCharacter { addr, token, character_id } => {
+            let result = load_character(pool, character_id).await;
+            Some(DbResult::CharacterLoaded { addr, token, character_id, result })
+        }
+        DbJob::DeleteCharacter { addr, user_id, character_id } => {
+            let result = db::characters::delete_character(pool, character_id, user_id).await.map_err(|e| {
+                log::error!("Error deleting character: {}", e);
+                "Internal server error".to_string()
+            });
+            Some(DbResult::CharacterDeleted { addr, character_id, result })
+        }
+        DbJob::SaveSpecialization { addr, character_id, specialization } => {
+            let result = db::characters::update_specialization(pool, character_id, specialization.as_str()).await;
+            if let Err(e) = &result {
+                log::error!("Error saving specialization: {}", e);
+            }
+            Some(DbResult::SpecializationSaved { addr, character_id, specialization, success: result.is_ok() })
+        }
+        DbJob::UpdateLastLogin { user_id } => {
+            if let Err(e) = db::users::update_last_login(pool, user_id).await {
+                log::error!("Error updating last login: {}", e);
+            }
+            None
+        }
+        DbJob::SavePosition { character_id, position } => {
+            match db::characters::update_position(pool, character_id, position.x, position.y, position.z).await {
+                Ok(_) => log::info!("Saved position for character {} on disconnect", character_id),
+                Err(e) => log::error!("Error saving position on disconnect: {}", e),
+            }
+            None
+        }
+        DbJob::SavePositions(positions) => {
+            match db::characters::batch_save_positions(pool, &positions).await {
+                Ok(_) => {
+                    log::info!("Auto-saved positions for {} players", positions.len());
+                    None
+                }
+                Err(e) => {
+                    log::error!("Error batch saving positions: {}", e);
+                    let character_ids = positions.iter().map(|(id, _, _, _)| *id).collect();
+                    Some(DbResult::PositionsNotSaved { character_ids })
+                }
+            }
+        }
+        DbJob::SaveLevel { character_id, level, experience } => {
+            match db::characters::update_level_and_xp(pool, character_id, level, experience).await {
+                Ok(_) => log::debug!("Updated level/XP in database for character {}", character_id),
+                Err(e) => log::error!("Error updating level/XP in database: {}", e),
+            }
+            None
+        }
+        DbJob::SaveInventory { character_id, items, equipment, gold } => {
+            match db::items::save_inventory(pool, character_id, &items, &equipment, gold).await {
+                Ok(_) => log::debug!("Saved inventory of character {}", character_id),
+                Err(e) => log::error!("Error saving inventory of character {}: {}", character_id, e),
+            }
+            None
+        }
+        DbJob::SaveShopTrade { character_id, items, equipment, gold, trade } => {
+            let record = (trade.shop, trade.item, trade.count, trade.gold);
+            match db::shops::save_trade(pool, character_id, &items, &equipment, gold, record).await {
+                Ok(_) => log::debug!("Saved shop trade of character {}: {:?}", character_id, trade),
+                Err(e) => log::error!("Error saving shop trade of character {}: {}", character_id, e),
+            }
+            None
+        }
+        DbJob::SavePlayerTrade { inventories } => {
+            let character_ids = [inventories[0].0, inventories[1].0];
+            let mut delay = TRADE_SAVE_RETRY_DELAY;
+            for attempt in 0..=TRADE_SAVE_RETRIES {
+                if attempt > 0 {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                match db::items::save_inventories(pool, &inventories).await {
+                    Ok(_) => {
+                        log::debug!("Saved trade between characters {} and {}", character_ids[0], character_ids[1]);
+                        return None;
+                    }
+                    Err(e) => log::error!(
+                        "Error saving trade between characters {} and {} (attempt {}): {}",
+                        character_ids[0], character_ids[1], attempt + 1, e
+                    ),
+                }
+            }
+            held.extend(character_ids);
+            Some(DbResult::PlayerTradeNotSaved { character_ids })
+        }
+        DbJob::ReleaseCharacters { character_ids } => {
+            for character_id in character_ids {
+                held.remove(&character_id);
+            }
+            None
+        }
+    }
+}
+
+async fn create_character(pool: &SqlitePool, user_id: i64, character: &CharacterData) -> Result<i64, String> {
+    // Check if character name exists
+    match db::characters::character_name_exists(pool, &character.name).await {
+        Ok(true) => return Err("Character name already exists".to_string()),
+        Ok(false) => {}
+        Err(e) => {
+            log::error!("Error checking character name: {}", e);
+            return Err("Internal server error".to_string());
+        }
+    }
+
+    let character_id = db::characters::create_character(pool, user_id, character).await.map_err(|e| {
+        log::error!("Error creating character: {}", e);
+        "Failed to create character".to_string()
+    })?;
+
+    // Starting equipment; the character is usable without it
+    let starter = Inventory::starter();
+    let equipment: Vec<_> = starter.equipment().iter().collect();
+    if let Err(e) = db::items::save_inventory(pool, character_id, &starter.stacks(), &equipment, starter.gold).await {
+        log::error!("Error giving starter items to character {}: {}", character_id, e);
+    }
+    Ok(character_id)
+}
+
+/// A character with its inventory and equipment. A character whose inventory can't be
+/// read is not loaded: playing it would save over the inventory with an empty one.
+async fn load_character(pool: &SqlitePool, character_id: i64) -> Result<Option<LoadedCharacter>, String> {
+    let internal_error = |e: sqlx::Error| {
+        log::error!("Error getting character: {}", e);
+        "Internal server error".to_string()
+    };
+    let Some(character) = db::characters::load_character(pool, character_id).await.map_err(internal_error)? else {
+        return Ok(None);
+    };
+    let items = db::items::load_inventory(pool, character_id).await.map_err(internal_error)?;
+    let equipment = db::items::load_equipment(pool, character_id).await.map_err(internal_error)?;
+    Ok(Some(LoadedCharacter { character, items, equipment }))
+}
@@ -0,0 +1,162 @@
+// Merchants: buying from and selling to the shops of the balance tables.
+//
+// Every trade is checked completely before anything changes: the merchant has the
+// item, the buyer has the gold and the room. Limited listings sell out and get one
+// item back every `restock` seconds; stock lives in memory and is full again after a
+// restart. The caller saves the changed inventory together with a record of the trade
+// in one database transaction (see `db::shops`).
+
+use crate::inventory::{Inventory, ItemError};
+use shared::bevy::prelude::Vec3;
+use shared::items::{ItemId, ShopId, ShopInfo};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Furthest a player may stand from a merchant to trade (meters). More than the
+/// client's NPC interaction range, so a step back doesn't break an open shop.
+pub const SHOP_RANGE: f32 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShopError {
+    UnknownShop,
+    TooFar,
+    NotSold,
+    SoldOut { left: u32 },
+    NotEnoughGold { price: u64 },
+    Worthless,
+    NotBought,
+    Item(ItemError),
+}
+
+impl fmt::Display for ShopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShopError::UnknownShop => write!(f, "Unknown merchant"),
+            ShopError::TooFar => write!(f, "Too far away from the merchant"),
+            ShopError::NotSold => write!(f, "The merchant doesn't sell that"),
+            ShopError::SoldOut { left: 0 } => write!(f, "Sold out"),
+            ShopError::SoldOut { left } => write!(f, "Only {} left", left),
+            ShopError::NotEnoughGold { price } => write!(f, "Not enough gold ({} needed)", price),
+            ShopError::Worthless => write!(f, "The merchant doesn't want that"),
+            ShopError::NotBought => write!(f, "The merchant doesn't buy that kind of item"),
+            ShopError::Item(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<ItemError> for ShopError {
+    fn from(e: ItemError) -> Self {
+        ShopError::Item(e)
+    }
+}
+
+/// A purchase or sale, recorded with the inventory it changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShopTrade {
+    pub shop: ShopId,
+    pub item: ItemId,
+    pub count: i64,  // Bought (positive) or sold (negative)
+    pub gold: i64,   // Paid (positive) or received (negative)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stock {
+    left: u32,
+    counted_at: Instant,  // Restocking counts from here
+}
+
+/// What the limited listings of all merchants have left
+#[derive(Debug, Default)]
+pub struct ShopStock {
+    stock: HashMap<(ShopId, ItemId), Stock>,
+}
+
+impl ShopStock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every listing of a merchant with what is left of it (None = unlimited)
+    pub fn listings(&mut self, shop: ShopId, info: &ShopInfo, now: Instant) -> Vec<(ItemId, Option<u32>)> {
+        let restock = Duration::from_secs_f32(info.restock);
+        info.listings.iter()
+            .map(|listing| (listing.item, listing.stock.map(|max| self.left(shop, listing.item, max, restock, now))))
+            .collect()
+    }
+
+    /// Buy `count` of `item` from a merchant, if it has that many and the buyer the
+    /// gold and the room. Nothing changes otherwise.
+    pub fn buy(
+        &mut self,
+        shop: ShopId,
+        info: &ShopInfo,
+        item: ItemId,
+        count: u32,
+        inventory: &mut Inventory,
+        now: Instant,
+    ) -> Result<ShopTrade, ShopError> {
+        let listing = info.listings.iter().find(|listing| listing.item == item).ok_or(ShopError::NotSold)?;
+        if count == 0 {
+            return Err(ItemError::InvalidCount.into());
+        }
+        if let Some(max) = listing.stock {
+            let left = self.left(shop, item, max, Duration::from_secs_f32(info.restock), now);
+            if count > left {
+                return Err(ShopError::SoldOut { left });
+            }
+        }
+        let price = listing.price as u64 * count as u64;
+        if inventory.gold < price {
+            return Err(ShopError::NotEnoughGold { price });
+        }
+
+        inventory.add(item, count)?;
+        inventory.gold -= price;
+        if let Some(stock) = self.stock.get_mut(&(shop, item)) {
+            stock.left -= count;
+        }
+        Ok(ShopTrade { shop, item, count: count as i64, gold: price as i64 })
+    }
+
+    /// Stock of a limited listing, restocked for the time since it was last counted
+    fn left(&mut self, shop: ShopId, item: ItemId, max: u32, restock: Duration, now: Instant) -> u32 {
+        let stock = self.stock.entry((shop, item)).or_insert(Stock { left: max, counted_at: now });
+        if stock.left >= max {
+            // Full (or the balance tables lowered the stock): nothing to restock yet
+            *stock = Stock { left: max, counted_at: now };
+            return max;
+        }
+        let restocked = (now.saturating_duration_since(stock.counted_at).as_secs_f32() / restock.as_secs_f32()) as u32;
+        if restocked > 0 {
+            stock.left = (stock.left + restocked).min(max);
+            stock.counted_at = if stock.left == max { now } else { stock.counted_at + restock * restocked };
+        }
+        stock.left
+    }
+}
+
+/// Sell `count` items from `slot` to a merchant that buys their type, for their value each
+pub fn sell(shop: ShopId, info: &ShopInfo, slot: usize, count: u32, inventory: &mut Inventory) -> Result<ShopTrade, ShopError> {
+    let stack = inventory.get(slot).ok_or(ItemError::EmptySlot)?;
+    let item = stack.item.info().ok_or(ShopError::Worthless)?;
+    if item.value == 0 {
+        return Err(ShopError::Worthless);
+    }
+    if !info.buys_item(&item) {
+        return Err(ShopError::NotBought);
+    }
+    let value = item.value;
+    let sold = inventory.take(slot, count)?;
+    let gold = value as u64 * sold.count as u64;
+    inventory.gold = inventory.gold.saturating_add(gold);
+    Ok(ShopTrade { shop, item: sold.item, count: -(sold.count as i64), gold: -(gold as i64) })
+}
+
+/// Whether a player at `position` is close enough to a merchant to trade
+pub fn check_range(info: &ShopInfo, position: Vec3) -> Result<(), ShopError> {
+    if position.distance(info.position) > SHOP_RANGE {
+        return Err(ShopError::TooFar);
+    }
+    Ok(())
+}
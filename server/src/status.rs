@@ -0,0 +1,228 @@
+// Status effects: timed buffs, debuffs and effects over time on a combatant.
+//
+// Every kind has a stacking rule for what happens when it is applied while one is
+// already running. Effects run out on their own; ticking kinds (damage and heal over
+// time) fire every TICK_INTERVAL until then. Any change is flagged so the server can
+// send the owner the new list for its buff bar.
+
+use shared::{EffectKind, SkillId, StatusEffectState};
+use std::time::{Duration, Instant};
+
+/// Time between two ticks of a damage or heal over time
+pub const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Defense reductions from several casts add up to this many
+const MAX_DEFENSE_REDUCTION_STACKS: u32 = 3;
+
+/// How a new application combines with a running effect of the same kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackRule {
+    /// The new application replaces the running one
+    Replace,
+    /// The higher magnitude is kept and runs until the later of both expiries
+    Strongest,
+    /// Magnitudes add up to this many stacks; every application refreshes the duration
+    Stack(u32),
+    /// Every caster and skill runs its own instance; casting again refreshes it
+    PerSource,
+}
+
+pub fn stack_rule(kind: EffectKind) -> StackRule {
+    match kind {
+        EffectKind::DefenseReduction => StackRule::Stack(MAX_DEFENSE_REDUCTION_STACKS),
+        EffectKind::DamageOverTime | EffectKind::HealOverTime => StackRule::PerSource,
+        EffectKind::Invisible | EffectKind::CrowdControlImmunity | EffectKind::LastStand => StackRule::Replace,
+        EffectKind::DamageReduction
+        | EffectKind::DefenseBuff
+        | EffectKind::AttackSpeed
+        | EffectKind::DamageBuff
+        | EffectKind::Stun => StackRule::Strongest,
+    }
+}
+
+/// Kinds that do something every TICK_INTERVAL instead of modifying stats
+pub fn is_ticking(kind: EffectKind) -> bool {
+    matches!(kind, EffectKind::DamageOverTime | EffectKind::HealOverTime)
+}
+
+/// Caster and skill an effect came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectOrigin {
+    pub source: u64,
+    pub skill: SkillId,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    pub origin: Option<EffectOrigin>,
+    pub magnitude: f32,          // Per stack; per tick for ticking kinds
+    pub stacks: u32,
+    pub duration: Duration,      // From the last (re)application to `expires`
+    pub expires: Instant,
+    next_tick: Option<Instant>,  // Ticking kinds only
+}
+
+impl StatusEffect {
+    fn is_running(&self, now: Instant) -> bool {
+        self.expires > now
+    }
+
+    /// Ticks that are due but not applied yet keep an expired effect around
+    fn has_pending_tick(&self) -> bool {
+        self.next_tick.is_some_and(|next| next <= self.expires)
+    }
+}
+
+/// One tick of a damage or heal over time, as returned by [`StatusEffects::due_ticks`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusTick {
+    pub kind: EffectKind,
+    pub origin: EffectOrigin,
+    pub amount: f32,
+}
+
+/// All status effects on one combatant
+#[derive(Debug, Clone, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+    changed: bool,  // Added, refreshed or removed since the last `take_changed`
+}
+
+impl StatusEffects {
+    /// Start, refresh or stack an effect according to its stacking rule.
+    /// Returns false if an immunity blocks it.
+    pub fn apply(
+        &mut self,
+        kind: EffectKind,
+        origin: Option<EffectOrigin>,
+        magnitude: f32,
+        duration: Duration,
+        now: Instant,
+    ) -> bool {
+        if kind == EffectKind::Stun && self.has(EffectKind::CrowdControlImmunity, now) {
+            return false;
+        }
+        if kind == EffectKind::CrowdControlImmunity {
+            // Immunity also breaks a running stun
+            self.remove(EffectKind::Stun);
+        }
+
+        let expires = now + duration;
+        let effect = StatusEffect {
+            kind,
+            origin,
+            magnitude,
+            stacks: 1,
+            duration,
+            expires,
+            next_tick: is_ticking(kind).then(|| now + TICK_INTERVAL),
+        };
+        self.changed = true;
+
+        let running = self.effects.iter().position(|e| e.kind == kind && e.is_running(now));
+        match (stack_rule(kind), running) {
+            (StackRule::Strongest, Some(index)) => {
+                let running = &mut self.effects[index];
+                if magnitude >= running.magnitude {
+                    running.magnitude = magnitude;
+                    running.origin = origin;
+                }
+                running.expires = running.expires.max(expires);
+                running.duration = running.expires - now;
+            }
+            (StackRule::Stack(max_stacks), Some(index)) => {
+                let running = &mut self.effects[index];
+                running.stacks = (running.stacks + 1).min(max_stacks);
+                running.magnitude = magnitude;
+                running.origin = origin;
+                running.expires = expires;
+                running.duration = duration;
+            }
+            (StackRule::PerSource, _) => {
+                self.effects.retain(|e| !(e.kind == kind && e.origin == origin));
+                self.effects.push(effect);
+            }
+            _ => {
+                self.effects.retain(|e| e.kind != kind);
+                self.effects.push(effect);
+            }
+        }
+        true
+    }
+
+    /// Combined magnitude of all running effects of a kind
+    pub fn magnitude(&self, kind: EffectKind, now: Instant) -> Option<f32> {
+        self.effects.iter()
+            .filter(|e| e.kind == kind && e.is_running(now))
+            .map(|e| e.magnitude * e.stacks as f32)
+            .reduce(|a, b| a + b)
+    }
+
+    pub fn has(&self, kind: EffectKind, now: Instant) -> bool {
+        self.effects.iter().any(|e| e.kind == kind && e.is_running(now))
+    }
+
+    /// Origin of the running effect of a kind
+    pub fn origin(&self, kind: EffectKind, now: Instant) -> Option<EffectOrigin> {
+        self.effects.iter()
+            .find(|e| e.kind == kind && e.is_running(now))
+            .and_then(|e| e.origin)
+    }
+
+    /// End every effect of a kind early. Returns whether one was running.
+    pub fn remove(&mut self, kind: EffectKind) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|e| e.kind != kind);
+        let removed = self.effects.len() != before;
+        self.changed |= removed;
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.changed |= !self.effects.is_empty();
+        self.effects.clear();
+    }
+
+    /// Ticks of damage and heal over time that are due, oldest first per effect.
+    /// The last tick of an effect lands exactly on its expiry.
+    pub fn due_ticks(&mut self, now: Instant) -> Vec<StatusTick> {
+        let mut ticks = Vec::new();
+        for effect in self.effects.iter_mut() {
+            let Some(origin) = effect.origin else { continue };
+            while let Some(next) = effect.next_tick.filter(|next| *next <= now && *next <= effect.expires) {
+                effect.next_tick = Some(next + TICK_INTERVAL);
+                ticks.push(StatusTick { kind: effect.kind, origin, amount: effect.magnitude * effect.stacks as f32 });
+            }
+        }
+        ticks
+    }
+
+    /// Drop effects that have run out (after their last tick). Returns whether any did.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|e| e.is_running(now) || e.has_pending_tick());
+        let expired = self.effects.len() != before;
+        self.changed |= expired;
+        expired
+    }
+
+    /// Whether the effects changed since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Running effects for the client's buff bar
+    pub fn states(&self, now: Instant) -> Vec<StatusEffectState> {
+        self.effects.iter()
+            .filter(|e| e.is_running(now))
+            .map(|e| StatusEffectState {
+                kind: e.kind,
+                skill: e.origin.map(|origin| origin.skill),
+                stacks: e.stacks,
+                remaining: e.expires.saturating_duration_since(now).as_secs_f32(),
+                duration: e.duration.as_secs_f32(),
+            })
+            .collect()
+    }
+}
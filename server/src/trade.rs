@@ -0,0 +1,243 @@
+// Trading between two players.
+//
+// A trade runs in two phases. First both players put items and gold on the table and
+// change their offers as they like; locking an offer makes it final. Once both offers
+// are locked, both players confirm and the server swaps everything at once. The swap
+// checks that each player still has what it offered and has room for what it gets;
+// otherwise the trade is cancelled and nothing changes. The caller saves both
+// inventories in one database transaction. Walking away or disconnecting cancels a
+// trade.
+
+use crate::inventory::{Inventory, ItemError};
+use shared::items::{ItemStack, TradeOffer};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Furthest two players may stand apart to trade (meters)
+pub const TRADE_RANGE: f32 = 5.0;
+
+/// How long a trade request can be accepted
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeError {
+    TradeSelf,
+    AlreadyTrading,
+    PartnerBusy,
+    NoRequest,
+    NotTrading,
+    TooFar,
+    Locked,
+    NotLocked,
+    DuplicateSlot,
+    NotEnoughGold,
+    OfferChanged,
+    NoRoom,
+    Item(ItemError),
+}
+
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeError::TradeSelf => write!(f, "You can't trade with yourself"),
+            TradeError::AlreadyTrading => write!(f, "You are already trading"),
+            TradeError::PartnerBusy => write!(f, "Player is already trading"),
+            TradeError::NoRequest => write!(f, "Trade request is no longer valid"),
+            TradeError::NotTrading => write!(f, "You are not trading"),
+            TradeError::TooFar => write!(f, "Too far away from your trade partner"),
+            TradeError::Locked => write!(f, "Your offer is locked"),
+            TradeError::NotLocked => write!(f, "Both offers must be locked first"),
+            TradeError::DuplicateSlot => write!(f, "Slot offered twice"),
+            TradeError::NotEnoughGold => write!(f, "Not enough gold"),
+            TradeError::OfferChanged => write!(f, "Offered items or gold are gone"),
+            TradeError::NoRoom => write!(f, "Not enough room for the traded items"),
+            TradeError::Item(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<ItemError> for TradeError {
+    fn from(e: ItemError) -> Self {
+        TradeError::Item(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Request {
+    requester: u64,
+    expires: Instant,
+}
+
+/// Open trades and requests between players, by network entity ID
+#[derive(Debug, Default)]
+pub struct TradeManager {
+    requests: HashMap<u64, Request>,   // Asked player -> newest request
+    partners: HashMap<u64, u64>,       // Trading player -> partner
+    offers: HashMap<u64, TradeOffer>,  // Trading player -> its offer
+}
+
+impl TradeManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask `target` to trade with `requester`
+    pub fn request(&mut self, requester: u64, target: u64, now: Instant) -> Result<(), TradeError> {
+        if requester == target {
+            return Err(TradeError::TradeSelf);
+        }
+        if self.partners.contains_key(&requester) {
+            return Err(TradeError::AlreadyTrading);
+        }
+        if self.partners.contains_key(&target) {
+            return Err(TradeError::PartnerBusy);
+        }
+        self.requests.insert(target, Request { requester, expires: now + REQUEST_TIMEOUT });
+        Ok(())
+    }
+
+    /// Open a trade with the player who asked us, with nothing offered yet
+    pub fn accept(&mut self, target: u64, requester: u64, now: Instant) -> Result<(), TradeError> {
+        let valid = self.requests.get(&target)
+            .is_some_and(|request| request.requester == requester && request.expires > now);
+        if !valid {
+            return Err(TradeError::NoRequest);
+        }
+        self.requests.remove(&target);
+        if self.partners.contains_key(&target) {
+            return Err(TradeError::AlreadyTrading);
+        }
+        if self.partners.contains_key(&requester) {
+            return Err(TradeError::PartnerBusy);
+        }
+
+        for (player, partner) in [(target, requester), (requester, target)] {
+            self.partners.insert(player, partner);
+            self.offers.insert(player, TradeOffer::default());
+        }
+        Ok(())
+    }
+
+    pub fn partner(&self, player: u64) -> Option<u64> {
+        self.partners.get(&player).copied()
+    }
+
+    /// Our offer and our partner's
+    pub fn offers(&self, player: u64) -> Option<(&TradeOffer, &TradeOffer)> {
+        let partner = self.partner(player)?;
+        Some((self.offers.get(&player)?, self.offers.get(&partner)?))
+    }
+
+    /// Both players of every open trade, each trade once
+    pub fn trades(&self) -> Vec<(u64, u64)> {
+        self.partners.iter()
+            .filter(|(player, partner)| player < partner)
+            .map(|(&player, &partner)| (player, partner))
+            .collect()
+    }
+
+    /// Replace a player's offer with `count` items from each slot and some gold, as far
+    /// as `inventory` has them
+    pub fn set_offer(&mut self, player: u64, items: &[(usize, u32)], gold: u64, inventory: &Inventory) -> Result<(), TradeError> {
+        let offer = self.offers.get(&player).ok_or(TradeError::NotTrading)?;
+        if offer.locked {
+            return Err(TradeError::Locked);
+        }
+        let mut offered = Vec::with_capacity(items.len());
+        for (index, &(slot, count)) in items.iter().enumerate() {
+            if items[..index].iter().any(|(other, _)| *other == slot) {
+                return Err(TradeError::DuplicateSlot);
+            }
+            let stack = inventory.get(slot).ok_or(ItemError::EmptySlot)?;
+            if count == 0 || count > stack.count {
+                return Err(ItemError::InvalidCount.into());
+            }
+            offered.push((slot as u16, ItemStack { count, ..stack }));
+        }
+        if gold > inventory.gold {
+            return Err(TradeError::NotEnoughGold);
+        }
+
+        self.offers.insert(player, TradeOffer { items: offered, gold, locked: false, confirmed: false });
+        Ok(())
+    }
+
+    /// Make a player's offer final
+    pub fn lock(&mut self, player: u64) -> Result<(), TradeError> {
+        let offer = self.offers.get_mut(&player).ok_or(TradeError::NotTrading)?;
+        offer.locked = true;
+        Ok(())
+    }
+
+    /// Agree to the swap. Returns whether both players have agreed.
+    pub fn confirm(&mut self, player: u64) -> Result<bool, TradeError> {
+        let (ours, theirs) = self.offers(player).ok_or(TradeError::NotTrading)?;
+        if !ours.locked || !theirs.locked {
+            return Err(TradeError::NotLocked);
+        }
+        let partner_confirmed = theirs.confirmed;
+        if let Some(offer) = self.offers.get_mut(&player) {
+            offer.confirmed = true;
+        }
+        Ok(partner_confirmed)
+    }
+
+    /// End a player's trade, completed or not. Returns the partner, our offer and theirs.
+    pub fn close(&mut self, player: u64) -> Option<(u64, TradeOffer, TradeOffer)> {
+        let partner = self.partners.remove(&player)?;
+        self.partners.remove(&partner);
+        let ours = self.offers.remove(&player).unwrap_or_default();
+        let theirs = self.offers.remove(&partner).unwrap_or_default();
+        Some((partner, ours, theirs))
+    }
+
+    /// Forget a player leaving the world: its requests and its trade. Returns the
+    /// trade partner left behind.
+    pub fn leave(&mut self, player: u64) -> Option<u64> {
+        self.requests.retain(|target, request| *target != player && request.requester != player);
+        self.close(player).map(|(partner, _, _)| partner)
+    }
+}
+
+/// Swap the offers of a trade between two inventories: both change or neither does
+pub fn swap(first: &mut Inventory, first_offer: &TradeOffer, second: &mut Inventory, second_offer: &TradeOffer) -> Result<(), TradeError> {
+    let (mut new_first, mut new_second) = (first.clone(), second.clone());
+    let from_first = take_offer(&mut new_first, first_offer)?;
+    let from_second = take_offer(&mut new_second, second_offer)?;
+    receive(&mut new_first, &from_second, second_offer.gold)?;
+    receive(&mut new_second, &from_first, first_offer.gold)?;
+
+    *first = new_first;
+    *second = new_second;
+    Ok(())
+}
+
+/// Take everything offered out of an inventory, if it is all still there
+fn take_offer(inventory: &mut Inventory, offer: &TradeOffer) -> Result<Vec<ItemStack>, TradeError> {
+    let mut taken = Vec::with_capacity(offer.items.len());
+    for &(slot, stack) in &offer.items {
+        let still_there = inventory.get(slot as usize)
+            .is_some_and(|current| current.item == stack.item && current.count >= stack.count);
+        if !still_there {
+            return Err(TradeError::OfferChanged);
+        }
+        taken.push(inventory.take(slot as usize, stack.count)?);
+    }
+    if inventory.gold < offer.gold {
+        return Err(TradeError::OfferChanged);
+    }
+    inventory.gold -= offer.gold;
+    Ok(taken)
+}
+
+fn receive(inventory: &mut Inventory, items: &[ItemStack], gold: u64) -> Result<(), TradeError> {
+    for stack in items {
+        inventory.add(stack.item, stack.count).map_err(|e| match e {
+            ItemError::Full => TradeError::NoRoom,
+            e => TradeError::Item(e),
+        })?;
+    }
+    inventory.gold = inventory.gold.saturating_add(gold);
+    Ok(())
+}
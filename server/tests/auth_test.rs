@@ -185,3 +185,23 @@ fn test_jwt_token() {
     assert_eq!(claims.user_id, 123);
     assert_eq!(claims.username, "testuser");
 }
+
+#[tokio::test]
+async fn test_gm_flag_reaches_the_session() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let mut session_manager = auth::SessionManager::new();
+
+    auth::handle_register(&pool, "gamemaster".to_string(), "password123".to_string(), None).await;
+    auth::handle_register(&pool, "player".to_string(), "password123".to_string(), None).await;
+    let gm = db::users::find_by_username(&pool, "gamemaster").await.unwrap().unwrap();
+    assert!(!gm.is_gm, "accounts start without GM rights");
+    db::users::set_gm(&pool, gm.id, true).await.unwrap();
+
+    for (username, expected) in [("gamemaster", true), ("player", false)] {
+        let response = auth::handle_login(&pool, &mut session_manager, username.to_string(), "password123".to_string()).await;
+        let shared::AuthResponse::LoginSuccess { token, .. } = response else {
+            panic!("Login of {} failed", username);
+        };
+        assert_eq!(session_manager.get_session(&token).unwrap().is_gm, expected, "{}", username);
+    }
+}
@@ -0,0 +1,198 @@
+use shared::balance::{self, BalanceAssembler, BalanceError, BalanceTables, BALANCE_VERSION, DEFAULT_BALANCE_PATH};
+use shared::{CharacterClass, MobType, SkillEffect, SkillId};
+use shared::bevy::prelude::Vec3;
+use shared::items::{ItemId, ItemType, LootDrop, LootEntry, ShopId, ShopListing};
+use std::path::Path;
+
+#[test]
+fn test_balance_file_on_disk_is_valid() {
+    // Tests run in the server crate, the path is relative to the workspace root
+    let path = Path::new("..").join(DEFAULT_BALANCE_PATH);
+    let tables = BalanceTables::load(&path).expect("balance file should load");
+    assert_eq!(tables.version, BALANCE_VERSION);
+    assert_eq!(tables.skills.len(), SkillId::ALL.len());
+}
+
+#[test]
+fn test_builtin_tables_drive_skill_info_and_stats() {
+    let tables = BalanceTables::builtin();
+    assert_eq!(tables.skill(SkillId::Schildwall).cooldown, 15.0);
+    assert!(matches!(SkillId::Kettenblitz.info().effect, SkillEffect::ChainLightning(4, _, _)));
+
+    // Krieger: 100 HP at level 1, +20 per level
+    let (health, mana, _) = shared::calculate_stats_for_level(11, &CharacterClass::Krieger);
+    assert_eq!(health, 300.0);
+    assert_eq!(mana, 150.0);
+}
+
+#[test]
+fn test_wrong_version_is_rejected() {
+    let mut tables = BalanceTables::builtin();
+    tables.version = BALANCE_VERSION + 1;
+    assert_eq!(
+        tables.validate().unwrap_err(),
+        BalanceError::Version { found: BALANCE_VERSION + 1, expected: BALANCE_VERSION }
+    );
+}
+
+#[test]
+fn test_missing_entries_are_rejected() {
+    let mut tables = BalanceTables::builtin();
+    tables.skills.remove(&SkillId::Tornado);
+    assert_eq!(tables.validate().unwrap_err(), BalanceError::MissingSkill(SkillId::Tornado));
+
+    let mut tables = BalanceTables::builtin();
+    tables.classes.remove(&CharacterClass::Sura);
+    assert_eq!(tables.validate().unwrap_err(), BalanceError::MissingClass(CharacterClass::Sura));
+
+    let mut tables = BalanceTables::builtin();
+    tables.mobs.remove(&MobType::Ork);
+    assert_eq!(tables.validate().unwrap_err(), BalanceError::MissingMob(MobType::Ork));
+}
+
+#[test]
+fn test_out_of_range_values_are_rejected() {
+    let mut tables = BalanceTables::builtin();
+    tables.skills.get_mut(&SkillId::Wirbelsturm).unwrap().cooldown = -1.0;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.skills.get_mut(&SkillId::Wiedergeburt).unwrap().effect = SkillEffect::Resurrect(1.5);
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.classes.get_mut(&CharacterClass::Ninja).unwrap().mana_per_level = f32::NAN;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+}
+
+#[test]
+fn test_parse_errors_are_reported() {
+    assert!(matches!(BalanceTables::parse("(version: 1, classes: {"), Err(BalanceError::Parse(_))));
+}
+
+#[test]
+fn test_install_keeps_current_tables_on_error() {
+    let mut tables = BalanceTables::builtin();
+    tables.skills.remove(&SkillId::Schildwall);
+    assert!(balance::install(tables).is_err());
+    assert_eq!(SkillId::Schildwall.info().name, "Schildwall");
+}
+
+#[test]
+fn test_death_rules_are_validated() {
+    let tables = BalanceTables::builtin();
+    let plaza = tables.death.nearest_respawn_point(Vec3::new(3.0, 1.0, -2.0)).unwrap();
+    assert_eq!(plaza.position, Vec3::new(0.0, 1.0, 0.0));
+
+    let mut tables = BalanceTables::builtin();
+    tables.death.experience_loss = 1.5;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.death.respawn_health = 0.0;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.death.respawn_points.clear();
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+}
+
+#[test]
+fn test_item_definitions_are_validated() {
+    let tables = BalanceTables::builtin();
+    let potion = tables.item(ItemId(1)).expect("Kleiner Heiltrank");
+    assert_eq!(potion.item_type, ItemType::Consumable);
+    assert!(potion.stack_size > 1);
+    assert!(tables.item(ItemId(99_999)).is_none());
+
+    // Equipment never stacks
+    let mut tables = BalanceTables::builtin();
+    tables.items.get_mut(&ItemId(200)).unwrap().stack_size = 5;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    // Consumables must do something
+    let mut tables = BalanceTables::builtin();
+    tables.items.get_mut(&ItemId(1)).unwrap().use_effect = None;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+}
+
+#[test]
+fn test_loot_tables_are_validated() {
+    let tables = BalanceTables::builtin();
+    assert!(tables.loot(MobType::Wolf).is_some_and(|table| !table.entries.is_empty()));
+
+    // Only items that exist
+    let mut tables = BalanceTables::builtin();
+    tables.loot.get_mut(&MobType::Wolf).unwrap().entries.push(LootEntry { weight: 1, drop: LootDrop::Item(ItemId(99_999), 1, 1) });
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    // Counts must fit a stack
+    let mut tables = BalanceTables::builtin();
+    tables.loot.get_mut(&MobType::Wolf).unwrap().entries.push(LootEntry { weight: 1, drop: LootDrop::Item(ItemId(200), 1, 2) });
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.loot.get_mut(&MobType::Ork).unwrap().gold = (10, 5);
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+}
+
+#[test]
+fn test_shops_are_validated() {
+    let tables = BalanceTables::builtin();
+    assert!(tables.shop(ShopId(1)).is_some_and(|shop| !shop.listings.is_empty()));
+
+    // Merchants never sell below what they pay
+    let mut tables = BalanceTables::builtin();
+    tables.shops.get_mut(&ShopId(1)).unwrap().listings.push(ShopListing { item: ItemId(202), price: 100, stock: None });
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    // Each item once per merchant
+    let mut tables = BalanceTables::builtin();
+    let shop = tables.shops.get_mut(&ShopId(1)).unwrap();
+    let first = shop.listings[0];
+    shop.listings.push(first);
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.shops.get_mut(&ShopId(2)).unwrap().listings[0].stock = Some(0);
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    let mut tables = BalanceTables::builtin();
+    tables.shops.get_mut(&ShopId(2)).unwrap().restock = 0.0;
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+
+    // Each item type bought once per merchant
+    let mut tables = BalanceTables::builtin();
+    tables.shops.get_mut(&ShopId(2)).unwrap().buys.push(ItemType::Weapon);
+    assert!(matches!(tables.validate(), Err(BalanceError::Invalid(_))));
+}
+
+#[test]
+fn test_tables_are_sent_in_chunks_that_fit_a_datagram() {
+    let tables = BalanceTables::builtin();
+    let chunks = tables.to_chunks().unwrap();
+    assert!(chunks.len() > 1, "Built-in tables should need several chunks");
+    assert!(chunks.iter().all(|chunk| chunk.len() <= balance::BALANCE_CHUNK_SIZE));
+
+    let mut assembler = BalanceAssembler::default();
+    let total = chunks.len() as u16;
+    let mut result = None;
+    for (index, chunk) in chunks.iter().enumerate() {
+        assert!(result.is_none(), "Tables finished before the last chunk");
+        result = assembler.add(index as u16, total, chunk);
+    }
+    let received = result.expect("last chunk should finish the tables").unwrap();
+    assert_eq!(received.items.len(), tables.items.len());
+    assert_eq!(received.skill(SkillId::Schildwall).cooldown, 15.0);
+
+    // Chunks of tables whose start was missed are ignored
+    assert!(assembler.add(1, total, &chunks[1]).is_none());
+}
+
+#[test]
+fn test_tables_too_large_to_send_are_rejected() {
+    let mut tables = BalanceTables::builtin();
+    let item = tables.items.get_mut(&ItemId(1)).unwrap();
+    item.name = "x".repeat(balance::BALANCE_CHUNK_SIZE * balance::MAX_BALANCE_CHUNKS);
+    assert!(matches!(tables.validate(), Err(BalanceError::TooLarge { .. })));
+}
@@ -0,0 +1,301 @@
+use server::combat::{self, CastError, CastTarget, Combatant, EffectKind, PeriodicKind, PeriodicTick};
+use shared::balance::RegenRates;
+use shared::bevy::prelude::Vec3;
+use shared::items::{Equipment, EquipmentSlot, ItemId};
+use shared::{CharacterAppearance, CharacterClass, CharacterData, SkillId, Specialization};
+use std::time::{Duration, Instant};
+
+fn gladiator(level: i32) -> CharacterData {
+    CharacterData {
+        name: "Tester".to_string(),
+        class: CharacterClass::Krieger,
+        appearance: CharacterAppearance::default(),
+        level,
+        experience: 0,
+        specialization: Some(Specialization::Gladiator),
+    }
+}
+
+#[test]
+fn test_skill_requires_specialization_and_level() {
+    let now = Instant::now();
+    let caster = Combatant::new(100.0, 100.0);
+
+    // Leibwächter skill on a Gladiator
+    let result = combat::validate_cast(&gladiator(40), &caster, Vec3::ZERO, SkillId::Schildwall, None, now);
+    assert_eq!(result.unwrap_err(), CastError::WrongSpecialization);
+
+    // Raserei unlocks at level 25
+    let result = combat::validate_cast(&gladiator(20), &caster, Vec3::ZERO, SkillId::Raserei, None, now);
+    assert_eq!(result.unwrap_err(), CastError::NotUnlocked { required_level: 25 });
+
+    assert!(combat::validate_cast(&gladiator(25), &caster, Vec3::ZERO, SkillId::Raserei, None, now).is_ok());
+}
+
+#[test]
+fn test_cooldown_and_mana_are_enforced() {
+    let now = Instant::now();
+    let character = gladiator(10);
+    let mut caster = Combatant::new(100.0, 100.0);
+    let info = SkillId::Wirbelsturm.info();
+
+    let cooldown = caster.begin_cast(SkillId::Wirbelsturm, &info, now);
+    assert_eq!(caster.mana, 100.0 - info.mana_cost);
+
+    let result = combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Wirbelsturm, None, now);
+    assert!(matches!(result, Err(CastError::OnCooldown { .. })), "got {:?}", result);
+
+    let later = now + Duration::from_secs_f32(cooldown + 0.1);
+    assert!(combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Wirbelsturm, None, later).is_ok());
+
+    caster.mana = 10.0;
+    let result = combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Wirbelsturm, None, later);
+    assert!(matches!(result, Err(CastError::NotEnoughMana { .. })), "got {:?}", result);
+}
+
+#[test]
+fn test_targeted_skill_checks_target_and_range() {
+    let now = Instant::now();
+    let character = gladiator(15);
+    let caster = Combatant::new(100.0, 100.0);
+    let enemy = Combatant::new(100.0, 100.0);
+
+    let result = combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Hinrichtung, None, now);
+    assert_eq!(result.unwrap_err(), CastError::NoTarget);
+
+    let far = CastTarget { position: Vec3::new(20.0, 0.0, 0.0), combatant: &enemy };
+    let result = combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Hinrichtung, Some(far), now);
+    assert!(matches!(result, Err(CastError::OutOfRange { .. })), "got {:?}", result);
+
+    let near = CastTarget { position: Vec3::new(2.0, 0.0, 0.0), combatant: &enemy };
+    assert!(combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Hinrichtung, Some(near), now).is_ok());
+}
+
+#[test]
+fn test_stunned_caster_cannot_act() {
+    let now = Instant::now();
+    let mut caster = Combatant::new(100.0, 100.0);
+    caster.apply_effect_from(None, EffectKind::Stun, 1.0, Duration::from_secs(2), now);
+
+    let result = combat::validate_cast(&gladiator(10), &caster, Vec3::ZERO, SkillId::Wirbelsturm, None, now);
+    assert_eq!(result.unwrap_err(), CastError::Stunned);
+
+    let later = now + Duration::from_secs(3);
+    assert!(combat::validate_cast(&gladiator(10), &caster, Vec3::ZERO, SkillId::Wirbelsturm, None, later).is_ok());
+}
+
+#[test]
+fn test_crowd_control_immunity_blocks_stun() {
+    let now = Instant::now();
+    let mut target = Combatant::new(100.0, 100.0);
+    target.apply_effect_from(None, EffectKind::CrowdControlImmunity, 1.0, Duration::from_secs(3), now);
+
+    assert!(!target.apply_effect_from(None, EffectKind::Stun, 1.0, Duration::from_secs(2), now));
+    assert!(!target.is_stunned(now));
+}
+
+#[test]
+fn test_damage_modifiers() {
+    let now = Instant::now();
+    let mut target = Combatant::new(100.0, 100.0);
+
+    target.apply_effect_from(None, EffectKind::DamageReduction, 0.5, Duration::from_secs(5), now);
+    assert_eq!(target.take_damage(20.0, now), 10.0);
+
+    target.apply_effect_from(None, EffectKind::DefenseReduction, 0.3, Duration::from_secs(5), now);
+    let dealt = target.take_damage(20.0, now);
+    assert!((dealt - 13.0).abs() < 0.001, "got {}", dealt);
+
+    // Effects run out
+    let later = now + Duration::from_secs(6);
+    assert_eq!(target.take_damage(20.0, later), 20.0);
+}
+
+#[test]
+fn test_last_stand_survives_lethal_hit_once() {
+    let now = Instant::now();
+    let mut target = Combatant::new(100.0, 100.0);
+    target.apply_effect_from(None, EffectKind::LastStand, 1.0, combat::LAST_STAND_WINDOW, now);
+
+    target.take_damage(500.0, now);
+    assert_eq!(target.health, 1.0);
+    assert!(target.has_effect(EffectKind::DefenseBuff, now));
+
+    target.take_damage(500.0, now);
+    assert!(!target.is_alive());
+}
+
+#[test]
+fn test_every_specialization_skill_is_defined() {
+    let specializations = [
+        Specialization::Leibwaechter, Specialization::Gladiator,
+        Specialization::Bogenschuetze, Specialization::Attentaeter,
+        Specialization::DaemonenJaeger, Specialization::Blutkrieger,
+        Specialization::Lebenshueter, Specialization::Sturmrufer,
+    ];
+
+    for specialization in specializations {
+        let skills = specialization.skills();
+        assert_eq!(skills.len(), 5);
+        for skill in skills {
+            let info = skill.info();
+            assert_ne!(info.name, "Unknown", "{:?} has no definition", skill);
+            assert!(info.cooldown > 0.0 && info.mana_cost > 0.0, "{:?} costs nothing", skill);
+        }
+        // One skill per unlock level
+        let levels: Vec<i32> = specialization.skills().iter().map(|s| s.info().required_level).collect();
+        assert_eq!(levels, vec![5, 10, 15, 25, 40], "{:?}", specialization);
+    }
+}
+
+#[test]
+fn test_damage_over_time_ticks_until_expired() {
+    let start = Instant::now();
+    let mut target = Combatant::new(100.0, 100.0);
+    target.add_periodic(7, SkillId::Giftpfeil, PeriodicKind::Damage, 5.0, 3.0, start);
+
+    // Nothing is due before the first interval
+    assert!(target.apply_periodic(start).is_empty());
+
+    let ticks = target.apply_periodic(start + Duration::from_millis(2500));
+    assert_eq!(ticks.len(), 2);
+    assert_eq!(ticks[0], PeriodicTick { source: 7, skill: SkillId::Giftpfeil, kind: PeriodicKind::Damage, amount: 5.0 });
+    assert_eq!(target.health, 90.0);
+
+    assert_eq!(target.apply_periodic(start + Duration::from_secs(10)).len(), 1);
+    assert!(target.apply_periodic(start + Duration::from_secs(20)).is_empty());
+    assert_eq!(target.health, 85.0);
+}
+
+#[test]
+fn test_heal_is_capped_and_skips_the_dead() {
+    let now = Instant::now();
+    let mut target = Combatant::new(100.0, 100.0);
+    target.take_damage(30.0, now);
+
+    assert_eq!(target.heal(50.0), 30.0);
+    assert_eq!(target.health, 100.0);
+
+    target.take_damage(200.0, now);
+    assert_eq!(target.heal(50.0), 0.0);
+    assert!(!target.is_alive());
+}
+
+#[test]
+fn test_resurrection_needs_a_dead_target() {
+    let now = Instant::now();
+    let character = CharacterData {
+        class: CharacterClass::Schamane,
+        specialization: Some(Specialization::Lebenshueter),
+        ..gladiator(40)
+    };
+    let caster = Combatant::new(100.0, 200.0);
+    let mut ally = Combatant::new(100.0, 100.0);
+
+    let target = CastTarget { position: Vec3::X, combatant: &ally };
+    let result = combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Wiedergeburt, Some(target), now);
+    assert_eq!(result.unwrap_err(), CastError::TargetAlive);
+
+    ally.take_damage(500.0, now);
+    let target = CastTarget { position: Vec3::X, combatant: &ally };
+    assert!(combat::validate_cast(&character, &caster, Vec3::ZERO, SkillId::Wiedergeburt, Some(target), now).is_ok());
+
+    assert_eq!(ally.resurrect(0.5), 50.0);
+    assert!(ally.is_alive());
+}
+
+#[test]
+fn test_invisible_target_cannot_be_targeted() {
+    let now = Instant::now();
+    let caster = Combatant::new(100.0, 100.0);
+    let mut enemy = Combatant::new(100.0, 100.0);
+    enemy.apply_effect_from(None, EffectKind::Invisible, 1.0, Duration::from_secs(6), now);
+
+    let target = CastTarget { position: Vec3::X, combatant: &enemy };
+    let result = combat::validate_cast(&gladiator(15), &caster, Vec3::ZERO, SkillId::Hinrichtung, Some(target), now);
+    assert_eq!(result.unwrap_err(), CastError::InvalidTarget);
+}
+
+#[test]
+fn test_damage_buff_scales_outgoing_damage() {
+    let now = Instant::now();
+    let mut caster = Combatant::new(100.0, 100.0);
+    assert_eq!(caster.damage_dealt_multiplier(now), 1.0);
+
+    caster.apply_effect_from(None, EffectKind::DamageBuff, 0.5, Duration::from_secs(15), now);
+    assert_eq!(caster.damage_dealt_multiplier(now), 1.5);
+}
+
+#[test]
+fn test_distance_to_segment() {
+    let start = Vec3::ZERO;
+    let end = Vec3::new(10.0, 0.0, 0.0);
+
+    assert_eq!(combat::distance_to_segment(Vec3::new(5.0, 0.0, 2.0), start, end), 2.0);
+    // Height is ignored
+    assert_eq!(combat::distance_to_segment(Vec3::new(5.0, 3.0, 0.0), start, end), 0.0);
+    // Beyond the end
+    assert_eq!(combat::distance_to_segment(Vec3::new(13.0, 0.0, 4.0), start, end), 5.0);
+}
+
+#[test]
+fn test_regeneration_is_slower_in_combat() {
+    let now = Instant::now();
+    let mut combatant = Combatant::new(100.0, 100.0);
+    combatant.mana = 0.0;
+    combatant.spend_stamina(60.0);
+    assert_eq!(combatant.stamina, 40.0);
+
+    let rates = RegenRates { health: 0.02, mana: 0.05, stamina: 0.1 };
+    combatant.regenerate(&rates, 2.0);
+    assert_eq!(combatant.mana, 10.0);
+    assert_eq!(combatant.stamina, 60.0);
+
+    // Taking damage starts combat for COMBAT_DURATION
+    combatant.take_damage(50.0, now);
+    assert!(combatant.in_combat(now));
+    assert!(!combatant.in_combat(now + combat::COMBAT_DURATION));
+
+    // Capped at the maximum
+    combatant.regenerate(&rates, 1000.0);
+    assert_eq!(combatant.vitals().health, 100.0);
+    assert_eq!(combatant.vitals().stamina, combatant.max_stamina);
+
+    // The dead don't regenerate
+    combatant.take_damage(500.0, now);
+    combatant.regenerate(&rates, 10.0);
+    assert!(!combatant.is_alive());
+}
+
+#[test]
+fn test_equipment_raises_stats_attack_and_defense() {
+    let now = Instant::now();
+    let character = gladiator(10);
+    let mut equipment = Equipment::default();
+    equipment.set(EquipmentSlot::Weapon, Some(ItemId(200)));  // Rostiges Schwert: 4 attack
+    equipment.set(EquipmentSlot::Armor, Some(ItemId(303)));   // Kettenhemd: 50 health, 12 defense
+
+    let naked = Combatant::for_character(&character, &Equipment::default());
+    let mut armored = Combatant::for_character(&character, &equipment);
+    assert_eq!(armored.max_health, naked.max_health + 50.0);
+    assert_eq!(armored.health, armored.max_health);
+    assert_eq!(armored.attack, 4.0);
+
+    // Defense cuts every hit, with diminishing returns
+    let dealt = armored.take_damage(100.0, now);
+    assert!((dealt - 100.0 * combat::defense_multiplier(12.0)).abs() < 0.01);
+    assert_eq!(combat::defense_multiplier(50.0), 0.5);
+    assert_eq!(combat::defense_multiplier(150.0), 0.25);
+
+    // Attack adds to the skill power
+    let info = SkillId::Wirbelsturm.info();
+    let bonus = combat::skill_damage(10, 4.0, &info) - combat::skill_damage(10, 0.0, &info);
+    assert!((bonus - 4.0 * info.damage_multiplier).abs() < 0.01);
+
+    // Taking the armor off caps health at the lower maximum
+    armored.heal(1000.0);
+    armored.set_equipment(&character, &Equipment::default());
+    assert_eq!(armored.max_health, naked.max_health);
+    assert_eq!(armored.health, naked.max_health);
+    assert_eq!(armored.defense, 0.0);
+}
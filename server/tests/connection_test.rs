@@ -0,0 +1,61 @@
+use server::connection::{ClientConnection, ConnectionState};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_handshake_requires_matching_challenge() {
+    let mut connection = ClientConnection::new(Instant::now());
+    assert!(!connection.is_connected());
+
+    connection.challenge(42);
+    assert_eq!(connection.state, ConnectionState::Challenged { challenge: 42 });
+
+    assert!(!connection.accept(7), "Wrong challenge must be rejected");
+    assert!(!connection.is_connected());
+
+    assert!(connection.accept(42));
+    assert!(connection.is_connected());
+}
+
+#[test]
+fn test_accept_without_challenge_fails() {
+    let mut connection = ClientConnection::new(Instant::now());
+
+    assert!(!connection.accept(0), "ChallengeResponse before Connect must be ignored");
+    assert_eq!(connection.state, ConnectionState::AwaitingConnect);
+}
+
+#[test]
+fn test_repeated_connect_keeps_the_challenge() {
+    let mut connection = ClientConnection::new(Instant::now());
+
+    assert_eq!(connection.connect(42), 42);
+    assert_eq!(connection.connect(7), 42, "A retried Connect must not replace the challenge");
+    assert!(connection.accept(42));
+
+    // A connected client that connects again starts a new handshake
+    assert_eq!(connection.connect(7), 7);
+    assert_eq!(connection.state, ConnectionState::Challenged { challenge: 7 });
+}
+
+#[test]
+fn test_idle_timeout() {
+    let start = Instant::now();
+    let connection = ClientConnection::new(start);
+    let timeout = Duration::from_secs(10);
+
+    assert!(!connection.is_timed_out(start + Duration::from_secs(5), timeout));
+    assert!(connection.is_timed_out(start + Duration::from_secs(11), timeout));
+}
+
+#[test]
+fn test_heartbeat_due_after_interval() {
+    let start = Instant::now();
+    let mut connection = ClientConnection::new(start);
+    let interval = Duration::from_secs(1);
+
+    assert!(!connection.heartbeat_due(start + Duration::from_millis(500), interval));
+    assert!(connection.heartbeat_due(start + interval, interval));
+
+    connection.last_heartbeat_sent = start + interval;
+    assert!(!connection.heartbeat_due(start + interval, interval));
+}
@@ -0,0 +1,32 @@
+use server::entity_id::EntityIdAllocator;
+use std::collections::HashSet;
+
+#[test]
+fn test_ids_are_unique() {
+    let mut allocator = EntityIdAllocator::new();
+
+    let ids: HashSet<u64> = (0..1000).map(|_| allocator.allocate()).collect();
+
+    assert_eq!(ids.len(), 1000, "Every allocated ID should be unique");
+}
+
+#[test]
+fn test_zero_is_never_allocated() {
+    let mut allocator = EntityIdAllocator::new();
+
+    for _ in 0..100 {
+        assert_ne!(allocator.allocate(), 0, "0 is reserved as 'no entity'");
+    }
+}
+
+#[test]
+fn test_ids_are_not_reused_after_leave() {
+    let mut allocator = EntityIdAllocator::new();
+
+    // Player joins, leaves and rejoins from the same address
+    let first_session = allocator.allocate();
+    let second_session = allocator.allocate();
+
+    assert_ne!(first_session, second_session);
+    assert!(second_session > first_session, "IDs should increase monotonically");
+}
@@ -0,0 +1,66 @@
+use server::experience::{self, MAX_LEVEL};
+use server::mobs::Mob;
+use shared::bevy::prelude::Vec3;
+use shared::MobType;
+use std::time::Instant;
+
+#[test]
+fn test_kill_experience_scales_with_level_difference() {
+    // Same level: full experience
+    assert_eq!(experience::kill_experience(100, 10, 10), 100);
+    // Stronger mobs give a capped bonus
+    assert_eq!(experience::kill_experience(100, 12, 10), 120);
+    assert_eq!(experience::kill_experience(100, 30, 10), 150);
+    // Weaker mobs give less, nothing from 10 levels below
+    assert_eq!(experience::kill_experience(100, 7, 10), 70);
+    assert_eq!(experience::kill_experience(100, 3, 13), 0);
+    assert_eq!(experience::kill_experience(100, 1, 40), 0);
+}
+
+#[test]
+fn test_adding_experience_levels_up() {
+    let needed = shared::calculate_xp_for_level(2);
+    assert_eq!(experience::add_experience(1, 0, needed - 1), (1, needed - 1));
+    assert_eq!(experience::add_experience(1, 0, needed + 5), (2, 5));
+
+    // Enough for several levels at once
+    let two_levels = shared::calculate_xp_for_level(2) + shared::calculate_xp_for_level(3);
+    assert_eq!(experience::add_experience(1, 0, two_levels), (3, 0));
+}
+
+#[test]
+fn test_negative_experience_is_ignored() {
+    assert_eq!(experience::add_experience(5, 300, -10_000), (5, 300));
+}
+
+#[test]
+fn test_max_level_keeps_experience() {
+    let (level, xp) = experience::add_experience(MAX_LEVEL, 0, 5_000);
+    assert_eq!((level, xp), (MAX_LEVEL, 5_000));
+    assert_eq!(experience::experience_needed(MAX_LEVEL), 0);
+}
+
+#[test]
+fn test_first_attacker_tags_the_mob() {
+    let mut mob = Mob::new(1, MobType::Wolf, 0, Vec3::ZERO, 0.0, Instant::now());
+    assert_eq!(mob.tagged_by, None);
+    mob.tag(7);
+    mob.tag(8);
+    assert_eq!(mob.tagged_by, Some(7));
+}
+
+#[test]
+fn test_death_penalty_is_a_share_of_the_level() {
+    let needed = experience::experience_needed(10);
+    let expected = (needed as f32 * 0.05).round() as i64;
+    assert_eq!(experience::death_penalty(10, needed - 1, 0.05), expected);
+    assert_eq!(experience::death_penalty(10, needed - 1, 0.0), 0);
+}
+
+#[test]
+fn test_death_never_costs_a_level() {
+    // Not enough experience in the level for the full penalty: lose what there is
+    assert_eq!(experience::death_penalty(10, 3, 0.5), 3);
+    assert_eq!(experience::death_penalty(10, 0, 0.5), 0);
+    assert_eq!(experience::death_penalty(MAX_LEVEL, 500, 0.5), 0);
+}
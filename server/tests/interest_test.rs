@@ -0,0 +1,113 @@
+use server::interest::{InterestManager, InterestEvent};
+use shared::bevy::prelude::Vec3;
+
+fn visible(manager: &InterestManager, observer: u64) -> Vec<u64> {
+    let mut ids: Vec<u64> = manager.visible_to(observer).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_entities_in_range_enter() {
+    let mut manager = InterestManager::new(50.0);
+
+    manager.update_entity(1, Vec3::new(0.0, 1.0, 0.0));
+    manager.add_observer(1);
+    manager.update_entity(2, Vec3::new(10.0, 1.0, 10.0));
+    manager.update_entity(3, Vec3::new(200.0, 1.0, 0.0));
+
+    let events = manager.update();
+
+    assert_eq!(events, vec![InterestEvent::Enter { observer: 1, entity: 2 }]);
+    assert_eq!(visible(&manager, 1), vec![2], "Far entity and self must not be visible");
+}
+
+#[test]
+fn test_enter_is_only_reported_once() {
+    let mut manager = InterestManager::new(50.0);
+
+    manager.update_entity(1, Vec3::ZERO);
+    manager.add_observer(1);
+    manager.update_entity(2, Vec3::new(5.0, 0.0, 0.0));
+
+    assert_eq!(manager.update().len(), 1);
+    assert!(manager.update().is_empty(), "No change should produce no events");
+}
+
+#[test]
+fn test_leave_when_moving_out_of_range() {
+    let mut manager = InterestManager::new(50.0);
+
+    manager.update_entity(1, Vec3::ZERO);
+    manager.add_observer(1);
+    manager.update_entity(2, Vec3::new(40.0, 0.0, 0.0));
+    manager.update();
+
+    // Just past the radius: still visible thanks to the leave margin
+    manager.update_entity(2, Vec3::new(52.0, 0.0, 0.0));
+    assert!(manager.update().is_empty());
+
+    // Far away: must leave
+    manager.update_entity(2, Vec3::new(120.0, 0.0, 0.0));
+    let events = manager.update();
+
+    assert_eq!(events, vec![InterestEvent::Leave { observer: 1, entity: 2 }]);
+    assert!(visible(&manager, 1).is_empty());
+}
+
+#[test]
+fn test_remove_entity_notifies_observers() {
+    let mut manager = InterestManager::new(50.0);
+
+    for id in 1..=3 {
+        manager.update_entity(id, Vec3::new(id as f32, 0.0, 0.0));
+        manager.add_observer(id);
+    }
+    manager.update();
+
+    let mut events = manager.remove_entity(2);
+    events.sort_by_key(|e| match e {
+        InterestEvent::Enter { observer, .. } | InterestEvent::Leave { observer, .. } => *observer,
+    });
+
+    assert_eq!(events, vec![
+        InterestEvent::Leave { observer: 1, entity: 2 },
+        InterestEvent::Leave { observer: 3, entity: 2 },
+    ]);
+    assert_eq!(visible(&manager, 1), vec![3]);
+}
+
+#[test]
+fn test_query_radius_across_cells() {
+    let mut manager = InterestManager::new(10.0);
+
+    manager.update_entity(1, Vec3::new(-1.0, 0.0, -1.0));
+    manager.update_entity(2, Vec3::new(1.0, 0.0, 1.0));
+    manager.update_entity(3, Vec3::new(30.0, 0.0, 0.0));
+
+    let mut found = manager.query_radius(Vec3::ZERO, 5.0);
+    found.sort();
+
+    assert_eq!(found, vec![1, 2], "Query must find entities in neighbouring cells");
+}
+
+#[test]
+fn test_changing_the_view_radius() {
+    let mut manager = InterestManager::new(10.0);
+
+    manager.update_entity(1, Vec3::ZERO);
+    manager.add_observer(1);
+    manager.update_entity(2, Vec3::new(40.0, 0.0, 0.0));
+    assert!(manager.update().is_empty());
+
+    manager.set_view_radius(50.0);
+    assert_eq!(manager.view_radius(), 50.0);
+    assert_eq!(manager.update(), vec![InterestEvent::Enter { observer: 1, entity: 2 }]);
+
+    // Entities keep moving between the cells of the new grid
+    manager.update_entity(2, Vec3::new(-45.0, 0.0, 20.0));
+    assert_eq!(manager.query_radius(Vec3::new(-40.0, 0.0, 20.0), 10.0), vec![2]);
+
+    manager.set_view_radius(10.0);
+    assert_eq!(manager.update(), vec![InterestEvent::Leave { observer: 1, entity: 2 }]);
+}
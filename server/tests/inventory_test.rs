@@ -0,0 +1,179 @@
+use server::inventory::{Inventory, ItemError, ITEM_USE_COOLDOWN};
+use shared::items::{EquipmentSlot, ItemEffect, ItemId, ItemStack, Loot, INVENTORY_SLOTS};
+use std::time::{Duration, Instant};
+
+const POTION: ItemId = ItemId(1);    // Kleiner Heiltrank, stacks to 20
+const PELT: ItemId = ItemId(100);    // Wolfsfell, stacks to 50
+const SWORD: ItemId = ItemId(200);   // Rostiges Schwert, doesn't stack
+const CHAINMAIL: ItemId = ItemId(303);  // Kettenhemd, level 10
+
+fn stack(item: ItemId, count: u32) -> Option<ItemStack> {
+    Some(ItemStack { item, count })
+}
+
+#[test]
+fn test_adding_fills_stacks_before_empty_slots() {
+    let mut inventory = Inventory::default();
+    inventory.add(POTION, 15).unwrap();
+    inventory.add(POTION, 10).unwrap();
+    assert_eq!(inventory.get(0), stack(POTION, 20));
+    assert_eq!(inventory.get(1), stack(POTION, 5));
+
+    // Equipment takes a slot each
+    inventory.add(SWORD, 2).unwrap();
+    assert_eq!(inventory.get(2), stack(SWORD, 1));
+    assert_eq!(inventory.get(3), stack(SWORD, 1));
+}
+
+#[test]
+fn test_adding_to_a_full_inventory_changes_nothing() {
+    let mut inventory = Inventory::default();
+    inventory.add(SWORD, INVENTORY_SLOTS as u32 - 1).unwrap();
+    inventory.add(POTION, 20).unwrap();
+    let before = inventory.clone();
+
+    assert_eq!(inventory.add(POTION, 1), Err(ItemError::Full));
+    assert_eq!(inventory.add(SWORD, 1), Err(ItemError::Full));
+    assert_eq!(inventory, before);
+    assert_eq!(inventory.add(ItemId(99_999), 1), Err(ItemError::UnknownItem));
+}
+
+#[test]
+fn test_picked_up_gold_always_fits() {
+    let mut inventory = Inventory::default();
+    inventory.add(SWORD, INVENTORY_SLOTS as u32).unwrap();
+    inventory.add_loot(Loot::Gold(25)).unwrap();
+    assert_eq!(inventory.gold, 25);
+    assert_eq!(inventory.add_loot(Loot::Item(ItemStack { item: PELT, count: 1 })), Err(ItemError::Full));
+}
+
+#[test]
+fn test_moving_merges_same_items_and_swaps_others() {
+    let mut inventory = Inventory::from_saved([(0, ItemStack { item: PELT, count: 30 }), (1, ItemStack { item: PELT, count: 30 })], [], 0);
+    inventory.add(SWORD, 1).unwrap();  // Slot 2
+
+    // Only what fits moves onto the stack
+    inventory.move_item(0, 1).unwrap();
+    assert_eq!(inventory.get(0), stack(PELT, 10));
+    assert_eq!(inventory.get(1), stack(PELT, 50));
+
+    inventory.move_item(2, 0).unwrap();
+    assert_eq!(inventory.get(0), stack(SWORD, 1));
+    assert_eq!(inventory.get(2), stack(PELT, 10));
+
+    inventory.move_item(2, 10).unwrap();
+    assert_eq!(inventory.get(2), None);
+    assert_eq!(inventory.get(10), stack(PELT, 10));
+
+    assert_eq!(inventory.move_item(3, 4), Err(ItemError::EmptySlot));
+    assert_eq!(inventory.move_item(0, INVENTORY_SLOTS), Err(ItemError::InvalidSlot));
+}
+
+#[test]
+fn test_splitting_needs_an_empty_slot_and_leaves_something() {
+    let mut inventory = Inventory::default();
+    inventory.add(POTION, 10).unwrap();
+    inventory.add(SWORD, 1).unwrap();
+
+    assert_eq!(inventory.split(0, 4, 1), Err(ItemError::SlotOccupied));
+    assert_eq!(inventory.split(0, 10, 5), Err(ItemError::InvalidCount));
+    assert_eq!(inventory.split(0, 0, 5), Err(ItemError::InvalidCount));
+
+    inventory.split(0, 4, 5).unwrap();
+    assert_eq!(inventory.get(0), stack(POTION, 6));
+    assert_eq!(inventory.get(5), stack(POTION, 4));
+}
+
+#[test]
+fn test_taking_items() {
+    let mut inventory = Inventory::default();
+    inventory.add(POTION, 3).unwrap();
+
+    assert_eq!(inventory.take(0, 4), Err(ItemError::InvalidCount));
+    assert_eq!(inventory.take(0, 2), Ok(ItemStack { item: POTION, count: 2 }));
+    assert_eq!(inventory.take(0, 1), Ok(ItemStack { item: POTION, count: 1 }));
+    assert_eq!(inventory.get(0), None);
+    assert_eq!(inventory.take(0, 1), Err(ItemError::EmptySlot));
+}
+
+#[test]
+fn test_invalid_saved_entries_are_dropped() {
+    let saved = [
+        (0, ItemStack { item: POTION, count: 5 }),
+        (1, ItemStack { item: ItemId(99_999), count: 1 }),   // Removed from the tables
+        (2, ItemStack { item: SWORD, count: 3 }),            // Over the stack size
+        (INVENTORY_SLOTS, ItemStack { item: POTION, count: 1 }),
+    ];
+    let equipment = [
+        (EquipmentSlot::Weapon, SWORD),
+        (EquipmentSlot::Helmet, SWORD),  // Not a helmet
+    ];
+    let inventory = Inventory::from_saved(saved, equipment, 250);
+    assert_eq!(inventory.stacks(), vec![(0, ItemStack { item: POTION, count: 5 })]);
+    assert_eq!(inventory.equipment().iter().collect::<Vec<_>>(), vec![(EquipmentSlot::Weapon, SWORD)]);
+    assert_eq!(inventory.gold, 250);
+}
+
+#[test]
+fn test_using_items_checks_level_and_cooldown() {
+    let now = Instant::now();
+    let mut inventory = Inventory::default();
+    inventory.add(POTION, 2).unwrap();
+    inventory.add(PELT, 1).unwrap();
+    inventory.add(CHAINMAIL, 1).unwrap();
+
+    assert!(matches!(inventory.check_use(0, 1, None, now), Ok(ItemEffect::RestoreHealth(_))));
+    assert_eq!(inventory.check_use(1, 1, None, now), Err(ItemError::NotUsable));
+    assert_eq!(inventory.check_use(5, 1, None, now), Err(ItemError::EmptySlot));
+
+    let just_used = Some(now - Duration::from_millis(500));
+    assert!(matches!(inventory.check_use(0, 1, just_used, now), Err(ItemError::OnCooldown { .. })));
+    let long_ago = Some(now - ITEM_USE_COOLDOWN);
+    assert!(inventory.check_use(0, 1, long_ago, now).is_ok());
+}
+
+#[test]
+fn test_starter_inventory() {
+    let inventory = Inventory::starter();
+    assert_eq!(inventory.get(0), stack(POTION, 5));
+    assert!(inventory.stacks().iter().any(|(_, stack)| stack.item == SWORD));
+}
+
+#[test]
+fn test_equipping_swaps_with_the_equipped_item() {
+    let mut inventory = Inventory::default();
+    inventory.add(SWORD, 1).unwrap();                // Slot 0
+    inventory.add(ItemId(201), 1).unwrap();          // Eisenschwert (level 8), slot 1
+    inventory.add(POTION, 1).unwrap();               // Slot 2
+    inventory.add(CHAINMAIL, 1).unwrap();            // Slot 3
+
+    inventory.equip(0, 1).unwrap();
+    assert_eq!(inventory.get(0), None);
+    assert_eq!(inventory.equipment().get(EquipmentSlot::Weapon), Some(SWORD));
+
+    assert_eq!(inventory.equip(1, 5), Err(ItemError::RequiresLevel { required_level: 8 }));
+    inventory.equip(1, 8).unwrap();
+    assert_eq!(inventory.get(1), stack(SWORD, 1));
+    assert_eq!(inventory.equipment().get(EquipmentSlot::Weapon), Some(ItemId(201)));
+
+    assert_eq!(inventory.equip(2, 50), Err(ItemError::NotEquipment));
+    assert_eq!(inventory.equip(0, 50), Err(ItemError::EmptySlot));
+    assert_eq!(inventory.equipment().get(EquipmentSlot::Armor), None);
+}
+
+#[test]
+fn test_unequipping_needs_room() {
+    let mut inventory = Inventory::default();
+    inventory.add(SWORD, 1).unwrap();
+    inventory.equip(0, 1).unwrap();
+    assert_eq!(inventory.unequip(EquipmentSlot::Helmet), Err(ItemError::NothingEquipped));
+
+    inventory.add(PELT, INVENTORY_SLOTS as u32 * 50).unwrap();
+    assert_eq!(inventory.unequip(EquipmentSlot::Weapon), Err(ItemError::Full));
+    assert_eq!(inventory.equipment().get(EquipmentSlot::Weapon), Some(SWORD));
+
+    inventory.take(7, 50).unwrap();
+    inventory.unequip(EquipmentSlot::Weapon).unwrap();
+    assert_eq!(inventory.get(7), stack(SWORD, 1));
+    assert_eq!(inventory.equipment().get(EquipmentSlot::Weapon), None);
+}
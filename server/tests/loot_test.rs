@@ -0,0 +1,98 @@
+use server::loot::{self, GroundItem, LootError, DESPAWN_TIME, OWNER_TIME, PICKUP_RANGE};
+use server::mobs::Xorshift;
+use shared::balance::BalanceTables;
+use shared::bevy::prelude::Vec3;
+use shared::items::{ItemId, ItemStack, Loot, LootDrop, LootEntry, LootTable, Rarity};
+use std::time::{Duration, Instant};
+
+const PELT: ItemId = ItemId(100);  // Wolfsfell, stacks to 50
+
+fn table(gold: (u32, u32), rolls: u32, entries: Vec<(u32, LootDrop)>) -> LootTable {
+    LootTable {
+        gold,
+        rolls,
+        entries: entries.into_iter().map(|(weight, drop)| LootEntry { weight, drop }).collect(),
+    }
+}
+
+fn pile(owners: Vec<u64>, now: Instant) -> GroundItem {
+    GroundItem::new(100, Loot::Gold(10), Vec3::new(10.0, 0.0, 0.0), owners, now)
+}
+
+#[test]
+fn test_rolls_drop_gold_in_range_and_table_items() {
+    let tables = BalanceTables::builtin();
+    let table = table((5, 10), 2, vec![(1, LootDrop::Item(PELT, 1, 3)), (1, LootDrop::Nothing)]);
+    let mut rng = Xorshift::new(3);
+
+    let (mut pelts, mut nothing) = (0, 0);
+    for _ in 0..200 {
+        let drops = loot::roll(&tables, &table, 3, &mut rng);
+        let Some(&Loot::Gold(gold)) = drops.first() else { panic!("No gold in {:?}", drops) };
+        assert!((5..=10).contains(&gold), "{} gold", gold);
+        for drop in &drops[1..] {
+            let Loot::Item(stack) = drop else { panic!("Second gold pile in {:?}", drops) };
+            assert_eq!(stack.item, PELT);
+            assert!((1..=3).contains(&stack.count));
+        }
+        pelts += drops.len() - 1;
+        nothing += 3 - drops.len();
+    }
+    // Both entries weigh the same
+    assert!(pelts > 100 && nothing > 100, "{} pelts, {} empty rolls", pelts, nothing);
+}
+
+#[test]
+fn test_equipment_rolls_fit_rarity_and_mob_level() {
+    let tables = BalanceTables::builtin();
+    let table = table((0, 0), 1, vec![(1, LootDrop::Equipment(Rarity::Uncommon))]);
+    let mut rng = Xorshift::new(11);
+
+    for _ in 0..50 {
+        for drop in loot::roll(&tables, &table, 9, &mut rng) {
+            let Loot::Item(ItemStack { item, count: 1 }) = drop else { panic!("Unexpected drop {:?}", drop) };
+            let info = item.info().unwrap();
+            assert!(info.item_type.is_equipment());
+            assert_eq!(info.rarity, Rarity::Uncommon);
+            assert!(info.required_level <= 9, "{} needs level {}", info.name, info.required_level);
+        }
+    }
+
+    // No uncommon equipment for level 1 mobs: nothing at all
+    assert!(loot::roll(&tables, &table, 1, &mut rng).is_empty());
+}
+
+#[test]
+fn test_owners_have_the_loot_to_themselves_for_a_while() {
+    let now = Instant::now();
+    let item = pile(vec![1, 2], now);
+    let here = Vec3::new(10.0, 1.0, 1.0);
+
+    assert_eq!(item.check_pickup(2, here, now), Ok(()));
+    assert!(matches!(item.check_pickup(3, here, now), Err(LootError::NotYours { .. })));
+    assert_eq!(item.check_pickup(3, here, now + OWNER_TIME), Ok(()));
+
+    // Dropped items are anyone's right away
+    assert_eq!(pile(Vec::new(), now).check_pickup(3, here, now), Ok(()));
+}
+
+#[test]
+fn test_pickup_needs_range_and_piles_despawn() {
+    let now = Instant::now();
+    let item = pile(Vec::new(), now);
+    let far = Vec3::new(10.0 + PICKUP_RANGE + 0.5, 1.0, 0.0);
+    assert_eq!(item.check_pickup(1, far, now), Err(LootError::TooFar));
+
+    assert!(!item.is_expired(now + DESPAWN_TIME - Duration::from_secs(1)));
+    assert!(item.is_expired(now + DESPAWN_TIME));
+}
+
+#[test]
+fn test_several_piles_are_scattered_around_the_mob() {
+    let center = Vec3::new(5.0, 0.0, 5.0);
+    assert_eq!(loot::scatter(center, 0, 1), center);
+    let a = loot::scatter(center, 0, 3);
+    let b = loot::scatter(center, 1, 3);
+    assert!(a.distance(b) > 0.5);
+    assert!(a.distance(center) < 2.0);
+}
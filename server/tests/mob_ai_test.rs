@@ -0,0 +1,139 @@
+use server::mob_ai::{self, MobAction, MobState, ThreatTable, ATTACK_INTERVAL, LEASH_RADIUS, TAUNT_DURATION};
+use server::mobs::Mob;
+use server::navigation::NavGrid;
+use shared::bevy::prelude::{Vec2, Vec3};
+use shared::city::Footprint;
+use shared::{MobType, Specialization};
+use std::time::{Duration, Instant};
+
+/// Open field without obstacles
+fn field() -> NavGrid {
+    NavGrid::new(&[], 50.0, 1.0, 0.5)
+}
+
+fn wolf(now: Instant) -> Mob {
+    Mob::new(1, MobType::Wolf, 0, Vec3::ZERO, 0.0, now)
+}
+
+#[test]
+fn test_highest_threat_is_the_target() {
+    let now = Instant::now();
+    let mut threat = ThreatTable::default();
+    assert_eq!(threat.target(now), None);
+
+    threat.add(10, 50.0);
+    threat.add(20, 80.0);
+    assert_eq!(threat.target(now), Some(20));
+
+    threat.add(10, 40.0);
+    assert_eq!(threat.target(now), Some(10));
+}
+
+#[test]
+fn test_taunt_forces_target_and_keeps_the_lead() {
+    let now = Instant::now();
+    let mut threat = ThreatTable::default();
+    threat.add(10, 500.0);
+
+    threat.taunt(30, now);
+    assert_eq!(threat.target(now), Some(30));
+
+    // After the taunt the taunter is still ahead until others out-damage it
+    let later = now + TAUNT_DURATION + Duration::from_secs(1);
+    assert_eq!(threat.target(later), Some(30));
+    threat.add(10, 100.0);
+    assert_eq!(threat.target(later), Some(10));
+}
+
+#[test]
+fn test_tanks_generate_more_threat() {
+    assert!(mob_ai::threat_multiplier(Some(Specialization::Leibwaechter)) > 1.0);
+    assert_eq!(mob_ai::threat_multiplier(Some(Specialization::Gladiator)), 1.0);
+    assert_eq!(mob_ai::threat_multiplier(None), 1.0);
+}
+
+#[test]
+fn test_mob_aggroes_chases_and_attacks() {
+    let now = Instant::now();
+    let info = MobType::Wolf.info();
+    let nav = field();
+    let mut mob = wolf(now);
+
+    // Out of aggro range: nothing happens
+    let far = [(7, Vec3::new(info.aggro_radius + 5.0, 1.0, 0.0))];
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &far, now, 0.1), None);
+    assert!(mob.brain.threat.is_empty());
+
+    // Close enough to notice, too far to hit: moves closer
+    let near = [(7, Vec3::new(info.aggro_radius - 1.0, 1.0, 0.0))];
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &near, now, 0.5), None);
+    assert_eq!(mob.brain.state, MobState::Chase { target: 7 });
+    assert!(mob.position.x > 0.0);
+
+    // In range: hits, then waits for the attack interval
+    let close = [(7, mob.position + Vec3::new(1.0, 1.0, 0.0))];
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &close, now, 0.1), Some(MobAction::Attack { target: 7 }));
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &close, now + Duration::from_millis(500), 0.1), None);
+    assert_eq!(
+        mob_ai::think(&mut mob, &info, &nav, &close, now + ATTACK_INTERVAL, 0.1),
+        Some(MobAction::Attack { target: 7 })
+    );
+}
+
+#[test]
+fn test_leashed_mob_evades_and_returns_home_healed() {
+    let now = Instant::now();
+    let info = MobType::Wolf.info();
+    let nav = field();
+    let mut mob = wolf(now);
+    mob.brain.threat.add(7, 10.0);
+    mob.combat.take_damage(50.0, now);
+    mob.position = Vec3::new(LEASH_RADIUS + 1.0, 0.0, 0.0);
+
+    let player = [(7, mob.position + Vec3::X)];
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &player, now, 0.1), None);
+    assert!(mob.brain.is_evading());
+    assert!(mob.brain.threat.is_empty());
+
+    // Walks home without reacting to the player, then is back to full health
+    for step in 1..=200 {
+        mob_ai::think(&mut mob, &info, &nav, &player, now + Duration::from_millis(100 * step), 0.1);
+        if !mob.brain.is_evading() {
+            break;
+        }
+    }
+    assert!(!mob.brain.is_evading());
+    assert!(mob.position.distance(Vec3::ZERO) < 0.5);
+    assert_eq!(mob.combat.health, mob.combat.max_health);
+}
+
+#[test]
+fn test_stunned_mob_does_nothing() {
+    let now = Instant::now();
+    let info = MobType::Wolf.info();
+    let nav = field();
+    let mut mob = wolf(now);
+    mob.combat.apply_effect_from(None, server::combat::EffectKind::Stun, 1.0, Duration::from_secs(2), now);
+
+    let player = [(7, Vec3::new(1.0, 0.0, 0.0))];
+    assert_eq!(mob_ai::think(&mut mob, &info, &nav, &player, now, 0.1), None);
+    assert!(mob.brain.threat.is_empty());
+}
+
+#[test]
+fn test_chasing_mob_walks_around_buildings() {
+    let now = Instant::now();
+    let info = MobType::Wolf.info();
+    // A wall between the mob and its target
+    let wall = Footprint::Box { center: Vec3::new(5.0, 0.0, 0.0), half_extents: Vec2::new(1.0, 6.0), rotation: 0.0 };
+    let nav = NavGrid::new(&[wall], 50.0, 1.0, 0.5);
+    let mut mob = wolf(now);
+    mob.brain.threat.add(7, 10.0);
+
+    let player = [(7, Vec3::new(10.0, 1.0, 0.0))];
+    for step in 1..=100 {
+        mob_ai::think(&mut mob, &info, &nav, &player, now + Duration::from_millis(100 * step), 0.1);
+        assert!(!wall.contains(mob.position, 0.0), "walked into the wall at {:?}", mob.position);
+    }
+    assert!(mob.position.distance(Vec3::new(10.0, 0.0, 0.0)) <= mob_ai::ATTACK_RANGE + 0.5);
+}
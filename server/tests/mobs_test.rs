@@ -0,0 +1,57 @@
+use server::mobs::{Mob, MobSpawner, SpawnRegion};
+use shared::bevy::prelude::Vec3;
+use shared::MobType;
+use std::time::{Duration, Instant};
+
+fn region(max_population: usize) -> SpawnRegion {
+    SpawnRegion {
+        name: "Testwiese",
+        mob: MobType::Wolf,
+        center: Vec3::new(50.0, 0.0, 0.0),
+        radius: 10.0,
+        max_population,
+        respawn: Duration::from_secs(20),
+    }
+}
+
+#[test]
+fn test_regions_fill_up_to_their_cap_inside_the_circle() {
+    let now = Instant::now();
+    let mut spawner = MobSpawner::new(vec![region(5)], 42);
+
+    let spawns = spawner.due_spawns(now);
+    assert_eq!(spawns.len(), 5);
+    for spawn in &spawns {
+        assert_eq!(spawn.mob, MobType::Wolf);
+        assert!(spawn.position.distance(Vec3::new(50.0, 0.0, 0.0)) <= 10.0, "{:?} outside the region", spawn.position);
+    }
+
+    // Full - nothing more until something dies
+    assert!(spawner.due_spawns(now + Duration::from_secs(60)).is_empty());
+    assert_eq!(spawner.population(0), 5);
+}
+
+#[test]
+fn test_dead_mobs_respawn_after_the_timer() {
+    let now = Instant::now();
+    let mut spawner = MobSpawner::new(vec![region(3)], 7);
+    spawner.due_spawns(now);
+
+    spawner.died(0, now);
+    assert_eq!(spawner.population(0), 2);
+    assert!(spawner.due_spawns(now + Duration::from_secs(19)).is_empty());
+
+    let respawned = spawner.due_spawns(now + Duration::from_secs(20));
+    assert_eq!(respawned.len(), 1);
+    assert_eq!(spawner.population(0), 3);
+}
+
+#[test]
+fn test_mob_stats_come_from_the_balance_tables() {
+    let mob = Mob::new(1, MobType::Bandit, 0, Vec3::ZERO, 0.0, Instant::now());
+    let info = MobType::Bandit.info();
+
+    assert_eq!(mob.combat.max_health, info.max_health);
+    assert_eq!(mob.combat.health, info.max_health);
+    assert_eq!(mob.level(), info.level);
+}
@@ -0,0 +1,100 @@
+use server::movement::{MovementValidator, MoveCheck};
+use shared::bevy::prelude::Vec3;
+use std::time::{Duration, Instant};
+
+const SPEED: f32 = 5.0;
+
+#[test]
+fn test_normal_walking_is_accepted() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+    let mut pos = Vec3::ZERO;
+
+    // 50ms position updates at full speed for two seconds
+    for i in 1..=40 {
+        let next = pos + Vec3::X * SPEED * 0.05;
+        let now = start + Duration::from_millis(50 * i);
+        assert_eq!(validator.check(pos, next, now), MoveCheck::Accepted);
+        pos = next;
+    }
+}
+
+#[test]
+fn test_teleport_is_rejected() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+
+    let result = validator.check(Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0), start + Duration::from_millis(50));
+
+    assert!(matches!(result, MoveCheck::TooFast { .. }), "Teleport should be rejected, got {:?}", result);
+}
+
+#[test]
+fn test_idle_time_does_not_bank_distance() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+
+    // Standing still for a minute must not allow a 60-second jump
+    let result = validator.check(Vec3::ZERO, Vec3::new(SPEED * 60.0, 0.0, 0.0), start + Duration::from_secs(60));
+
+    assert!(matches!(result, MoveCheck::TooFast { .. }));
+}
+
+#[test]
+fn test_climbing_and_falling_are_limited() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+    let now = start + Duration::from_millis(100);
+
+    // A short drop is fine, falling out of the sky in a tenth of a second is not
+    assert_eq!(validator.check(Vec3::new(0.0, 3.0, 0.0), Vec3::ZERO, now), MoveCheck::Accepted);
+    assert!(matches!(
+        validator.check(Vec3::new(0.0, 50.0, 0.0), Vec3::ZERO, now + Duration::from_millis(100)),
+        MoveCheck::TooFast { .. }
+    ));
+    assert!(matches!(
+        validator.check(Vec3::ZERO, Vec3::new(0.0, 50.0, 0.0), now + Duration::from_millis(100)),
+        MoveCheck::TooFast { .. }
+    ));
+}
+
+#[test]
+fn test_rapid_updates_do_not_add_up() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+    let mut pos = Vec3::ZERO;
+
+    // Half a meter every millisecond is 100 times the walking speed
+    let mut rejected = false;
+    for i in 1..=20 {
+        let next = pos + Vec3::X * 0.5;
+        match validator.check(pos, next, start + Duration::from_millis(i)) {
+            MoveCheck::Accepted => pos = next,
+            MoveCheck::TooFast { .. } => rejected = true,
+        }
+    }
+
+    assert!(rejected, "Rapid updates should hit TooFast");
+    assert!(pos.x <= 1.5, "Moved {} meters in 20ms", pos.x);
+}
+
+#[test]
+fn test_non_finite_position_is_rejected() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+
+    let result = validator.check(Vec3::ZERO, Vec3::new(f32::NAN, 0.0, 0.0), start + Duration::from_millis(50));
+
+    assert!(matches!(result, MoveCheck::TooFast { .. }));
+}
+
+#[test]
+fn test_sprint_speed_allows_faster_moves() {
+    let start = Instant::now();
+    let mut validator = MovementValidator::new(SPEED, start);
+    let now = start + Duration::from_millis(500);
+    let far = Vec3::X * SPEED * 0.5 * 1.6 + Vec3::X;
+
+    assert!(matches!(validator.check(Vec3::ZERO, far, now), MoveCheck::TooFast { .. }));
+    assert_eq!(validator.check_at_speed(Vec3::ZERO, far, now, SPEED * 1.6), MoveCheck::Accepted);
+}
@@ -0,0 +1,92 @@
+use server::navigation::NavGrid;
+use shared::bevy::prelude::{Vec2, Vec3};
+use shared::city::{self, Footprint};
+
+fn wall() -> Footprint {
+    Footprint::Box { center: Vec3::ZERO, half_extents: Vec2::new(1.0, 8.0), rotation: 0.0 }
+}
+
+fn grid(footprints: &[Footprint]) -> NavGrid {
+    NavGrid::new(footprints, 30.0, 0.5, 0.5)
+}
+
+fn assert_walkable(nav: &NavGrid, from: Vec3, path: &[Vec3]) {
+    let mut previous = from;
+    for waypoint in path {
+        assert!(nav.line_of_sight(previous, *waypoint), "blocked between {:?} and {:?}", previous, waypoint);
+        previous = *waypoint;
+    }
+}
+
+#[test]
+fn test_open_field_is_a_straight_line() {
+    let nav = grid(&[]);
+    let to = Vec3::new(10.0, 0.0, 5.0);
+    assert_eq!(nav.find_path(Vec3::ZERO, to), Some(vec![to]));
+}
+
+#[test]
+fn test_path_goes_around_a_wall() {
+    let nav = grid(&[wall()]);
+    let from = Vec3::new(-5.0, 0.0, 0.0);
+    let to = Vec3::new(5.0, 0.0, 0.0);
+    assert!(!nav.line_of_sight(from, to));
+
+    let path = nav.find_path(from, to).expect("way around the wall");
+    assert_eq!(path.last(), Some(&to));
+    assert_walkable(&nav, from, &path);
+    // Smoothed: one corner on each end of the wall is enough
+    assert!(path.len() <= 4, "path not smoothed: {:?}", path);
+}
+
+#[test]
+fn test_rotated_buildings_block_their_footprint() {
+    let house = Footprint::Box { center: Vec3::ZERO, half_extents: Vec2::new(4.0, 0.5), rotation: std::f32::consts::FRAC_PI_2 };
+    let nav = grid(&[house]);
+    // Turned by 90 degrees the long side runs along Z
+    assert!(!nav.is_walkable(Vec3::new(0.0, 0.0, 3.0)));
+    assert!(nav.is_walkable(Vec3::new(3.0, 0.0, 0.0)));
+}
+
+#[test]
+fn test_goal_inside_an_obstacle_ends_next_to_it() {
+    let block = Footprint::Circle { center: Vec3::new(10.0, 0.0, 0.0), radius: 2.0 };
+    let nav = grid(&[block, wall()]);
+    let path = nav.find_path(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(10.0, 0.0, 0.0)).expect("path");
+    let end = *path.last().unwrap();
+    assert!(nav.is_walkable(end));
+    assert!(end.distance(Vec3::new(10.0, 0.0, 0.0)) < 4.0);
+}
+
+#[test]
+fn test_enclosed_goal_is_unreachable() {
+    // A ring of walls with nothing inside walkable
+    let walls = [
+        Footprint::Box { center: Vec3::new(10.0, 0.0, 0.0), half_extents: Vec2::new(0.5, 6.0), rotation: 0.0 },
+        Footprint::Box { center: Vec3::new(20.0, 0.0, 0.0), half_extents: Vec2::new(0.5, 6.0), rotation: 0.0 },
+        Footprint::Box { center: Vec3::new(15.0, 0.0, 6.0), half_extents: Vec2::new(6.0, 0.5), rotation: 0.0 },
+        Footprint::Box { center: Vec3::new(15.0, 0.0, -6.0), half_extents: Vec2::new(6.0, 0.5), rotation: 0.0 },
+    ];
+    let nav = grid(&walls);
+    let inside = Vec3::new(15.0, 0.0, 0.0);
+    assert!(nav.is_walkable(inside));
+    assert_eq!(nav.find_path(Vec3::ZERO, inside), None);
+}
+
+#[test]
+fn test_city_grid_matches_the_layout() {
+    let nav = NavGrid::city();
+    // Player spawn and the plaza are open, buildings and the fountain are not
+    assert!(nav.is_walkable(Vec3::new(0.0, 1.0, 0.0)));
+    assert!(!nav.is_walkable(city::FOUNTAIN_POSITION));
+    for building in city::buildings() {
+        assert!(!nav.is_walkable(building.position), "{:?} at {:?} is walkable", building.kind, building.position);
+    }
+
+    // From the wolves' meadow into the tavern's back yard and back out
+    let from = Vec3::new(-8.0, 0.0, 50.0);
+    let to = Vec3::new(-8.0, 0.0, 20.0);
+    let path = nav.find_path(from, to).expect("path through the city");
+    assert_eq!(path.last(), Some(&to));
+    assert_walkable(&nav, from, &path);
+}
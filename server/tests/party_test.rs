@@ -0,0 +1,66 @@
+use server::party::{PartyError, PartyManager, MAX_PARTY_SIZE, INVITE_TIMEOUT};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_invite_and_accept_forms_party() {
+    let now = Instant::now();
+    let mut party = PartyManager::new();
+
+    party.invite(1, 2, now).unwrap();
+    assert!(party.members(1).is_empty(), "no party before the invitation is accepted");
+
+    let members = party.accept(2, 1, now).unwrap();
+    assert_eq!(members, vec![1, 2]);
+    assert!(party.same_party(1, 2));
+    assert_eq!(party.members(2), vec![1, 2]);
+}
+
+#[test]
+fn test_invitation_must_match_and_not_expire() {
+    let now = Instant::now();
+    let mut party = PartyManager::new();
+
+    assert_eq!(party.invite(1, 1, now).unwrap_err(), PartyError::InviteSelf);
+
+    party.invite(1, 2, now).unwrap();
+    assert_eq!(party.accept(2, 3, now).unwrap_err(), PartyError::NoInvitation);
+
+    let late = now + INVITE_TIMEOUT + Duration::from_secs(1);
+    assert_eq!(party.accept(2, 1, late).unwrap_err(), PartyError::NoInvitation);
+    assert!(!party.same_party(1, 2));
+}
+
+#[test]
+fn test_only_leader_invites_and_size_is_capped() {
+    let now = Instant::now();
+    let mut party = PartyManager::new();
+    party.invite(1, 2, now).unwrap();
+    party.accept(2, 1, now).unwrap();
+
+    assert_eq!(party.invite(2, 3, now).unwrap_err(), PartyError::NotLeader);
+    assert_eq!(party.invite(1, 2, now).unwrap_err(), PartyError::AlreadyInParty);
+
+    for member in 3..=MAX_PARTY_SIZE as u64 {
+        party.invite(1, member, now).unwrap();
+        party.accept(member, 1, now).unwrap();
+    }
+    assert_eq!(party.invite(1, 99, now).unwrap_err(), PartyError::PartyFull);
+}
+
+#[test]
+fn test_leaving_dissolves_party_of_one() {
+    let now = Instant::now();
+    let mut party = PartyManager::new();
+    for member in [2, 3] {
+        party.invite(1, member, now).unwrap();
+        party.accept(member, 1, now).unwrap();
+    }
+
+    // The leader leaves; the next member leads
+    assert_eq!(party.leave(1), vec![2, 3]);
+    assert_eq!(party.members(3), vec![2, 3]);
+
+    assert_eq!(party.leave(3), vec![2]);
+    assert!(party.members(2).is_empty());
+    assert!(!party.same_party(2, 3));
+}
@@ -0,0 +1,299 @@
+use server::{auth, db};
+use server::inventory::STARTER_ITEMS;
+use server::persistence::{spawn_db_worker, DbJob, DbResult, LoadedCharacter};
+use server::shops::ShopTrade;
+use shared::items::{EquipmentSlot, ItemId, ItemStack, ShopId};
+use shared::{CharacterAppearance, CharacterClass, CharacterData};
+use std::net::SocketAddr;
+use tokio::sync::mpsc;
+
+fn client_addr() -> SocketAddr {
+    "127.0.0.1:40000".parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_worker_answers_requests_in_order() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool, results_tx);
+
+    // Login is queued right behind the registration and must see the new user
+    jobs.send(DbJob::Register {
+        addr: client_addr(),
+        username: "worker".to_string(),
+        password: "password123".to_string(),
+        email: None,
+    }).unwrap();
+    jobs.send(DbJob::Login {
+        addr: client_addr(),
+        username: "worker".to_string(),
+        password: "password123".to_string(),
+    }).unwrap();
+
+    match results.recv().await.unwrap() {
+        DbResult::Registered { addr, response } => {
+            assert_eq!(addr, client_addr());
+            assert!(matches!(response, shared::AuthResponse::RegisterSuccess), "Got {:?}", response);
+        }
+        other => panic!("Expected Registered, got {:?}", other),
+    }
+
+    match results.recv().await.unwrap() {
+        DbResult::LoginVerified { result: Ok(login), .. } => {
+            assert_eq!(login.username, "worker");
+            assert!(login.characters.is_empty());
+        }
+        other => panic!("Expected successful LoginVerified, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_fire_and_forget_jobs_produce_no_result() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool, results_tx);
+
+    jobs.send(DbJob::SaveLevel { character_id: 1, level: 2, experience: 0 }).unwrap();
+    jobs.send(DbJob::Login {
+        addr: client_addr(),
+        username: "nobody".to_string(),
+        password: "password123".to_string(),
+    }).unwrap();
+
+    // The first thing back is the login answer - the save was silent
+    match results.recv().await.unwrap() {
+        DbResult::LoginVerified { result: Err(_), .. } => {}
+        other => panic!("Expected failed LoginVerified, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_start_session_rejects_second_login() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let mut session_manager = auth::SessionManager::new();
+
+    auth::handle_register(&pool, "twice".to_string(), "password123".to_string(), None).await;
+
+    let first = auth::verify_login(&pool, "twice".to_string(), "password123".to_string()).await.unwrap();
+    let second = auth::verify_login(&pool, "twice".to_string(), "password123".to_string()).await.unwrap();
+
+    assert!(matches!(auth::start_session(&mut session_manager, first), shared::AuthResponse::LoginSuccess { .. }));
+    assert!(matches!(auth::start_session(&mut session_manager, second), shared::AuthResponse::LoginFailed { .. }));
+}
+
+#[tokio::test]
+async fn test_inventory_is_created_saved_and_loaded() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool, results_tx);
+
+    jobs.send(DbJob::Register {
+        addr: client_addr(),
+        username: "packer".to_string(),
+        password: "password123".to_string(),
+        email: None,
+    }).unwrap();
+    jobs.send(DbJob::Login {
+        addr: client_addr(),
+        username: "packer".to_string(),
+        password: "password123".to_string(),
+    }).unwrap();
+    results.recv().await.unwrap();
+    let user_id = match results.recv().await.unwrap() {
+        DbResult::LoginVerified { result: Ok(login), .. } => login.user_id,
+        other => panic!("Expected successful LoginVerified, got {:?}", other),
+    };
+
+    jobs.send(DbJob::CreateCharacter {
+        addr: client_addr(),
+        user_id,
+        character: CharacterData {
+            name: "Packesel".to_string(),
+            class: CharacterClass::Krieger,
+            appearance: CharacterAppearance::default(),
+            level: 1,
+            experience: 0,
+            specialization: None,
+        },
+    }).unwrap();
+    let character_id = match results.recv().await.unwrap() {
+        DbResult::CharacterCreated { result: Ok(id), .. } => id,
+        other => panic!("Expected successful CharacterCreated, got {:?}", other),
+    };
+
+    // New characters start with the starter items
+    let load = || DbJob::LoadCharacter { addr: client_addr(), token: "token".to_string(), character_id };
+    jobs.send(load()).unwrap();
+    match results.recv().await.unwrap() {
+        DbResult::CharacterLoaded { result: Ok(Some(LoadedCharacter { character, items, equipment })), .. } => {
+            assert_eq!(character.gold, 0);
+            assert!(equipment.is_empty());
+            let starter: Vec<_> = STARTER_ITEMS.iter().map(|&(item, count)| ItemStack { item, count }).collect();
+            assert_eq!(items.into_iter().map(|(_, stack)| stack).collect::<Vec<_>>(), starter);
+        }
+        other => panic!("Expected loaded character, got {:?}", other),
+    }
+
+    // Saving replaces the whole inventory and equipment
+    let saved = vec![(7, ItemStack { item: ItemId(100), count: 12 })];
+    let equipped = vec![(EquipmentSlot::Weapon, ItemId(200)), (EquipmentSlot::Boots, ItemId(302))];
+    jobs.send(DbJob::SaveInventory { character_id, items: saved.clone(), equipment: equipped.clone(), gold: 340 }).unwrap();
+    jobs.send(load()).unwrap();
+    match results.recv().await.unwrap() {
+        DbResult::CharacterLoaded { result: Ok(Some(LoadedCharacter { character, items, mut equipment })), .. } => {
+            assert_eq!(character.gold, 340);
+            assert_eq!(items, saved);
+            equipment.sort_by_key(|(slot, _)| slot.as_str());
+            assert_eq!(equipment, vec![(EquipmentSlot::Boots, ItemId(302)), (EquipmentSlot::Weapon, ItemId(200))]);
+        }
+        other => panic!("Expected loaded character, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_shop_trade_is_saved_with_the_inventory() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool.clone(), results_tx);
+
+    auth::handle_register(&pool, "haendler".to_string(), "password123".to_string(), None).await;
+    let login = auth::verify_login(&pool, "haendler".to_string(), "password123".to_string()).await.unwrap();
+    jobs.send(DbJob::CreateCharacter {
+        addr: client_addr(),
+        user_id: login.user_id,
+        character: CharacterData {
+            name: "Kundin".to_string(),
+            class: CharacterClass::Krieger,
+            appearance: CharacterAppearance::default(),
+            level: 1,
+            experience: 0,
+            specialization: None,
+        },
+    }).unwrap();
+    let character_id = match results.recv().await.unwrap() {
+        DbResult::CharacterCreated { result: Ok(id), .. } => id,
+        other => panic!("Expected successful CharacterCreated, got {:?}", other),
+    };
+
+    let bought = vec![(0, ItemStack { item: ItemId(1), count: 2 })];
+    let trade = ShopTrade { shop: ShopId(1), item: ItemId(1), count: 2, gold: 24 };
+    jobs.send(DbJob::SaveShopTrade { character_id, items: bought.clone(), equipment: Vec::new(), gold: 76, trade }).unwrap();
+    jobs.send(DbJob::LoadCharacter { addr: client_addr(), token: "token".to_string(), character_id }).unwrap();
+    match results.recv().await.unwrap() {
+        DbResult::CharacterLoaded { result: Ok(Some(LoadedCharacter { character, items, .. })), .. } => {
+            assert_eq!(character.gold, 76);
+            assert_eq!(items, bought);
+        }
+        other => panic!("Expected loaded character, got {:?}", other),
+    }
+
+    // The trade is on record next to the inventory it produced
+    let recorded: (i64, i64, i64, i64) = sqlx::query_as(
+        "SELECT shop_id, item_id, count, gold FROM shop_transactions WHERE character_id = ?1"
+    )
+    .bind(character_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(recorded, (1, 1, 2, 24));
+}
+
+#[tokio::test]
+async fn test_player_trade_saves_both_inventories() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool.clone(), results_tx);
+
+    auth::handle_register(&pool, "tauscher".to_string(), "password123".to_string(), None).await;
+    let login = auth::verify_login(&pool, "tauscher".to_string(), "password123".to_string()).await.unwrap();
+    let mut character_ids = Vec::new();
+    for name in ["Erster", "Zweiter"] {
+        jobs.send(DbJob::CreateCharacter {
+            addr: client_addr(),
+            user_id: login.user_id,
+            character: CharacterData {
+                name: name.to_string(),
+                class: CharacterClass::Ninja,
+                appearance: CharacterAppearance::default(),
+                level: 1,
+                experience: 0,
+                specialization: None,
+            },
+        }).unwrap();
+        match results.recv().await.unwrap() {
+            DbResult::CharacterCreated { result: Ok(id), .. } => character_ids.push(id),
+            other => panic!("Expected successful CharacterCreated, got {:?}", other),
+        }
+    }
+
+    let first = (character_ids[0], vec![(0, ItemStack { item: ItemId(100), count: 30 })], Vec::new(), 60);
+    let second = (character_ids[1], vec![(3, ItemStack { item: ItemId(200), count: 1 })], Vec::new(), 40);
+    jobs.send(DbJob::SavePlayerTrade { inventories: [first.clone(), second.clone()] }).unwrap();
+
+    for (character_id, saved_items, _, saved_gold) in [first, second] {
+        jobs.send(DbJob::LoadCharacter { addr: client_addr(), token: "token".to_string(), character_id }).unwrap();
+        match results.recv().await.unwrap() {
+            DbResult::CharacterLoaded { result: Ok(Some(LoadedCharacter { character, items, .. })), .. } => {
+                assert_eq!(character.gold as u64, saved_gold);
+                assert_eq!(items, saved_items);
+            }
+            other => panic!("Expected loaded character, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_failed_player_trade_holds_its_characters_until_released() {
+    let pool = db::init_database("sqlite::memory:").await.unwrap();
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let jobs = spawn_db_worker(pool.clone(), results_tx);
+
+    auth::handle_register(&pool, "pechvogel".to_string(), "password123".to_string(), None).await;
+    let login = auth::verify_login(&pool, "pechvogel".to_string(), "password123".to_string()).await.unwrap();
+    jobs.send(DbJob::CreateCharacter {
+        addr: client_addr(),
+        user_id: login.user_id,
+        character: CharacterData {
+            name: "Pechvogel".to_string(),
+            class: CharacterClass::Sura,
+            appearance: CharacterAppearance::default(),
+            level: 1,
+            experience: 0,
+            specialization: None,
+        },
+    }).unwrap();
+    let character_id = match results.recv().await.unwrap() {
+        DbResult::CharacterCreated { result: Ok(id), .. } => id,
+        other => panic!("Expected successful CharacterCreated, got {:?}", other),
+    };
+
+    // The partner's character doesn't exist, so its half of the trade can't be written.
+    // The player relogs and keeps playing while the save is still being retried.
+    let first = (character_id, vec![(0, ItemStack { item: ItemId(100), count: 30 })], Vec::new(), 60);
+    let missing = (character_id + 1000, vec![(0, ItemStack { item: ItemId(200), count: 1 })], Vec::new(), 40);
+    jobs.send(DbJob::SavePlayerTrade { inventories: [first.clone(), missing] }).unwrap();
+    jobs.send(DbJob::LoadCharacter { addr: client_addr(), token: "token".to_string(), character_id }).unwrap();
+    let (_, items, equipment, gold) = first;
+    jobs.send(DbJob::SaveInventory { character_id, items, equipment, gold }).unwrap();
+
+    match results.recv().await.unwrap() {
+        DbResult::PlayerTradeNotSaved { character_ids } => {
+            assert_eq!(character_ids, [character_id, character_id + 1000]);
+        }
+        other => panic!("Expected PlayerTradeNotSaved, got {:?}", other),
+    }
+    match results.recv().await.unwrap() {
+        DbResult::CharacterLoaded { result: Err(_), .. } => {}
+        other => panic!("Loading a character of an unsaved trade must fail, got {:?}", other),
+    }
+
+    jobs.send(DbJob::ReleaseCharacters { character_ids: [character_id, character_id + 1000] }).unwrap();
+    jobs.send(DbJob::LoadCharacter { addr: client_addr(), token: "token".to_string(), character_id }).unwrap();
+    match results.recv().await.unwrap() {
+        DbResult::CharacterLoaded { result: Ok(Some(LoadedCharacter { character, items, .. })), .. } => {
+            assert_ne!(character.gold, 60, "Half of a failed trade must not be saved");
+            assert!(!items.contains(&(0, ItemStack { item: ItemId(100), count: 30 })));
+        }
+        other => panic!("Expected loaded character, got {:?}", other),
+    }
+}
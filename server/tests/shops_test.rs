@@ -0,0 +1,115 @@
+use server::inventory::{Inventory, ItemError};
+use server::shops::{self, ShopError, ShopStock, ShopTrade, SHOP_RANGE};
+use shared::bevy::prelude::Vec3;
+use shared::items::{ItemId, ItemStack, ItemType, ShopId, ShopInfo, ShopListing, INVENTORY_SLOTS};
+use std::time::{Duration, Instant};
+
+const SHOP: ShopId = ShopId(1);
+const POTION: ItemId = ItemId(1);  // Kleiner Heiltrank, worth 5, stacks to 20
+const SWORD: ItemId = ItemId(200); // Rostiges Schwert, worth 3
+const PELT: ItemId = ItemId(100);  // Wolfsfell, not sold here
+
+fn shop() -> ShopInfo {
+    ShopInfo {
+        name: "Testladen".to_string(),
+        position: Vec3::new(10.0, 1.0, 0.0),
+        restock: 60.0,
+        listings: vec![
+            ShopListing { item: POTION, price: 12, stock: None },
+            ShopListing { item: SWORD, price: 10, stock: Some(2) },
+        ],
+        buys: vec![ItemType::Consumable, ItemType::Weapon],
+    }
+}
+
+fn with_gold(gold: u64) -> Inventory {
+    Inventory::from_saved([], [], gold)
+}
+
+#[test]
+fn test_buying_takes_gold_and_adds_items() {
+    let (info, now) = (shop(), Instant::now());
+    let mut stock = ShopStock::new();
+    let mut inventory = with_gold(100);
+
+    let trade = stock.buy(SHOP, &info, POTION, 3, &mut inventory, now).unwrap();
+    assert_eq!(trade, ShopTrade { shop: SHOP, item: POTION, count: 3, gold: 36 });
+    assert_eq!(inventory.gold, 64);
+    assert_eq!(inventory.get(0), Some(ItemStack { item: POTION, count: 3 }));
+
+    assert_eq!(stock.buy(SHOP, &info, PELT, 1, &mut inventory, now), Err(ShopError::NotSold));
+    assert_eq!(stock.buy(SHOP, &info, POTION, 0, &mut inventory, now), Err(ShopError::Item(ItemError::InvalidCount)));
+}
+
+#[test]
+fn test_failed_purchases_change_nothing() {
+    let (info, now) = (shop(), Instant::now());
+    let mut stock = ShopStock::new();
+
+    let mut poor = with_gold(20);
+    assert_eq!(stock.buy(SHOP, &info, POTION, 2, &mut poor, now), Err(ShopError::NotEnoughGold { price: 24 }));
+    assert_eq!(poor.gold, 20);
+    assert!(poor.stacks().is_empty());
+
+    // Full bags: neither the gold nor the merchant's stock is taken
+    let full: Vec<_> = (0..INVENTORY_SLOTS).map(|slot| (slot, ItemStack { item: SWORD, count: 1 })).collect();
+    let mut full = Inventory::from_saved(full, [], 100);
+    assert_eq!(stock.buy(SHOP, &info, SWORD, 1, &mut full, now), Err(ShopError::Item(ItemError::Full)));
+    assert_eq!(full.gold, 100);
+    assert_eq!(stock.listings(SHOP, &info, now), vec![(POTION, None), (SWORD, Some(2))]);
+}
+
+#[test]
+fn test_limited_listings_sell_out_and_restock() {
+    let (info, start) = (shop(), Instant::now());
+    let mut stock = ShopStock::new();
+    let mut inventory = with_gold(1000);
+
+    stock.buy(SHOP, &info, SWORD, 1, &mut inventory, start).unwrap();
+    assert_eq!(stock.buy(SHOP, &info, SWORD, 2, &mut inventory, start), Err(ShopError::SoldOut { left: 1 }));
+    stock.buy(SHOP, &info, SWORD, 1, &mut inventory, start).unwrap();
+    assert_eq!(stock.buy(SHOP, &info, SWORD, 1, &mut inventory, start), Err(ShopError::SoldOut { left: 0 }));
+    assert_eq!(inventory.gold, 980);
+
+    // One back per restock interval, never more than the listing's stock
+    let later = |secs| start + Duration::from_secs(secs);
+    assert_eq!(stock.listings(SHOP, &info, later(59)), vec![(POTION, None), (SWORD, Some(0))]);
+    assert_eq!(stock.listings(SHOP, &info, later(61)), vec![(POTION, None), (SWORD, Some(1))]);
+    assert_eq!(stock.listings(SHOP, &info, later(121)), vec![(POTION, None), (SWORD, Some(2))]);
+    assert_eq!(stock.listings(SHOP, &info, later(1000)), vec![(POTION, None), (SWORD, Some(2))]);
+
+    // Other merchants have their own stock
+    assert_eq!(stock.listings(ShopId(2), &info, start), vec![(POTION, None), (SWORD, Some(2))]);
+}
+
+#[test]
+fn test_selling_pays_the_item_value() {
+    let info = shop();
+    let mut inventory = Inventory::from_saved([(4, ItemStack { item: POTION, count: 5 })], [], 10);
+
+    let trade = shops::sell(SHOP, &info, 4, 2, &mut inventory).unwrap();
+    assert_eq!(trade, ShopTrade { shop: SHOP, item: POTION, count: -2, gold: -10 });
+    assert_eq!(inventory.gold, 20);
+    assert_eq!(inventory.get(4), Some(ItemStack { item: POTION, count: 3 }));
+
+    assert_eq!(shops::sell(SHOP, &info, 5, 1, &mut inventory), Err(ShopError::Item(ItemError::EmptySlot)));
+    assert_eq!(shops::sell(SHOP, &info, 4, 4, &mut inventory), Err(ShopError::Item(ItemError::InvalidCount)));
+    assert_eq!(inventory.gold, 20);
+}
+
+#[test]
+fn test_merchants_only_buy_their_kind_of_items() {
+    let info = shop();
+    let mut inventory = Inventory::from_saved([(0, ItemStack { item: PELT, count: 3 })], [], 10);
+
+    assert_eq!(shops::sell(SHOP, &info, 0, 1, &mut inventory), Err(ShopError::NotBought));
+    assert_eq!(inventory.get(0), Some(ItemStack { item: PELT, count: 3 }));
+    assert_eq!(inventory.gold, 10);
+}
+
+#[test]
+fn test_trading_needs_the_merchant_nearby() {
+    let info = shop();
+    assert_eq!(shops::check_range(&info, info.position + Vec3::X * (SHOP_RANGE - 0.1)), Ok(()));
+    assert_eq!(shops::check_range(&info, info.position + Vec3::Z * (SHOP_RANGE + 0.1)), Err(ShopError::TooFar));
+}
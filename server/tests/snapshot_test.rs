@@ -0,0 +1,108 @@
+use shared::bevy::prelude::Vec3;
+use shared::snapshot::{self, EntitySnapshot, SnapshotEntities, SnapshotHistory};
+use std::f32::consts::PI;
+
+fn entities(states: &[(u64, EntitySnapshot)]) -> SnapshotEntities {
+    states.iter().copied().collect()
+}
+
+#[test]
+fn test_position_quantization_roundtrip() {
+    let position = Vec3::new(123.456, -7.891, 0.004);
+    let restored = snapshot::dequantize_position(snapshot::quantize_position(position));
+
+    assert!((restored - position).abs().max_element() <= 0.005, "Got {:?}", restored);
+}
+
+#[test]
+fn test_yaw_quantization_wraps() {
+    for yaw in [0.0, PI / 2.0, PI, -PI / 2.0, 3.0 * PI] {
+        let restored = snapshot::dequantize_yaw(snapshot::quantize_yaw(yaw));
+        let diff = (restored - yaw.rem_euclid(2.0 * PI)).abs();
+        assert!(diff < 0.001 || (2.0 * PI - diff) < 0.001, "yaw {} restored as {}", yaw, restored);
+    }
+}
+
+#[test]
+fn test_full_snapshot_without_baseline() {
+    let current = entities(&[(1, EntitySnapshot::new(Vec3::new(1.0, 0.0, 2.0), 0.5, 3))]);
+
+    let delta = snapshot::diff(None, &current);
+
+    assert_eq!(delta.entities.len(), 1);
+    assert!(delta.entities[0].position.is_some() && delta.entities[0].yaw.is_some() && delta.entities[0].level.is_some());
+    assert_eq!(snapshot::apply(None, &delta.entities, &delta.removed), Some(current));
+}
+
+#[test]
+fn test_unchanged_entities_are_omitted() {
+    let state = EntitySnapshot::new(Vec3::new(5.0, 0.0, 5.0), 1.0, 2);
+    let baseline = entities(&[(1, state), (2, state)]);
+
+    let delta = snapshot::diff(Some(&baseline), &baseline);
+
+    assert!(delta.is_empty(), "Nothing changed, nothing should be sent: {:?}", delta);
+}
+
+#[test]
+fn test_only_changed_fields_are_sent() {
+    let before = EntitySnapshot::new(Vec3::new(5.0, 0.0, 5.0), 1.0, 2);
+    let after = EntitySnapshot::new(Vec3::new(5.5, 0.0, 5.0), 1.0, 2);
+    let baseline = entities(&[(1, before)]);
+    let current = entities(&[(1, after)]);
+
+    let delta = snapshot::diff(Some(&baseline), &current);
+
+    assert_eq!(delta.entities.len(), 1);
+    assert!(delta.entities[0].position.is_some());
+    assert_eq!(delta.entities[0].yaw, None);
+    assert_eq!(delta.entities[0].level, None);
+    assert_eq!(snapshot::apply(Some(&baseline), &delta.entities, &delta.removed), Some(current));
+}
+
+#[test]
+fn test_removed_entities() {
+    let state = EntitySnapshot::new(Vec3::ZERO, 0.0, 1);
+    let baseline = entities(&[(1, state), (2, state)]);
+    let current = entities(&[(1, state)]);
+
+    let delta = snapshot::diff(Some(&baseline), &current);
+
+    assert_eq!(delta.removed, vec![2]);
+    assert_eq!(snapshot::apply(Some(&baseline), &delta.entities, &delta.removed), Some(current));
+}
+
+#[test]
+fn test_partial_delta_without_baseline_is_rejected() {
+    let before = EntitySnapshot::new(Vec3::ZERO, 0.0, 1);
+    let after = EntitySnapshot::new(Vec3::X, 0.0, 1);
+    let delta = snapshot::diff(Some(&entities(&[(1, before)])), &entities(&[(1, after)]));
+
+    // Client lost the baseline - it must not invent the missing fields
+    assert_eq!(snapshot::apply(None, &delta.entities, &delta.removed), None);
+}
+
+#[test]
+fn test_history_keeps_recent_ticks() {
+    let mut history = SnapshotHistory::default();
+    for tick in 1..=(snapshot::SNAPSHOT_HISTORY as u32 + 10) {
+        history.insert(tick, SnapshotEntities::new());
+    }
+
+    assert!(history.get(1).is_none(), "Oldest ticks should be evicted");
+    assert!(history.get(snapshot::SNAPSHOT_HISTORY as u32 + 10).is_some());
+}
+
+#[test]
+fn test_history_replaces_a_reused_tick() {
+    let mut history = SnapshotHistory::default();
+    let old = entities(&[(1, EntitySnapshot::new(Vec3::ZERO, 0.0, 1))]);
+    let new = entities(&[(2, EntitySnapshot::new(Vec3::ONE, 0.0, 3))]);
+    history.insert(5, old);
+    history.insert(5, new.clone());
+
+    assert_eq!(history.get(5), Some(&new));
+
+    history.clear();
+    assert!(history.get(5).is_none());
+}
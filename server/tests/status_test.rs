@@ -0,0 +1,87 @@
+use server::status::{EffectOrigin, StatusEffects};
+use shared::{EffectKind, SkillId};
+use std::time::{Duration, Instant};
+
+fn origin(source: u64, skill: SkillId) -> Option<EffectOrigin> {
+    Some(EffectOrigin { source, skill })
+}
+
+#[test]
+fn test_strongest_keeps_higher_magnitude_and_later_expiry() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    effects.apply(EffectKind::DamageReduction, None, 0.5, Duration::from_secs(5), now);
+    effects.apply(EffectKind::DamageReduction, None, 0.3, Duration::from_secs(8), now);
+
+    assert_eq!(effects.magnitude(EffectKind::DamageReduction, now), Some(0.5));
+    assert!(effects.has(EffectKind::DamageReduction, now + Duration::from_secs(7)));
+    assert_eq!(effects.states(now).len(), 1);
+}
+
+#[test]
+fn test_stacks_add_up_to_the_limit() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    for _ in 0..5 {
+        effects.apply(EffectKind::DefenseReduction, origin(1, SkillId::Kriegsschrei), 0.1, Duration::from_secs(5), now);
+    }
+
+    let magnitude = effects.magnitude(EffectKind::DefenseReduction, now).unwrap();
+    assert!((magnitude - 0.3).abs() < 0.001, "got {}", magnitude);
+    assert_eq!(effects.states(now)[0].stacks, 3);
+}
+
+#[test]
+fn test_effects_over_time_run_per_source() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    effects.apply(EffectKind::DamageOverTime, origin(1, SkillId::Giftpfeil), 5.0, Duration::from_secs(3), now);
+    effects.apply(EffectKind::DamageOverTime, origin(2, SkillId::Giftpfeil), 5.0, Duration::from_secs(3), now);
+    // Same caster again refreshes instead of adding a third
+    effects.apply(EffectKind::DamageOverTime, origin(1, SkillId::Giftpfeil), 5.0, Duration::from_secs(3), now);
+
+    assert_eq!(effects.due_ticks(now + Duration::from_secs(1)).len(), 2);
+}
+
+#[test]
+fn test_immunity_blocks_and_breaks_stuns() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    effects.apply(EffectKind::Stun, None, 1.0, Duration::from_secs(2), now);
+
+    effects.apply(EffectKind::CrowdControlImmunity, None, 1.0, Duration::from_secs(3), now);
+    assert!(!effects.has(EffectKind::Stun, now));
+    assert!(!effects.apply(EffectKind::Stun, None, 1.0, Duration::from_secs(2), now));
+}
+
+#[test]
+fn test_expiry_waits_for_the_last_tick() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    effects.apply(EffectKind::HealOverTime, origin(1, SkillId::Erneuerung), 4.0, Duration::from_secs(2), now);
+
+    // Checked late: the effect has run out, but its ticks were not applied yet
+    let late = now + Duration::from_secs(5);
+    assert!(!effects.expire(late));
+    assert_eq!(effects.due_ticks(late).len(), 2);
+    assert!(effects.expire(late));
+    assert!(effects.states(late).is_empty());
+}
+
+#[test]
+fn test_changes_are_flagged_once() {
+    let now = Instant::now();
+    let mut effects = StatusEffects::default();
+    assert!(!effects.take_changed());
+
+    effects.apply(EffectKind::DamageBuff, origin(3, SkillId::Zauberklinge), 0.25, Duration::from_secs(10), now);
+    assert!(effects.take_changed());
+    assert!(!effects.take_changed());
+
+    let state = &effects.states(now)[0];
+    assert_eq!(state.skill, Some(SkillId::Zauberklinge));
+    assert_eq!(state.remaining, 10.0);
+
+    effects.expire(now + Duration::from_secs(11));
+    assert!(effects.take_changed());
+}
@@ -0,0 +1,142 @@
+use server::inventory::{Inventory, ItemError};
+use server::trade::{self, TradeError, TradeManager, REQUEST_TIMEOUT};
+use shared::items::{ItemId, ItemStack, TradeOffer, INVENTORY_SLOTS};
+use std::time::{Duration, Instant};
+
+const POTION: ItemId = ItemId(1);  // Kleiner Heiltrank, stacks to 20
+const PELT: ItemId = ItemId(100);  // Wolfsfell, stacks to 50
+const SWORD: ItemId = ItemId(200); // Rostiges Schwert
+
+fn inventory(items: &[(usize, ItemId, u32)], gold: u64) -> Inventory {
+    Inventory::from_saved(items.iter().map(|&(slot, item, count)| (slot, ItemStack { item, count })), [], gold)
+}
+
+fn open_trade(trades: &mut TradeManager, now: Instant) {
+    trades.request(1, 2, now).unwrap();
+    trades.accept(2, 1, now).unwrap();
+}
+
+fn offer(items: &[(u16, ItemId, u32)], gold: u64) -> TradeOffer {
+    TradeOffer {
+        items: items.iter().map(|&(slot, item, count)| (slot, ItemStack { item, count })).collect(),
+        gold,
+        locked: true,
+        confirmed: true,
+    }
+}
+
+#[test]
+fn test_requests_open_a_trade() {
+    let now = Instant::now();
+    let mut trades = TradeManager::new();
+    assert_eq!(trades.request(1, 1, now), Err(TradeError::TradeSelf));
+    assert_eq!(trades.accept(2, 1, now), Err(TradeError::NoRequest));
+
+    // Requests run out
+    trades.request(1, 2, now).unwrap();
+    assert_eq!(trades.accept(2, 1, now + REQUEST_TIMEOUT), Err(TradeError::NoRequest));
+
+    open_trade(&mut trades, now);
+    assert_eq!(trades.partner(1), Some(2));
+    assert_eq!(trades.partner(2), Some(1));
+    assert_eq!(trades.trades(), vec![(1, 2)]);
+    assert_eq!(trades.request(3, 1, now), Err(TradeError::PartnerBusy));
+    assert_eq!(trades.request(2, 3, now), Err(TradeError::AlreadyTrading));
+}
+
+#[test]
+fn test_offers_must_be_in_the_inventory() {
+    let now = Instant::now();
+    let mut trades = TradeManager::new();
+    open_trade(&mut trades, now);
+    let bag = inventory(&[(0, POTION, 5), (3, SWORD, 1)], 50);
+
+    assert_eq!(trades.set_offer(1, &[(1, 1)], 0, &bag), Err(TradeError::Item(ItemError::EmptySlot)));
+    assert_eq!(trades.set_offer(1, &[(0, 6)], 0, &bag), Err(TradeError::Item(ItemError::InvalidCount)));
+    assert_eq!(trades.set_offer(1, &[(0, 2), (0, 2)], 0, &bag), Err(TradeError::DuplicateSlot));
+    assert_eq!(trades.set_offer(1, &[], 51, &bag), Err(TradeError::NotEnoughGold));
+    assert_eq!(trades.set_offer(3, &[], 0, &bag), Err(TradeError::NotTrading));
+
+    trades.set_offer(1, &[(0, 2), (3, 1)], 20, &bag).unwrap();
+    let (ours, theirs) = trades.offers(1).unwrap();
+    assert_eq!(ours.items, vec![(0, ItemStack { item: POTION, count: 2 }), (3, ItemStack { item: SWORD, count: 1 })]);
+    assert_eq!(ours.gold, 20);
+    assert_eq!(theirs, &TradeOffer::default());
+}
+
+#[test]
+fn test_both_must_lock_before_confirming() {
+    let now = Instant::now();
+    let mut trades = TradeManager::new();
+    open_trade(&mut trades, now);
+    let bag = inventory(&[(0, POTION, 5)], 50);
+
+    trades.set_offer(1, &[(0, 1)], 0, &bag).unwrap();
+    trades.lock(1).unwrap();
+    assert_eq!(trades.set_offer(1, &[(0, 5)], 0, &bag), Err(TradeError::Locked));
+    assert_eq!(trades.confirm(1), Err(TradeError::NotLocked));
+
+    // The partner can still change its offer until it locks it too
+    trades.set_offer(2, &[], 10, &bag).unwrap();
+    trades.lock(2).unwrap();
+    assert_eq!(trades.confirm(1), Ok(false));
+    assert_eq!(trades.confirm(2), Ok(true));
+}
+
+#[test]
+fn test_leaving_cancels_trades_and_requests() {
+    let now = Instant::now();
+    let mut trades = TradeManager::new();
+    open_trade(&mut trades, now);
+    trades.request(3, 4, now).unwrap();
+
+    assert_eq!(trades.leave(2), Some(1));
+    assert_eq!(trades.partner(1), None);
+    assert!(trades.trades().is_empty());
+
+    assert_eq!(trades.leave(3), None);
+    assert_eq!(trades.accept(4, 3, now + Duration::from_secs(1)), Err(TradeError::NoRequest));
+}
+
+#[test]
+fn test_swap_moves_items_and_gold_both_ways() {
+    let mut first = inventory(&[(0, POTION, 5), (1, SWORD, 1)], 100);
+    let mut second = inventory(&[(2, PELT, 30)], 10);
+
+    trade::swap(&mut first, &offer(&[(0, POTION, 2), (1, SWORD, 1)], 40), &mut second, &offer(&[(2, PELT, 30)], 0)).unwrap();
+
+    assert_eq!(first.gold, 60);
+    assert_eq!(first.get(0), Some(ItemStack { item: POTION, count: 3 }));
+    assert_eq!(first.get(1), Some(ItemStack { item: PELT, count: 30 }));
+    assert_eq!(second.gold, 50);
+    assert_eq!(second.get(0), Some(ItemStack { item: POTION, count: 2 }));
+    assert_eq!(second.get(1), Some(ItemStack { item: SWORD, count: 1 }));
+    assert_eq!(second.get(2), None);
+}
+
+#[test]
+fn test_failed_swaps_change_neither_inventory() {
+    // The offered potions were used up after the offer was made
+    let mut first = inventory(&[(0, POTION, 1)], 100);
+    let mut second = inventory(&[], 10);
+    let before = (first.clone(), second.clone());
+    let result = trade::swap(&mut first, &offer(&[(0, POTION, 2)], 0), &mut second, &offer(&[], 10));
+    assert_eq!(result, Err(TradeError::OfferChanged));
+    assert_eq!((first, second), before);
+
+    // Gold spent in the meantime
+    let mut first = inventory(&[(0, POTION, 2)], 5);
+    let mut second = inventory(&[], 10);
+    let result = trade::swap(&mut first, &offer(&[(0, POTION, 2)], 20), &mut second, &offer(&[], 0));
+    assert_eq!(result, Err(TradeError::OfferChanged));
+    assert_eq!(first.gold, 5);
+
+    // No room for what the partner gives
+    let full: Vec<_> = (0..INVENTORY_SLOTS).map(|slot| (slot, SWORD, 1)).collect();
+    let mut first = inventory(&full, 0);
+    let mut second = inventory(&[(0, PELT, 3)], 10);
+    let before = (first.clone(), second.clone());
+    let result = trade::swap(&mut first, &offer(&[], 0), &mut second, &offer(&[(0, PELT, 3)], 10));
+    assert_eq!(result, Err(TradeError::NoRoom));
+    assert_eq!((first, second), before);
+}
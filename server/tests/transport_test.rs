@@ -0,0 +1,123 @@
+use shared::transport::{Packet, PacketPayload, ReliableEndpoint, RESEND_INTERVAL};
+use std::time::Instant;
+
+fn payload(n: u8) -> Vec<u8> {
+    vec![n]
+}
+
+#[test]
+fn test_reliable_in_order_delivery() {
+    let now = Instant::now();
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    for i in 0..3 {
+        let packet = sender.send_reliable(payload(i), now);
+        assert_eq!(receiver.receive(packet), vec![payload(i)]);
+    }
+}
+
+#[test]
+fn test_out_of_order_packets_are_buffered() {
+    let now = Instant::now();
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    let first = sender.send_reliable(payload(0), now);
+    let second = sender.send_reliable(payload(1), now);
+    let third = sender.send_reliable(payload(2), now);
+
+    // Third arrives first, then second: nothing can be delivered until the gap is filled
+    assert!(receiver.receive(third).is_empty());
+    assert!(receiver.receive(second).is_empty());
+
+    assert_eq!(receiver.receive(first), vec![payload(0), payload(1), payload(2)]);
+}
+
+#[test]
+fn test_duplicates_are_dropped() {
+    let now = Instant::now();
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    let packet = sender.send_reliable(payload(7), now);
+
+    assert_eq!(receiver.receive(packet.clone()).len(), 1);
+    assert!(receiver.receive(packet).is_empty(), "A resent packet must not be delivered twice");
+}
+
+#[test]
+fn test_lost_packet_is_resent_until_acked() {
+    let now = Instant::now();
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    // First transmission is lost
+    let _lost = sender.send_reliable(payload(1), now);
+    assert_eq!(sender.pending_count(), 1);
+
+    // Nothing is resent before the interval elapses
+    assert!(sender.poll(now).is_empty());
+
+    let resends = sender.poll(now + RESEND_INTERVAL);
+    assert_eq!(resends.len(), 1);
+
+    // Receiver gets the resend and acks it
+    let delivered = receiver.receive(resends.into_iter().next().unwrap());
+    assert_eq!(delivered, vec![payload(1)]);
+
+    let acks = receiver.poll(now + RESEND_INTERVAL);
+    assert_eq!(acks.len(), 1);
+    assert!(matches!(acks[0].payload, PacketPayload::Ack));
+
+    sender.receive(acks.into_iter().next().unwrap());
+    assert_eq!(sender.pending_count(), 0);
+    assert!(sender.poll(now + RESEND_INTERVAL * 4).is_empty());
+}
+
+#[test]
+fn test_selective_ack_skips_received_packets() {
+    let now = Instant::now();
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    let _lost = sender.send_reliable(payload(0), now);
+    let arrived = sender.send_reliable(payload(1), now);
+    receiver.receive(arrived);
+
+    // Ack says "nothing contiguous yet, but seq 1 arrived"
+    let ack = receiver.poll(now).remove(0);
+    sender.receive(ack);
+
+    let resends = sender.poll(now + RESEND_INTERVAL);
+    assert_eq!(resends.len(), 1, "Only the missing packet should be resent");
+    assert!(matches!(resends[0].payload, PacketPayload::Reliable { seq: 0, .. }));
+}
+
+#[test]
+fn test_unreliable_is_delivered_immediately() {
+    let mut sender = ReliableEndpoint::new();
+    let mut receiver = ReliableEndpoint::new();
+
+    let packet = sender.send_unreliable(payload(9));
+    assert_eq!(receiver.receive(packet), vec![payload(9)]);
+    assert_eq!(sender.pending_count(), 0, "Unreliable packets are never resent");
+}
+
+#[test]
+fn test_typed_round_trip() {
+    let now = Instant::now();
+    let mut client = ReliableEndpoint::new();
+    let mut server = ReliableEndpoint::new();
+
+    let message = shared::ClientMessage::Disconnect;
+    let bytes = client.send(&message, message.is_reliable(), now).unwrap();
+
+    let received: Vec<shared::ClientMessage> = server.receive_bytes(&bytes);
+    assert!(matches!(received.as_slice(), [shared::ClientMessage::Disconnect]));
+
+    // Garbage datagrams are ignored
+    let garbage: Vec<shared::ClientMessage> = server.receive_bytes(&[0xff, 0x01]);
+    assert!(garbage.is_empty());
+    assert!(Packet::from_bytes(&[]).is_none());
+}
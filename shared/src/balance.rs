@@ -0,0 +1,501 @@
+// Balance tables: skill numbers, per-class stat growth and regeneration, sprinting, the
+// interest radius, mobs, death penalty and respawn points, item definitions, loot tables
+// and merchants.
+//
+// The tables live in `data/balance.ron` of this crate. A copy is compiled in as the
+// default, so `SkillId::info()` and `calculate_stats_for_level` always have numbers.
+// At startup both sides load the file from disk (validated, a broken file is an error)
+// and install it. The server watches the file and sends the tables when it changes, so
+// balance tweaks need no rebuild. The encoded tables outgrow a datagram, so they travel
+// as `ServerMessage::BalanceChunk` pieces that `BalanceAssembler` puts back together.
+
+use crate::items::{ItemEffect, ItemId, ItemInfo, ItemType, LootDrop, LootTable, ShopId, ShopInfo};
+use crate::{CharacterClass, MobInfo, MobType, SkillEffect, SkillId, SkillInfo};
+use bevy::prelude::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+/// Layout version of the balance file. Files with another version are rejected.
+pub const BALANCE_VERSION: u32 = 9;
+
+/// Where server and client look for the balance file, relative to the workspace root
+pub const DEFAULT_BALANCE_PATH: &str = "shared/data/balance.ron";
+
+/// The balance file as it was at compile time
+const BUILTIN_BALANCE: &str = include_str!("../data/balance.ron");
+
+/// Bytes of encoded tables per `ServerMessage::BalanceChunk`, well below a datagram
+pub const BALANCE_CHUNK_SIZE: usize = 1024;
+
+/// Most chunks the encoded tables may take
+pub const MAX_BALANCE_CHUNKS: usize = 256;
+
+/// Stats at level 1, the gain per level and regeneration of a class
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClassBalance {
+    pub base_health: f32,
+    pub base_mana: f32,
+    pub base_stamina: f32,
+    pub health_per_level: f32,
+    pub mana_per_level: f32,
+    pub stamina_per_level: f32,
+    pub regen: RegenRates,         // Out of combat
+    pub combat_regen: RegenRates,  // While in combat
+}
+
+/// Regeneration per second as a fraction of the maximum (0.01 = 1% per second)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RegenRates {
+    pub health: f32,
+    pub mana: f32,
+    pub stamina: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SprintBalance {
+    pub speed_multiplier: f32,    // Times PLAYER_MOVE_SPEED
+    pub stamina_per_second: f32,  // Drained while sprinting at full speed
+}
+
+/// What dying costs and where players come back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeathBalance {
+    pub experience_loss: f32,  // Fraction of the experience the current level needs
+    pub respawn_health: f32,   // Fraction of the maximum after respawning
+    pub respawn_mana: f32,
+    pub respawn_points: Vec<RespawnPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RespawnPoint {
+    pub name: String,
+    pub position: Vec3,
+}
+
+impl DeathBalance {
+    /// Respawn point closest to where a player died
+    pub fn nearest_respawn_point(&self, position: Vec3) -> Option<&RespawnPoint> {
+        self.respawn_points.iter()
+            .min_by(|a, b| a.position.distance_squared(position).total_cmp(&b.position.distance_squared(position)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceTables {
+    pub version: u32,
+    pub sprint: SprintBalance,
+    pub interest_radius: f32,  // Clients are only told about entities within this distance (meters)
+    pub death: DeathBalance,
+    pub classes: HashMap<CharacterClass, ClassBalance>,
+    pub skills: HashMap<SkillId, SkillInfo>,
+    pub mobs: HashMap<MobType, MobInfo>,
+    pub items: HashMap<ItemId, ItemInfo>,
+    pub loot: HashMap<MobType, LootTable>,  // Mobs without a table drop nothing
+    pub shops: HashMap<ShopId, ShopInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    Io(String),
+    Parse(String),
+    Version { found: u32, expected: u32 },
+    MissingClass(CharacterClass),
+    MissingSkill(SkillId),
+    MissingMob(MobType),
+    Invalid(String),
+    TooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceError::Io(e) => write!(f, "Cannot read balance file: {}", e),
+            BalanceError::Parse(e) => write!(f, "Invalid balance file: {}", e),
+            BalanceError::Version { found, expected } => {
+                write!(f, "Balance file has version {}, expected {}", found, expected)
+            }
+            BalanceError::MissingClass(class) => write!(f, "No stats for class {:?}", class),
+            BalanceError::MissingSkill(skill) => write!(f, "No entry for skill {:?}", skill),
+            BalanceError::MissingMob(mob) => write!(f, "No entry for mob {:?}", mob),
+            BalanceError::Invalid(reason) => write!(f, "Invalid balance value: {}", reason),
+            BalanceError::TooLarge { size, max } => {
+                write!(f, "Balance tables take {} bytes encoded, at most {} can be sent", size, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+impl BalanceTables {
+    /// The compiled-in tables
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_BALANCE).unwrap_or_else(|e| panic!("Built-in balance tables: {}", e))
+    }
+
+    /// Read and validate a balance file
+    pub fn load(path: &Path) -> Result<Self, BalanceError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| BalanceError::Io(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&text)
+    }
+
+    /// Parse and validate the RON text of a balance file
+    pub fn parse(text: &str) -> Result<Self, BalanceError> {
+        let tables: Self = ron::from_str(text).map_err(|e| BalanceError::Parse(e.to_string()))?;
+        tables.validate()?;
+        Ok(tables)
+    }
+
+    /// Check the version, that every class, skill and mob has an entry and that all
+    /// numbers are in range
+    pub fn validate(&self) -> Result<(), BalanceError> {
+        if self.version != BALANCE_VERSION {
+            return Err(BalanceError::Version { found: self.version, expected: BALANCE_VERSION });
+        }
+
+        check_non_negative("sprint.stamina_per_second", self.sprint.stamina_per_second)?;
+        if !self.sprint.speed_multiplier.is_finite() || self.sprint.speed_multiplier < 1.0 {
+            return Err(BalanceError::Invalid("sprint.speed_multiplier must be at least 1".to_string()));
+        }
+        if !self.interest_radius.is_finite() || self.interest_radius <= 0.0 {
+            return Err(BalanceError::Invalid("interest_radius must be above 0".to_string()));
+        }
+
+        let death = &self.death;
+        for (field, value) in [
+            ("death.experience_loss", death.experience_loss),
+            ("death.respawn_health", death.respawn_health),
+            ("death.respawn_mana", death.respawn_mana),
+        ] {
+            check_non_negative(field, value)?;
+            if value > 1.0 {
+                return Err(BalanceError::Invalid(format!("{} must be between 0 and 1", field)));
+            }
+        }
+        if death.respawn_health <= 0.0 {
+            return Err(BalanceError::Invalid("death.respawn_health must be above 0".to_string()));
+        }
+        if death.respawn_points.is_empty() {
+            return Err(BalanceError::Invalid("death.respawn_points must not be empty".to_string()));
+        }
+        for point in &death.respawn_points {
+            if point.name.trim().is_empty() || !point.position.is_finite() {
+                return Err(BalanceError::Invalid(format!("death.respawn_points: invalid point {:?}", point)));
+            }
+        }
+
+        for class in CharacterClass::ALL {
+            let stats = self.classes.get(&class).ok_or(BalanceError::MissingClass(class))?;
+            let values = [
+                ("base_health", stats.base_health),
+                ("base_mana", stats.base_mana),
+                ("base_stamina", stats.base_stamina),
+                ("health_per_level", stats.health_per_level),
+                ("mana_per_level", stats.mana_per_level),
+                ("stamina_per_level", stats.stamina_per_level),
+                ("regen.health", stats.regen.health),
+                ("regen.mana", stats.regen.mana),
+                ("regen.stamina", stats.regen.stamina),
+                ("combat_regen.health", stats.combat_regen.health),
+                ("combat_regen.mana", stats.combat_regen.mana),
+                ("combat_regen.stamina", stats.combat_regen.stamina),
+            ];
+            for (field, value) in values {
+                check_non_negative(&format!("{:?}.{}", class, field), value)?;
+            }
+            if stats.base_health <= 0.0 {
+                return Err(BalanceError::Invalid(format!("{:?}.base_health must be above 0", class)));
+            }
+        }
+
+        for skill in SkillId::ALL {
+            let info = self.skills.get(&skill).ok_or(BalanceError::MissingSkill(skill))?;
+            validate_skill(skill, info)?;
+        }
+
+        for mob in MobType::ALL {
+            let info = self.mobs.get(&mob).ok_or(BalanceError::MissingMob(mob))?;
+            validate_mob(mob, info)?;
+        }
+
+        for (&id, info) in &self.items {
+            validate_item(id, info)?;
+        }
+
+        for (&mob, table) in &self.loot {
+            self.validate_loot(mob, table)?;
+        }
+
+        for (&id, shop) in &self.shops {
+            self.validate_shop(id, shop)?;
+        }
+
+        let size = self.encode()?.len();
+        let max = BALANCE_CHUNK_SIZE * MAX_BALANCE_CHUNKS;
+        if size > max {
+            return Err(BalanceError::TooLarge { size, max });
+        }
+        Ok(())
+    }
+
+    /// The encoded tables in pieces of at most `BALANCE_CHUNK_SIZE` bytes
+    pub fn to_chunks(&self) -> Result<Vec<Vec<u8>>, BalanceError> {
+        Ok(self.encode()?.chunks(BALANCE_CHUNK_SIZE).map(<[u8]>::to_vec).collect())
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, BalanceError> {
+        bincode::serialize(self).map_err(|e| BalanceError::Parse(e.to_string()))
+    }
+
+    pub fn skill(&self, skill: SkillId) -> &SkillInfo {
+        // Validation guarantees an entry for every skill
+        &self.skills[&skill]
+    }
+
+    pub fn class(&self, class: CharacterClass) -> &ClassBalance {
+        &self.classes[&class]
+    }
+
+    pub fn mob(&self, mob: MobType) -> &MobInfo {
+        &self.mobs[&mob]
+    }
+
+    pub fn item(&self, item: ItemId) -> Option<&ItemInfo> {
+        self.items.get(&item)
+    }
+
+    pub fn loot(&self, mob: MobType) -> Option<&LootTable> {
+        self.loot.get(&mob)
+    }
+
+    pub fn shop(&self, shop: ShopId) -> Option<&ShopInfo> {
+        self.shops.get(&shop)
+    }
+
+    /// Loot tables may only name items that exist, in counts that fit a stack
+    fn validate_loot(&self, mob: MobType, table: &LootTable) -> Result<(), BalanceError> {
+        let invalid = |reason: String| Err(BalanceError::Invalid(format!("{:?}.loot: {}", mob, reason)));
+        if table.gold.0 > table.gold.1 {
+            return invalid("gold: least is above most".to_string());
+        }
+        if table.rolls > 0 && table.entries.iter().all(|entry| entry.weight == 0) {
+            return invalid("rolls need entries with a weight above 0".to_string());
+        }
+        for entry in &table.entries {
+            let LootDrop::Item(item, least, most) = entry.drop else { continue };
+            let Some(info) = self.item(item) else {
+                return invalid(format!("unknown item {}", item.0));
+            };
+            if least < 1 || least > most || most > info.stack_size {
+                return invalid(format!("item {} count must be between 1 and its stack size", item.0));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merchants sell items that exist, each once, and never below what they pay for
+    /// it (buying and selling back would make gold)
+    fn validate_shop(&self, id: ShopId, shop: &ShopInfo) -> Result<(), BalanceError> {
+        let invalid = |reason: String| Err(BalanceError::Invalid(format!("Shop {} ({}): {}", id.0, shop.name, reason)));
+        if shop.name.trim().is_empty() {
+            return invalid("name is empty".to_string());
+        }
+        if !shop.position.is_finite() {
+            return invalid("position must be finite".to_string());
+        }
+        if !shop.restock.is_finite() || shop.restock <= 0.0 {
+            return invalid("restock must be above 0".to_string());
+        }
+        for (index, listing) in shop.listings.iter().enumerate() {
+            let Some(info) = self.item(listing.item) else {
+                return invalid(format!("unknown item {}", listing.item.0));
+            };
+            if shop.listings[..index].iter().any(|other| other.item == listing.item) {
+                return invalid(format!("item {} is listed twice", listing.item.0));
+            }
+            if listing.price == 0 || listing.price < info.value {
+                return invalid(format!("item {} must cost at least its value ({})", listing.item.0, info.value));
+            }
+            if listing.stock == Some(0) {
+                return invalid(format!("item {}: stock must be at least 1", listing.item.0));
+            }
+        }
+        for (index, item_type) in shop.buys.iter().enumerate() {
+            if shop.buys[..index].contains(item_type) {
+                return invalid(format!("buys {:?} twice", item_type));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_skill(skill: SkillId, info: &SkillInfo) -> Result<(), BalanceError> {
+    if info.name.trim().is_empty() {
+        return Err(BalanceError::Invalid(format!("{:?}.name is empty", skill)));
+    }
+    if info.required_level < 1 {
+        return Err(BalanceError::Invalid(format!("{:?}.required_level must be at least 1", skill)));
+    }
+    check_non_negative(&format!("{:?}.cooldown", skill), info.cooldown)?;
+    check_non_negative(&format!("{:?}.mana_cost", skill), info.mana_cost)?;
+    check_non_negative(&format!("{:?}.damage_multiplier", skill), info.damage_multiplier)?;
+    check_non_negative(&format!("{:?}.range", skill), info.range)?;
+
+    for value in effect_values(&info.effect) {
+        check_non_negative(&format!("{:?}.effect", skill), value)?;
+    }
+
+    // Values that are fractions of health or damage
+    let fraction = match info.effect {
+        SkillEffect::DamageReduction(amount, _) => Some(amount),
+        SkillEffect::DefenseReduction(amount, _, _) => Some(amount),
+        SkillEffect::ExecuteDamage(threshold, _) => Some(threshold),
+        SkillEffect::Lifesteal(fraction, _) => Some(fraction),
+        SkillEffect::ChainLightning(_, _, falloff) => Some(falloff),
+        SkillEffect::Resurrect(fraction) => Some(fraction),
+        _ => None,
+    };
+    if fraction.is_some_and(|value| value > 1.0) {
+        return Err(BalanceError::Invalid(format!("{:?}.effect: fractions must be between 0 and 1", skill)));
+    }
+    Ok(())
+}
+
+fn validate_mob(mob: MobType, info: &MobInfo) -> Result<(), BalanceError> {
+    if info.name.trim().is_empty() {
+        return Err(BalanceError::Invalid(format!("{:?}.name is empty", mob)));
+    }
+    if info.level < 1 {
+        return Err(BalanceError::Invalid(format!("{:?}.level must be at least 1", mob)));
+    }
+    if info.experience < 0 {
+        return Err(BalanceError::Invalid(format!("{:?}.experience must not be negative", mob)));
+    }
+    check_non_negative(&format!("{:?}.damage", mob), info.damage)?;
+    check_non_negative(&format!("{:?}.aggro_radius", mob), info.aggro_radius)?;
+    check_non_negative(&format!("{:?}.move_speed", mob), info.move_speed)?;
+    if !info.max_health.is_finite() || info.max_health <= 0.0 {
+        return Err(BalanceError::Invalid(format!("{:?}.max_health must be above 0", mob)));
+    }
+    Ok(())
+}
+
+fn validate_item(id: ItemId, info: &ItemInfo) -> Result<(), BalanceError> {
+    let invalid = |reason: &str| Err(BalanceError::Invalid(format!("Item {} ({}): {}", id.0, info.name, reason)));
+    if info.name.trim().is_empty() {
+        return invalid("name is empty");
+    }
+    if info.stack_size < 1 {
+        return invalid("stack_size must be at least 1");
+    }
+    if info.item_type.is_equipment() && info.stack_size != 1 {
+        return invalid("equipment does not stack");
+    }
+    if info.required_level < 1 {
+        return invalid("required_level must be at least 1");
+    }
+    let stats = info.stats;
+    for value in [stats.health, stats.mana, stats.stamina, stats.attack, stats.defense] {
+        check_non_negative(&format!("Item {}.stats", id.0), value)?;
+    }
+
+    let consumable = info.item_type == ItemType::Consumable;
+    match info.use_effect {
+        Some(ItemEffect::RestoreHealth(amount) | ItemEffect::RestoreMana(amount)) if consumable && !(amount.is_finite() && amount > 0.0) => {
+            return invalid("use_effect must restore more than 0");
+        }
+        Some(_) if consumable => {}
+        Some(_) => return invalid("only consumables have a use_effect"),
+        None if consumable => return invalid("consumables need a use_effect"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Every number of an effect
+fn effect_values(effect: &SkillEffect) -> Vec<f32> {
+    match *effect {
+        SkillEffect::None => vec![],
+        SkillEffect::AreaDamage(radius) => vec![radius],
+        SkillEffect::DamageReduction(amount, duration) => vec![amount, duration],
+        SkillEffect::Taunt(radius) => vec![radius],
+        SkillEffect::Stun(duration, radius) => vec![duration, radius],
+        SkillEffect::CrowdControlImmunity(duration) => vec![duration],
+        SkillEffect::Revive(health, duration) => vec![health, duration],
+        SkillEffect::DefenseReduction(amount, duration, radius) => vec![amount, duration, radius],
+        SkillEffect::ExecuteDamage(threshold, bonus) => vec![threshold, bonus],
+        SkillEffect::AttackSpeedBuff(amount, duration) => vec![amount, duration],
+        SkillEffect::DashStun(distance, duration) => vec![distance, duration],
+        SkillEffect::Heal(power, radius) => vec![power, radius],
+        SkillEffect::HealOverTime(power, duration) => vec![power, duration],
+        SkillEffect::DamageOverTime(damage, duration) => vec![damage, duration],
+        SkillEffect::Lifesteal(fraction, radius) => vec![fraction, radius],
+        SkillEffect::Invisibility(duration) => vec![duration],
+        SkillEffect::TeleportBehind(distance) => vec![distance],
+        SkillEffect::ChainLightning(_, radius, falloff) => vec![radius, falloff],
+        SkillEffect::Resurrect(fraction) => vec![fraction],
+        SkillEffect::DamageBuff(amount, duration) => vec![amount, duration],
+        SkillEffect::LeapBack(distance) => vec![distance],
+        SkillEffect::Pierce(length) => vec![length],
+    }
+}
+
+fn check_non_negative(field: &str, value: f32) -> Result<(), BalanceError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(BalanceError::Invalid(format!("{} must be a non-negative number, got {}", field, value)));
+    }
+    Ok(())
+}
+
+/// Puts the chunks of the tables back together. They arrive in order on the reliable
+/// channel; chunk 0 starts new tables.
+#[derive(Debug, Default)]
+pub struct BalanceAssembler {
+    data: Vec<u8>,
+    next: usize,
+}
+
+impl BalanceAssembler {
+    /// Add the chunk `index` of `total`. Returns the decoded tables after the last one.
+    pub fn add(&mut self, index: u16, total: u16, data: &[u8]) -> Option<Result<BalanceTables, BalanceError>> {
+        let (index, total) = (index as usize, total as usize);
+        if index == 0 {
+            self.data.clear();
+            self.next = 0;
+        }
+        // Chunks of tables whose start we missed, or more than any valid tables take
+        if index != self.next || total > MAX_BALANCE_CHUNKS || data.len() > BALANCE_CHUNK_SIZE {
+            return None;
+        }
+        self.data.extend_from_slice(data);
+        self.next += 1;
+        if self.next < total {
+            return None;
+        }
+
+        self.next = 0;
+        let data = std::mem::take(&mut self.data);
+        Some(bincode::deserialize(&data).map_err(|e| BalanceError::Parse(e.to_string())))
+    }
+}
+
+fn active() -> &'static RwLock<Arc<BalanceTables>> {
+    static ACTIVE: OnceLock<RwLock<Arc<BalanceTables>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| RwLock::new(Arc::new(BalanceTables::builtin())))
+}
+
+/// The tables currently in use
+pub fn current() -> Arc<BalanceTables> {
+    active().read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Replace the tables in use. Invalid tables are rejected and the old ones stay.
+pub fn install(tables: BalanceTables) -> Result<(), BalanceError> {
+    tables.validate()?;
+    *active().write().unwrap_or_else(PoisonError::into_inner) = Arc::new(tables);
+    Ok(())
+}
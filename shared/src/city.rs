@@ -0,0 +1,122 @@
+// Static layout of the city: which buildings stand where, and the ground they block.
+//
+// The client spawns its building models and Rapier colliders from this; the server
+// builds its navigation grid from the same footprints, so mobs walk around exactly
+// the walls players collide with.
+
+use bevy::prelude::{Quat, Vec2, Vec3};
+
+/// Building types of the Medieval Village Kit templates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildingKind {
+    SmallHouse,
+    MediumHouse,
+    Tavern,
+    Church,
+    Smithy,
+    MarketStall,
+    Tower,
+}
+
+impl BuildingKind {
+    /// Half-extents of the building's box collider; it sits on the ground
+    pub fn collider_half_extents(self) -> Vec3 {
+        match self {
+            BuildingKind::SmallHouse => Vec3::new(1.2, 1.5, 1.2),  // Slightly larger than 2x3x2
+            BuildingKind::MediumHouse => Vec3::new(2.2, 1.5, 2.2),
+            BuildingKind::Tavern => Vec3::new(3.2, 1.5, 2.2),
+            BuildingKind::Church => Vec3::new(3.2, 1.5, 3.2),
+            BuildingKind::Smithy => Vec3::new(2.2, 1.5, 2.2),
+            BuildingKind::MarketStall => Vec3::new(1.2, 1.5, 1.2),
+            BuildingKind::Tower => Vec3::new(2.2, 3.0, 2.2),  // Tall building
+        }
+    }
+}
+
+/// One building of the city
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildingPlacement {
+    pub kind: BuildingKind,
+    pub position: Vec3,
+    pub rotation: f32,  // Around the Y axis (radians)
+}
+
+impl BuildingPlacement {
+    pub fn footprint(&self) -> Footprint {
+        let half_extents = self.kind.collider_half_extents();
+        Footprint::Box {
+            center: self.position,
+            half_extents: Vec2::new(half_extents.x, half_extents.z),
+            rotation: self.rotation,
+        }
+    }
+}
+
+/// Central fountain on the plaza (a cylinder collider)
+pub const FOUNTAIN_POSITION: Vec3 = Vec3::new(-6.0, 0.0, 6.0);
+pub const FOUNTAIN_RADIUS: f32 = 2.5;
+pub const FOUNTAIN_HEIGHT: f32 = 3.0;
+
+/// Ground area an obstacle covers, on the XZ plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Footprint {
+    /// Rotated rectangle
+    Box { center: Vec3, half_extents: Vec2, rotation: f32 },
+    Circle { center: Vec3, radius: f32 },
+}
+
+impl Footprint {
+    /// Whether `point` (height ignored) lies within `margin` of the obstacle
+    pub fn contains(&self, point: Vec3, margin: f32) -> bool {
+        match *self {
+            Footprint::Box { center, half_extents, rotation } => {
+                // Into the building's frame: undo its rotation
+                let local = Quat::from_rotation_y(-rotation) * Vec3::new(point.x - center.x, 0.0, point.z - center.z);
+                local.x.abs() <= half_extents.x + margin && local.z.abs() <= half_extents.y + margin
+            }
+            Footprint::Circle { center, radius } => {
+                Vec2::new(point.x - center.x, point.z - center.z).length() <= radius + margin
+            }
+        }
+    }
+}
+
+/// All buildings around the central plaza (40x40m). Players spawn at (0,1,0).
+pub fn buildings() -> Vec<BuildingPlacement> {
+    use BuildingKind::*;
+    let building = |kind, x, z, rotation| BuildingPlacement { kind, position: Vec3::new(x, 0.0, z), rotation };
+    vec![
+        // North side
+        building(Tavern, -8.0, 28.0, 0.15),         // "The Golden Dragon"
+        building(Smithy, -28.0, 28.0, -0.2),        // "Ironforge"
+        building(MediumHouse, 24.0, 30.0, 0.3),
+        building(SmallHouse, 32.0, 26.0, -0.4),
+        building(Tower, 35.0, 35.0, 0.0),           // Watchtower
+        // East side
+        building(MediumHouse, 30.0, 10.0, 0.1),
+        building(SmallHouse, 31.0, -8.0, -0.25),
+        building(MediumHouse, 27.0, -20.0, 0.5),
+        // South side
+        building(Smithy, 2.0, -30.0, -0.1),         // "Blackforge"
+        building(SmallHouse, 18.0, -31.0, -0.3),
+        building(SmallHouse, 30.0, -27.0, 0.2),
+        // West side
+        building(Church, -32.0, 8.0, 0.08),         // "St. Michael's"
+        building(MediumHouse, -30.0, -16.0, -0.15),
+        building(SmallHouse, -34.0, -31.0, 0.45),
+        building(Tavern, -36.0, 24.0, -0.3),        // "The Rusty Sword"
+        // Market stalls on the plaza
+        building(MarketStall, 22.0, 4.0, 0.6),
+        building(MarketStall, 20.0, -3.0, -0.4),
+        building(MarketStall, 18.5, 10.0, 0.2),
+    ]
+}
+
+/// Every obstacle on the ground: buildings and the fountain. Props (crates, fences)
+/// have no colliders and are not included.
+pub fn footprints() -> Vec<Footprint> {
+    buildings().iter()
+        .map(BuildingPlacement::footprint)
+        .chain(std::iter::once(Footprint::Circle { center: FOUNTAIN_POSITION, radius: FOUNTAIN_RADIUS }))
+        .collect()
+}
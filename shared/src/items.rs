@@ -0,0 +1,292 @@
+// Items: definitions (numbers live in the balance tables, like skills and mobs), the
+// stacks characters carry in their inventory slots, loot and merchants.
+
+use crate::balance;
+use bevy::prelude::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Slots in a character's inventory
+pub const INVENTORY_SLOTS: usize = 40;
+
+/// Item definition ID, as stored in the database. Definitions come from the balance
+/// tables, so an ID may be unknown after an item was removed from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub u32);
+
+impl ItemId {
+    /// Definition from the active balance tables (see `balance`)
+    pub fn info(&self) -> Option<ItemInfo> {
+        balance::current().item(*self).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ItemType {
+    Weapon,
+    Helmet,
+    Armor,
+    Boots,
+    Accessory,   // Rings and amulets
+    Consumable,  // Used up by `UseItem`
+    Material,    // Loot to sell or craft with
+}
+
+impl ItemType {
+    /// Worn rather than carried: never stacks
+    pub fn is_equipment(self) -> bool {
+        self.equipment_slot().is_some()
+    }
+
+    /// Where the item goes when equipped
+    pub fn equipment_slot(self) -> Option<EquipmentSlot> {
+        match self {
+            ItemType::Weapon => Some(EquipmentSlot::Weapon),
+            ItemType::Helmet => Some(EquipmentSlot::Helmet),
+            ItemType::Armor => Some(EquipmentSlot::Armor),
+            ItemType::Boots => Some(EquipmentSlot::Boots),
+            ItemType::Accessory => Some(EquipmentSlot::Accessory),
+            ItemType::Consumable | ItemType::Material => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemType::Weapon => "Waffe",
+            ItemType::Helmet => "Helm",
+            ItemType::Armor => "Rüstung",
+            ItemType::Boots => "Stiefel",
+            ItemType::Accessory => "Schmuck",
+            ItemType::Consumable => "Verbrauchsgut",
+            ItemType::Material => "Material",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rarity::Common => "Gewöhnlich",
+            Rarity::Uncommon => "Ungewöhnlich",
+            Rarity::Rare => "Selten",
+            Rarity::Epic => "Episch",
+            Rarity::Legendary => "Legendär",
+        }
+    }
+}
+
+/// Bonuses an item gives while equipped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemStats {
+    pub health: f32,
+    pub mana: f32,
+    pub stamina: f32,
+    pub attack: f32,
+    pub defense: f32,
+}
+
+impl std::ops::AddAssign for ItemStats {
+    fn add_assign(&mut self, other: Self) {
+        self.health += other.health;
+        self.mana += other.mana;
+        self.stamina += other.stamina;
+        self.attack += other.attack;
+        self.defense += other.defense;
+    }
+}
+
+/// What using a consumable does
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ItemEffect {
+    RestoreHealth(f32),
+    RestoreMana(f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemInfo {
+    pub name: String,
+    pub description: String,
+    pub item_type: ItemType,
+    pub rarity: Rarity,
+    pub stack_size: u32,      // Most items in one slot
+    pub required_level: i32,  // To use or equip it
+    #[serde(default)]
+    pub stats: ItemStats,
+    #[serde(default)]
+    pub use_effect: Option<ItemEffect>,  // Consumables only
+    pub value: u32,           // Gold a merchant pays for one
+}
+
+/// Some number of one item in an inventory slot (never zero)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+/// A pile lying on the ground: some gold or a stack of items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Loot {
+    Gold(u64),
+    Item(ItemStack),
+}
+
+/// What a mob drops when it dies (see `balance`): gold between the two amounts, plus
+/// `rolls` picks from the weighted entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootTable {
+    pub gold: (u32, u32),  // Least and most
+    pub rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootEntry {
+    pub weight: u32,  // Chance of this entry is weight / sum of all weights
+    pub drop: LootDrop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LootDrop {
+    Nothing,
+    Item(ItemId, u32, u32),  // item, least and most
+    Equipment(Rarity),       // Any equipment of this rarity the mob's level allows
+}
+
+/// Merchant ID, as stored with every shop transaction (never reuse one)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ShopId(pub u32);
+
+impl ShopId {
+    /// Merchant from the active balance tables (see `balance`)
+    pub fn info(&self) -> Option<ShopInfo> {
+        balance::current().shop(*self).cloned()
+    }
+}
+
+/// A merchant NPC, what it sells and what it buys. Merchants buy items of their
+/// `buys` types with a value above 0 for that value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShopInfo {
+    pub name: String,
+    pub position: Vec3,    // Where the NPC stands
+    pub restock: f32,      // Seconds until one more of a limited listing is back
+    pub listings: Vec<ShopListing>,
+    pub buys: Vec<ItemType>,
+}
+
+impl ShopInfo {
+    /// Whether the merchant takes this item off a player's hands
+    pub fn buys_item(&self, info: &ItemInfo) -> bool {
+        info.value > 0 && self.buys.contains(&info.item_type)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShopListing {
+    pub item: ItemId,
+    pub price: u32,            // Gold for one
+    #[serde(default)]
+    pub stock: Option<u32>,    // Most the merchant has at once; None = unlimited
+}
+
+/// One player's side of a trade between two players
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeOffer {
+    pub items: Vec<(u16, ItemStack)>,  // (slot in the offering player's inventory, stack)
+    pub gold: u64,
+    pub locked: bool,     // The offer can't change any more
+    pub confirmed: bool,  // Agreed to the swap after both offers were locked
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EquipmentSlot {
+    Weapon,
+    Helmet,
+    Armor,
+    Boots,
+    Accessory,
+}
+
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 5] = [
+        EquipmentSlot::Weapon,
+        EquipmentSlot::Helmet,
+        EquipmentSlot::Armor,
+        EquipmentSlot::Boots,
+        EquipmentSlot::Accessory,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "Waffe",
+            EquipmentSlot::Helmet => "Kopf",
+            EquipmentSlot::Armor => "Körper",
+            EquipmentSlot::Boots => "Füße",
+            EquipmentSlot::Accessory => "Schmuck",
+        }
+    }
+
+    /// For storing in the database
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "Weapon",
+            EquipmentSlot::Helmet => "Helmet",
+            EquipmentSlot::Armor => "Armor",
+            EquipmentSlot::Boots => "Boots",
+            EquipmentSlot::Accessory => "Accessory",
+        }
+    }
+
+    pub fn from_string(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.as_str() == s)
+    }
+}
+
+/// What a character wears: at most one item per equipment slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Equipment {
+    items: [Option<ItemId>; EquipmentSlot::ALL.len()],
+}
+
+impl Equipment {
+    pub fn get(&self, slot: EquipmentSlot) -> Option<ItemId> {
+        self.items[slot.index()]
+    }
+
+    /// Put an item into a slot (or empty it). Returns what was there before.
+    pub fn set(&mut self, slot: EquipmentSlot, item: Option<ItemId>) -> Option<ItemId> {
+        std::mem::replace(&mut self.items[slot.index()], item)
+    }
+
+    /// Equipped items with their slots
+    pub fn iter(&self) -> impl Iterator<Item = (EquipmentSlot, ItemId)> + '_ {
+        EquipmentSlot::ALL.into_iter().filter_map(|slot| self.get(slot).map(|item| (slot, item)))
+    }
+
+    /// Sum of the bonuses of everything equipped (unknown items give nothing)
+    pub fn stats(&self) -> ItemStats {
+        let mut stats = ItemStats::default();
+        for (_, item) in self.iter() {
+            if let Some(info) = item.info() {
+                stats += info.stats;
+            }
+        }
+        stats
+    }
+}
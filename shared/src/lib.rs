@@ -1,12 +1,24 @@
 use serde::{Deserialize, Serialize};
 use bevy::prelude::*;
+use std::time::Duration;
 
 // Re-export bevy for server use
 pub use bevy;
 
+pub mod transport;
+pub mod snapshot;
+pub mod balance;
+pub mod city;
+pub mod items;
+
 // Network configuration
 pub const PROTOCOL_ID: u64 = 1000;
 pub const SERVER_ADDR: &str = "127.0.0.1:5000";
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);   // Keepalive in both directions
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);  // Silence before a peer is considered gone
+
+// Movement
+pub const PLAYER_MOVE_SPEED: f32 = 5.0;  // Units per second - client movement and server validation
 
 // Character data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,7 +31,7 @@ pub struct CharacterData {
     pub specialization: Option<Specialization>,  // Unlocked at level 5
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
 pub enum CharacterClass {
     #[default]
     Krieger,
@@ -29,6 +41,13 @@ pub enum CharacterClass {
 }
 
 impl CharacterClass {
+    pub const ALL: [CharacterClass; 4] = [
+        CharacterClass::Krieger,
+        CharacterClass::Ninja,
+        CharacterClass::Sura,
+        CharacterClass::Schamane,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             CharacterClass::Krieger => "Krieger",
@@ -210,129 +229,74 @@ pub enum SkillId {
 }
 
 impl SkillId {
+    pub const ALL: [SkillId; 40] = [
+        SkillId::Schildwall, SkillId::Provokation, SkillId::Erderschuetterung, SkillId::EiserneHaut, SkillId::LetzteBastion,
+        SkillId::Wirbelsturm, SkillId::Kriegsschrei, SkillId::Hinrichtung, SkillId::Raserei, SkillId::ToedlicherStoss,
+        SkillId::Praezisionsschuss, SkillId::Pfeilhagel, SkillId::Giftpfeil, SkillId::Rueckwaertssprung, SkillId::Durchschlag,
+        SkillId::Schattenschritt, SkillId::Dolchwirbel, SkillId::ToedlicheGifte, SkillId::Unsichtbarkeit, SkillId::Gnadenstoss,
+        SkillId::Flammenschlag, SkillId::Seelenraub, SkillId::Zauberklinge, SkillId::DunklerSchutz, SkillId::DaemonischeVerwandlung,
+        SkillId::Blutgier, SkillId::Seelenketten, SkillId::Vampirschlag, SkillId::Furchtaura, SkillId::Seelenernte,
+        SkillId::HeilendeWelle, SkillId::Naturschild, SkillId::Erneuerung, SkillId::SegnungDerNatur, SkillId::Wiedergeburt,
+        SkillId::Blitzschlag, SkillId::Kettenblitz, SkillId::Tornado, SkillId::Erdspiesse, SkillId::ZornDerElemente,
+    ];
+
+    /// Numbers for this skill from the active balance tables (see `balance`)
     pub fn info(&self) -> SkillInfo {
-        match self {
-            // Leibwächter Skills
-            SkillId::Schildwall => SkillInfo {
-                name: "Schildwall",
-                description: "Reduziert eingehenden Schaden um 50% für 5 Sekunden",
-                cooldown: 15.0,
-                mana_cost: 20.0,
-                required_level: 5,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::DamageReduction(0.5, 5.0),
-            },
-            SkillId::Provokation => SkillInfo {
-                name: "Provokation",
-                description: "Zwingt alle Monster im Umkreis (10m) dich anzugreifen",
-                cooldown: 10.0,
-                mana_cost: 25.0,
-                required_level: 10,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::Taunt(10.0),
-            },
-            SkillId::Erderschuetterung => SkillInfo {
-                name: "Erderschütterung",
-                description: "Schlägt auf den Boden, betäubt Monster im Umkreis (5m) für 2s",
-                cooldown: 20.0,
-                mana_cost: 40.0,
-                required_level: 15,
-                damage_multiplier: 1.5,
-                effect: SkillEffect::Stun(2.0, 5.0),
-            },
-            SkillId::EiserneHaut => SkillInfo {
-                name: "Eiserne Haut",
-                description: "Immun gegen Crowd Control für 3s",
-                cooldown: 30.0,
-                mana_cost: 50.0,
-                required_level: 25,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::CrowdControlImmunity(3.0),
-            },
-            SkillId::LetzteBastion => SkillInfo {
-                name: "Letzte Bastion",
-                description: "Bei tödlichem Schaden: Überlebt mit 1 HP, +100% Verteidigung für 5s",
-                cooldown: 60.0,
-                mana_cost: 80.0,
-                required_level: 40,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::Revive(1.0, 5.0),
-            },
-            
-            // Gladiator Skills
-            SkillId::Wirbelsturm => SkillInfo {
-                name: "Wirbelsturm",
-                description: "Rotiert mit Schwert, trifft alle Feinde im Umkreis (3m)",
-                cooldown: 12.0,
-                mana_cost: 30.0,
-                required_level: 5,
-                damage_multiplier: 1.2,
-                effect: SkillEffect::AreaDamage(3.0),
-            },
-            SkillId::Kriegsschrei => SkillInfo {
-                name: "Kriegsschrei",
-                description: "Reduziert Verteidigung aller Feinde im Umkreis (8m) um 30% für 6s",
-                cooldown: 20.0,
-                mana_cost: 25.0,
-                required_level: 10,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::DefenseReduction(0.3, 6.0, 8.0),
-            },
-            SkillId::Hinrichtung => SkillInfo {
-                name: "Hinrichtung",
-                description: "Mächtiger Einzelschlag, +100% Schaden gegen Feinde unter 30% HP",
-                cooldown: 15.0,
-                mana_cost: 45.0,
-                required_level: 15,
-                damage_multiplier: 2.0,
-                effect: SkillEffect::ExecuteDamage(0.3, 1.0),
-            },
-            SkillId::Raserei => SkillInfo {
-                name: "Raserei",
-                description: "+50% Angriffsgeschwindigkeit für 8 Sekunden",
-                cooldown: 25.0,
-                mana_cost: 40.0,
-                required_level: 25,
-                damage_multiplier: 0.0,
-                effect: SkillEffect::AttackSpeedBuff(0.5, 8.0),
-            },
-            SkillId::ToedlicherStoss => SkillInfo {
-                name: "Tödlicher Stoß",
-                description: "Stürmt zum Ziel (bis 15m), betäubt für 1.5s",
-                cooldown: 30.0,
-                mana_cost: 70.0,
-                required_level: 40,
-                damage_multiplier: 2.5,
-                effect: SkillEffect::DashStun(15.0, 1.5),
-            },
-            
-            // Für jetzt erstmal nur die ersten zwei Specs implementieren
-            // TODO: Restliche Skills implementieren
-            _ => SkillInfo {
-                name: "Unknown",
-                description: "Not implemented yet",
-                cooldown: 10.0,
-                mana_cost: 30.0,
-                required_level: 5,
-                damage_multiplier: 1.0,
-                effect: SkillEffect::None,
-            }
-        }
+        balance::current().skill(*self).clone()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillInfo {
-    pub name: &'static str,
-    pub description: &'static str,
+    pub name: String,
+    pub description: String,
     pub cooldown: f32,      // Seconds
     pub mana_cost: f32,
     pub required_level: i32,
     pub damage_multiplier: f32,
+    pub range: f32,         // Meters to the target, 0 = no target (self or area around the caster)
     pub effect: SkillEffect,
 }
 
-#[derive(Debug, Clone)]
+impl SkillInfo {
+    /// Whether the skill is cast on a chosen target rather than on/around the caster
+    pub fn needs_target(&self) -> bool {
+        self.range > 0.0
+    }
+}
+
+/// Kinds of monsters. Their numbers come from the balance tables.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MobType {
+    Wolf,
+    Wildschwein,
+    Bandit,
+    Ork,
+}
+
+impl MobType {
+    pub const ALL: [MobType; 4] = [MobType::Wolf, MobType::Wildschwein, MobType::Bandit, MobType::Ork];
+
+    /// Numbers for this mob from the active balance tables (see `balance`)
+    pub fn info(&self) -> MobInfo {
+        balance::current().mob(*self).clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MobInfo {
+    pub name: String,
+    pub level: i32,
+    pub max_health: f32,
+    pub damage: f32,          // Per hit
+    pub experience: i64,      // Reward for the kill
+    pub aggro_radius: f32,    // Meters at which it notices players
+    pub move_speed: f32,      // Meters per second
+}
+
+/// What a skill does besides its direct damage. Radii of targeted skills (range > 0)
+/// are measured from the target, otherwise from the caster. Radius 0 = the target only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SkillEffect {
     None,
     AreaDamage(f32),                          // radius
@@ -345,6 +309,67 @@ pub enum SkillEffect {
     ExecuteDamage(f32, f32),                  // threshold, bonus_multiplier
     AttackSpeedBuff(f32, f32),                // amount, duration
     DashStun(f32, f32),                       // distance, stun_duration
+    Heal(f32, f32),                           // power_multiplier, radius (0 = caster only)
+    HealOverTime(f32, f32),                   // power_multiplier per second, duration
+    DamageOverTime(f32, f32),                 // damage_multiplier per second, duration
+    Lifesteal(f32, f32),                      // fraction of damage healed, radius
+    Invisibility(f32),                        // duration
+    TeleportBehind(f32),                      // distance behind the target
+    ChainLightning(u32, f32, f32),            // jumps, jump_radius, damage_falloff per jump
+    Resurrect(f32),                           // health fraction
+    DamageBuff(f32, f32),                     // amount, duration
+    LeapBack(f32),                            // distance
+    Pierce(f32),                              // line length through the target
+}
+
+/// Timed state on a combatant: buffs, debuffs and effects over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EffectKind {
+    DamageReduction,       // Incoming damage * (1 - magnitude)
+    DefenseReduction,      // Incoming damage * (1 + magnitude)
+    DefenseBuff,           // Incoming damage / (1 + magnitude)
+    AttackSpeed,           // Cooldowns / (1 + magnitude)
+    DamageBuff,            // Outgoing damage * (1 + magnitude)
+    Invisible,             // Hidden from other players, can't be targeted
+    Stun,                  // Can't move or use skills
+    CrowdControlImmunity,  // Stuns are ignored
+    LastStand,             // Next lethal hit leaves `magnitude` HP instead
+    DamageOverTime,        // `magnitude` damage per tick
+    HealOverTime,          // `magnitude` healing per tick
+}
+
+impl EffectKind {
+    /// Debuffs, as opposed to buffs
+    pub fn is_harmful(&self) -> bool {
+        matches!(self, EffectKind::DefenseReduction | EffectKind::Stun | EffectKind::DamageOverTime)
+    }
+}
+
+/// Current and maximum health, mana and stamina, owned by the server
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Vitals {
+    pub health: f32,
+    pub max_health: f32,
+    pub mana: f32,
+    pub max_mana: f32,
+    pub stamina: f32,
+    pub max_stamina: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyMember {
+    pub entity: u64,
+    pub name: String,
+}
+
+/// An active status effect as shown on the HUD
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffectState {
+    pub kind: EffectKind,
+    pub skill: Option<SkillId>,  // Skill that applied it, if any
+    pub stacks: u32,
+    pub remaining: f32,          // Seconds
+    pub duration: f32,           // Seconds, full length
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -369,7 +394,7 @@ pub enum AuthMessage {
     Login { username: String, password: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AuthResponse {
     RegisterSuccess,
     RegisterFailed { reason: String },
@@ -390,6 +415,11 @@ pub struct CharacterSummary {
 // Network messages
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ClientMessage {
+    // Connection
+    Connect { protocol_id: u64 },
+    ChallengeResponse { challenge: u64 },
+    Heartbeat,
+    
     // Authentication
     Auth(AuthMessage),
     
@@ -399,10 +429,41 @@ pub enum ClientMessage {
     DeleteCharacter { token: String, character_id: i64 },
     
     // Gameplay
-    Join { character: CharacterData },
     Move { direction: Vec3 },
-    UpdatePosition { position: Vec3 },  // Absolute position update
-    GainExperience { amount: i64 },  // Dev command for testing
+    UpdatePosition { position: Vec3, yaw: f32, sequence: u32, sprinting: bool },  // Absolute position after all inputs up to `sequence`
+    AckSnapshot { tick: u32 },  // Latest WorldState decoded - becomes the next delta baseline
+    GmGiveExperience { amount: i64 },  // GM accounts only (checked by the server); negative takes away a level
+    
+    // Combat
+    UseSkill { skill: SkillId, target: Option<u64> },  // Target = network entity ID
+    Respawn,  // Dead: get up at the nearest respawn point
+    
+    // Inventory (slots are indices into our inventory)
+    MoveItem { from: u16, to: u16 },                // Into an empty slot, onto the same item (stacks up) or swap
+    SplitItem { slot: u16, count: u32, to: u16 },   // Move `count` items of a stack into the empty slot `to`
+    DropItem { slot: u16, count: u32 },
+    UseItem { slot: u16 },
+    EquipItem { slot: u16 },                        // Anything equipped in its place goes into `slot`
+    UnequipItem { slot: items::EquipmentSlot },     // Into the first empty slot
+    PickUpItem { id: u64 },                         // Ground item by network entity ID
+    
+    // Merchants (we must stand near them)
+    OpenShop { shop: items::ShopId },                           // Answered with ShopStock
+    BuyItem { shop: items::ShopId, item: items::ItemId, count: u32 },
+    SellItem { shop: items::ShopId, slot: u16, count: u32 },
+    
+    // Trading with another player (we must stand near them)
+    TradeRequest { target: u64 },                    // Network entity ID of the player to trade with
+    TradeAccept { requester: u64 },
+    TradeOffer { items: Vec<(u16, u32)>, gold: u64 }, // Replaces our whole offer: (slot, count)
+    TradeLock,                                       // Our offer is final
+    TradeConfirm,                                    // Swap, once both offers are locked
+    TradeCancel,
+    
+    // Party
+    PartyInvite { target: u64 },    // Network entity ID of the player to invite
+    PartyAccept { inviter: u64 },
+    PartyLeave,
     
     // Specialization
     ChooseSpecialization { token: String, specialization: Specialization },
@@ -410,8 +471,18 @@ pub enum ClientMessage {
     Disconnect,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerMessage {
+    // Connection
+    ConnectionChallenge { challenge: u64 },
+    ConnectionAccepted,
+    ConnectionRejected { reason: String },  // Final: the client stops trying to connect
+    SessionEnded { reason: String },  // Left the world and logged out; the connection stays
+    Heartbeat,
+    
+    // Movement
+    PositionCorrection { position: Vec3, sequence: u32 },  // Authoritative position as of input `sequence`
+    
     // Authentication
     AuthResponse(AuthResponse),
     
@@ -420,6 +491,7 @@ pub enum ServerMessage {
     CharacterCreationFailed { reason: String },
     CharacterSelected { 
         character_id: i64,
+        entity_id: u64,  // Our own network entity ID (targets, combat events)
         character_name: String,
         character_class: CharacterClass,
         position: Vec3,
@@ -435,10 +507,21 @@ pub enum ServerMessage {
     CharacterDeletionFailed { reason: String },
     
     // Gameplay
-    PlayerJoined { id: u64, character: CharacterData, position: Vec3 },
+    PlayerJoined { id: u64, character: CharacterData, position: Vec3, weapon: Option<items::ItemId> },
     PlayerLeft { id: u64 },
+    EquipmentChanged { id: u64, weapon: Option<items::ItemId> },  // A nearby player's visible equipment
+    MobSpawned { id: u64, mob: MobType, position: Vec3, health: f32, max_health: f32 },  // Came into view; moves with WorldState
+    MobLeft { id: u64 },  // Out of view, dead or despawned
+    GroundItemSpawned { id: u64, loot: items::Loot, position: Vec3 },  // Came into view (dropped or looted)
+    GroundItemRemoved { id: u64 },  // Out of view, picked up or despawned
     PlayerMoved { id: u64, position: Vec3 },
-    WorldState { players: Vec<PlayerState> },
+    WorldState {
+        tick: u32,
+        baseline: Option<u32>,  // Tick the entity deltas are relative to (None = full snapshot)
+        server_time: f64,       // Seconds since server start
+        entities: Vec<snapshot::EntityDelta>,
+        removed: Vec<u64>,
+    },
     
     // Leveling System
     ExperienceGained { amount: i64, new_total: i64, xp_needed: i64 },
@@ -449,6 +532,42 @@ pub enum ServerMessage {
         new_max_stamina: f32,
     },
     
+    // Combat
+    SkillUsed { caster: u64, skill: SkillId, target: Option<u64>, cooldown: f32 },
+    SkillFailed { skill: SkillId, reason: String },
+    Damage { source: u64, target: u64, skill: Option<SkillId>, amount: f32, health: f32 },  // `health` = target's HP afterwards, no skill = mob attack
+    Healed { source: u64, target: u64, skill: SkillId, amount: f32, health: f32 },  // Heals, lifesteal, resurrection
+    EffectApplied { source: u64, target: u64, skill: SkillId, duration: f32 },     // Buff, debuff or stun from `skill`
+    Vitals(Vitals),                                                                // Own HP/mana/stamina after a change
+    StatusEffects { entity: u64, effects: Vec<StatusEffectState> },                // All active effects after any change
+    Died { experience_lost: i64, respawn_point: String },                          // We died; `Respawn` brings us to `respawn_point`
+    Respawned { position: Vec3 },                                                  // Alive again (respawn or resurrection)
+    
+    // Inventory
+    Inventory { slots: Vec<Option<items::ItemStack>>, equipment: items::Equipment, gold: u64 },  // Our whole inventory after any change
+    ItemFailed { reason: String },
+    
+    // Merchants
+    ShopStock { shop: items::ShopId, stock: Vec<(items::ItemId, Option<u32>)> },  // What is left of each listing (None = unlimited), after opening and every purchase
+    ShopFailed { reason: String },
+    
+    // Trading
+    TradeRequested { requester: u64, name: String },
+    TradeOpened { partner: u64, name: String },
+    TradeUpdated { ours: items::TradeOffer, theirs: items::TradeOffer },  // After every change to either offer
+    TradeCompleted,                                    // Items and gold were swapped (our new Inventory follows)
+    TradeCancelled { reason: String },
+    TradeFailed { reason: String },                    // The trade goes on
+    
+    // Party
+    PartyInvitation { inviter: u64, name: String },
+    PartyMembers { members: Vec<PartyMember> },       // Everyone in our party including us, empty = no party
+    PartyMemberVitals { entity: u64, vitals: Vitals },  // A party member near us
+    PartyFailed { reason: String },
+    
+    // Balance
+    BalanceChunk { index: u16, total: u16, data: Vec<u8> },  // Piece of the encoded balance tables, which replace the client's
+    
     // Specialization
     SpecializationChosen { specialization: Specialization },
     SpecializationFailed { reason: String },
@@ -457,6 +576,42 @@ pub enum ServerMessage {
     TimeUpdate { hour: f32 },  // 0.0 - 24.0 (12.0 = noon, 0.0 = midnight)
 }
 
+impl ClientMessage {
+    /// Whether this message must arrive. Position updates are superseded by the next one,
+    /// so losing a few is cheaper than resending them.
+    pub fn is_reliable(&self) -> bool {
+        !matches!(self,
+            ClientMessage::Move { .. }
+            | ClientMessage::UpdatePosition { .. }
+            | ClientMessage::AckSnapshot { .. }
+            // Handshake runs before the reliable channel exists and is retried instead
+            | ClientMessage::Connect { .. }
+            | ClientMessage::ChallengeResponse { .. }
+            | ClientMessage::Heartbeat
+        )
+    }
+    
+    /// Messages accepted from clients that have not completed the handshake
+    pub fn is_handshake(&self) -> bool {
+        matches!(self, ClientMessage::Connect { .. } | ClientMessage::ChallengeResponse { .. })
+    }
+}
+
+impl ServerMessage {
+    /// Whether this message must arrive. Snapshots are sent continuously, so a lost one
+    /// is simply replaced by the next.
+    pub fn is_reliable(&self) -> bool {
+        !matches!(self,
+            ServerMessage::PlayerMoved { .. }
+            | ServerMessage::WorldState { .. }
+            | ServerMessage::ConnectionChallenge { .. }
+            | ServerMessage::ConnectionAccepted
+            | ServerMessage::ConnectionRejected { .. }
+            | ServerMessage::Heartbeat
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerState {
     pub id: u64,
@@ -525,26 +680,21 @@ pub fn calculate_xp_for_level(level: i32) -> i64 {
 }
 
 // Calculate max stats based on level and class
-// Base stats at level 1: HP=100, Mana=100, Stamina=100
-// Per level gains depend on class
+// Base stats and per level gains come from the class table in the balance file
 pub fn calculate_stats_for_level(level: i32, class: &CharacterClass) -> (f32, f32, f32) {
-    let base_hp = 100.0;
-    let base_mana = 100.0;
-    let base_stamina = 100.0;
-    
+    let stats = *balance::current().class(*class);
     let levels_gained = (level - 1) as f32;
-    
-    // Class-specific stat multipliers
-    let (hp_per_level, mana_per_level, stamina_per_level) = match class {
-        CharacterClass::Krieger => (20.0, 5.0, 12.0),   // Tanky warrior, low mana
-        CharacterClass::Ninja => (12.0, 8.0, 15.0),     // Agile assassin, high stamina
-        CharacterClass::Sura => (15.0, 12.0, 10.0),     // Balanced magic warrior
-        CharacterClass::Schamane => (8.0, 18.0, 8.0),   // Shaman healer, high mana
-    };
-    
-    let max_health = base_hp + (levels_gained * hp_per_level);
-    let max_mana = base_mana + (levels_gained * mana_per_level);
-    let max_stamina = base_stamina + (levels_gained * stamina_per_level);
-    
+
+    let max_health = stats.base_health + (levels_gained * stats.health_per_level);
+    let max_mana = stats.base_mana + (levels_gained * stats.mana_per_level);
+    let max_stamina = stats.base_stamina + (levels_gained * stats.stamina_per_level);
+
     (max_health, max_mana, max_stamina)
 }
+
+// Max stats with the bonuses of the equipped items on top of the level stats
+pub fn calculate_stats(level: i32, class: &CharacterClass, equipment: &items::Equipment) -> (f32, f32, f32) {
+    let (max_health, max_mana, max_stamina) = calculate_stats_for_level(level, class);
+    let bonus = equipment.stats();
+    (max_health + bonus.health, max_mana + bonus.mana, max_stamina + bonus.stamina)
+}
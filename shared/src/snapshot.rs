@@ -0,0 +1,186 @@
+// Delta-compressed world snapshots.
+//
+// The server numbers every snapshot it sends (tick) and remembers what it sent to each
+// client. Clients ack the ticks they decoded; the next snapshot only carries what changed
+// relative to the newest acked one (the baseline). Positions and yaw are quantized so
+// unchanged values compare equal and changed ones stay small on the wire.
+// Static data (name, class, appearance) is not part of snapshots - it arrives once with
+// `PlayerJoined`.
+
+use bevy::prelude::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::TAU;
+
+/// Quantization steps per world unit (1 cm)
+pub const POSITION_SCALE: f32 = 100.0;
+
+/// Snapshots kept per client for use as baselines
+pub const SNAPSHOT_HISTORY: usize = 32;
+
+pub fn quantize_position(position: Vec3) -> [i32; 3] {
+    [
+        (position.x * POSITION_SCALE).round() as i32,
+        (position.y * POSITION_SCALE).round() as i32,
+        (position.z * POSITION_SCALE).round() as i32,
+    ]
+}
+
+pub fn dequantize_position(position: [i32; 3]) -> Vec3 {
+    Vec3::new(
+        position[0] as f32 / POSITION_SCALE,
+        position[1] as f32 / POSITION_SCALE,
+        position[2] as f32 / POSITION_SCALE,
+    )
+}
+
+/// Map an angle in radians to 1/65536 of a full turn
+pub fn quantize_yaw(yaw: f32) -> u16 {
+    let turns = yaw.rem_euclid(TAU) / TAU;
+    ((turns * 65536.0).round() as u32 % 65536) as u16
+}
+
+pub fn dequantize_yaw(yaw: u16) -> f32 {
+    yaw as f32 / 65536.0 * TAU
+}
+
+/// Replicated dynamic state of one entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub position: [i32; 3],
+    pub yaw: u16,
+    pub level: i32,
+}
+
+impl EntitySnapshot {
+    pub fn new(position: Vec3, yaw: f32, level: i32) -> Self {
+        Self {
+            position: quantize_position(position),
+            yaw: quantize_yaw(yaw),
+            level,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        dequantize_position(self.position)
+    }
+
+    pub fn yaw(&self) -> f32 {
+        dequantize_yaw(self.yaw)
+    }
+}
+
+/// Changed fields of one entity relative to the baseline. Entities that are new to the
+/// baseline have every field set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityDelta {
+    pub id: u64,
+    pub position: Option<[i32; 3]>,
+    pub yaw: Option<u16>,
+    pub level: Option<i32>,
+}
+
+/// Full state of every entity in a snapshot, by network entity ID
+pub type SnapshotEntities = HashMap<u64, EntitySnapshot>;
+
+/// What changed between a baseline and the current snapshot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDelta {
+    pub entities: Vec<EntityDelta>,
+    /// Entities in the baseline that are no longer part of the snapshot
+    pub removed: Vec<u64>,
+}
+
+impl SnapshotDelta {
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Encode `current` relative to `baseline` (`None` = client has nothing, send everything)
+pub fn diff(baseline: Option<&SnapshotEntities>, current: &SnapshotEntities) -> SnapshotDelta {
+    let mut delta = SnapshotDelta::default();
+
+    for (&id, state) in current {
+        let base = baseline.and_then(|b| b.get(&id));
+        if base == Some(state) {
+            continue;
+        }
+        delta.entities.push(EntityDelta {
+            id,
+            position: changed(base.map(|b| b.position), state.position),
+            yaw: changed(base.map(|b| b.yaw), state.yaw),
+            level: changed(base.map(|b| b.level), state.level),
+        });
+    }
+
+    if let Some(baseline) = baseline {
+        delta.removed = baseline.keys()
+            .filter(|id| !current.contains_key(id))
+            .copied()
+            .collect();
+    }
+
+    delta
+}
+
+/// Rebuild the full snapshot from `baseline` and a delta. Returns `None` if the delta
+/// does not fit the baseline (an entity missing from it arrived without all fields).
+pub fn apply(baseline: Option<&SnapshotEntities>, entities: &[EntityDelta], removed: &[u64]) -> Option<SnapshotEntities> {
+    let mut result = baseline.cloned().unwrap_or_default();
+
+    for id in removed {
+        result.remove(id);
+    }
+
+    for delta in entities {
+        let state = match result.get(&delta.id) {
+            Some(base) => EntitySnapshot {
+                position: delta.position.unwrap_or(base.position),
+                yaw: delta.yaw.unwrap_or(base.yaw),
+                level: delta.level.unwrap_or(base.level),
+            },
+            None => EntitySnapshot {
+                position: delta.position?,
+                yaw: delta.yaw?,
+                level: delta.level?,
+            },
+        };
+        result.insert(delta.id, state);
+    }
+
+    Some(result)
+}
+
+fn changed<T: PartialEq>(base: Option<T>, current: T) -> Option<T> {
+    match base {
+        Some(base) if base == current => None,
+        _ => Some(current),
+    }
+}
+
+/// The most recent snapshots by tick, used as baselines on both ends
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotHistory {
+    entries: VecDeque<(u32, SnapshotEntities)>,
+}
+
+impl SnapshotHistory {
+    /// Store a snapshot, replacing an older one with the same tick
+    pub fn insert(&mut self, tick: u32, entities: SnapshotEntities) {
+        self.entries.retain(|(t, _)| *t != tick);
+        self.entries.push_back((tick, entities));
+        if self.entries.len() > SNAPSHOT_HISTORY {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn get(&self, tick: u32) -> Option<&SnapshotEntities> {
+        self.entries.iter().find(|(t, _)| *t == tick).map(|(_, entities)| entities)
+    }
+
+    /// Forget every baseline, e.g. when the server's ticks start over
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
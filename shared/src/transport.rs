@@ -0,0 +1,216 @@
+// Reliable, ordered message delivery on top of plain UDP datagrams.
+//
+// Every datagram is a bincode-encoded `Packet`. Reliable payloads carry a sequence
+// number and are resent until the peer acknowledges them; the receiver buffers
+// out-of-order packets and only hands payloads to the game in sequence order.
+// Unreliable payloads (position updates, snapshots) bypass all of that.
+// Every packet piggybacks the sender's current ack state, so no extra traffic is
+// needed while both sides are talking.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How long to wait for an ack before a reliable packet is sent again
+pub const RESEND_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Out-of-order packets further ahead than this are dropped (and resent later by the peer)
+const MAX_OUT_OF_ORDER: u32 = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Packet {
+    /// Every reliable sequence number below this has been received
+    pub ack: u32,
+    /// Bit `i` set = reliable sequence `ack + 1 + i` has also been received
+    pub ack_bits: u32,
+    pub payload: PacketPayload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PacketPayload {
+    Reliable { seq: u32, data: Vec<u8> },
+    Unreliable { data: Vec<u8> },
+    /// Carries only the ack fields
+    Ack,
+}
+
+impl Packet {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize(bytes).ok()
+    }
+}
+
+struct PendingPacket {
+    seq: u32,
+    data: Vec<u8>,
+    last_sent: Instant,
+}
+
+/// One side of a reliable connection. The server keeps one per client address.
+pub struct ReliableEndpoint {
+    /// Sequence number for the next reliable payload we send
+    next_send_seq: u32,
+    /// Reliable payloads sent but not yet acknowledged (ordered by sequence)
+    pending: VecDeque<PendingPacket>,
+    /// Next reliable sequence number we expect to deliver
+    next_recv_seq: u32,
+    /// Reliable payloads received ahead of a gap
+    out_of_order: BTreeMap<u32, Vec<u8>>,
+    /// We received something reliable and have not sent an ack for it yet
+    ack_pending: bool,
+}
+
+impl Default for ReliableEndpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReliableEndpoint {
+    pub fn new() -> Self {
+        Self {
+            next_send_seq: 0,
+            pending: VecDeque::new(),
+            next_recv_seq: 0,
+            out_of_order: BTreeMap::new(),
+            ack_pending: false,
+        }
+    }
+
+    /// Wrap a payload in a reliable packet and remember it for resending
+    pub fn send_reliable(&mut self, data: Vec<u8>, now: Instant) -> Packet {
+        let seq = self.next_send_seq;
+        self.next_send_seq += 1;
+
+        self.pending.push_back(PendingPacket {
+            seq,
+            data: data.clone(),
+            last_sent: now,
+        });
+
+        self.packet(PacketPayload::Reliable { seq, data })
+    }
+
+    /// Wrap a payload in a fire-and-forget packet
+    pub fn send_unreliable(&mut self, data: Vec<u8>) -> Packet {
+        self.packet(PacketPayload::Unreliable { data })
+    }
+
+    /// Serialize `message` and wrap it in a packet, ready to go on the wire
+    pub fn send<T: Serialize>(&mut self, message: &T, reliable: bool, now: Instant) -> Result<Vec<u8>, bincode::Error> {
+        let data = bincode::serialize(message)?;
+        let packet = if reliable {
+            self.send_reliable(data, now)
+        } else {
+            self.send_unreliable(data)
+        };
+        packet.to_bytes()
+    }
+
+    /// Process an incoming packet. Returns the payloads that are now ready for the game,
+    /// in order.
+    pub fn receive(&mut self, packet: Packet) -> Vec<Vec<u8>> {
+        self.process_ack(packet.ack, packet.ack_bits);
+
+        match packet.payload {
+            PacketPayload::Unreliable { data } => vec![data],
+            PacketPayload::Ack => Vec::new(),
+            PacketPayload::Reliable { seq, data } => {
+                // Always ack, even duplicates - our previous ack may have been lost
+                self.ack_pending = true;
+
+                if seq < self.next_recv_seq || seq - self.next_recv_seq > MAX_OUT_OF_ORDER {
+                    return Vec::new();
+                }
+                self.out_of_order.insert(seq, data);
+
+                // Deliver everything that is now contiguous
+                let mut delivered = Vec::new();
+                while let Some(data) = self.out_of_order.remove(&self.next_recv_seq) {
+                    delivered.push(data);
+                    self.next_recv_seq += 1;
+                }
+                delivered
+            }
+        }
+    }
+
+    /// Decode a datagram and deserialize every payload it releases
+    pub fn receive_bytes<T: for<'de> Deserialize<'de>>(&mut self, bytes: &[u8]) -> Vec<T> {
+        let Some(packet) = Packet::from_bytes(bytes) else { return Vec::new() };
+
+        self.receive(packet)
+            .iter()
+            .filter_map(|data| bincode::deserialize(data).ok())
+            .collect()
+    }
+
+    /// Packets that need to go out now: resends of unacknowledged reliable payloads
+    /// and a standalone ack if nothing else carried it
+    pub fn poll(&mut self, now: Instant) -> Vec<Packet> {
+        let ack = self.next_recv_seq;
+        let ack_bits = self.ack_bits();
+
+        let mut packets = Vec::new();
+        for pending in self.pending.iter_mut() {
+            if now.duration_since(pending.last_sent) >= RESEND_INTERVAL {
+                pending.last_sent = now;
+                packets.push(Packet {
+                    ack,
+                    ack_bits,
+                    payload: PacketPayload::Reliable {
+                        seq: pending.seq,
+                        data: pending.data.clone(),
+                    },
+                });
+            }
+        }
+
+        if packets.is_empty() && self.ack_pending {
+            packets.push(Packet { ack, ack_bits, payload: PacketPayload::Ack });
+        }
+        self.ack_pending = false;
+
+        packets
+    }
+
+    /// Number of reliable payloads still waiting for an ack
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn packet(&mut self, payload: PacketPayload) -> Packet {
+        // Any outgoing packet carries our acks
+        self.ack_pending = false;
+        Packet {
+            ack: self.next_recv_seq,
+            ack_bits: self.ack_bits(),
+            payload,
+        }
+    }
+
+    fn ack_bits(&self) -> u32 {
+        let mut bits = 0u32;
+        for &seq in self.out_of_order.keys() {
+            let offset = seq - self.next_recv_seq - 1;
+            if offset < 32 {
+                bits |= 1 << offset;
+            }
+        }
+        bits
+    }
+
+    fn process_ack(&mut self, ack: u32, ack_bits: u32) {
+        self.pending.retain(|p| {
+            if p.seq < ack {
+                return false;
+            }
+            let offset = p.seq - ack;
+            !((1..=32).contains(&offset) && ack_bits & (1 << (offset - 1)) != 0)
+        });
+    }
+}